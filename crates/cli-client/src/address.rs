@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use simplicityhl::elements::address::AddressError;
+use simplicityhl::elements::{Address, AddressParams};
+
+#[derive(thiserror::Error, Debug)]
+pub enum AddrError {
+    #[error("Invalid address '{address}': {err}")]
+    Parse { address: String, err: AddressError },
+
+    #[error("Address '{address}' is for {found}, but the wallet is configured for {expected}")]
+    NetworkMismatch {
+        address: String,
+        found: &'static str,
+        expected: &'static str,
+    },
+}
+
+/// Parse a user-supplied address and check it belongs to the configured network.
+///
+/// Use [`Address::is_blinded`] on the result to tell confidential destinations apart.
+pub fn validate_address(s: &str, expected_params: &'static AddressParams) -> Result<Address, AddrError> {
+    let address = Address::from_str(s.trim()).map_err(|err| AddrError::Parse {
+        address: s.to_string(),
+        err,
+    })?;
+
+    if address.params != expected_params {
+        return Err(AddrError::NetworkMismatch {
+            address: s.to_string(),
+            found: network_name(address.params),
+            expected: network_name(expected_params),
+        });
+    }
+
+    Ok(address)
+}
+
+/// Short human-readable description, e.g. "confidential, Liquid testnet".
+#[must_use]
+pub fn describe_address(address: &Address) -> String {
+    let kind = if address.is_blinded() {
+        "confidential"
+    } else {
+        "unconfidential"
+    };
+
+    format!("{kind}, {}", network_name(address.params))
+}
+
+fn network_name(params: &AddressParams) -> &'static str {
+    if *params == AddressParams::LIQUID {
+        "Liquid mainnet"
+    } else if *params == AddressParams::LIQUID_TESTNET {
+        "Liquid testnet"
+    } else if *params == AddressParams::ELEMENTS {
+        "Elements regtest"
+    } else {
+        "an unknown network"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::Script;
+
+    fn unconfidential(params: &'static AddressParams) -> String {
+        Address::p2sh(&Script::new(), None, params).to_string()
+    }
+
+    #[test]
+    fn test_validate_address_network() {
+        let testnet = unconfidential(&AddressParams::LIQUID_TESTNET);
+        let address = validate_address(&testnet, &AddressParams::LIQUID_TESTNET).unwrap();
+        assert!(!address.is_blinded());
+
+        let mainnet = unconfidential(&AddressParams::LIQUID);
+        assert!(matches!(
+            validate_address(&mainnet, &AddressParams::LIQUID_TESTNET),
+            Err(AddrError::NetworkMismatch { .. })
+        ));
+
+        assert!(matches!(
+            validate_address("not-an-address", &AddressParams::LIQUID_TESTNET),
+            Err(AddrError::Parse { .. })
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use simplicityhl::elements::AssetId;
+use simplicityhl_core::LIQUID_TESTNET_BITCOIN_ASSET;
+
+use crate::error::Error;
+
+/// File next to the config file that extends the built-in asset registry.
+pub const ASSET_REGISTRY_FILENAME: &str = "assets.json";
+
+/// How an asset is shown to the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetInfo {
+    pub ticker: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Number of decimal places amounts are shown with; base units are divided by 10^precision.
+    #[serde(default)]
+    pub precision: u8,
+}
+
+/// Maps asset IDs to tickers and display precision, falling back to the asset hex for unknown assets.
+#[derive(Debug, Clone)]
+pub struct AssetRegistry {
+    assets: HashMap<AssetId, AssetInfo>,
+}
+
+impl Default for AssetRegistry {
+    fn default() -> Self {
+        let mut assets = HashMap::new();
+        assets.insert(
+            *LIQUID_TESTNET_BITCOIN_ASSET,
+            AssetInfo {
+                ticker: "LBTC".to_string(),
+                name: Some("Liquid Bitcoin (testnet)".to_string()),
+                precision: 8,
+            },
+        );
+
+        Self { assets }
+    }
+}
+
+impl AssetRegistry {
+    /// The built-in assets, extended by the JSON file at `path` if it exists, then by `overrides`.
+    ///
+    /// The file holds an object keyed by asset ID hex, e.g.
+    /// `{"<asset id>": {"ticker": "USDt", "name": "Tether USD", "precision": 8}}`.
+    pub fn load(path: impl AsRef<Path>, overrides: &BTreeMap<String, AssetInfo>) -> Result<Self, Error> {
+        let mut registry = Self::default();
+
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let entries: BTreeMap<String, AssetInfo> = serde_json::from_str(&content)
+                    .map_err(|e| Error::Config(format!("Invalid {ASSET_REGISTRY_FILENAME}: {e}")))?;
+                registry.extend(&entries)?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        registry.extend(overrides)?;
+
+        Ok(registry)
+    }
+
+    /// Register or replace assets keyed by asset ID hex.
+    pub fn extend(&mut self, entries: &BTreeMap<String, AssetInfo>) -> Result<(), Error> {
+        for (asset_id, info) in entries {
+            let asset_id = AssetId::from_str(asset_id)
+                .map_err(|e| Error::Config(format!("Invalid asset ID '{asset_id}' in asset registry: {e}")))?;
+            self.assets.insert(asset_id, info.clone());
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get(&self, asset_id: &AssetId) -> Option<&AssetInfo> {
+        self.assets.get(asset_id)
+    }
+
+    /// The asset's ticker, or its full hex when it is not registered.
+    #[must_use]
+    pub fn ticker(&self, asset_id: &AssetId) -> String {
+        self.get(asset_id)
+            .map_or_else(|| asset_id.to_string(), |info| info.ticker.clone())
+    }
+
+    /// An amount in base units, scaled by the asset's registered precision.
+    #[must_use]
+    pub fn format_amount(&self, asset_id: &AssetId, value: u64) -> String {
+        let precision = self.get(asset_id).map_or(0, |info| info.precision);
+
+        format_with_precision(value, precision)
+    }
+}
+
+fn format_with_precision(value: u64, precision: u8) -> String {
+    if precision == 0 {
+        return value.to_string();
+    }
+
+    let scale = 10u128.pow(u32::from(precision));
+    let value = u128::from(value);
+
+    format!(
+        "{}.{:0width$}",
+        value / scale,
+        value % scale,
+        width = usize::from(precision)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount_uses_registered_precision() {
+        let registry = AssetRegistry::default();
+        let unknown = AssetId::from_slice(&[7; 32]).unwrap();
+
+        assert_eq!(
+            registry.format_amount(&LIQUID_TESTNET_BITCOIN_ASSET, 123_456_789),
+            "1.23456789"
+        );
+        assert_eq!(registry.format_amount(&LIQUID_TESTNET_BITCOIN_ASSET, 5), "0.00000005");
+        assert_eq!(registry.format_amount(&unknown, 5), "5");
+        assert_eq!(registry.ticker(&unknown), unknown.to_string());
+    }
+
+    #[test]
+    fn test_overrides_replace_registered_assets() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            LIQUID_TESTNET_BITCOIN_ASSET.to_string(),
+            AssetInfo {
+                ticker: "tL-BTC".to_string(),
+                name: None,
+                precision: 0,
+            },
+        );
+
+        let registry = AssetRegistry::load("/nonexistent/assets.json", &overrides).unwrap();
+
+        assert_eq!(registry.ticker(&LIQUID_TESTNET_BITCOIN_ASSET), "tL-BTC");
+        assert_eq!(registry.format_amount(&LIQUID_TESTNET_BITCOIN_ASSET, 5), "5");
+
+        let info = overrides[&LIQUID_TESTNET_BITCOIN_ASSET.to_string()].clone();
+        overrides.insert("not-hex".to_string(), info);
+        assert!(AssetRegistry::load("/nonexistent/assets.json", &overrides).is_err());
+    }
+}
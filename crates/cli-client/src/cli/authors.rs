@@ -0,0 +1,88 @@
+use crate::cli::{AuthorsCommand, Cli};
+use crate::config::{Config, RelayConfig};
+use crate::error::Error;
+
+use nostr::PublicKey;
+
+impl Cli {
+    pub(crate) fn run_authors(&self, mut config: Config, command: &AuthorsCommand) -> Result<(), Error> {
+        match command {
+            AuthorsCommand::Allow { pubkey } => {
+                allow_author(&mut config.relay, *pubkey);
+                config.save(&self.config)?;
+                println!("Allowed {}", pubkey.to_hex());
+            }
+            AuthorsCommand::Block { pubkey } => {
+                block_author(&mut config.relay, *pubkey);
+                config.save(&self.config)?;
+                println!("Blocked {}", pubkey.to_hex());
+            }
+            AuthorsCommand::Remove { pubkey } => {
+                config.relay.author_allowlist.retain(|author| author != pubkey);
+                config.relay.author_blocklist.retain(|author| author != pubkey);
+                config.save(&self.config)?;
+                println!("Removed {} from the author lists", pubkey.to_hex());
+            }
+            AuthorsCommand::List => {
+                println!("Allowed authors:");
+                if config.relay.author_allowlist.is_empty() {
+                    println!("  (any author)");
+                }
+                for author in &config.relay.author_allowlist {
+                    println!("  {}", author.to_hex());
+                }
+
+                println!("Blocked authors:");
+                if config.relay.author_blocklist.is_empty() {
+                    println!("  (none)");
+                }
+                for author in &config.relay.author_blocklist {
+                    println!("  {}", author.to_hex());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Add an author to the allowlist, taking it off the blocklist.
+fn allow_author(relay: &mut RelayConfig, author: PublicKey) {
+    relay.author_blocklist.retain(|blocked| *blocked != author);
+    if !relay.author_allowlist.contains(&author) {
+        relay.author_allowlist.push(author);
+    }
+}
+
+/// Add an author to the blocklist, taking it off the allowlist.
+fn block_author(relay: &mut RelayConfig, author: PublicKey) {
+    relay.author_allowlist.retain(|allowed| *allowed != author);
+    if !relay.author_blocklist.contains(&author) {
+        relay.author_blocklist.push(author);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nostr::Keys;
+
+    #[test]
+    fn test_author_policy() {
+        let maker = Keys::generate().public_key();
+        let stranger = Keys::generate().public_key();
+        let mut relay = RelayConfig::default();
+
+        assert!(relay.accepts_author(&stranger));
+
+        allow_author(&mut relay, maker);
+        assert!(relay.accepts_author(&maker));
+        assert!(!relay.accepts_author(&stranger));
+
+        block_author(&mut relay, maker);
+        assert!(!relay.accepts_author(&maker));
+        assert!(relay.author_allowlist.is_empty());
+        assert!(relay.accepts_author(&stranger));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::cli::Cli;
+use crate::cli::interactive::{Clock, SystemClock, format_asset_with_tag, format_relative_time, is_offer_expired};
+use crate::cli::option_offer::{LocalOptionOfferData, collect_active_option_offers, list_option_offer_contracts};
+use crate::config::Config;
+use crate::error::Error;
+
+use std::cmp::Reverse;
+
+use contracts::option_offer::OptionOfferArguments;
+use simplicityhl::elements::AssetId;
+
+impl Cli {
+    pub(crate) async fn run_best_offer(
+        &self,
+        config: Config,
+        collateral_asset: AssetId,
+        settlement_asset: AssetId,
+    ) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+
+        let offer_contracts = list_option_offer_contracts(&wallet, None).await?;
+        let active_offers = collect_active_option_offers(&wallet, &config, offer_contracts).await?;
+
+        let Some(best) = select_best_offer(&SystemClock, active_offers, collateral_asset, settlement_asset) else {
+            println!("No active option offers found for this pair.");
+            println!("Run `sync nostr` and `sync spent` to refresh the local order book.");
+            return Ok(());
+        };
+
+        let args = &best.option_offer_args;
+        let store = wallet.store();
+
+        let collateral_name = format_asset_with_tag(store, &collateral_asset).await;
+        let settlement_name = format_asset_with_tag(store, &settlement_asset).await;
+        let premium_name = format_asset_with_tag(store, &args.get_premium_asset_id()).await;
+
+        let settlement_for_all = best
+            .available_collateral()
+            .saturating_mul(args.collateral_per_contract());
+        let premium_for_all = best
+            .available_collateral()
+            .saturating_mul(args.premium_per_collateral());
+
+        println!("Best Option Offer:");
+        println!("------------------");
+        println!(
+            "  Offer event: {}",
+            best.metadata.nostr_event_id.as_deref().unwrap_or("local")
+        );
+        println!(
+            "  Seller: {}",
+            best.metadata.nostr_author.as_deref().unwrap_or("unknown")
+        );
+        println!(
+            "  Price: {} {settlement_name} per {collateral_name}",
+            args.collateral_per_contract()
+        );
+        println!(
+            "  Premium: {} {premium_name} per {collateral_name}",
+            args.premium_per_collateral()
+        );
+        println!(
+            "  Collateral available: {} {collateral_name}",
+            best.available_collateral()
+        );
+        println!(
+            "  Taking all: pay {settlement_for_all} {settlement_name}, receive {premium_for_all} {premium_name} premium"
+        );
+        println!(
+            "  Expiry: {} ({})",
+            args.expiry_time(),
+            format_relative_time(i64::from(args.expiry_time()))
+        );
+
+        if let Some(event_id) = &best.metadata.nostr_event_id {
+            println!();
+            println!("Take it with: option-offer take --offer-event {event_id}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Pick the offer for a collateral/settlement pair that costs the taker the least settlement per collateral.
+fn select_best_offer(
+    clock: &impl Clock,
+    offers: Vec<LocalOptionOfferData>,
+    collateral_asset: AssetId,
+    settlement_asset: AssetId,
+) -> Option<LocalOptionOfferData> {
+    offers
+        .into_iter()
+        .filter(|offer| is_takeable_for_pair(clock, &offer.option_offer_args, collateral_asset, settlement_asset))
+        .min_by_key(|offer| taker_rank(&offer.option_offer_args))
+}
+
+fn is_takeable_for_pair(
+    clock: &impl Clock,
+    args: &OptionOfferArguments,
+    collateral_asset: AssetId,
+    settlement_asset: AssetId,
+) -> bool {
+    is_offer_for_pair(args, collateral_asset, settlement_asset) && !is_offer_expired(clock, args.expiry_time())
+}
+
+/// Whether an offer sells `collateral_asset` for `settlement_asset`.
+pub(crate) fn is_offer_for_pair(
+    args: &OptionOfferArguments,
+    collateral_asset: AssetId,
+    settlement_asset: AssetId,
+) -> bool {
+    args.get_collateral_asset_id() == collateral_asset && args.get_settlement_asset_id() == settlement_asset
+}
+
+/// Lower ranks are better for the taker: cheapest price first, then the highest premium, then the
+/// offer that expires last.
+///
+/// The premium is usually paid in a third asset, so it cannot be netted against the price in
+/// settlement units; it only decides between offers at the same price.
+fn taker_rank(args: &OptionOfferArguments) -> (u64, Reverse<u64>, Reverse<u32>) {
+    (
+        args.collateral_per_contract(),
+        Reverse(args.premium_per_collateral()),
+        Reverse(args.expiry_time()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cli::interactive::FixedClock;
+    use crate::metadata::ContractMetadata;
+
+    use contracts::option_offer::get_option_offer_address;
+    use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{AddressParams, OutPoint, Txid};
+
+    const NOW: u32 = 1_704_067_200;
+
+    fn asset(byte: u8) -> AssetId {
+        AssetId::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn offer(collateral: u8, settlement: u8, price: u64, premium: u64, expiry: u32) -> LocalOptionOfferData {
+        let option_offer_args = OptionOfferArguments::new(
+            asset(collateral),
+            asset(3),
+            asset(settlement),
+            price,
+            premium,
+            expiry,
+            [2; 32],
+        );
+        let taproot_pubkey_gen = TaprootPubkeyGen::from(
+            &option_offer_args,
+            &AddressParams::LIQUID_TESTNET,
+            &get_option_offer_address,
+        )
+        .unwrap();
+
+        LocalOptionOfferData {
+            option_offer_args,
+            taproot_pubkey_gen,
+            metadata: ContractMetadata::default(),
+            current_outpoint: OutPoint::new(Txid::from_byte_array([1; 32]), 0),
+            current_value: 1_000,
+        }
+    }
+
+    fn terms(offer: &LocalOptionOfferData) -> (u64, u64, u32) {
+        let args = &offer.option_offer_args;
+        (
+            args.collateral_per_contract(),
+            args.premium_per_collateral(),
+            args.expiry_time(),
+        )
+    }
+
+    #[test]
+    fn test_best_offer_prefers_lowest_price_then_latest_expiry() {
+        let clock = FixedClock(i64::from(NOW));
+        let offers = vec![
+            offer(1, 2, 120, 1, NOW + 3600),
+            offer(1, 2, 100, 1, NOW + 3600),
+            offer(1, 2, 100, 1, NOW + 7200),
+            offer(1, 2, 50, 1, NOW - 1),
+            offer(1, 4, 10, 1, NOW + 7200),
+        ];
+
+        let best = select_best_offer(&clock, offers, asset(1), asset(2)).unwrap();
+
+        assert_eq!(terms(&best), (100, 1, NOW + 7200));
+    }
+
+    #[test]
+    fn test_best_offer_breaks_price_ties_on_premium() {
+        let clock = FixedClock(i64::from(NOW));
+        let offers = vec![
+            offer(1, 2, 100, 1, NOW + 7200),
+            offer(1, 2, 100, 3, NOW + 3600),
+            offer(1, 2, 90, 0, NOW + 3600),
+        ];
+
+        // A lower price still wins over any premium
+        let best = select_best_offer(&clock, offers, asset(1), asset(2)).unwrap();
+        assert_eq!(terms(&best), (90, 0, NOW + 3600));
+
+        let offers = vec![offer(1, 2, 100, 1, NOW + 7200), offer(1, 2, 100, 3, NOW + 3600)];
+        let best = select_best_offer(&clock, offers, asset(1), asset(2)).unwrap();
+        assert_eq!(terms(&best), (100, 3, NOW + 3600));
+
+        assert!(select_best_offer(&clock, Vec::new(), asset(1), asset(2)).is_none());
+    }
+}
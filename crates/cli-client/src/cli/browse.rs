@@ -18,7 +18,11 @@ impl Cli {
         println!();
 
         let options_results = client.fetch_options(config.address_params()).await?;
-        let valid_options: Vec<OptionCreatedEvent> = options_results.into_iter().filter_map(Result::ok).collect();
+        let valid_options: Vec<OptionCreatedEvent> = options_results
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|event| config.relay.accepts_author(&event.pubkey))
+            .collect();
 
         println!("Available Options:");
         println!("------------------");
@@ -47,7 +51,11 @@ impl Cli {
         println!();
 
         let offers_results = client.fetch_option_offers(config.address_params()).await?;
-        let valid_offers: Vec<OptionOfferCreatedEvent> = offers_results.into_iter().filter_map(Result::ok).collect();
+        let valid_offers: Vec<OptionOfferCreatedEvent> = offers_results
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|event| config.relay.accepts_author(&event.pubkey))
+            .collect();
 
         println!("Available Option Offers (from NOSTR):");
         println!("-------------------------------------");
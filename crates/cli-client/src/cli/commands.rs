@@ -1,15 +1,18 @@
-use clap::Subcommand;
-use simplicityhl::elements::{Address, AssetId, OutPoint};
+use std::path::PathBuf;
+
+use clap::{Subcommand, ValueEnum};
+use nostr::PublicKey;
+use simplicityhl::elements::{AssetId, OutPoint, Txid};
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
-    /// Wallet management (init, address, balance, utxos, import, spend)
+    /// Wallet management (init, address, xpub, balance, utxos, import, spend, watch)
     Wallet {
         #[command(subcommand)]
         command: WalletCommand,
     },
 
-    /// Basic transactions (transfer, split, merge, issue, reissue)
+    /// Basic transactions (transfer, split, merge, issue, reissue, broadcast)
     Tx {
         #[command(subcommand)]
         command: TxCommand,
@@ -27,22 +30,318 @@ pub enum Command {
         command: OptionOfferCommand,
     },
 
+    /// NOSTR identity management (show, rotate)
+    Key {
+        #[command(subcommand)]
+        command: KeyCommand,
+    },
+
+    /// Trusted NOSTR authors (allow, block, list), persisted to the config file
+    Authors {
+        #[command(subcommand)]
+        command: AuthorsCommand,
+    },
+
     /// Fetch options/swaps from NOSTR, sync to coin-store, display
     Browse,
 
     /// Show my holdings with expiration warnings
     Positions,
 
+    /// Show the cheapest active option offer for a collateral/settlement pair
+    BestOffer {
+        /// Collateral asset ID the taker wants to receive
+        #[arg(long)]
+        collateral: AssetId,
+        /// Settlement asset ID the taker pays with
+        #[arg(long)]
+        settlement: AssetId,
+    },
+
+    /// Print executed option offer prices for a pair as CSV rows (timestamp, price, size)
+    PriceHistory {
+        /// Collateral asset ID sold by the offers
+        #[arg(long)]
+        collateral: AssetId,
+        /// Settlement asset ID the takers paid with
+        #[arg(long)]
+        settlement: AssetId,
+        /// Only include takes published at or after this Unix timestamp
+        #[arg(long)]
+        since: Option<u64>,
+    },
+
+    /// Check that each configured relay is reachable and accepts our event kinds
+    RelayStatus,
+
     /// Sync coin-store with blockchain via Esplora and/or NOSTR
     Sync {
+        /// Confirm automated actions enabled under `[automation]` (e.g. auto-withdraw)
+        #[arg(long, global = true)]
+        yes: bool,
+
         #[command(subcommand)]
         command: SyncCommand,
     },
 
+    /// Watch a contract address and alert when its UTXO set changes
+    Watch {
+        /// Taproot pubkey gen or NOSTR event ID (prefix) of a locally known contract
+        target: String,
+        /// Polling interval in seconds
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+        /// Shell command to run on each change (receives `DEX_WATCH_TARGET`, `DEX_WATCH_ADDED`, `DEX_WATCH_REMOVED`)
+        #[arg(long)]
+        hook: Option<String>,
+    },
+
+    /// Point a local contract at the NOSTR event it was republished under
+    ///
+    /// The event must announce the same contract and funding output, so `refresh` and `watch`
+    /// keep following the contract after its maker republishes it.
+    Relink {
+        /// Taproot pubkey gen string of the local contract
+        #[arg(long)]
+        tpg: String,
+        /// Event ID of the new announcement
+        #[arg(long)]
+        event: String,
+    },
+
+    /// Contract source diagnostics
+    Contract {
+        #[command(subcommand)]
+        command: ContractCommand,
+    },
+
+    /// Wallet utilities (stats, gen-seed, tx fee, bump-fee, prove/check-control, backup, restore, balance, prune)
+    Helper {
+        #[command(subcommand)]
+        command: HelperCommand,
+    },
+
+    /// Full-screen order book: browse active option offers and take one
+    #[cfg(feature = "tui")]
+    Tui,
+
+    /// [dev] Drive a local regtest node (mine blocks, advance time)
+    #[cfg(feature = "dev-commands")]
+    Regtest {
+        #[command(subcommand)]
+        command: RegtestCommand,
+    },
+
     /// Show current configuration
     Config,
 }
 
+/// Regtest node commands for testing expiry-gated flows locally
+#[cfg(feature = "dev-commands")]
+#[derive(Debug, Subcommand)]
+pub enum RegtestCommand {
+    /// Mine blocks, optionally moving the node clock forward first so timelocks can be satisfied
+    Advance {
+        /// Number of blocks to mine
+        #[arg(long, default_value_t = 1)]
+        blocks: u32,
+        /// Move the node clock forward by this duration before mining (e.g. "2h", "31d")
+        #[arg(long, value_parser = humantime::parse_duration)]
+        time: Option<std::time::Duration>,
+    },
+}
+
+/// Wallet utilities: trading statistics, seed generation, fee tools, address control proofs, backups
+/// and local store maintenance
+#[derive(Debug, Subcommand)]
+pub enum HelperCommand {
+    /// Summarize my trading history: action counts, premium earned, fees paid and volume per asset
+    Stats {
+        /// Only include actions recorded at or after this Unix timestamp
+        #[arg(long)]
+        since: Option<u64>,
+    },
+    /// Generate a fresh random seed for `wallet init`
+    GenSeed {
+        /// Allow printing the seed when stdout is not a terminal (e.g. piped to a file)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Inspect recorded transactions
+    Tx {
+        #[command(subcommand)]
+        command: HelperTxCommand,
+    },
+    /// Replace an unconfirmed wallet transaction (sent with `fee.rbf`) with one paying a higher fee
+    BumpFee {
+        /// Transaction to replace, as recorded in the local store
+        txid: Txid,
+        /// New total fee in satoshis, taken from the wallet's LBTC change
+        #[arg(long)]
+        new_fee: u64,
+        /// Broadcast the replacement immediately
+        #[arg(long)]
+        broadcast: bool,
+    },
+    /// Sign a message with the wallet key, printing a proof that you control the wallet address
+    ProveControl {
+        /// Message to sign, e.g. a nonce agreed with the counterparty
+        #[arg(long, short = 'm')]
+        message: String,
+    },
+    /// Verify a proof printed by `helper prove-control`
+    CheckControl {
+        /// Proof bundle as JSON, or a path to a file containing it
+        bundle: String,
+    },
+    /// Write a consistent snapshot of the wallet database and a manifest to a single archive file
+    Backup {
+        /// Archive file to create
+        archive: PathBuf,
+    },
+    /// Restore the wallet database from an archive written by `helper backup`
+    Restore {
+        /// Archive file to restore from
+        archive: PathBuf,
+    },
+    /// Show what the wallet has locked in its own option offers and options, per asset and role
+    Balance {
+        /// Also show spendable and in-contract funds per asset, and split each locked amount into
+        /// what is still on offer and what is reclaimable after expiry
+        #[arg(long)]
+        detailed: bool,
+    },
+    /// Delete spent UTXOs from the local store and compact the database file
+    Prune {
+        /// Only delete spent UTXOs confirmed below this block height
+        #[arg(long)]
+        older_than_height: Option<u32>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HelperTxCommand {
+    /// Show the fee actually paid by a recorded transaction and its effective rate
+    Fee {
+        /// Transaction ID (must have been recorded by this wallet)
+        txid: Txid,
+    },
+}
+
+/// Contract source diagnostics
+#[derive(Debug, Subcommand)]
+pub enum ContractCommand {
+    /// Compile a bundled contract source with placeholder arguments and report any errors
+    CheckSource {
+        /// Contract to check (all bundled contracts if omitted)
+        name: Option<ContractSourceName>,
+    },
+    /// Recompile the contract announced by a NOSTR event and check that its taproot address holds the funds
+    /// and its advertised expiry is the one the program enforces
+    VerifyAddress {
+        /// Event ID of the option or option offer announcement
+        event_id: String,
+    },
+    /// Spend a stuck option offer output directly, for contracts the local store cannot see
+    ///
+    /// The contract arguments must be given with `--arguments` or recovered from the offer's
+    /// announcement with `--event`, so the program can be compiled and checked against `--tpg`.
+    Reclaim {
+        /// Taproot pubkey gen string of the contract
+        #[arg(long)]
+        tpg: String,
+        /// Contract output to spend (txid:vout); for `expiry`, the collateral output, with the premium right after it
+        #[arg(long)]
+        outpoint: OutPoint,
+        /// Contract branch to spend through
+        #[arg(long, value_enum)]
+        branch: ReclaimBranch,
+        /// Contract arguments as hex, in the encoding the store keeps them in
+        #[arg(long, conflicts_with = "event", required_unless_present = "event")]
+        arguments: Option<String>,
+        /// Event ID of the option offer announcement to recover the arguments from
+        #[arg(long)]
+        event: Option<String>,
+        /// Fee amount in satoshis (auto-estimated if not specified)
+        #[arg(long)]
+        fee: Option<u64>,
+        /// Broadcast transaction
+        #[arg(long)]
+        broadcast: bool,
+    },
+}
+
+/// Simplicity execution trace printed while finalizing contract inputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TrackerLog {
+    /// No trace
+    #[default]
+    Off,
+    /// Report failing jets and assertions
+    Error,
+    /// Trace every jet call and branch taken
+    Trace,
+}
+
+/// Contract sources bundled with the client
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ContractSourceName {
+    Option,
+    OptionOffer,
+}
+
+/// Option offer branch spent by `contract reclaim`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReclaimBranch {
+    /// Reclaim collateral and premium after the offer expired
+    Expiry,
+    /// Claim the settlement takers paid into the offer
+    Withdraw,
+}
+
+/// How `option-offer create --round` adjusts amounts that are not a multiple of the collateral
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AmountRounding {
+    Up,
+    Down,
+    Nearest,
+}
+
+/// NOSTR identity commands
+#[derive(Debug, Subcommand)]
+pub enum KeyCommand {
+    /// Show the public key offers and actions are published with
+    Show,
+
+    /// Generate a new NOSTR key for publishing, archiving the current one
+    Rotate,
+}
+
+/// NOSTR author policy commands
+#[derive(Debug, Subcommand)]
+pub enum AuthorsCommand {
+    /// Add an author to the allowlist (once non-empty, only allowed authors are shown)
+    Allow {
+        /// Author public key (hex or npub)
+        pubkey: PublicKey,
+    },
+
+    /// Add an author to the blocklist
+    Block {
+        /// Author public key (hex or npub)
+        pubkey: PublicKey,
+    },
+
+    /// Remove an author from both lists
+    Remove {
+        /// Author public key (hex or npub)
+        pubkey: PublicKey,
+    },
+
+    /// Show the allowlist and blocklist
+    List,
+}
+
 /// Wallet management commands
 #[derive(Debug, Subcommand)]
 pub enum WalletCommand {
@@ -52,11 +351,25 @@ pub enum WalletCommand {
     /// Show wallet details
     Address,
 
+    /// Print the extended public key of a BIP32 account (`m/<account>'`) for watch-only tooling
+    Xpub {
+        /// Account index
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+    },
+
     /// Show wallet balance
     Balance,
 
     /// List all UTXOs stored in wallet
-    Utxos,
+    Utxos {
+        /// Page to show, starting at 1 (used with --page-size)
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+        /// Show UTXOs a page at a time instead of all at once
+        #[arg(long)]
+        page_size: Option<u32>,
+    },
 
     /// Import a UTXO into the wallet
     Import {
@@ -75,6 +388,13 @@ pub enum WalletCommand {
         #[arg(long, short = 'o')]
         outpoint: OutPoint,
     },
+
+    /// Follow confirmed wallet and tracked contract activity, updating the store as it happens
+    Watch {
+        /// Polling interval in seconds
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+    },
 }
 
 /// Basic transaction commands
@@ -85,9 +405,9 @@ pub enum TxCommand {
         /// Asset ID (defaults to native LBTC if not specified)
         #[arg(long)]
         asset_id: Option<AssetId>,
-        /// Recipient address
+        /// Recipient address (must match the configured network; confidential addresses get a blinded output)
         #[arg(long)]
-        to: Address,
+        to: String,
         /// Amount to send
         #[arg(long)]
         amount: u64,
@@ -117,9 +437,12 @@ pub enum TxCommand {
         /// Asset ID to merge (defaults to native LBTC if not specified)
         #[arg(long)]
         asset_id: Option<AssetId>,
-        /// Number of UTXOs to merge
+        /// Number of UTXOs to merge, smallest first
         #[arg(long)]
         count: usize,
+        /// Leave the N largest UTXOs out of the merge, e.g. `--keep 3` keeps enough LBTC coins to create an option
+        #[arg(long, default_value_t = 0)]
+        keep: usize,
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
@@ -141,6 +464,24 @@ pub enum TxCommand {
         broadcast: bool,
     },
 
+    /// Broadcast a pre-built transaction and record it in the wallet
+    Broadcast {
+        /// Signed transaction hex
+        hex: String,
+        /// Blinding secret key (hex) for confidential outputs (prompted if outputs are confidential)
+        #[arg(long)]
+        blinder: Option<String>,
+    },
+
+    /// Run the Simplicity program of every known contract input of a finalized transaction, without broadcasting
+    Verify {
+        /// Finalized transaction hex
+        hex: String,
+        /// File with the spent outputs, one consensus-encoded `TxOut` hex per line in input order
+        #[arg(long)]
+        utxos: PathBuf,
+    },
+
     /// Reissue an existing asset using reissuance token
     ReissueAsset {
         /// Asset ID to reissue
@@ -178,12 +519,30 @@ pub enum OptionCommand {
         /// Total strike price (settlement needed to exercise ALL contracts)
         #[arg(long)]
         total_strike: u64,
-        /// Expiry time as Unix timestamp or duration (e.g., +30d)
+        /// Expiry time as Unix timestamp or duration (e.g., +30d), defaults to `defaults.offer_expiry` from config
+        #[arg(long)]
+        expiry: Option<String>,
+        /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
-        expiry: String,
+        fee: Option<u64>,
+        /// Publish only to these relays (comma-separated), instead of every configured relay
+        #[arg(long, value_delimiter = ',')]
+        relays: Vec<String>,
+        /// Broadcast transaction
+        #[arg(long)]
+        broadcast: bool,
+    },
+
+    /// Fund an option whose creation transaction was broadcast but whose funding never went through
+    ResumeFunding {
+        /// Txid of the broadcast creation transaction
+        creation_txid: Txid,
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
+        /// Publish only to these relays (comma-separated), instead of every configured relay
+        #[arg(long, value_delimiter = ',')]
+        relays: Vec<String>,
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
@@ -197,6 +556,9 @@ pub enum OptionCommand {
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
+        /// Unconfirmed transaction this one spends from; the fee is raised to confirm both
+        #[arg(long, conflicts_with = "fee")]
+        cpfp_parent: Option<Txid>,
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
@@ -213,6 +575,9 @@ pub enum OptionCommand {
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
+        /// Proceed even if the fee exceeds `safety.max_fee_ratio` of the value claimed
+        #[arg(long)]
+        force: bool,
     },
 
     /// Claim settlement after options were exercised (use Grantor Token to get settlement asset)
@@ -226,6 +591,9 @@ pub enum OptionCommand {
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
+        /// Proceed even if the fee exceeds `safety.max_fee_ratio` of the value claimed
+        #[arg(long)]
+        force: bool,
     },
 
     /// Cancel an option (requires both Option + Grantor tokens)
@@ -265,25 +633,82 @@ pub enum OptionOfferCommand {
         /// Total settlement amount expected (used to calculate `collateral_per_contract`)
         #[arg(long)]
         settlement_amount: Option<u64>,
-        /// Expiry time as Unix timestamp or duration (e.g., +30d)
-        #[arg(long)]
-        expiry: String,
+        /// Expiry time as Unix timestamp or duration (e.g., +30d), defaults to `defaults.offer_expiry` from config
+        #[arg(long)]
+        expiry: Option<String>,
+        /// Expired offer event ID whose collateral + premium are reclaimed and rolled into this offer
+        #[arg(
+            long,
+            conflicts_with_all = ["collateral_asset", "collateral_amount", "premium_asset", "premium_amount"]
+        )]
+        collateral_from_contract: Option<String>,
+        /// Local label to group this offer with others (e.g., "mm-batch-1")
+        #[arg(long)]
+        label: Option<String>,
+        /// Round premium and settlement to a multiple of the collateral amount instead of rejecting them
+        #[arg(long, value_enum, conflicts_with = "collateral_from_contract")]
+        round: Option<AmountRounding>,
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
+        /// Publish only to these relays (comma-separated), instead of every configured relay
+        #[arg(long, value_delimiter = ',')]
+        relays: Vec<String>,
         /// Broadcast transaction and publish to NOSTR
         #[arg(long)]
         broadcast: bool,
     },
 
+    /// Create many option offers from a JSON file, publishing them over one relay connection
+    ///
+    /// The file holds a list of offers, e.g.
+    /// `[{"collateral_asset": "<id>", "collateral_amount": 1000, "premium_asset": "<id>", "premium_amount": 5000,
+    /// "settlement_asset": "<id>", "settlement_amount": 2000000, "expiry": "+7d", "label": "grid-1"}]`.
+    /// `expiry` and `label` are optional; premium and settlement must be multiples of the collateral amount.
+    CreateBatch {
+        /// Path to the JSON file with the offer specs
+        file: PathBuf,
+        /// Label for offers that do not set their own
+        #[arg(long)]
+        label: Option<String>,
+        /// Fee per offer in satoshis (auto-estimated if not specified)
+        #[arg(long)]
+        fee: Option<u64>,
+        /// Publish only to these relays (comma-separated), instead of every configured relay
+        #[arg(long, value_delimiter = ',')]
+        relays: Vec<String>,
+        /// Fund, publish and record the offers (otherwise only validate the file and show the plan)
+        #[arg(long)]
+        broadcast: bool,
+    },
+
+    /// List active option offers known locally
+    List {
+        /// Only list offers stored with this label
+        #[arg(long)]
+        label: Option<String>,
+        /// Maximum number of offers to show (defaults to `ui.max_list_rows` from config, 0 for all)
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
     /// Take an option offer (pay settlement to receive collateral + premium)
     Take {
         /// Offer event ID from NOSTR (interactive selection if not provided)
         #[arg(long)]
         offer_event: Option<String>,
+        /// Only consider offers stored with this label
+        #[arg(long)]
+        label: Option<String>,
+        /// Maximum number of offers to show (defaults to `ui.max_list_rows` from config, 0 for all)
+        #[arg(long)]
+        limit: Option<usize>,
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
+        /// Unconfirmed transaction this one spends from; the fee is raised to confirm both
+        #[arg(long, conflicts_with = "fee")]
+        cpfp_parent: Option<Txid>,
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
@@ -294,12 +719,21 @@ pub enum OptionOfferCommand {
         /// Offer event ID from NOSTR (interactive selection if not provided)
         #[arg(long)]
         offer_event: Option<String>,
+        /// Cancel every expired offer stored with this label (or scope `--offer-event` to it)
+        #[arg(long)]
+        label: Option<String>,
+        /// Maximum number of offers to show (defaults to `ui.max_list_rows` from config, 0 for all)
+        #[arg(long)]
+        limit: Option<usize>,
         /// Fee amount in satoshis (auto-estimated if not specified)
         #[arg(long)]
         fee: Option<u64>,
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
+        /// Proceed even if the fee exceeds `safety.max_fee_ratio` of the value claimed
+        #[arg(long)]
+        force: bool,
     },
 
     /// Withdraw settlement after offer was taken (claim your payment)
@@ -313,6 +747,51 @@ pub enum OptionOfferCommand {
         /// Broadcast transaction
         #[arg(long)]
         broadcast: bool,
+        /// Proceed even if the fee exceeds `safety.max_fee_ratio` of the value claimed
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Sanity-check an offer's strike and premium against a spot price before taking it
+    Analyze {
+        /// Offer event ID from NOSTR (must be synced locally)
+        offer_event: String,
+        /// Current price of the collateral, in settlement asset units per collateral unit
+        #[arg(long)]
+        spot: f64,
+    },
+
+    /// Estimate how many option offers of a given size the wallet can fund
+    Capacity {
+        /// Collateral asset ID (defaults to native LBTC if not specified)
+        #[arg(long)]
+        collateral_asset: Option<AssetId>,
+        /// Collateral amount deposited per offer
+        #[arg(long)]
+        collateral_amount: u64,
+        /// Premium asset ID (defaults to native LBTC if not specified)
+        #[arg(long)]
+        premium_asset: Option<AssetId>,
+        /// Premium amount deposited per offer
+        #[arg(long)]
+        premium_amount: u64,
+        /// Fee per offer in satoshis (estimated from the configured fee rate if not specified)
+        #[arg(long)]
+        fee: Option<u64>,
+    },
+
+    /// [dev] Take one of your own offers, wait for confirmation, then withdraw the settlement
+    #[cfg(feature = "dev-commands")]
+    Roundtrip {
+        /// Offer event ID from NOSTR (must be an offer created by this wallet)
+        #[arg(long)]
+        offer_event: String,
+        /// Fee amount in satoshis for each transaction (auto-estimated if not specified)
+        #[arg(long)]
+        fee: Option<u64>,
+        /// Seconds between confirmation checks
+        #[arg(long, default_value_t = 30)]
+        poll_interval: u64,
     },
 }
 
@@ -333,4 +812,7 @@ pub enum SyncCommand {
 
     /// Only sync action history for existing contracts from NOSTR (does not populate UTXOs)
     History,
+
+    /// Compare local contract history with the actions relays report, changing nothing
+    Check,
 }
@@ -0,0 +1,655 @@
+use std::collections::HashMap;
+
+use coin_store::{UtxoFilter, UtxoStore, compile_program, decode_arguments};
+use contracts::option_offer::{
+    OPTION_OFFER_SOURCE, OptionOfferArguments, get_option_offer_address, get_option_offer_program,
+};
+use contracts::options::{OPTION_SOURCE, OptionsArguments, get_options_address, get_options_program};
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use nostr::EventId;
+use options_relay::{
+    OPTION_CREATED, OPTION_OFFER_CREATED, OptionCreatedEvent, OptionOfferCreatedEvent, ParseError, advertised_expiry,
+};
+use simplicityhl::Arguments;
+use simplicityhl::elements::hashes::Hash;
+use simplicityhl::elements::pset::PartiallySignedTransaction;
+use simplicityhl::elements::pset::serialize::Serialize;
+use simplicityhl::elements::{Address, OutPoint, Transaction, TxOut, Txid};
+use simplicityhl::simplicity::Cmr;
+use simplicityhl::simplicity::hex::DisplayHex;
+use simplicityhl_core::LIQUID_TESTNET_BITCOIN_ASSET;
+
+use crate::cli::commands::{ContractSourceName, ReclaimBranch};
+use crate::cli::inputs::wallet_fee_input;
+use crate::cli::interactive::{SystemClock, extract_entries_from_result, format_relative_time, is_offer_expired};
+use crate::cli::option_offer::{fetch_offer_utxo, finalize_option_offer_expiry, finalize_option_offer_withdraw};
+use crate::cli::{Cli, ContractCommand};
+use crate::config::Config;
+use crate::error::Error;
+use crate::explorer::{fetch_outspends, fetch_transaction, run_blocking};
+use crate::fee::{PLACEHOLDER_FEE, estimate_fee_signed, lbtc_fee_shortfall};
+use crate::sync::{get_contract_metadata, relink_contract_event};
+
+impl ContractSourceName {
+    const ALL: [Self; 2] = [Self::Option, Self::OptionOffer];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Option => "option",
+            Self::OptionOffer => "option-offer",
+        }
+    }
+
+    const fn source(self) -> &'static str {
+        match self {
+            Self::Option => OPTION_SOURCE,
+            Self::OptionOffer => OPTION_OFFER_SOURCE,
+        }
+    }
+
+    /// Well-formed but meaningless arguments, enough to type-check every parameter of the source.
+    fn placeholder_arguments(self) -> Arguments {
+        let asset = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let outpoint = OutPoint::new(Txid::from_byte_array([0; 32]), 0);
+
+        match self {
+            Self::Option => {
+                OptionsArguments::new(0, 0, 1, 1, asset, asset, [0; 32], (outpoint, false), (outpoint, false))
+                    .build_option_arguments()
+            }
+            Self::OptionOffer => OptionOfferArguments::new(asset, asset, asset, 1, 0, 0, [2; 32]).build_arguments(),
+        }
+    }
+}
+
+/// What an announcement claims about its contract, rebuilt from the event's arguments.
+struct AddressClaim {
+    name: ContractSourceName,
+    arguments: Arguments,
+    sdk_cmr: Cmr,
+    address: Address,
+    claimed_address: Address,
+    utxo: OutPoint,
+    /// Expiry in the arguments, so enforced by the program.
+    expiry_time: u32,
+    /// Expiry in the event's `expiry` tag, which listings show and relays filter on.
+    advertised_expiry: Option<u64>,
+}
+
+/// Turn a failed taproot check while parsing an announcement into a loud mismatch error.
+fn parse_announcement_error(e: ParseError) -> Error {
+    match e {
+        ParseError::TaprootVerification(e) => Error::Config(format!(
+            "ADDRESS MISMATCH: the announced taproot address does not belong to the claimed program ({e})"
+        )),
+        e => Error::EventParse(e),
+    }
+}
+
+impl AddressClaim {
+    fn from_event(event: &nostr::Event, config: &Config) -> Result<Self, Error> {
+        let params = config.address_params();
+
+        if event.kind == OPTION_CREATED {
+            let parsed = OptionCreatedEvent::from_event(event, params).map_err(parse_announcement_error)?;
+            let args = &parsed.options_args;
+            let pubkey = parsed.taproot_pubkey_gen.get_x_only_pubkey();
+
+            Ok(Self {
+                name: ContractSourceName::Option,
+                arguments: args.build_option_arguments(),
+                sdk_cmr: get_options_program(args)?.commit().cmr(),
+                address: get_options_address(&pubkey, args, params)?,
+                claimed_address: parsed.taproot_pubkey_gen.address,
+                utxo: parsed.utxo,
+                expiry_time: args.expiry_time(),
+                advertised_expiry: advertised_expiry(event),
+            })
+        } else if event.kind == OPTION_OFFER_CREATED {
+            let parsed = OptionOfferCreatedEvent::from_event(event, params).map_err(parse_announcement_error)?;
+            let args = &parsed.option_offer_args;
+            let pubkey = parsed.taproot_pubkey_gen.get_x_only_pubkey();
+
+            Ok(Self {
+                name: ContractSourceName::OptionOffer,
+                arguments: args.build_arguments(),
+                sdk_cmr: get_option_offer_program(args)?.commit().cmr(),
+                address: get_option_offer_address(&pubkey, args, params)?,
+                claimed_address: parsed.taproot_pubkey_gen.address,
+                utxo: parsed.utxo,
+                expiry_time: args.expiry_time(),
+                advertised_expiry: advertised_expiry(event),
+            })
+        } else {
+            Err(Error::Config(format!(
+                "Event {} is of kind {}, not an option or option offer announcement",
+                event.id, event.kind
+            )))
+        }
+    }
+}
+
+/// Check that the expiry an announcement advertises is the one its program enforces.
+/// Refuse to reclaim an output that the contract named by `--tpg` does not lock.
+fn check_locked_to_contract(outpoint: OutPoint, txout: &TxOut, contract_address: &Address) -> Result<(), Error> {
+    if txout.script_pubkey != contract_address.script_pubkey() {
+        return Err(Error::Config(format!(
+            "Output {outpoint} is not locked to contract {contract_address}"
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_advertised_expiry(enforced: u32, advertised: Option<u64>) -> Result<(), Error> {
+    match advertised {
+        Some(advertised) if advertised != u64::from(enforced) => Err(Error::Config(format!(
+            "EXPIRY MISMATCH: the event advertises expiry {advertised}, but the program enforces {enforced}"
+        ))),
+        Some(_) => Ok(()),
+        None => Err(Error::Config(format!(
+            "EXPIRY MISMATCH: the event has no expiry tag, but the program enforces {enforced}"
+        ))),
+    }
+}
+
+impl Cli {
+    pub(crate) async fn run_contract(&self, config: &Config, command: &ContractCommand) -> Result<(), Error> {
+        match command {
+            ContractCommand::CheckSource { name } => {
+                let names = name.map_or(ContractSourceName::ALL.to_vec(), |n| vec![n]);
+                let mut failed = 0usize;
+
+                for name in names {
+                    match compile_program(name.source(), name.placeholder_arguments()) {
+                        Ok(program) => println!("{}: OK (CMR {})", name.label(), program.commit().cmr()),
+                        Err(e) => {
+                            failed += 1;
+                            println!("{}: FAILED", name.label());
+                            println!("{e}");
+                        }
+                    }
+                }
+
+                if failed > 0 {
+                    return Err(Error::Config(format!(
+                        "{failed} contract source(s) failed to compile with the installed simplicityhl"
+                    )));
+                }
+
+                Ok(())
+            }
+            ContractCommand::VerifyAddress { event_id } => self.verify_contract_address(config, event_id).await,
+            ContractCommand::Reclaim {
+                tpg,
+                outpoint,
+                branch,
+                arguments,
+                event,
+                fee,
+                broadcast,
+            } => {
+                let args = self
+                    .recover_option_offer_arguments(config, arguments.as_deref(), event.as_deref())
+                    .await?;
+                self.reclaim_option_offer_output(config, tpg, *outpoint, *branch, &args, *fee, *broadcast)
+                    .await
+            }
+        }
+    }
+
+    /// Relink a local contract to the announcement it was republished under.
+    ///
+    /// The event must announce the same contract, with a funding output the store already
+    /// holds for it, and come from the author of the event it replaces.
+    pub(crate) async fn run_relink(&self, config: &Config, tpg: &str, event_id: &str) -> Result<(), Error> {
+        let event_id =
+            EventId::from_hex(event_id).map_err(|e| Error::Config(format!("Invalid event ID '{event_id}': {e}")))?;
+
+        let client = self.get_read_only_client(config).await?;
+        let event = client.fetch_event(event_id).await;
+        client.disconnect().await;
+        let event =
+            event?.ok_or_else(|| Error::Config(format!("Event {event_id} not found on the configured relays")))?;
+
+        let params = config.address_params();
+        let (taproot_pubkey_gen, utxo) = if event.kind == OPTION_CREATED {
+            let parsed = OptionCreatedEvent::from_event(&event, params).map_err(parse_announcement_error)?;
+            (parsed.taproot_pubkey_gen, parsed.utxo)
+        } else if event.kind == OPTION_OFFER_CREATED {
+            let parsed = OptionOfferCreatedEvent::from_event(&event, params).map_err(parse_announcement_error)?;
+            (parsed.taproot_pubkey_gen, parsed.utxo)
+        } else {
+            return Err(Error::Config(format!(
+                "Event {event_id} is of kind {}, not an option or option offer announcement",
+                event.kind
+            )));
+        };
+
+        if taproot_pubkey_gen.to_string() != tpg {
+            return Err(Error::Config(format!(
+                "Event {event_id} announces contract {taproot_pubkey_gen}, not {tpg}"
+            )));
+        }
+
+        let wallet = self.get_wallet(config).await?;
+        match <_ as UtxoStore>::get_contract_by_outpoint(wallet.store(), utxo).await? {
+            Some(owner) if owner == tpg => {}
+            Some(owner) => {
+                return Err(Error::Config(format!(
+                    "Event {event_id} references {utxo}, which belongs to contract {owner}"
+                )));
+            }
+            None => {
+                return Err(Error::Config(format!(
+                    "Event {event_id} references {utxo}, which is not a stored output of contract {tpg}. \
+                     Run `sync full` if the contract was funded since the last sync."
+                )));
+            }
+        }
+
+        if let Some(metadata) = get_contract_metadata(wallet.store(), &taproot_pubkey_gen).await?
+            && let Some(author) = metadata.nostr_author
+            && author != event.pubkey.to_hex()
+        {
+            return Err(Error::Config(format!(
+                "Event {event_id} is signed by {}, but the contract was announced by {author}",
+                event.pubkey.to_hex()
+            )));
+        }
+
+        let previous = relink_contract_event(wallet.store(), &taproot_pubkey_gen, &event_id, &event.pubkey).await?;
+        println!(
+            "Relinked {tpg}: {} -> {}",
+            previous.as_deref().unwrap_or("(none)"),
+            event_id.to_hex()
+        );
+
+        Ok(())
+    }
+
+    /// Option offer arguments from `--arguments` hex, or from the offer's announcement on the relays.
+    async fn recover_option_offer_arguments(
+        &self,
+        config: &Config,
+        arguments: Option<&str>,
+        event_id: Option<&str>,
+    ) -> Result<OptionOfferArguments, Error> {
+        if let Some(arguments) = arguments {
+            let arguments = decode_arguments(&hex::decode(arguments)?)?;
+            return OptionOfferArguments::from_arguments(&arguments)
+                .ok()
+                .ok_or_else(|| Error::Config("Arguments do not describe an option offer".to_string()));
+        }
+
+        let event_id = event_id.ok_or_else(|| {
+            Error::Config("Supply --arguments or --event so the contract program can be compiled".to_string())
+        })?;
+        let event_id =
+            EventId::from_hex(event_id).map_err(|e| Error::Config(format!("Invalid event ID '{event_id}': {e}")))?;
+
+        let client = self.get_read_only_client(config).await?;
+        let event = client.fetch_event(event_id).await;
+        client.disconnect().await;
+        let event =
+            event?.ok_or_else(|| Error::Config(format!("Event {event_id} not found on the configured relays")))?;
+
+        if event.kind != OPTION_OFFER_CREATED {
+            return Err(Error::Config(format!(
+                "Event {event_id} is of kind {}, not an option offer announcement",
+                event.kind
+            )));
+        }
+
+        Ok(OptionOfferCreatedEvent::from_event(&event, config.address_params())?.option_offer_args)
+    }
+
+    /// Spend an option offer output through `branch` without looking the contract up in the store.
+    ///
+    /// Everything the local scan would provide is checked on-chain instead: the output must be
+    /// unspent and locked to the address `tpg` derives from the arguments.
+    #[allow(clippy::too_many_arguments)]
+    async fn reclaim_option_offer_output(
+        &self,
+        config: &Config,
+        tpg: &str,
+        outpoint: OutPoint,
+        branch: ReclaimBranch,
+        args: &OptionOfferArguments,
+        fee: Option<u64>,
+        broadcast: bool,
+    ) -> Result<(), Error> {
+        let params = config.address_params();
+        let taproot_pubkey_gen = TaprootPubkeyGen::build_from_str(tpg, args, params, &get_option_offer_address)?;
+
+        let fetch_contract_output = async |outpoint: OutPoint| {
+            let txout = fetch_offer_utxo(outpoint).await?;
+            check_locked_to_contract(outpoint, &txout, &taproot_pubkey_gen.address)?;
+            Ok(txout)
+        };
+
+        let mut contract_inputs = vec![(outpoint, fetch_contract_output(outpoint).await?)];
+        if branch == ReclaimBranch::Expiry {
+            if !is_offer_expired(&SystemClock, args.expiry_time()) {
+                return Err(Error::Config(format!(
+                    "Offer expires {}; the expiry branch cannot be spent yet",
+                    format_relative_time(i64::from(args.expiry_time()))
+                )));
+            }
+
+            let premium_vout = outpoint
+                .vout
+                .checked_add(1)
+                .ok_or_else(|| Error::Config(format!("Output {outpoint} has no premium output after it")))?;
+            let premium_outpoint = OutPoint::new(outpoint.txid, premium_vout);
+            contract_inputs.push((premium_outpoint, fetch_contract_output(premium_outpoint).await?));
+        }
+
+        for (outpoint, txout) in &contract_inputs {
+            println!(
+                "Contract input: {outpoint} ({} of {})",
+                txout
+                    .value
+                    .explicit()
+                    .map_or_else(|| "confidential".to_string(), |v| v.to_string()),
+                txout
+                    .asset
+                    .explicit()
+                    .map_or_else(|| "confidential".to_string(), |a| a.to_string()),
+            );
+        }
+
+        let wallet = self.get_wallet(config).await?;
+        let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+
+        let fee_filter = UtxoFilter::new()
+            .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+            .script_pubkey(script_pubkey.clone())
+            .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
+        let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
+        let Some(fee_utxo) = extract_entries_from_result(&results[0]).into_iter().next() else {
+            return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, fee, config.get_fee_rate()).await);
+        };
+        let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
+
+        let utxos: Vec<TxOut> = contract_inputs
+            .iter()
+            .chain(std::iter::once(&fee_input))
+            .map(|(_, txout)| txout.clone())
+            .collect();
+
+        let build = |f: u64| -> Result<PartiallySignedTransaction, Error> {
+            Ok(match branch {
+                ReclaimBranch::Expiry => contracts::sdk::build_option_offer_expiry(
+                    contract_inputs[0].clone(),
+                    contract_inputs[1].clone(),
+                    fee_input.clone(),
+                    f,
+                    args,
+                    script_pubkey.clone(),
+                )?,
+                ReclaimBranch::Withdraw => contracts::sdk::build_option_offer_withdraw(
+                    contract_inputs[0].clone(),
+                    fee_input.clone(),
+                    f,
+                    args,
+                    script_pubkey.clone(),
+                )?,
+            })
+        };
+        let finalize = |tx: Transaction, utxos: &[TxOut], verify: bool| match branch {
+            ReclaimBranch::Expiry => finalize_option_offer_expiry(
+                tx,
+                utxos,
+                args,
+                &taproot_pubkey_gen,
+                &wallet,
+                params,
+                verify,
+                self.tracker_log,
+            ),
+            ReclaimBranch::Withdraw => finalize_option_offer_withdraw(
+                tx,
+                utxos,
+                args,
+                &taproot_pubkey_gen,
+                &wallet,
+                params,
+                verify,
+                self.tracker_log,
+            ),
+        };
+
+        let actual_fee = estimate_fee_signed(
+            fee.as_ref(),
+            config.get_fee_rate(),
+            |f| Ok((build(f)?, utxos.clone())),
+            |tx, utxos| finalize(tx, utxos, false),
+        )?;
+        println!("Fee: {actual_fee} sats");
+
+        let tx = finalize(build(actual_fee)?.extract_tx()?, &utxos, config.safety.verify_finalized)?;
+
+        if broadcast {
+            cli_helper::explorer::broadcast_tx(&tx).await?;
+            println!("Broadcasted: {}", tx.txid());
+
+            wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+        } else {
+            println!("{}", tx.serialize().to_lower_hex_string());
+        }
+
+        Ok(())
+    }
+
+    /// Recompile the announced contract and check every link from source to the funded output.
+    async fn verify_contract_address(&self, config: &Config, event_id: &str) -> Result<(), Error> {
+        let event_id =
+            EventId::from_hex(event_id).map_err(|e| Error::Config(format!("Invalid event ID '{event_id}': {e}")))?;
+
+        let client = self.get_read_only_client(config).await?;
+        let event = client.fetch_event(event_id).await;
+        client.disconnect().await;
+        let event =
+            event?.ok_or_else(|| Error::Config(format!("Event {event_id} not found on the configured relays")))?;
+
+        let claim = AddressClaim::from_event(&event, config)?;
+        println!("Contract: {}", claim.name.label());
+
+        let cmr = compile_program(claim.name.source(), claim.arguments)?.commit().cmr();
+        println!("Source CMR:  {cmr}");
+        if cmr != claim.sdk_cmr {
+            return Err(Error::Config(format!(
+                "CMR MISMATCH: bundled source compiles to {cmr}, but the contracts SDK builds {}",
+                claim.sdk_cmr
+            )));
+        }
+
+        println!("Address:     {}", claim.address);
+        if claim.address != claim.claimed_address {
+            return Err(Error::Config(format!(
+                "ADDRESS MISMATCH: program hashes to {}, but the event claims {}",
+                claim.address, claim.claimed_address
+            )));
+        }
+
+        // The arguments were compiled into the CMR checked above, so their expiry is the enforced one
+        println!(
+            "Expiry:      {} ({})",
+            claim.expiry_time,
+            format_relative_time(i64::from(claim.expiry_time))
+        );
+        check_advertised_expiry(claim.expiry_time, claim.advertised_expiry)?;
+
+        let utxo = claim.utxo;
+        let tx = run_blocking(move || fetch_transaction(utxo.txid)).await?;
+        let output = tx.output.get(claim.utxo.vout as usize).ok_or_else(|| {
+            Error::Config(format!(
+                "Funding transaction {} has no output {}",
+                claim.utxo.txid, claim.utxo.vout
+            ))
+        })?;
+        if output.script_pubkey != claim.address.script_pubkey() {
+            return Err(Error::Config(format!(
+                "ADDRESS MISMATCH: on-chain output {} is not locked to {}",
+                claim.utxo, claim.address
+            )));
+        }
+
+        println!("On-chain:    {} is locked to the claimed program", claim.utxo);
+        if let Some(spend) = run_blocking(move || fetch_outspends(utxo.txid))
+            .await?
+            .get(claim.utxo.vout as usize)
+            .filter(|status| status.spent)
+        {
+            println!(
+                "Note: the output has since been spent by {}",
+                spend.txid.as_deref().unwrap_or("an unknown transaction")
+            );
+        }
+        println!("OK");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use coin_store::Store;
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::{AddressParams, Script, TxOutWitness};
+    use simplicityhl_core::derive_public_blinder_key;
+
+    const PARAMS: &AddressParams = &AddressParams::LIQUID_TESTNET;
+
+    fn lbtc_input(seed: u8, value: u64) -> (OutPoint, TxOut) {
+        let txout = TxOut {
+            asset: Asset::Explicit(*LIQUID_TESTNET_BITCOIN_ASSET),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: Script::from(vec![0x51]),
+            witness: TxOutWitness::default(),
+        };
+        (OutPoint::new(Txid::from_byte_array([seed; 32]), 0), txout)
+    }
+
+    fn assert_same_tpg(decoded: &TaprootPubkeyGen, original: &TaprootPubkeyGen) {
+        assert_eq!(decoded.to_string(), original.to_string());
+        assert_eq!(decoded.seed, original.seed);
+        assert_eq!(decoded.pubkey, original.pubkey);
+        assert_eq!(decoded.address, original.address);
+    }
+
+    #[test]
+    fn test_advertised_expiry_must_match_program() {
+        assert!(check_advertised_expiry(1_706_745_600, Some(1_706_745_600)).is_ok());
+        assert!(check_advertised_expiry(1_706_745_600, Some(1_706_745_601)).is_err());
+        assert!(check_advertised_expiry(1_706_745_600, None).is_err());
+    }
+
+    #[test]
+    fn test_reclaim_rejects_output_not_locked_to_tpg() {
+        let asset = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let args = OptionOfferArguments::new(asset, asset, asset, 100, 1, 1_000, [2; 32]);
+        let tpg = TaprootPubkeyGen::from(&args, PARAMS, &get_option_offer_address).unwrap();
+
+        let (outpoint, mut txout) = lbtc_input(1, 1000);
+        assert!(check_locked_to_contract(outpoint, &txout, &tpg.address).is_err());
+
+        txout.script_pubkey = tpg.address.script_pubkey();
+        assert!(check_locked_to_contract(outpoint, &txout, &tpg.address).is_ok());
+    }
+
+    /// Every stored contract is keyed by `TaprootPubkeyGen::to_string()` and rebuilt with `build_from_str`.
+    /// This pins that round trip for each contract type, through the store, so a format change in the
+    /// contracts dependency fails here instead of silently orphaning stored contracts.
+    #[tokio::test]
+    async fn test_taproot_pubkey_gen_storage_roundtrip_for_all_contract_types() {
+        let path = "/tmp/test_cli_tpg_roundtrip.db";
+        let _ = std::fs::remove_file(path);
+        let store = Store::create(path).await.unwrap();
+
+        let asset = *LIQUID_TESTNET_BITCOIN_ASSET;
+
+        let (first_fee, second_fee) = (lbtc_input(1, 5_000), lbtc_input(2, 5_000));
+        let option_args = OptionsArguments::new(
+            1_704_067_200,
+            1_706_745_600,
+            10,
+            100,
+            asset,
+            asset,
+            [3; 32],
+            (first_fee.0, false),
+            (second_fee.0, false),
+        );
+        let (_, option_tpg) = contracts::sdk::build_option_creation(
+            &derive_public_blinder_key().public_key(),
+            first_fee,
+            second_fee,
+            &option_args,
+            [3; 32],
+            500,
+            PARAMS,
+        )
+        .unwrap();
+
+        let user_pubkey = derive_public_blinder_key().x_only_public_key().0.serialize();
+        let offer_args = OptionOfferArguments::new(asset, asset, asset, 100, 1, 1_706_745_600, user_pubkey);
+        let (_, offer_tpg) = contracts::sdk::build_option_offer_deposit(
+            lbtc_input(4, 10_000),
+            lbtc_input(5, 10_000),
+            lbtc_input(6, 5_000),
+            1_000,
+            500,
+            &offer_args,
+            PARAMS,
+        )
+        .unwrap();
+
+        store
+            .add_contract(
+                OPTION_SOURCE,
+                option_args.build_option_arguments(),
+                option_tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        store
+            .add_contract(
+                OPTION_OFFER_SOURCE,
+                offer_args.build_arguments(),
+                offer_tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let stored = store.list_contracts_by_source(OPTION_SOURCE).await.unwrap();
+        let decoded = TaprootPubkeyGen::build_from_str(
+            &stored[0].1,
+            &option_args,
+            PARAMS,
+            &contracts::options::get_options_address,
+        )
+        .unwrap();
+        assert_same_tpg(&decoded, &option_tpg);
+
+        let stored = store.list_contracts_by_source(OPTION_OFFER_SOURCE).await.unwrap();
+        let decoded = TaprootPubkeyGen::build_from_str(
+            &stored[0].1,
+            &offer_args,
+            PARAMS,
+            &contracts::option_offer::get_option_offer_address,
+        )
+        .unwrap();
+        assert_same_tpg(&decoded, &offer_tpg);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,854 @@
+use crate::cli::interactive::{current_timestamp, format_settlement_asset, get_grantor_tokens_from_wallet};
+use crate::cli::price_history::taken_collateral;
+use crate::cli::tables::{BalanceDisplay, display_balance_table};
+use crate::cli::{Cli, HelperCommand, HelperTxCommand};
+use crate::config::Config;
+use crate::error::Error;
+use crate::fee::WITNESS_SCALE_FACTOR;
+use crate::metadata::{ContractMetadata, HistoryEntry};
+use crate::signing::sign_p2pk_inputs;
+use crate::sync::decode_contract_arguments;
+use crate::wallet::Wallet;
+#[cfg(not(feature = "sqlcipher"))]
+use crate::wallet::encryption_unsupported;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::str::FromStr;
+
+use coin_store::{Store, UtxoStore};
+use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments, get_option_offer_address};
+use contracts::options::OPTION_SOURCE;
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use options_relay::ActionType;
+use signer::{Signer, SigningBackend};
+use simplicityhl::elements::confidential;
+use simplicityhl::elements::pset::serialize::Serialize;
+use simplicityhl::elements::secp256k1_zkp::schnorr::Signature;
+use simplicityhl::elements::secp256k1_zkp::{self as secp256k1, Keypair};
+use simplicityhl::elements::{AddressParams, AssetId, OutPoint, Script, Transaction, Txid};
+use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
+use simplicityhl::simplicity::hex::DisplayHex;
+use simplicityhl_core::{LIQUID_TESTNET_BITCOIN_ASSET, get_p2pk_address};
+
+/// Manifest keys written by `helper backup`, next to the schema version the store records itself.
+const BACKUP_NETWORK_KEY: &str = "network";
+const BACKUP_APP_VERSION_KEY: &str = "app_version";
+const BACKUP_CREATED_AT_KEY: &str = "created_at";
+
+/// Offer terms needed to value a take of it.
+struct OfferTerms {
+    script: Script,
+    collateral_asset: AssetId,
+    premium_asset: AssetId,
+    premium_per_collateral: u64,
+}
+
+/// Totals over the wallet's own contract actions.
+#[derive(Debug, Default)]
+struct TradingStats {
+    actions: BTreeMap<String, usize>,
+    premium_earned: BTreeMap<AssetId, u64>,
+    fees_paid: u64,
+    volume: BTreeMap<AssetId, u64>,
+    counted: HashSet<Txid>,
+    /// History entries skipped because their transaction is not in the local store.
+    unknown: usize,
+}
+
+impl TradingStats {
+    fn count(&self, action: ActionType) -> usize {
+        self.actions.get(action.as_str()).copied().unwrap_or(0)
+    }
+
+    /// Count `action`, and add the fee and volume of `tx` the first time it is seen.
+    ///
+    /// Volume is the explicit value sent to anyone but the wallet itself: contract deposits and
+    /// counterparty payments, excluding change and the fee.
+    fn record(&mut self, action: &str, tx: &Transaction, wallet_script: &Script) {
+        *self.actions.entry(action.to_string()).or_default() += 1;
+
+        if !self.counted.insert(tx.txid()) {
+            return;
+        }
+
+        for out in &tx.output {
+            let (Some(asset), Some(value)) = (out.asset.explicit(), out.value.explicit()) else {
+                continue;
+            };
+            if out.is_fee() {
+                self.fees_paid += value;
+            } else if out.script_pubkey != *wallet_script {
+                *self.volume.entry(asset).or_default() += value;
+            }
+        }
+    }
+
+    fn record_premium(&mut self, asset: AssetId, amount: u64) {
+        *self.premium_earned.entry(asset).or_default() += amount;
+    }
+}
+
+impl Cli {
+    pub(crate) async fn run_helper(&self, config: Config, command: &HelperCommand) -> Result<(), Error> {
+        match command {
+            HelperCommand::Stats { since } => self.run_stats(config, *since).await,
+            HelperCommand::GenSeed { force } => Self::run_gen_seed(*force),
+            HelperCommand::Tx {
+                command: HelperTxCommand::Fee { txid },
+            } => self.run_tx_fee(config, *txid).await,
+            HelperCommand::BumpFee {
+                txid,
+                new_fee,
+                broadcast,
+            } => self.run_bump_fee(config, *txid, *new_fee, *broadcast).await,
+            HelperCommand::ProveControl { message } => self.run_prove_control(&config, message),
+            HelperCommand::CheckControl { bundle } => Self::run_check_control(&config, bundle),
+            HelperCommand::Backup { archive } => self.run_backup(config, archive).await,
+            HelperCommand::Restore { archive } => self.run_restore(config, archive).await,
+            HelperCommand::Balance { detailed } => self.run_balance(config, *detailed).await,
+            HelperCommand::Prune { older_than_height } => {
+                let wallet = self.get_wallet(&config).await?;
+
+                let removed = wallet.store().prune_spent(*older_than_height).await?;
+                wallet.store().vacuum().await?;
+
+                println!("Pruned {removed} spent UTXOs");
+
+                Ok(())
+            }
+        }
+    }
+
+    fn run_gen_seed(force: bool) -> Result<(), Error> {
+        if !std::io::stdout().is_terminal() && !force {
+            return Err(Error::Config(
+                "Refusing to write a plaintext seed to a non-terminal output. Re-run with --force to allow it."
+                    .to_string(),
+            ));
+        }
+
+        let seed = generate_seed()?;
+
+        eprintln!("WARNING: this seed controls all wallet funds. It is shown only once and is not stored.");
+        eprintln!("Write it down offline and never share it.");
+        println!("{}", hex::encode(seed));
+        eprintln!();
+        eprintln!("Initialize the wallet with: SIMPLICITY_DEX_SEED=<seed> simplicity-dex wallet init");
+
+        Ok(())
+    }
+
+    async fn run_tx_fee(&self, config: Config, txid: Txid) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+
+        let tx = <_ as UtxoStore>::get_transaction(wallet.store(), txid)
+            .await?
+            .ok_or_else(|| Error::Config(format!("Transaction {txid} is not recorded in the wallet")))?;
+
+        let declared_fee = tx.all_fees().get(&*LIQUID_TESTNET_BITCOIN_ASSET).copied().unwrap_or(0);
+
+        let paid_fee = native_balance_delta(wallet.store(), &tx).await?;
+        let vsize = tx.weight().div_ceil(WITNESS_SCALE_FACTOR);
+
+        match paid_fee {
+            Some(fee) => {
+                #[allow(clippy::cast_precision_loss)]
+                let rate = fee as f64 * 1000.0 / vsize as f64;
+
+                println!("Transaction: {txid}");
+                println!("  Fee paid: {fee} sats");
+                println!("  Weight: {} WU ({vsize} vB)", tx.weight());
+                println!("  Effective rate: {rate:.2} sats/kvb");
+                println!("  Configured rate: {} sats/kvb", config.get_fee_rate());
+
+                if fee != declared_fee {
+                    eprintln!("Warning: inputs minus outputs ({fee}) differs from the fee output ({declared_fee})");
+                }
+            }
+            None => {
+                #[allow(clippy::cast_precision_loss)]
+                let rate = declared_fee as f64 * 1000.0 / vsize as f64;
+
+                println!("Transaction: {txid}");
+                println!("  Fee output: {declared_fee} sats (some input/output values are unknown to the wallet)");
+                println!("  Weight: {} WU ({vsize} vB)", tx.weight());
+                println!("  Effective rate: {rate:.2} sats/kvb");
+                println!("  Configured rate: {} sats/kvb", config.get_fee_rate());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_bump_fee(&self, config: Config, txid: Txid, new_fee: u64, broadcast: bool) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let store = wallet.store();
+        let wallet_script = wallet.p2pk_address()?.script_pubkey();
+
+        let original = <_ as UtxoStore>::get_transaction(store, txid)
+            .await?
+            .ok_or_else(|| Error::Config(format!("Transaction {txid} is not recorded in the wallet")))?;
+
+        // Only wallet inputs can be re-signed here; contract inputs would need their witnesses rebuilt
+        let mut utxos = Vec::with_capacity(original.input.len());
+        for input in &original.input {
+            let prevout = input.previous_output;
+            let utxo = <_ as UtxoStore>::get_transaction(store, prevout.txid)
+                .await?
+                .and_then(|prev_tx| prev_tx.output.get(prevout.vout as usize).cloned())
+                .filter(|utxo| utxo.script_pubkey == wallet_script)
+                .ok_or_else(|| {
+                    Error::Config(format!(
+                        "Input {prevout} is not a recorded wallet coin; only wallet-only transactions can be fee-bumped"
+                    ))
+                })?;
+            utxos.push(utxo);
+        }
+
+        let replacement = replace_with_fee(&original, &wallet_script, new_fee)?;
+        let replacement = sign_p2pk_inputs(replacement, &utxos, &wallet, config.address_params(), 0)?;
+
+        println!("Replacing {txid} with fee {new_fee} sats");
+
+        if !broadcast {
+            println!("{}", replacement.serialize().to_lower_hex_string());
+            return Ok(());
+        }
+
+        cli_helper::explorer::broadcast_tx(&replacement).await?;
+        println!("Broadcasted: {}", replacement.txid());
+
+        // Blinded outputs are unchanged by the replacement, so their keys carry over by index
+        let mut blinder_keys = HashMap::new();
+        for vout in 0..original.output.len() {
+            #[allow(clippy::cast_possible_truncation)]
+            let outpoint = OutPoint::new(txid, vout as u32);
+            if let Some(key) = store.get_blinder_key(outpoint).await? {
+                let keypair = Keypair::from_seckey_slice(secp256k1::SECP256K1, &key)
+                    .map_err(|e| Error::Config(format!("Invalid stored blinding key: {e}")))?;
+                blinder_keys.insert(vout, keypair);
+            }
+        }
+
+        <_ as UtxoStore>::rollback_transaction(store, txid).await?;
+        store.insert_transaction(&replacement, blinder_keys).await?;
+
+        Ok(())
+    }
+
+    fn run_prove_control(&self, config: &Config, message: &str) -> Result<(), Error> {
+        let signer = Signer::from_seed(&self.parse_seed()?)?;
+
+        let proof = ControlProof::sign(&signer, message, config.address_params())?;
+
+        println!(
+            "{}",
+            serde_json::to_string(&proof).map_err(|e| Error::ControlProof(e.to_string()))?
+        );
+
+        Ok(())
+    }
+
+    fn run_check_control(config: &Config, bundle: &str) -> Result<(), Error> {
+        let json = if bundle.trim_start().starts_with('{') {
+            bundle.to_string()
+        } else {
+            std::fs::read_to_string(bundle)?
+        };
+        let proof: ControlProof = serde_json::from_str(&json).map_err(|e| Error::ControlProof(e.to_string()))?;
+
+        proof.verify(config.address_params())?;
+
+        println!("Valid proof: the signer controls {}", proof.address);
+        println!("Message: {}", proof.message);
+
+        Ok(())
+    }
+
+    async fn run_backup(&self, config: Config, archive: &Path) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let manifest = [
+            (BACKUP_NETWORK_KEY, config.network.name.as_str().to_string()),
+            (BACKUP_APP_VERSION_KEY, env!("CARGO_PKG_VERSION").to_string()),
+            (BACKUP_CREATED_AT_KEY, current_timestamp().to_string()),
+        ];
+        wallet.store().backup(archive, &manifest).await?;
+
+        println!("Wallet database backed up to {}", archive.display());
+        println!("Schema version: {}", Store::schema_version());
+
+        Ok(())
+    }
+
+    async fn run_restore(&self, config: Config, archive: &Path) -> Result<(), Error> {
+        let db_passphrase = self.db_passphrase(&config)?;
+
+        let manifest = match db_passphrase.as_deref() {
+            None => Store::read_backup_manifest(archive).await?,
+            #[cfg(feature = "sqlcipher")]
+            Some(passphrase) => Store::read_backup_manifest_encrypted(archive, passphrase).await?,
+            #[cfg(not(feature = "sqlcipher"))]
+            Some(_) => return Err(encryption_unsupported()),
+        };
+        let network = config.network.name.as_str();
+        match manifest.get(BACKUP_NETWORK_KEY) {
+            Some(found) if found == network => {}
+            found => {
+                return Err(Error::Config(format!(
+                    "Archive is for network {}, but the config uses {network}",
+                    found.map_or("unknown", String::as_str)
+                )));
+            }
+        }
+
+        let db_path = config.database_path();
+        std::fs::create_dir_all(&config.storage.data_dir)?;
+        match db_passphrase.as_deref() {
+            None => Store::restore(archive, &db_path).await?,
+            #[cfg(feature = "sqlcipher")]
+            Some(passphrase) => Store::restore_encrypted(archive, &db_path, passphrase).await?,
+            #[cfg(not(feature = "sqlcipher"))]
+            Some(_) => return Err(encryption_unsupported()),
+        };
+
+        println!("Wallet database restored to {}", db_path.display());
+        if let Some(version) = manifest.get(BACKUP_APP_VERSION_KEY) {
+            println!("Backup written by version {version}");
+        }
+        println!("Run `sync full` to catch up with activity since the backup was taken.");
+
+        Ok(())
+    }
+
+    async fn run_balance(&self, config: Config, detailed: bool) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let assets = self.asset_registry(&config)?;
+
+        if detailed {
+            let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+            let contract_scripts = owned_contract_scripts(&wallet, &config, &script_pubkey).await?;
+
+            let balances = wallet
+                .store()
+                .balance_detailed(&script_pubkey, &contract_scripts)
+                .await?;
+
+            if balances.is_empty() {
+                println!("No UTXOs found");
+            } else {
+                let displays: Vec<BalanceDisplay> = balances
+                    .iter()
+                    .map(|(asset, balance)| BalanceDisplay {
+                        asset: assets.ticker(asset),
+                        spendable: assets.format_amount(asset, balance.spendable),
+                        in_contracts: assets.format_amount(asset, balance.in_contracts),
+                        total: assets.format_amount(asset, balance.total()),
+                    })
+                    .collect();
+                display_balance_table(&displays);
+            }
+            println!();
+        }
+
+        let locked = wallet.locked_collateral(current_timestamp()).await?;
+        if locked.is_empty() {
+            println!("Nothing locked in your contracts");
+            return Ok(());
+        }
+
+        println!("Locked in your contracts:");
+        for ((asset, role), amount) in &locked {
+            let total = assets.format_amount(asset, amount.total());
+            if detailed {
+                println!(
+                    "  {} {}: {total} locked ({} on offer, {} reclaimable after expiry)",
+                    assets.ticker(asset),
+                    role.as_str(),
+                    assets.format_amount(asset, amount.offered),
+                    assets.format_amount(asset, amount.reclaimable)
+                );
+            } else {
+                println!("  {} {}: {total} locked", assets.ticker(asset), role.as_str());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_stats(&self, config: Config, since: Option<u64>) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let store = wallet.store();
+        let wallet_script = wallet.p2pk_address()?.script_pubkey();
+
+        let mut stats = TradingStats::default();
+
+        let option_contracts = <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, OPTION_SOURCE).await?;
+        for (_, _, metadata_bytes) in &option_contracts {
+            for entry in history_since(metadata_bytes.as_deref(), since) {
+                record_entry(store, &mut stats, &entry, &wallet_script, None).await?;
+            }
+        }
+
+        let offer_contracts =
+            <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, OPTION_OFFER_SOURCE).await?;
+        for (args_bytes, tpg_str, metadata_bytes) in &offer_contracts {
+            let terms = decode_contract_arguments(args_bytes, tpg_str)
+                .and_then(|args| OptionOfferArguments::from_arguments(&args).ok())
+                .and_then(|args| {
+                    let tpg = TaprootPubkeyGen::build_from_str(
+                        tpg_str,
+                        &args,
+                        config.address_params(),
+                        &get_option_offer_address,
+                    )
+                    .ok()?;
+                    Some(OfferTerms {
+                        script: tpg.address.script_pubkey(),
+                        collateral_asset: args.get_collateral_asset_id(),
+                        premium_asset: args.get_premium_asset_id(),
+                        premium_per_collateral: args.premium_per_collateral(),
+                    })
+                });
+
+            for entry in history_since(metadata_bytes.as_deref(), since) {
+                record_entry(store, &mut stats, &entry, &wallet_script, terms.as_ref()).await?;
+            }
+        }
+
+        print_stats(&stats, since);
+
+        Ok(())
+    }
+}
+
+/// Script pubkeys of contracts holding the wallet's funds: option offers it made and options it holds grantor tokens for.
+async fn owned_contract_scripts(
+    wallet: &Wallet,
+    config: &Config,
+    user_script_pubkey: &Script,
+) -> Result<Vec<Script>, Error> {
+    let mut scripts = Vec::new();
+
+    let offer_contracts =
+        <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
+    let wallet_pubkey = wallet.public_key().serialize();
+
+    for (args_bytes, tpg_str, _) in offer_contracts {
+        let Some(arguments) = decode_contract_arguments(&args_bytes, &tpg_str) else {
+            continue;
+        };
+        let Ok(args) = OptionOfferArguments::from_arguments(&arguments) else {
+            continue;
+        };
+        if args.user_pubkey() != wallet_pubkey {
+            continue;
+        }
+        if let Ok(tpg) = TaprootPubkeyGen::build_from_str(
+            &tpg_str,
+            &args,
+            config.address_params(),
+            &contracts::option_offer::get_option_offer_address,
+        ) {
+            scripts.push(tpg.address.script_pubkey());
+        }
+    }
+
+    for grantor in get_grantor_tokens_from_wallet(wallet, OPTION_SOURCE, user_script_pubkey).await? {
+        if let Ok(tpg) = TaprootPubkeyGen::build_from_str(
+            &grantor.taproot_pubkey_gen_str,
+            &grantor.option_arguments,
+            config.address_params(),
+            &contracts::options::get_options_address,
+        ) {
+            scripts.push(tpg.address.script_pubkey());
+        }
+    }
+
+    scripts.sort();
+    scripts.dedup();
+
+    Ok(scripts)
+}
+
+/// Draw a seed from the OS RNG, retrying in the negligible case it is not a valid secret key.
+fn generate_seed() -> Result<[u8; Signer::SEED_LEN], Error> {
+    loop {
+        let mut seed = [0u8; Signer::SEED_LEN];
+        getrandom::fill(&mut seed).map_err(|e| Error::Config(format!("OS random number generator failed: {e}")))?;
+
+        if Signer::from_seed(&seed).is_ok() {
+            return Ok(seed);
+        }
+    }
+}
+
+/// Proof that the holder of a wallet key controls its p2pk address.
+///
+/// The address commits to the key only through a Simplicity program, so the key is included for verification.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ControlProof {
+    address: String,
+    public_key: String,
+    message: String,
+    signature: String,
+}
+
+impl ControlProof {
+    fn sign(signer: &Signer, message: &str, params: &'static AddressParams) -> Result<Self, Error> {
+        Ok(Self {
+            address: signer.p2pk_address(params)?.to_string(),
+            public_key: signer.public_key().to_string(),
+            message: message.to_string(),
+            signature: signer.sign_message(message.as_bytes()).to_string(),
+        })
+    }
+
+    /// Check that the key derives the address and signed the message.
+    fn verify(&self, params: &'static AddressParams) -> Result<(), Error> {
+        let public_key = XOnlyPublicKey::from_str(&self.public_key)
+            .map_err(|e| Error::ControlProof(format!("invalid public key: {e}")))?;
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|e| Error::ControlProof(format!("invalid signature encoding: {e}")))?;
+
+        let address = get_p2pk_address(&public_key, params)?;
+        if address.to_string() != self.address {
+            return Err(Error::ControlProof(format!(
+                "key derives {address}, not {}",
+                self.address
+            )));
+        }
+
+        if !signer::verify_message(&public_key, self.message.as_bytes(), &signature) {
+            return Err(Error::ControlProof("signature does not match the message".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// History entries of one contract recorded at or after `since`.
+fn history_since(metadata_bytes: Option<&[u8]>, since: Option<u64>) -> Vec<HistoryEntry> {
+    let Some(metadata) = metadata_bytes.and_then(|bytes| ContractMetadata::from_bytes(bytes).ok()) else {
+        return Vec::new();
+    };
+
+    metadata
+        .history
+        .into_iter()
+        .filter(|entry| since.is_none_or(|since| u64::try_from(entry.timestamp).is_ok_and(|ts| ts >= since)))
+        .collect()
+}
+
+/// Add one history entry to `stats` if its transaction spent a wallet coin.
+///
+/// Contracts synced from NOSTR carry the counterparty's creation entry; requiring a wallet input
+/// keeps their actions out of the totals.
+async fn record_entry(
+    store: &Store,
+    stats: &mut TradingStats,
+    entry: &HistoryEntry,
+    wallet_script: &Script,
+    offer: Option<&OfferTerms>,
+) -> Result<(), Error> {
+    let Some(tx) = stored_transaction(store, entry.txid.as_deref()).await? else {
+        stats.unknown += 1;
+        return Ok(());
+    };
+    if !spends_wallet_coin(store, &tx, wallet_script).await? {
+        return Ok(());
+    }
+
+    stats.record(&entry.action, &tx, wallet_script);
+
+    if entry.action == ActionType::OptionOfferExercised.as_str()
+        && let Some(offer) = offer
+    {
+        let taken = taken_collateral(&tx, &offer.script, offer.collateral_asset);
+        stats.record_premium(offer.premium_asset, taken.saturating_mul(offer.premium_per_collateral));
+    }
+
+    Ok(())
+}
+
+async fn stored_transaction(store: &Store, txid: Option<&str>) -> Result<Option<Transaction>, Error> {
+    let Some(txid) = txid.and_then(|txid| Txid::from_str(txid).ok()) else {
+        return Ok(None);
+    };
+
+    Ok(<_ as UtxoStore>::get_transaction(store, txid).await?)
+}
+
+async fn spends_wallet_coin(store: &Store, tx: &Transaction, wallet_script: &Script) -> Result<bool, Error> {
+    for input in &tx.input {
+        let prevout = input.previous_output;
+        if let Some(prev_tx) = <_ as UtxoStore>::get_transaction(store, prevout.txid).await?
+            && prev_tx
+                .output
+                .get(prevout.vout as usize)
+                .is_some_and(|out| out.script_pubkey == *wallet_script)
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Compute `sum(inputs) - sum(outputs)` in the native asset, using unblinded values from the store.
+///
+/// Returns `None` if any input or non-fee output amount cannot be resolved.
+async fn native_balance_delta(store: &coin_store::Store, tx: &Transaction) -> Result<Option<u64>, Error> {
+    let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+
+    let mut inputs_total: u64 = 0;
+    for input in &tx.input {
+        let Some((asset, value)) = <_ as UtxoStore>::get_output_amount(store, input.previous_output).await? else {
+            return Ok(None);
+        };
+        if asset == lbtc {
+            inputs_total += value;
+        }
+    }
+
+    let mut outputs_total: u64 = 0;
+    for (vout, output) in tx.output.iter().enumerate() {
+        if output.is_fee() {
+            continue;
+        }
+
+        let amount = match (output.asset.explicit(), output.value.explicit()) {
+            (Some(asset), Some(value)) => Some((asset, value)),
+            #[allow(clippy::cast_possible_truncation)]
+            _ => <_ as UtxoStore>::get_output_amount(store, OutPoint::new(tx.txid(), vout as u32)).await?,
+        };
+        let Some((asset, value)) = amount else {
+            return Ok(None);
+        };
+        if asset == lbtc {
+            outputs_total += value;
+        }
+    }
+
+    Ok(inputs_total.checked_sub(outputs_total))
+}
+
+/// `tx` with its fee raised to `new_fee`, the difference taken from the wallet's explicit LBTC change.
+///
+/// Every input must already signal replaceability. Input witnesses are cleared for re-signing.
+fn replace_with_fee(tx: &Transaction, wallet_script: &Script, new_fee: u64) -> Result<Transaction, Error> {
+    if let Some(input) = tx.input.iter().find(|input| !input.sequence.is_rbf()) {
+        return Err(Error::Config(format!(
+            "Input {} does not signal replaceability; enable `fee.rbf` before sending transactions you may need to bump",
+            input.previous_output
+        )));
+    }
+
+    let mut replacement = tx.clone();
+
+    let fee_output = replacement
+        .output
+        .iter_mut()
+        .find(|out| out.is_fee())
+        .ok_or_else(|| Error::Config("Transaction has no fee output".to_string()))?;
+    let old_fee = fee_output.value.explicit().unwrap_or(0);
+    if new_fee <= old_fee {
+        return Err(Error::Config(format!(
+            "New fee ({new_fee} sats) must be higher than the current fee ({old_fee} sats)"
+        )));
+    }
+    fee_output.value = confidential::Value::Explicit(new_fee);
+    let increase = new_fee - old_fee;
+
+    let change = replacement
+        .output
+        .iter_mut()
+        .filter(|out| {
+            !out.is_fee()
+                && out.script_pubkey == *wallet_script
+                && out.asset.explicit() == Some(*LIQUID_TESTNET_BITCOIN_ASSET)
+        })
+        .filter_map(|out| out.value.explicit().map(|value| (value, out)))
+        .max_by_key(|(value, _)| *value);
+    let Some((change_value, change_output)) = change.filter(|(value, _)| *value > increase) else {
+        return Err(Error::Config(format!(
+            "No explicit LBTC change output larger than the {increase} sat fee increase"
+        )));
+    };
+    change_output.value = confidential::Value::Explicit(change_value - increase);
+
+    for input in &mut replacement.input {
+        input.witness.script_witness.clear();
+    }
+
+    Ok(replacement)
+}
+
+fn print_stats(stats: &TradingStats, since: Option<u64>) {
+    println!("Trading Stats");
+    println!("=============");
+    if let Some(since) = since {
+        println!("Since: {since}");
+    }
+    println!();
+
+    println!("Option offers:");
+    println!("  Created:    {}", stats.count(ActionType::OptionOfferCreated));
+    println!("  Taken:      {}", stats.count(ActionType::OptionOfferExercised));
+    println!("  Cancelled:  {}", stats.count(ActionType::OptionOfferCancelled));
+    println!("Options:");
+    println!("  Created:    {}", stats.count(ActionType::OptionCreated));
+    println!("  Funded:     {}", stats.count(ActionType::OptionFunded));
+    println!("  Exercised:  {}", stats.count(ActionType::OptionExercised));
+    println!("  Expired:    {}", stats.count(ActionType::OptionExpired));
+    println!("  Cancelled:  {}", stats.count(ActionType::OptionCancelled));
+    println!("  Settlements claimed: {}", stats.count(ActionType::SettlementClaimed));
+
+    let other: Vec<_> = stats
+        .actions
+        .iter()
+        .filter(|(action, _)| ActionType::from_str(action).is_err())
+        .collect();
+    if !other.is_empty() {
+        println!("Other actions:");
+        for (action, count) in other {
+            println!("  {action}: {count}");
+        }
+    }
+    println!();
+
+    println!("Fees paid: {} sats", stats.fees_paid);
+
+    println!("Premium earned:");
+    if stats.premium_earned.is_empty() {
+        println!("  (none)");
+    }
+    for (asset, amount) in &stats.premium_earned {
+        println!("  {amount} {}", format_settlement_asset(asset));
+    }
+
+    println!("Volume sent:");
+    if stats.volume.is_empty() {
+        println!("  (none)");
+    }
+    for (asset, amount) in &stats.volume {
+        println!("  {amount} {}", format_settlement_asset(asset));
+    }
+
+    if stats.unknown > 0 {
+        println!();
+        println!(
+            "{} history entries skipped: transaction not in the local store (try 'sync full')",
+            stats.unknown
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{LockTime, Sequence, TxIn, TxOut, TxOutWitness};
+
+    #[test]
+    fn test_record_counts_fee_and_volume_once_per_transaction() {
+        let collateral = AssetId::from_slice(&[1; 32]).unwrap();
+        let wallet_script = Script::from(vec![0x51]);
+        let offer_script = Script::from(vec![0x52]);
+
+        let output = |asset: AssetId, value: u64, script_pubkey: &Script| TxOut {
+            asset: Asset::Explicit(asset),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        };
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![
+                output(collateral, 10_000, &offer_script),
+                output(collateral, 500, &wallet_script),
+                TxOut::new_fee(120, *LIQUID_TESTNET_BITCOIN_ASSET),
+            ],
+        };
+
+        let mut stats = TradingStats::default();
+        stats.record(ActionType::OptionOfferCreated.as_str(), &tx, &wallet_script);
+        stats.record("option_offer_withdrawn", &tx, &wallet_script);
+
+        assert_eq!(stats.count(ActionType::OptionOfferCreated), 1);
+        assert_eq!(stats.actions.get("option_offer_withdrawn"), Some(&1));
+        assert_eq!(stats.fees_paid, 120);
+        assert_eq!(stats.volume.get(&collateral), Some(&10_000));
+    }
+
+    #[test]
+    fn test_replace_with_fee_takes_increase_from_wallet_change() {
+        let wallet_script = Script::from(vec![0x51]);
+        let recipient = Script::from(vec![0x52]);
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+
+        let output = |value: u64, script_pubkey: &Script| TxOut {
+            asset: Asset::Explicit(lbtc),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        };
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::from_byte_array([1; 32]), 0),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                ..TxIn::default()
+            }],
+            output: vec![
+                output(5_000, &recipient),
+                output(2_000, &wallet_script),
+                TxOut::new_fee(100, lbtc),
+            ],
+        };
+
+        let replacement = replace_with_fee(&tx, &wallet_script, 250).unwrap();
+        assert_eq!(replacement.output[0].value.explicit(), Some(5_000));
+        assert_eq!(replacement.output[1].value.explicit(), Some(1_850));
+        assert_eq!(replacement.output[2].value.explicit(), Some(250));
+
+        assert!(replace_with_fee(&tx, &wallet_script, 100).is_err());
+        assert!(replace_with_fee(&tx, &wallet_script, 2_100).is_err());
+
+        tx.input[0].sequence = Sequence::MAX;
+        assert!(replace_with_fee(&tx, &wallet_script, 250).is_err());
+    }
+
+    #[test]
+    fn test_control_proof_round_trip() {
+        let params = &AddressParams::LIQUID_TESTNET;
+        let signer = Signer::from_seed(&[1; Signer::SEED_LEN]).unwrap();
+        let other = Signer::from_seed(&[2; Signer::SEED_LEN]).unwrap();
+
+        let proof = ControlProof::sign(&signer, "otc deal 42", params).unwrap();
+        let json = serde_json::to_string(&proof).unwrap();
+        let parsed: ControlProof = serde_json::from_str(&json).unwrap();
+        assert!(parsed.verify(params).is_ok());
+
+        let tampered = ControlProof {
+            message: "otc deal 43".to_string(),
+            ..ControlProof::sign(&signer, "otc deal 42", params).unwrap()
+        };
+        assert!(tampered.verify(params).is_err());
+
+        let wrong_address = ControlProof {
+            address: other.p2pk_address(params).unwrap().to_string(),
+            ..ControlProof::sign(&signer, "otc deal 42", params).unwrap()
+        };
+        assert!(wrong_address.verify(params).is_err());
+    }
+}
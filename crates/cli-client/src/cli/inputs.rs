@@ -0,0 +1,227 @@
+use crate::error::Error;
+
+use coin_store::UtxoEntry;
+use simplicityhl::elements::pset::{Input, PartiallySignedTransaction};
+use simplicityhl::elements::{OutPoint, Script, Transaction, TxOut, Txid, confidential};
+use simplicityhl_core::LIQUID_TESTNET_BITCOIN_ASSET;
+
+/// Pick UTXOs in query order until they cover `required`, skipping `exclude`.
+///
+/// Keeping the query order honours the configured selection order (largest first by default).
+/// `what` names the inputs in the error, e.g. "settlement" or "LBTC fee".
+pub(crate) fn select_inputs(
+    entries: &[&UtxoEntry],
+    required: u64,
+    exclude: Option<OutPoint>,
+    what: &str,
+) -> Result<Vec<(OutPoint, TxOut)>, Error> {
+    let mut selected = Vec::new();
+    let mut total: u64 = 0;
+    for entry in entries.iter().filter(|entry| Some(*entry.outpoint()) != exclude) {
+        if total >= required {
+            break;
+        }
+        total += entry.value().unwrap_or(0);
+        selected.push(entry.as_input());
+    }
+
+    if selected.is_empty() || total < required {
+        return Err(Error::Config(format!(
+            "Insufficient {what} balance: need {required}, have {total} across {} UTXO(s)",
+            selected.len()
+        )));
+    }
+
+    Ok(selected)
+}
+
+/// Move the outputs of `txid` to the front, keeping the query order otherwise.
+///
+/// Used with `--cpfp-parent` so [`select_inputs`] spends the parent's output when the wallet holds one.
+pub(crate) fn prefer_outputs_of(entries: &mut [&UtxoEntry], txid: Txid) {
+    entries.sort_by_key(|entry| entry.outpoint().txid != txid);
+}
+
+/// Fail unless every fee input is locked to `wallet_script`.
+///
+/// The fee inputs are signed as plain wallet inputs, so a contract-controlled coin that slipped
+/// into the selection would otherwise only fail at finalization for lack of a contract witness.
+pub(crate) fn ensure_wallet_fee_inputs(inputs: &[(OutPoint, TxOut)], wallet_script: &Script) -> Result<(), Error> {
+    if let Some((outpoint, _)) = inputs.iter().find(|(_, txout)| txout.script_pubkey != *wallet_script) {
+        return Err(Error::Config(format!(
+            "Fee input {outpoint} is not locked to the wallet address and may be a contract UTXO; \
+             refusing to spend it as a fee. Run 'sync full' to refresh the local UTXO set."
+        )));
+    }
+
+    Ok(())
+}
+
+/// The fee input for `entry`, checked to be locked to `wallet_script`.
+pub(crate) fn wallet_fee_input(entry: &UtxoEntry, wallet_script: &Script) -> Result<(OutPoint, TxOut), Error> {
+    let input = entry.as_input();
+    ensure_wallet_fee_inputs(std::slice::from_ref(&input), wallet_script)?;
+    Ok(input)
+}
+
+/// Collapse the selected UTXOs into the single input an SDK builder expects.
+///
+/// The builders only read the input value to size the change output, so they are handed the
+/// first outpoint carrying the combined value; `spread_inputs` restores the real inputs.
+pub(crate) fn aggregate_inputs(inputs: &[(OutPoint, TxOut)], what: &str) -> Result<(OutPoint, TxOut), Error> {
+    let [(first_outpoint, first_txout), rest @ ..] = inputs else {
+        return Err(Error::Config(format!("No {what} inputs selected")));
+    };
+
+    if rest.is_empty() {
+        return Ok((*first_outpoint, first_txout.clone()));
+    }
+
+    let mut total: u64 = 0;
+    for (outpoint, txout) in inputs {
+        let value = txout.value.explicit().ok_or_else(|| {
+            Error::Config(format!(
+                "{what} UTXO {outpoint} is confidential; combining {what} inputs requires explicit UTXOs"
+            ))
+        })?;
+        total += value;
+    }
+
+    let mut aggregate = first_txout.clone();
+    aggregate.value = confidential::Value::Explicit(total);
+
+    Ok((*first_outpoint, aggregate))
+}
+
+/// Replace the aggregated input at `index` with the real UTXOs, appending the extra ones after
+/// every input the builder created.
+///
+/// The builder's inputs keep their positions, so contract inputs are still finalized at the
+/// indices their covenant checks; the extra UTXOs only add wallet inputs after them.
+pub(crate) fn spread_inputs(pst: &mut PartiallySignedTransaction, index: usize, inputs: &[(OutPoint, TxOut)]) {
+    let Some((_, first_txout)) = inputs.first() else {
+        return;
+    };
+
+    pst.inputs_mut()[index].witness_utxo = Some(first_txout.clone());
+
+    for (outpoint, txout) in &inputs[1..] {
+        let mut input = Input::from_prevout(*outpoint);
+        input.witness_utxo = Some(txout.clone());
+        pst.add_input(input);
+    }
+}
+
+/// Drop the LBTC change output the builder paid to `change_script` if the fee input exactly
+/// covered the fee and left it at zero.
+///
+/// Such an output is dust and would be rejected by the network. Only the builder's change, the
+/// last LBTC output to `change_script`, is considered; other zero-value outputs are kept.
+pub(crate) fn remove_empty_fee_change(tx: &mut Transaction, change_script: &Script) {
+    let change = tx.output.iter().rposition(|out| {
+        out.script_pubkey == *change_script && out.asset.explicit() == Some(*LIQUID_TESTNET_BITCOIN_ASSET)
+    });
+
+    if let Some(index) = change
+        && tx.output[index].value.explicit() == Some(0)
+    {
+        tx.output.remove(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{Asset, Nonce};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{Script, TxOutWitness, Txid};
+
+    fn lbtc_entry(seed: u8, value: u64) -> UtxoEntry {
+        let txout = lbtc_txout(value, Script::new());
+        UtxoEntry::new_explicit(OutPoint::new(Txid::from_byte_array([seed; 32]), 0), txout)
+    }
+
+    fn lbtc_txout(value: u64, script_pubkey: Script) -> TxOut {
+        TxOut {
+            asset: Asset::Explicit(*LIQUID_TESTNET_BITCOIN_ASSET),
+            value: confidential::Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey,
+            witness: TxOutWitness::default(),
+        }
+    }
+
+    #[test]
+    fn test_remove_empty_fee_change_only_drops_builder_change() {
+        let change_script = Script::from(vec![0x51]);
+        let other_script = Script::from(vec![0x52]);
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![
+                lbtc_txout(0, other_script.clone()),
+                lbtc_txout(500, change_script.clone()),
+                lbtc_txout(0, change_script.clone()),
+                TxOut::new_fee(160, *LIQUID_TESTNET_BITCOIN_ASSET),
+            ],
+        };
+        remove_empty_fee_change(&mut tx, &change_script);
+
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[0].script_pubkey, other_script);
+        assert_eq!(tx.output[1].value.explicit(), Some(500));
+        assert!(tx.output[2].is_fee());
+
+        // Change that is not empty stays, and so does the earlier zero-value output
+        remove_empty_fee_change(&mut tx, &change_script);
+        assert_eq!(tx.output.len(), 3);
+    }
+
+    #[test]
+    fn test_fee_from_fragmented_lbtc_exactly_covered() {
+        let change_script = Script::from(vec![0x51]);
+        let entries = [lbtc_entry(1, 100), lbtc_entry(2, 60), lbtc_entry(3, 40)];
+        let refs: Vec<&UtxoEntry> = entries.iter().collect();
+
+        let inputs = select_inputs(&refs, 160, None, "LBTC fee").unwrap();
+        assert_eq!(inputs.len(), 2);
+        let (_, aggregate) = aggregate_inputs(&inputs, "LBTC fee").unwrap();
+        assert_eq!(aggregate.value.explicit(), Some(160));
+
+        // A builder sizing change from the aggregate leaves 160 - 160 = 0 behind
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![
+                lbtc_txout(500, change_script.clone()),
+                lbtc_txout(0, change_script.clone()),
+                TxOut::new_fee(160, *LIQUID_TESTNET_BITCOIN_ASSET),
+            ],
+        };
+        remove_empty_fee_change(&mut tx, &change_script);
+
+        assert_eq!(tx.output.len(), 2);
+        assert_eq!(tx.output[0].value.explicit(), Some(500));
+        assert!(tx.output[1].is_fee());
+    }
+
+    #[test]
+    fn test_fee_input_must_be_locked_to_wallet() {
+        let wallet_script = Script::new();
+        let wallet_coin = lbtc_entry(1, 100);
+        assert!(wallet_fee_input(&wallet_coin, &wallet_script).is_ok());
+
+        let contract_coin = UtxoEntry::new_explicit(
+            OutPoint::new(Txid::from_byte_array([2; 32]), 0),
+            lbtc_txout(100, Script::from(vec![0x51, 0x20])),
+        );
+        let err = wallet_fee_input(&contract_coin, &wallet_script).unwrap_err();
+        assert!(err.to_string().contains("not locked to the wallet address"));
+
+        let inputs = [wallet_coin.as_input(), contract_coin.as_input()];
+        assert!(ensure_wallet_fee_inputs(&inputs, &wallet_script).is_err());
+    }
+}
@@ -7,6 +7,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use coin_store::{UtxoEntry, UtxoFilter, UtxoQueryResult, UtxoStore};
 
 use contracts::options::OptionsArguments;
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
 
 use simplicityhl::elements::Script;
 use simplicityhl::elements::hex::ToHex;
@@ -24,6 +25,39 @@ pub struct TokenDisplay {
     pub status: String,
 }
 
+/// Source of the current Unix time, in seconds.
+///
+/// Expiry-dependent decisions take a clock so they can be tested against a fixed instant.
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+/// Wall-clock time, used everywhere outside of tests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[allow(clippy::cast_possible_wrap)]
+    fn now(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+}
+
+/// A clock frozen at a given Unix timestamp.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub i64);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> i64 {
+        self.0
+    }
+}
+
 /// Format a past timestamp as "X ago" for history entries.
 #[must_use]
 #[allow(clippy::cast_possible_wrap)]
@@ -63,14 +97,14 @@ pub fn format_time_ago(timestamp: i64) -> String {
 
 /// Format a future expiry timestamp as "in X days" or "[EXPIRED]".
 #[must_use]
-#[allow(clippy::cast_possible_wrap)]
 pub fn format_relative_time(expiry_timestamp: i64) -> String {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs() as i64)
-        .unwrap_or(0);
+    format_relative_time_at(&SystemClock, expiry_timestamp)
+}
 
-    let diff_secs = expiry_timestamp - now;
+/// Same as [`format_relative_time`], measured against the given clock.
+#[must_use]
+pub fn format_relative_time_at(clock: &impl Clock, expiry_timestamp: i64) -> String {
+    let diff_secs = expiry_timestamp - clock.now();
 
     if diff_secs <= 0 {
         return "[EXPIRED]".to_string();
@@ -145,6 +179,27 @@ pub fn prompt_amount(prompt: &str) -> io::Result<u64> {
     )
 }
 
+pub fn prompt_text(prompt: &str) -> io::Result<String> {
+    print!("{prompt}: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().to_string())
+}
+
+/// Read a secret without echoing it to the terminal.
+pub fn prompt_secret(prompt: &str) -> io::Result<String> {
+    rpassword::prompt_password(format!("{prompt}: "))
+}
+
+/// Ask a yes/no question, treating anything but `y`/`yes` as no.
+pub fn prompt_confirm(prompt: &str) -> io::Result<bool> {
+    let answer = prompt_text(&format!("{prompt} [y/N]"))?;
+    Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
 #[must_use]
 pub fn truncate_with_ellipsis(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
@@ -183,12 +238,23 @@ pub fn parse_expiry(expiry: &str) -> Result<i64, Error> {
     )))
 }
 
-#[allow(clippy::cast_possible_wrap)]
+/// Resolve an optional `--expiry` argument, falling back to the configured default offer expiry.
+pub fn resolve_expiry(expiry: Option<&str>, config: &crate::config::Config) -> Result<i64, Error> {
+    match expiry {
+        Some(expiry) => parse_expiry(expiry),
+        None => parse_expiry(&format!("+{}", config.defaults.offer_expiry)),
+    }
+}
+
+#[must_use]
 pub fn current_timestamp() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_secs() as i64)
-        .unwrap_or(0)
+    SystemClock.now()
+}
+
+/// Whether an option offer is past its expiry and its collateral can be reclaimed by the maker.
+#[must_use]
+pub fn is_offer_expired(clock: &impl Clock, expiry_time: u32) -> bool {
+    clock.now() > i64::from(expiry_time)
 }
 
 pub fn extract_entries_from_result(result: &UtxoQueryResult) -> Vec<&UtxoEntry> {
@@ -289,16 +355,140 @@ pub async fn get_option_tokens_from_wallet(
     Ok(enriched)
 }
 
+/// Lifecycle status of an options contract, computed from locally synced UTXOs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractStatus {
+    /// Collateral is locked and the contract has not expired
+    Active,
+    /// The contract has expired but collateral is still locked
+    Expired,
+    /// All collateral is gone but settlement is waiting to be withdrawn
+    Withdrawable,
+    /// Nothing is left at the contract address
+    Closed,
+}
+
+impl ContractStatus {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Expired => "expired",
+            Self::Withdrawable => "withdrawable",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+impl std::fmt::Display for ContractStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Status of a contract together with the amounts still locked at its address.
+#[derive(Debug, Clone, Copy)]
+pub struct ContractState {
+    pub status: ContractStatus,
+    pub collateral: u64,
+    pub settlement: u64,
+}
+
+/// Compute the current state of the contract an enriched token belongs to.
+pub async fn get_contract_state(
+    wallet: &crate::wallet::Wallet,
+    enriched: &EnrichedTokenEntry,
+) -> Result<ContractState, Error> {
+    let tpg = TaprootPubkeyGen::build_from_str(
+        &enriched.taproot_pubkey_gen_str,
+        &enriched.option_arguments,
+        wallet.params(),
+        &contracts::options::get_options_address,
+    )?;
+
+    let (collateral, settlement) = contract_amounts(wallet.store(), tpg, &enriched.option_arguments).await?;
+
+    Ok(ContractState {
+        status: compute_contract_status(
+            &SystemClock,
+            collateral,
+            settlement,
+            enriched.option_arguments.expiry_time(),
+        ),
+        collateral,
+        settlement,
+    })
+}
+
+/// Derive a contract's status from the amounts locked at its address and its expiry.
+#[must_use]
+pub fn compute_contract_status(
+    clock: &impl Clock,
+    collateral: u64,
+    settlement: u64,
+    expiry_time: u32,
+) -> ContractStatus {
+    let is_expired = clock.now() >= i64::from(expiry_time);
+
+    match (collateral > 0, settlement > 0) {
+        (true, _) if is_expired => ContractStatus::Expired,
+        (true, _) => ContractStatus::Active,
+        (false, true) => ContractStatus::Withdrawable,
+        (false, false) => ContractStatus::Closed,
+    }
+}
+
+/// Collateral and settlement still locked at an options contract, from locally synced UTXOs.
+async fn contract_amounts(
+    store: &coin_store::Store,
+    taproot_pubkey_gen: TaprootPubkeyGen,
+    args: &OptionsArguments,
+) -> Result<(u64, u64), Error> {
+    let collateral_filter = UtxoFilter::new()
+        .taproot_pubkey_gen(taproot_pubkey_gen.clone())
+        .asset_id(args.get_collateral_asset_id());
+    let settlement_filter = UtxoFilter::new()
+        .taproot_pubkey_gen(taproot_pubkey_gen)
+        .asset_id(args.get_settlement_asset_id());
+
+    let results = <_ as UtxoStore>::query_utxos(store, &[collateral_filter, settlement_filter]).await?;
+
+    let sum = |result: &UtxoQueryResult| -> u64 {
+        extract_entries_from_result(result)
+            .into_iter()
+            .filter_map(UtxoEntry::value)
+            .sum()
+    };
+
+    Ok((sum(&results[0]), sum(&results[1])))
+}
+
+/// Compute contract states for a list of enriched token entries, in the same order.
+pub async fn get_contract_states(
+    wallet: &crate::wallet::Wallet,
+    entries: &[EnrichedTokenEntry],
+) -> Result<Vec<ContractState>, Error> {
+    let mut states = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        states.push(get_contract_state(wallet, entry).await?);
+    }
+
+    Ok(states)
+}
+
 /// Select from enriched token entries that include contract arguments.
-/// This shows settlement and expiry information from the contract.
+/// This shows settlement, expiry and the contract status with the amounts still available.
 pub fn select_enriched_token_interactive<'a>(
     entries: &'a [EnrichedTokenEntry],
+    states: &[ContractState],
     prompt: &str,
 ) -> Result<&'a EnrichedTokenEntry, Error> {
     let displays: Vec<TokenDisplay> = entries
         .iter()
+        .zip(states)
         .enumerate()
-        .map(|(idx, enriched)| {
+        .map(|(idx, (enriched, state))| {
             let settlement_asset = enriched.option_arguments.get_settlement_asset_id();
             let settlement_per_contract = enriched.option_arguments.settlement_per_contract();
             let expiry_time = enriched.option_arguments.expiry_time();
@@ -318,7 +508,10 @@ pub fn select_enriched_token_interactive<'a>(
                     format_settlement_asset(&settlement_asset)
                 ),
                 expires: format_relative_time(i64::from(expiry_time)),
-                status: contract_addr,
+                status: format!(
+                    "{contract_addr} [{}: {} collateral, {} settlement]",
+                    state.status, state.collateral, state.settlement
+                ),
             }
         })
         .collect();
@@ -551,7 +744,12 @@ pub fn select_asset_interactive<'a>(
 mod tests {
     use super::*;
 
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{AddressParams, AssetId, OutPoint, TxOut, TxOutWitness, Txid};
+
     const ACCEPTABLE_THRESHOLD: i64 = 2;
+    const NOW: i64 = 1_704_067_200;
 
     #[test]
     #[allow(clippy::cast_possible_wrap)]
@@ -628,6 +826,19 @@ mod tests {
         assert!((result - expected).abs() < ACCEPTABLE_THRESHOLD);
     }
 
+    #[test]
+    fn test_resolve_expiry_falls_back_to_config_default() {
+        let mut config = crate::config::Config::default();
+        config.defaults.offer_expiry = "2h".to_string();
+
+        let now = current_timestamp();
+        let result = resolve_expiry(None, &config).unwrap();
+        let expected = now + 2 * 3600;
+        assert!((result - expected).abs() < ACCEPTABLE_THRESHOLD);
+
+        assert_eq!(resolve_expiry(Some("1704067200"), &config).unwrap(), 1_704_067_200);
+    }
+
     #[test]
     fn test_parse_expiry_invalid_format() {
         let result = parse_expiry("invalid");
@@ -655,4 +866,105 @@ mod tests {
         let result = parse_expiry("-30d");
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_contract_amounts_sum_each_asset_at_the_contract() {
+        let path = "/tmp/test_cli_contract_amounts.db";
+        let _ = std::fs::remove_file(path);
+        let store = coin_store::Store::create(path).await.unwrap();
+
+        let collateral = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let settlement = AssetId::from_slice(&[7; 32]).unwrap();
+        let outpoint = |seed: u8| OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+        let args = OptionsArguments::new(
+            1_704_067_200,
+            1_706_745_600,
+            10,
+            100,
+            collateral,
+            settlement,
+            [3; 32],
+            (outpoint(1), false),
+            (outpoint(2), false),
+        );
+        let tpg = TaprootPubkeyGen::from(
+            &args,
+            &AddressParams::LIQUID_TESTNET,
+            &contracts::options::get_options_address,
+        )
+        .unwrap();
+        store
+            .add_contract(
+                contracts::options::OPTION_SOURCE,
+                args.build_option_arguments(),
+                tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let txout = |asset: AssetId, value: u64, script_pubkey: Script| TxOut {
+            asset: Asset::Explicit(asset),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey,
+            witness: TxOutWitness::default(),
+        };
+        let contract_script = tpg.address.script_pubkey();
+        for (seed, asset, value, script_pubkey) in [
+            (3, collateral, 400, contract_script.clone()),
+            (4, collateral, 600, contract_script.clone()),
+            (5, settlement, 2_000, contract_script),
+            // Not at the contract address
+            (6, collateral, 9_999, Script::from(vec![0x51])),
+        ] {
+            store
+                .insert(outpoint(seed), txout(asset, value, script_pubkey), None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(contract_amounts(&store, tpg, &args).await.unwrap(), (1_000, 2_000));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_format_relative_time_at_fixed_clock() {
+        let clock = FixedClock(NOW);
+
+        assert_eq!(format_relative_time_at(&clock, NOW), "[EXPIRED]");
+        assert_eq!(format_relative_time_at(&clock, NOW + 30 * 60), "[URGENT] in 30 minutes");
+        assert_eq!(format_relative_time_at(&clock, NOW + 6 * 3600), "[SOON] in 6 hours");
+        assert_eq!(format_relative_time_at(&clock, NOW + 3 * 24 * 3600), "in 3 days");
+    }
+
+    #[test]
+    fn test_is_offer_expired_boundary() {
+        let expiry = u32::try_from(NOW).unwrap();
+
+        assert!(!is_offer_expired(&FixedClock(NOW - 1), expiry));
+        assert!(!is_offer_expired(&FixedClock(NOW), expiry));
+        assert!(is_offer_expired(&FixedClock(NOW + 1), expiry));
+    }
+
+    #[test]
+    fn test_compute_contract_status() {
+        let expiry = u32::try_from(NOW).unwrap();
+        let before = FixedClock(NOW - 1);
+        let at = FixedClock(NOW);
+
+        assert_eq!(compute_contract_status(&before, 100, 0, expiry), ContractStatus::Active);
+        assert_eq!(compute_contract_status(&at, 100, 0, expiry), ContractStatus::Expired);
+        assert_eq!(
+            compute_contract_status(&before, 0, 50, expiry),
+            ContractStatus::Withdrawable
+        );
+        assert_eq!(
+            compute_contract_status(&at, 0, 50, expiry),
+            ContractStatus::Withdrawable
+        );
+        assert_eq!(compute_contract_status(&at, 0, 0, expiry), ContractStatus::Closed);
+    }
 }
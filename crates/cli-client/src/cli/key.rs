@@ -0,0 +1,179 @@
+use crate::cli::interactive::current_timestamp;
+use crate::cli::{Cli, KeyCommand};
+use crate::config::Config;
+use crate::error::Error;
+
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use nostr::nips::nip19::ToBech32;
+use nostr::{Keys, SecretKey};
+
+/// Where the NOSTR key used for publishing comes from.
+pub(super) enum NostrKeySource {
+    /// Derived from the wallet seed (no key file yet).
+    Seed,
+    /// Loaded from the key file managed by `key rotate`.
+    File(PathBuf),
+}
+
+impl Cli {
+    pub(crate) fn run_key(&self, config: &Config, command: &KeyCommand) -> Result<(), Error> {
+        match command {
+            KeyCommand::Show => {
+                let (keys, source) = self.nostr_keys(config)?;
+
+                println!("NOSTR public key: {}", keys.public_key().to_hex());
+                println!("npub: {}", to_npub(&keys)?);
+                match source {
+                    NostrKeySource::Seed => println!("Source: wallet seed (run `key rotate` to use a separate key)"),
+                    NostrKeySource::File(path) => println!("Source: {}", path.display()),
+                }
+
+                Ok(())
+            }
+            KeyCommand::Rotate => {
+                let (old_keys, source) = self.nostr_keys(config)?;
+
+                let new_keys = Keys::generate();
+                std::fs::create_dir_all(&config.storage.data_dir)?;
+
+                let key_path = config.nostr_key_path();
+                let archived = match source {
+                    NostrKeySource::File(_) => Some(archived_key_path(&key_path, current_timestamp())),
+                    NostrKeySource::Seed => None,
+                };
+                install_key_file(&key_path, &new_keys, archived.as_deref())?;
+
+                if let Some(archived) = archived {
+                    println!("Archived previous key to {}", archived.display());
+                }
+
+                println!("Previous public key: {}", old_keys.public_key().to_hex());
+                println!("New public key:      {}", new_keys.public_key().to_hex());
+                println!("npub: {}", to_npub(&new_keys)?);
+                println!();
+                println!("New offers and actions are published with the new key.");
+                println!("Existing contracts keep the key they were published with in their metadata.");
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Load the NOSTR publishing key, falling back to the wallet seed when no key file exists.
+    pub(super) fn nostr_keys(&self, config: &Config) -> Result<(Keys, NostrKeySource), Error> {
+        let key_path = config.nostr_key_path();
+
+        match std::fs::read_to_string(&key_path) {
+            Ok(content) => {
+                let keys = Keys::parse(content.trim())
+                    .map_err(|e| Error::Config(format!("Invalid NOSTR key in {}: {e}", key_path.display())))?;
+                Ok((keys, NostrKeySource::File(key_path)))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let seed = self.parse_seed()?;
+                let secret_key = SecretKey::from_slice(&seed)
+                    .map_err(|e| Error::Config(format!("Invalid seed for NOSTR key: {e}")))?;
+                Ok((Keys::new(secret_key), NostrKeySource::Seed))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn to_npub(keys: &Keys) -> Result<String, Error> {
+    keys.public_key()
+        .to_bech32()
+        .map_err(|e| Error::Config(format!("Failed to encode npub: {e}")))
+}
+
+fn archived_key_path(path: &Path, timestamp: i64) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map_or_else(Default::default, |name| name.to_string_lossy());
+    path.with_file_name(format!("{file_name}.{timestamp}.old"))
+}
+
+/// Replace the key file at `path` with `keys`, moving the current one to `archive` if given.
+///
+/// The new key is fully written to a temporary file first, so a failed write leaves the current
+/// key in place. If installing it fails after archiving, the archived key is moved back.
+fn install_key_file(path: &Path, keys: &Keys, archive: Option<&Path>) -> Result<(), Error> {
+    let file_name = path
+        .file_name()
+        .map_or_else(Default::default, |name| name.to_string_lossy());
+    let temp_path = path.with_file_name(format!("{file_name}.new"));
+    // Left over from an interrupted rotation
+    if let Err(e) = std::fs::remove_file(&temp_path)
+        && e.kind() != ErrorKind::NotFound
+    {
+        return Err(e.into());
+    }
+    write_key_file(&temp_path, keys)?;
+
+    if let Some(archive) = archive {
+        std::fs::rename(path, archive)?;
+    }
+
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        if let Some(archive) = archive
+            && let Err(restore) = std::fs::rename(archive, path)
+        {
+            tracing::warn!("Failed to restore previous key from {}: {restore}", archive.display());
+        }
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+fn write_key_file(path: &Path, keys: &Keys) -> Result<(), Error> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    writeln!(file, "{}", keys.secret_key().to_secret_hex())?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archived_key_path_keeps_directory() {
+        let archived = archived_key_path(Path::new("/data/nostr.key"), 1_704_067_200);
+
+        assert_eq!(archived, PathBuf::from("/data/nostr.key.1704067200.old"));
+    }
+
+    #[test]
+    fn test_install_key_file_archives_previous_key() {
+        let dir = std::env::temp_dir().join("test_cli_key_rotate");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("nostr.key");
+        let archive = archived_key_path(&path, 1_704_067_200);
+        let (old_keys, new_keys) = (Keys::generate(), Keys::generate());
+        let read = |path: &Path| Keys::parse(std::fs::read_to_string(path).unwrap().trim()).unwrap();
+
+        install_key_file(&path, &old_keys, None).unwrap();
+        assert_eq!(read(&path).public_key(), old_keys.public_key());
+
+        install_key_file(&path, &new_keys, Some(&archive)).unwrap();
+        assert_eq!(read(&path).public_key(), new_keys.public_key());
+        assert_eq!(read(&archive).public_key(), old_keys.public_key());
+        assert!(!dir.join("nostr.key.new").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,27 +1,48 @@
+mod authors;
+mod best_offer;
 mod browse;
 mod commands;
+mod contract;
+mod helper;
+mod inputs;
 mod interactive;
+mod key;
+mod offer_analysis;
 mod option;
 mod option_offer;
 mod positions;
+mod price_history;
+#[cfg(feature = "dev-commands")]
+mod regtest;
+mod relay_status;
 mod sync;
 mod tables;
+#[cfg(feature = "tui")]
+mod tui;
 mod tx;
 mod wallet;
+mod watch;
 
+use crate::assets::{ASSET_REGISTRY_FILENAME, AssetRegistry};
 use crate::error::Error;
 
 use crate::config::{Config, default_config_path};
 use crate::wallet::Wallet;
 
 use clap::Parser;
-use nostr::SecretKey;
-use options_relay::{PublishingClient, ReadOnlyClient};
+use nostr::{EventId, PublicKey};
+use options_relay::{ActionCompletedEvent, PublishingClient, ReadOnlyClient};
 use std::path::PathBuf;
 
 use signer::Signer;
-
-pub use commands::{Command, OptionCommand, OptionOfferCommand, SyncCommand, TxCommand, WalletCommand};
+use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
+
+#[cfg(feature = "dev-commands")]
+pub use commands::RegtestCommand;
+pub use commands::{
+    AmountRounding, AuthorsCommand, Command, ContractCommand, HelperCommand, HelperTxCommand, KeyCommand,
+    OptionCommand, OptionOfferCommand, SyncCommand, TrackerLog, TxCommand, WalletCommand,
+};
 pub use interactive::{GRANTOR_TOKEN_TAG, OPTION_TOKEN_TAG};
 pub use option_offer::OPTION_OFFER_COLLATERAL_TAG;
 
@@ -35,6 +56,24 @@ pub struct Cli {
     #[arg(short, long, env = "SIMPLICITY_DEX_SEED")]
     pub seed: Option<String>,
 
+    /// Open the wallet watch-only from its x-only public key (hex) instead of the seed; signing commands fail
+    #[arg(long, env = "SIMPLICITY_DEX_WATCH_ONLY", conflicts_with = "seed")]
+    pub watch_only: Option<String>,
+
+    /// Passphrase for the wallet database when `storage.encrypted` is set (prompted if not provided)
+    #[arg(long, env = "SIMPLICITY_DEX_DB_PASSPHRASE", hide_env_values = true)]
+    pub db_passphrase: Option<String>,
+
+    /// Simplicity tracker output when finalizing contract inputs, to see which jet or branch fails
+    #[arg(long, value_enum, default_value_t = TrackerLog::Off, global = true)]
+    pub tracker_log: TrackerLog,
+
+    /// Derive output blinding factors from the seed and the transaction instead of fresh randomness,
+    /// so rebuilding a confidential transaction yields identical bytes. For tests and CI only: anyone
+    /// holding the seed can recompute the blinding factors of every transaction built this way
+    #[arg(long, env = "SIMPLICITY_DEX_DETERMINISTIC_BLINDING", global = true)]
+    pub deterministic_blinding: bool,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -50,6 +89,11 @@ impl Cli {
     }
 
     fn parse_seed(&self) -> Result<[u8; Signer::SEED_LEN], Error> {
+        // Never fall back to the default seed when the user asked not to use one
+        if self.watch_only.is_some() {
+            return Err(Error::WatchOnly);
+        }
+
         let seed_hex = self.seed.as_deref().unwrap_or(DEFAULT_SEED);
 
         let bytes = hex::decode(seed_hex)?;
@@ -63,11 +107,45 @@ impl Cli {
         })
     }
 
+    /// The database passphrase, prompting for it when the database is encrypted and none was given.
+    fn db_passphrase(&self, config: &Config) -> Result<Option<String>, Error> {
+        if !config.storage.encrypted {
+            return Ok(None);
+        }
+
+        let passphrase = match &self.db_passphrase {
+            Some(passphrase) => passphrase.clone(),
+            None => interactive::prompt_secret("Database passphrase")?,
+        };
+
+        if passphrase.is_empty() {
+            return Err(Error::Config("Database passphrase must not be empty".to_string()));
+        }
+
+        Ok(Some(passphrase))
+    }
+
     async fn get_wallet(&self, config: &Config) -> Result<Wallet, Error> {
-        let seed = self.parse_seed()?;
         let db_path = config.database_path();
+        let db_passphrase = self.db_passphrase(config)?;
 
-        Wallet::open(&seed, &db_path, config.address_params()).await
+        if let Some(public_key) = &self.watch_only {
+            let public_key = public_key
+                .parse::<XOnlyPublicKey>()
+                .map_err(|e| Error::Config(format!("Invalid watch-only public key '{public_key}': {e}")))?;
+
+            return Wallet::open_watch_only(public_key, &db_path, db_passphrase.as_deref(), config.address_params())
+                .await;
+        }
+
+        let seed = self.parse_seed()?;
+
+        Wallet::open(&seed, &db_path, db_passphrase.as_deref(), config.address_params()).await
+    }
+
+    /// The asset registry, extended by `assets.json` next to the config file and the config's `[assets]` section.
+    fn asset_registry(&self, config: &Config) -> Result<AssetRegistry, Error> {
+        AssetRegistry::load(self.config.with_file_name(ASSET_REGISTRY_FILENAME), &config.assets)
     }
 
     async fn get_read_only_client(&self, config: &Config) -> Result<ReadOnlyClient, Error> {
@@ -79,18 +157,31 @@ impl Cli {
     }
 
     async fn get_publishing_client(&self, config: &Config) -> Result<PublishingClient, Error> {
-        let seed = self.parse_seed()?;
         let relay_config = config.relay.get_nostr_relay_config();
 
-        let secret_key =
-            SecretKey::from_slice(&seed).map_err(|e| Error::Config(format!("Invalid seed for NOSTR key: {e}")))?;
-        let keys = nostr::Keys::new(secret_key);
+        let (keys, _) = self.nostr_keys(config)?;
 
         let client = PublishingClient::connect(relay_config, keys).await?;
 
         Ok(client)
     }
 
+    /// Publish an action on a contract, returning the event ID and the key it was signed with.
+    async fn publish_action(
+        &self,
+        config: &Config,
+        action_event: &ActionCompletedEvent,
+    ) -> Result<(EventId, PublicKey), Error> {
+        let publishing_client = self.get_publishing_client(config).await?;
+
+        let published_id = publishing_client.publish_action_completed(action_event).await?;
+        let author = publishing_client.public_key().await?;
+
+        publishing_client.disconnect().await;
+
+        Ok((published_id, author))
+    }
+
     pub async fn run(&self) -> Result<(), Error> {
         let config = self.load_config();
 
@@ -99,9 +190,30 @@ impl Cli {
             Command::Tx { command } => self.run_tx(config, command).await,
             Command::Option { command } => Box::pin(self.run_option(config, command)).await,
             Command::OptionOffer { command } => Box::pin(self.run_option_offer(config, command)).await,
+            Command::Key { command } => self.run_key(&config, command),
+            Command::Authors { command } => self.run_authors(config, command),
             Command::Browse => self.run_browse(config).await,
             Command::Positions => self.run_positions(config).await,
-            Command::Sync { command } => self.run_sync(config, command).await,
+            Command::BestOffer { collateral, settlement } => {
+                self.run_best_offer(config, *collateral, *settlement).await
+            }
+            Command::PriceHistory {
+                collateral,
+                settlement,
+                since,
+            } => self.run_price_history(config, *collateral, *settlement, *since).await,
+            Command::RelayStatus => self.run_relay_status(config).await,
+            Command::Sync { yes, command } => self.run_sync(config, command, *yes).await,
+            Command::Watch { target, interval, hook } => {
+                self.run_watch(config, target, *interval, hook.as_deref()).await
+            }
+            Command::Relink { tpg, event } => self.run_relink(&config, tpg, event).await,
+            Command::Contract { command } => self.run_contract(&config, command).await,
+            Command::Helper { command } => self.run_helper(config, command).await,
+            #[cfg(feature = "tui")]
+            Command::Tui => Box::pin(self.run_tui(config)).await,
+            #[cfg(feature = "dev-commands")]
+            Command::Regtest { command } => Self::run_regtest(&config, command),
             Command::Config => {
                 println!("{config:#?}");
                 Ok(())
@@ -0,0 +1,173 @@
+use crate::cli::Cli;
+use crate::cli::interactive::format_asset_with_tag;
+use crate::config::Config;
+use crate::error::Error;
+use crate::metadata::ContractMetadata;
+use crate::sync::decode_contract_arguments;
+
+use coin_store::UtxoStore;
+use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments};
+
+/// Below this spot/strike ratio the taker pays far more than the collateral is worth.
+const DEEP_OUT_OF_THE_MONEY: f64 = 0.8;
+
+/// How much a taker gains or loses by taking an offer at a given spot price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OfferRisk {
+    /// Collateral plus premium is worth at least the settlement paid
+    Fair,
+    /// The taker overpays, but the premium covers part of the gap
+    Overpriced,
+    /// Deeply out of the money with a premium too small to compensate; likely a trap
+    Trap,
+}
+
+impl std::fmt::Display for OfferRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fair => write!(f, "fair"),
+            Self::Overpriced => write!(f, "overpriced"),
+            Self::Trap => write!(f, "TRAP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OfferAnalysis {
+    /// Spot price over the offer's strike (settlement per collateral)
+    moneyness: f64,
+    /// Premium received per unit of settlement paid, when premium and settlement are the same asset
+    premium_yield: Option<f64>,
+    /// Value received (collateral at spot plus premium) over settlement paid, minus one
+    taker_edge: f64,
+    risk: OfferRisk,
+}
+
+impl Cli {
+    pub(crate) async fn run_offer_analyze(&self, config: Config, offer_event: &str, spot: f64) -> Result<(), Error> {
+        if !spot.is_finite() || spot <= 0.0 {
+            return Err(Error::Config("Spot price must be greater than 0".to_string()));
+        }
+
+        let wallet = self.get_wallet(&config).await?;
+        let store = wallet.store();
+
+        let offer_contracts =
+            <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, OPTION_OFFER_SOURCE).await?;
+        let args = offer_contracts
+            .into_iter()
+            .find_map(|(args_bytes, tpg_str, metadata_bytes)| {
+                let metadata = ContractMetadata::from_bytes(metadata_bytes.as_ref()?).ok()?;
+                if !metadata.nostr_event_id.as_ref()?.starts_with(offer_event) {
+                    return None;
+                }
+                let arguments = decode_contract_arguments(&args_bytes, &tpg_str)?;
+                OptionOfferArguments::from_arguments(&arguments).ok()
+            })
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Offer event not found: {offer_event}. Run `sync nostr` to import it first."
+                ))
+            })?;
+
+        let analysis = analyze_offer(&args, spot);
+
+        let collateral_name = format_asset_with_tag(store, &args.get_collateral_asset_id()).await;
+        let settlement_name = format_asset_with_tag(store, &args.get_settlement_asset_id()).await;
+        let premium_name = format_asset_with_tag(store, &args.get_premium_asset_id()).await;
+
+        println!("Option Offer Analysis:");
+        println!("----------------------");
+        println!(
+            "  Strike: {} {settlement_name} per {collateral_name}",
+            args.collateral_per_contract()
+        );
+        println!("  Spot: {spot} {settlement_name} per {collateral_name}");
+        println!(
+            "  Premium: {} {premium_name} per {collateral_name}",
+            args.premium_per_collateral()
+        );
+        println!("  Moneyness (spot / strike): {:.3}", analysis.moneyness);
+        match analysis.premium_yield {
+            Some(premium_yield) => println!("  Premium / strike: {:.2}%", premium_yield * 100.0),
+            None => println!("  Premium / strike: n/a (premium is not paid in the settlement asset)"),
+        }
+        println!("  Taker edge: {:+.2}%", analysis.taker_edge * 100.0);
+        println!("  Risk: {}", analysis.risk);
+
+        if analysis.risk == OfferRisk::Trap {
+            println!();
+            println!("Taking this offer pays far more than the collateral is worth at the given spot price.");
+        }
+
+        Ok(())
+    }
+}
+
+/// Compare what a taker pays (the strike) with what they receive (collateral at `spot`, plus premium).
+///
+/// The premium only counts towards the value received when it is paid in the settlement asset,
+/// since `spot` is the only price supplied.
+#[allow(clippy::cast_precision_loss)]
+fn analyze_offer(args: &OptionOfferArguments, spot: f64) -> OfferAnalysis {
+    let strike = args.collateral_per_contract() as f64;
+    let moneyness = spot / strike;
+
+    let premium_yield = (args.get_premium_asset_id() == args.get_settlement_asset_id())
+        .then(|| args.premium_per_collateral() as f64 / strike);
+
+    let taker_edge = moneyness + premium_yield.unwrap_or(0.0) - 1.0;
+
+    let risk = if taker_edge >= 0.0 {
+        OfferRisk::Fair
+    } else if moneyness < DEEP_OUT_OF_THE_MONEY {
+        OfferRisk::Trap
+    } else {
+        OfferRisk::Overpriced
+    };
+
+    OfferAnalysis {
+        moneyness,
+        premium_yield,
+        taker_edge,
+        risk,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::AssetId;
+
+    fn asset(byte: u8) -> AssetId {
+        AssetId::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn offer_args(premium_asset: u8, price: u64, premium: u64) -> OptionOfferArguments {
+        OptionOfferArguments::new(
+            asset(1),
+            asset(premium_asset),
+            asset(2),
+            price,
+            premium,
+            1_704_067_200,
+            [2; 32],
+        )
+    }
+
+    #[test]
+    fn test_analyze_offer_flags_deep_out_of_the_money() {
+        let fair = analyze_offer(&offer_args(2, 100, 5), 98.0);
+        assert_eq!(fair.risk, OfferRisk::Fair);
+        assert_eq!(fair.premium_yield, Some(0.05));
+
+        let overpriced = analyze_offer(&offer_args(2, 100, 1), 90.0);
+        assert_eq!(overpriced.risk, OfferRisk::Overpriced);
+
+        let trap = analyze_offer(&offer_args(3, 100, 50), 50.0);
+        assert_eq!(trap.risk, OfferRisk::Trap);
+        assert_eq!(trap.premium_yield, None);
+        assert!((trap.moneyness - 0.5).abs() < f64::EPSILON);
+    }
+}
@@ -1,29 +1,37 @@
 use std::collections::HashMap;
 
+use crate::cli::inputs::{
+    aggregate_inputs, ensure_wallet_fee_inputs, prefer_outputs_of, remove_empty_fee_change, select_inputs,
+    spread_inputs, wallet_fee_input,
+};
 use crate::cli::interactive::{
     GRANTOR_TOKEN_TAG, OPTION_TOKEN_TAG, current_timestamp, extract_entries_from_result, extract_entries_from_results,
-    format_relative_time, get_grantor_tokens_from_wallet, get_option_tokens_from_wallet, parse_expiry, prompt_amount,
-    select_enriched_token_interactive,
+    format_relative_time, get_contract_states, get_grantor_tokens_from_wallet, get_option_tokens_from_wallet,
+    prompt_amount, resolve_expiry, select_enriched_token_interactive,
 };
-use crate::cli::{Cli, OptionCommand};
+use crate::cli::{Cli, OptionCommand, TrackerLog};
 use crate::config::Config;
 use crate::error::Error;
-use crate::fee::{PLACEHOLDER_FEE, estimate_fee_signed};
+use crate::fee::{
+    CpfpParent, PLACEHOLDER_FEE, check_fee_ratio, estimate_fee_signed, lbtc_fee_shortfall, select_with_fee,
+};
 use crate::metadata::{ContractMetadata, HistoryEntry};
-use crate::signing::sign_p2pk_inputs;
-use crate::sync::add_history_entry;
+use crate::signing::{finalize_contract_input, sign_p2pk_inputs, verify_finalized_input};
+use crate::wallet::Wallet;
 
-use coin_store::{UtxoFilter, UtxoStore};
-use contracts::options::{OPTION_SOURCE, OptionsArguments, finalize_options_transaction, get_options_program};
+use coin_store::{Store, UtxoEntry, UtxoFilter, UtxoStore};
+use contracts::options::{OPTION_SOURCE, OptionsArguments, get_options_program};
 use contracts::sdk::taproot_pubkey_gen::{TaprootPubkeyGen, get_random_seed};
 use options_relay::{ActionCompletedEvent, ActionType, OptionCreatedEvent};
 use simplicityhl::elements::pset::serialize::Serialize;
 use simplicityhl::elements::secp256k1_zkp::SECP256K1;
-use simplicityhl::elements::{OutPoint, TxOut, TxOutSecrets};
+use simplicityhl::elements::{AddressParams, OutPoint, Script, Transaction, TxOut, TxOutSecrets, Txid};
 use simplicityhl::simplicity::hex::DisplayHex;
-use simplicityhl::tracker::TrackerLogLevel;
 use simplicityhl_core::{LIQUID_TESTNET_BITCOIN_ASSET, LIQUID_TESTNET_GENESIS, derive_public_blinder_key};
 
+/// Position of the fee input in an option exercise transaction.
+const EXERCISE_FEE_INPUT_INDEX: usize = 3;
+
 impl Cli {
     #[allow(clippy::too_many_lines)]
     pub(crate) async fn run_option(&self, config: Config, command: &OptionCommand) -> Result<(), Error> {
@@ -38,6 +46,7 @@ impl Cli {
                 total_strike,
                 expiry,
                 fee,
+                relays,
                 broadcast,
             } => {
                 println!("Creating option contract...");
@@ -59,7 +68,7 @@ impl Cli {
                 let collateral_per_contract = *total_collateral / *num_contracts;
                 let settlement_per_contract = *total_strike / *num_contracts;
 
-                let expiry_time = parse_expiry(expiry)?;
+                let expiry_time = resolve_expiry(expiry.as_deref(), &config)?;
                 let start_time = current_timestamp();
 
                 println!("  Total collateral: {total_collateral} of {collateral_asset}");
@@ -67,9 +76,9 @@ impl Cli {
                 println!("  Number of contracts: {num_contracts}");
                 println!("  Per-contract collateral: {collateral_per_contract}");
                 println!("  Per-contract strike: {settlement_per_contract}");
-                println!("  Expiry: {} ({})", expiry, format_relative_time(expiry_time));
+                println!("  Expiry: {expiry_time} ({})", format_relative_time(expiry_time));
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let is_lbtc_collateral = *collateral_asset == *LIQUID_TESTNET_BITCOIN_ASSET;
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
@@ -97,8 +106,8 @@ impl Cli {
 
                 let coll_query_results;
 
-                let (collateral_outpoint, collateral_txout, funding_fee_utxo) = if is_lbtc_collateral {
-                    (*lbtc_entries[2].outpoint(), lbtc_entries[2].txout().clone(), None)
+                let ((collateral_outpoint, collateral_txout), funding_fee_utxo) = if is_lbtc_collateral {
+                    (lbtc_entries[2].as_input(), None)
                 } else {
                     let collateral_filter = UtxoFilter::new()
                         .asset_id(*collateral_asset)
@@ -112,14 +121,13 @@ impl Cli {
                     })?;
 
                     (
-                        *coll_entry.outpoint(),
-                        coll_entry.txout().clone(),
-                        Some((*lbtc_entries[2].outpoint(), lbtc_entries[2].txout().clone())),
+                        coll_entry.as_input(),
+                        Some(wallet_fee_input(&lbtc_entries[2], &script_pubkey)?),
                     )
                 };
 
-                let first_fee_utxo = (*lbtc_entries[0].outpoint(), lbtc_entries[0].txout().clone());
-                let second_fee_utxo = (*lbtc_entries[1].outpoint(), lbtc_entries[1].txout().clone());
+                let first_fee_utxo = wallet_fee_input(&lbtc_entries[0], &script_pubkey)?;
+                let second_fee_utxo = wallet_fee_input(&lbtc_entries[1], &script_pubkey)?;
 
                 let issuance_asset_entropy = get_random_seed();
 
@@ -194,172 +202,143 @@ impl Cli {
                 );
                 let collateral_utxo = (collateral_outpoint, collateral_txout);
 
-                let funding_fee = if let Some(f) = fee {
-                    *f
-                } else {
-                    let (pst, branch) = contracts::sdk::build_option_funding(
-                        &blinding_keypair,
-                        option_token_utxo.clone(),
-                        grantor_token_utxo.clone(),
-                        collateral_utxo.clone(),
-                        funding_fee_utxo.as_ref(),
-                        &args,
-                        *total_collateral,
-                        PLACEHOLDER_FEE,
-                    )?;
-                    let mut tx = pst.extract_tx()?;
-                    let mut utxos: Vec<TxOut> = vec![
-                        option_token_utxo.1.clone(),
-                        grantor_token_utxo.1.clone(),
-                        collateral_utxo.1.clone(),
-                    ];
-                    if let Some((_, fee_txout)) = &funding_fee_utxo {
-                        utxos.push(fee_txout.clone());
-                    }
-                    let options_program = get_options_program(&args)?;
-                    for i in 0..2 {
-                        tx = finalize_options_transaction(
-                            tx,
-                            &taproot_pubkey_gen.get_x_only_pubkey(),
-                            &options_program,
-                            &utxos,
-                            i,
-                            &branch,
-                            config.address_params(),
-                            *LIQUID_TESTNET_GENESIS,
-                            TrackerLogLevel::None,
-                        )?;
-                    }
-                    let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 2)?;
-                    let signed_weight = tx.weight();
-                    let fee_rate = config.get_fee_rate();
-                    let estimated = crate::fee::calculate_fee(signed_weight, fee_rate);
-                    println!(
-                        "Estimated funding fee: {estimated} sats (signed weight: {signed_weight}, rate: {fee_rate} sats/kvb)"
-                    );
-                    estimated
-                };
-
-                println!("  Funding fee: {funding_fee} sats");
-
-                let (funding_pst, option_branch) = contracts::sdk::build_option_funding(
-                    &blinding_keypair,
-                    option_token_utxo.clone(),
-                    grantor_token_utxo.clone(),
-                    collateral_utxo.clone(),
-                    funding_fee_utxo.as_ref(),
+                let funding_tx = build_funding_tx(
+                    &wallet,
+                    &config,
                     &args,
+                    &taproot_pubkey_gen,
+                    option_token_utxo,
+                    grantor_token_utxo,
+                    collateral_utxo,
+                    funding_fee_utxo,
                     *total_collateral,
-                    funding_fee,
+                    *fee,
+                    self.tracker_log,
                 )?;
 
-                let mut funding_tx = funding_pst.extract_tx()?;
-                let mut funding_utxos: Vec<TxOut> = vec![
-                    option_token_utxo.1.clone(),
-                    grantor_token_utxo.1.clone(),
-                    collateral_utxo.1.clone(),
-                ];
-                if let Some((_, fee_txout)) = &funding_fee_utxo {
-                    funding_utxos.push(fee_txout.clone());
-                }
+                if *broadcast {
+                    cli_helper::explorer::broadcast_tx(&creation_tx).await?;
+                    println!("Creation tx: {creation_txid}");
 
-                let options_program = get_options_program(&args)?;
-                for i in 0..2 {
-                    funding_tx = finalize_options_transaction(
-                        funding_tx,
-                        &taproot_pubkey_gen.get_x_only_pubkey(),
-                        &options_program,
-                        &funding_utxos,
-                        i,
-                        &option_branch,
-                        config.address_params(),
-                        *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
-                    )?;
+                    // Record the tokens before funding so a failed funding broadcast can be resumed
+                    record_option_creation(
+                        wallet.store(),
+                        &args,
+                        &taproot_pubkey_gen,
+                        &creation_tx,
+                        *total_collateral,
+                        start_time,
+                    )
+                    .await?;
+
+                    if let Err(e) = cli_helper::explorer::broadcast_tx(&funding_tx).await {
+                        println!(
+                            "Funding broadcast failed. Retry with: option resume-funding {creation_txid} --broadcast"
+                        );
+                        return Err(e.into());
+                    }
+                    println!("Funding tx: {}", funding_tx.txid());
+
+                    self.publish_option_funding(&wallet, &config, &args, &taproot_pubkey_gen, &funding_tx, relays)
+                        .await?;
+                } else {
+                    println!("Creation tx: {}", creation_tx.serialize().to_lower_hex_string());
+                    println!("Funding tx: {}", funding_tx.serialize().to_lower_hex_string());
                 }
 
-                let funding_tx = sign_p2pk_inputs(funding_tx, &funding_utxos, &wallet, config.address_params(), 2)?;
+                Ok(())
+            }
+            OptionCommand::ResumeFunding {
+                creation_txid,
+                fee,
+                relays,
+                broadcast,
+            } => {
+                println!("Resuming funding of option creation {creation_txid}...");
 
-                if *broadcast {
-                    cli_helper::explorer::broadcast_tx(&creation_tx).await?;
-                    println!("Creation tx: {}", creation_tx.txid());
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
-                    cli_helper::explorer::broadcast_tx(&funding_tx).await?;
-                    println!("Funding tx: {}", funding_tx.txid());
+                let PendingFunding {
+                    args,
+                    taproot_pubkey_gen,
+                    option_token,
+                    grantor_token,
+                    total_collateral,
+                } = find_pending_funding(wallet.store(), &script_pubkey, wallet.params(), *creation_txid).await?;
 
-                    let publishing_client = self.get_publishing_client(&config).await?;
-                    let funding_outpoint = OutPoint::new(funding_tx.txid(), 0);
-                    let option_event =
-                        OptionCreatedEvent::new(args.clone(), funding_outpoint, taproot_pubkey_gen.clone());
-                    let nostr_event_id = publishing_client.publish_option_created(&option_event).await?;
-                    println!("Published option creation event to NOSTR: {nostr_event_id}");
-
-                    let funded_action =
-                        ActionCompletedEvent::new(nostr_event_id, ActionType::OptionFunded, funding_outpoint);
-                    let funded_event_id = publishing_client.publish_action_completed(&funded_action).await?;
-                    println!("Published funding action: {funded_event_id}");
-
-                    let history = vec![
-                        HistoryEntry::with_txid_and_nostr(
-                            ActionType::OptionCreated.as_str(),
-                            &creation_tx.txid().to_string(),
-                            &nostr_event_id.to_hex(),
-                            start_time,
-                        ),
-                        HistoryEntry::with_txid_and_nostr(
-                            ActionType::OptionFunded.as_str(),
-                            &funding_tx.txid().to_string(),
-                            &funded_event_id.to_hex(),
-                            start_time,
-                        ),
-                    ];
+                let collateral_asset = args.get_collateral_asset_id();
+                let is_lbtc_collateral = collateral_asset == *LIQUID_TESTNET_BITCOIN_ASSET;
+                let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
 
-                    let metadata = ContractMetadata::from_nostr_with_history(
-                        nostr_event_id.to_hex(),
-                        publishing_client.public_key().await?.to_hex(),
-                        start_time,
-                        history,
-                    );
-                    let metadata_bytes = metadata.to_bytes()?;
-
-                    wallet
-                        .store()
-                        .add_contract(
-                            OPTION_SOURCE,
-                            args.build_option_arguments(),
-                            taproot_pubkey_gen.clone(),
-                            Some(&metadata_bytes),
-                        )
-                        .await?;
+                println!("  Total collateral: {total_collateral} of {collateral_asset}");
 
-                    let mut blinder_keys = HashMap::new();
-                    blinder_keys.insert(0, blinding_keypair);
-                    wallet
-                        .store()
-                        .insert_transaction(&creation_tx, blinder_keys.clone())
-                        .await?;
-                    blinder_keys.insert(1, blinding_keypair);
-                    wallet.store().insert_transaction(&funding_tx, blinder_keys).await?;
+                let (collateral_utxo, funding_fee_utxo) = if is_lbtc_collateral {
+                    let collateral_filter = UtxoFilter::new()
+                        .asset_id(collateral_asset)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(total_collateral + initial_fee)
+                        .limit(1);
+                    let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter]).await?;
+                    let entries = extract_entries_from_results(results);
+                    let entry = entries.first().ok_or_else(|| {
+                        Error::Config(format!(
+                            "Need a single LBTC UTXO of at least {} sats for collateral and fee",
+                            total_collateral + initial_fee
+                        ))
+                    })?;
+
+                    (entry.as_input(), None)
+                } else {
+                    let collateral_filter = UtxoFilter::new()
+                        .asset_id(collateral_asset)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(total_collateral);
+                    let fee_filter = UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee)
+                        .limit(1);
+                    let results =
+                        <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter, fee_filter]).await?;
 
-                    let (option_token_id, _) = args.get_option_token_ids();
-                    let (grantor_token_id, _) = args.get_grantor_token_ids();
+                    let collateral_entries = extract_entries_from_result(&results[0]);
+                    let collateral_entry = collateral_entries.first().ok_or_else(|| {
+                        Error::Config(format!("No UTXOs found for collateral asset {collateral_asset}"))
+                    })?;
+                    let fee_entries = extract_entries_from_result(&results[1]);
+                    let Some(fee_entry) = fee_entries.first() else {
+                        return Err(
+                            lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await,
+                        );
+                    };
 
-                    wallet
-                        .store()
-                        .insert_contract_token(&taproot_pubkey_gen, option_token_id, OPTION_TOKEN_TAG)
-                        .await?;
-                    wallet
-                        .store()
-                        .insert_contract_token(&taproot_pubkey_gen, grantor_token_id, GRANTOR_TOKEN_TAG)
-                        .await?;
+                    (
+                        collateral_entry.as_input(),
+                        Some(wallet_fee_input(fee_entry, &script_pubkey)?),
+                    )
+                };
 
-                    println!("  Option token: {option_token_id}");
-                    println!("  Grantor token: {grantor_token_id}");
-                    println!("  Contract address: {}", taproot_pubkey_gen.address);
+                let funding_tx = build_funding_tx(
+                    &wallet,
+                    &config,
+                    &args,
+                    &taproot_pubkey_gen,
+                    token_input(&option_token)?,
+                    token_input(&grantor_token)?,
+                    collateral_utxo,
+                    funding_fee_utxo,
+                    total_collateral,
+                    *fee,
+                    self.tracker_log,
+                )?;
 
-                    publishing_client.disconnect().await;
+                if *broadcast {
+                    cli_helper::explorer::broadcast_tx(&funding_tx).await?;
+                    println!("Funding tx: {}", funding_tx.txid());
+
+                    self.publish_option_funding(&wallet, &config, &args, &taproot_pubkey_gen, &funding_tx, relays)
+                        .await?;
                 } else {
-                    println!("Creation tx: {}", creation_tx.serialize().to_lower_hex_string());
                     println!("Funding tx: {}", funding_tx.serialize().to_lower_hex_string());
                 }
 
@@ -368,11 +347,17 @@ impl Cli {
             OptionCommand::Exercise {
                 option_token,
                 fee,
+                cpfp_parent,
                 broadcast,
             } => {
                 println!("Exercising option...");
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let cpfp_parent = match cpfp_parent {
+                    Some(txid) => Some(CpfpParent::fetch(*txid).await?),
+                    None => None,
+                };
+
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let option_entries = get_option_tokens_from_wallet(&wallet, OPTION_SOURCE, &script_pubkey).await?;
                 if option_entries.is_empty() {
                     return Err(Error::Config("No option contract tokens found".to_string()));
@@ -435,8 +420,10 @@ impl Cli {
                         .ok_or_else(|| Error::Config("Option token not found or no collateral available".to_string()))?
                 } else {
                     println!("  (Showing one entry per contract with collateral available)");
+                    let states = get_contract_states(&wallet, &entries_with_collateral).await?;
                     select_enriched_token_interactive(
                         &entries_with_collateral,
+                        &states,
                         "Select contract to exercise options from",
                     )?
                 };
@@ -463,66 +450,11 @@ impl Cli {
 
                 println!("  Burning: {amount_to_burn} option tokens");
 
-                let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
-
                 let settlement_asset_id = option_arguments.get_settlement_asset_id();
                 let settlement_required = amount_to_burn * option_arguments.settlement_per_contract();
 
                 let settlement_is_lbtc = settlement_asset_id == *LIQUID_TESTNET_BITCOIN_ASSET;
 
-                let (settlement_input, fee_input) = if settlement_is_lbtc {
-                    let combined_filter = UtxoFilter::new()
-                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                        .script_pubkey(script_pubkey.clone())
-                        .required_value(settlement_required + initial_fee);
-
-                    let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[combined_filter]).await?;
-                    let entries = extract_entries_from_result(&results[0]);
-
-                    if entries.is_empty() {
-                        return Err(Error::Config(format!(
-                            "No LBTC UTXOs found for settlement + fee. Need {} sats",
-                            settlement_required + initial_fee
-                        )));
-                    }
-
-                    let utxo = &entries[0];
-                    ((*utxo.outpoint(), utxo.txout().clone()), None)
-                } else {
-                    // Separate queries for different assets
-                    let fee_filter = UtxoFilter::new()
-                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                        .script_pubkey(script_pubkey.clone())
-                        .required_value(initial_fee);
-
-                    let settlement_filter = UtxoFilter::new()
-                        .asset_id(settlement_asset_id)
-                        .script_pubkey(script_pubkey.clone())
-                        .required_value(settlement_required);
-
-                    let results =
-                        <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter, settlement_filter]).await?;
-                    let fee_entries = extract_entries_from_result(&results[0]);
-                    let settlement_entries = extract_entries_from_result(&results[1]);
-
-                    if fee_entries.is_empty() {
-                        return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
-                    }
-                    if settlement_entries.is_empty() {
-                        return Err(Error::Config(format!(
-                            "No settlement asset UTXOs found. Need {settlement_required} of {settlement_asset_id}"
-                        )));
-                    }
-
-                    let fee_utxo = &fee_entries[0];
-                    let settlement_utxo = &settlement_entries[0];
-
-                    (
-                        (*settlement_utxo.outpoint(), settlement_utxo.txout().clone()),
-                        Some((*fee_utxo.outpoint(), fee_utxo.txout().clone())),
-                    )
-                };
-
                 let collateral_filter = UtxoFilter::new()
                     .taproot_pubkey_gen(taproot_pubkey_gen.clone())
                     .asset_id(option_arguments.get_collateral_asset_id());
@@ -536,72 +468,166 @@ impl Cli {
 
                 let collateral_entry = &collateral_entries[0];
 
-                let collateral_input = (*collateral_entry.outpoint(), collateral_entry.txout().clone());
-                let option_input = (*option_entry.outpoint(), option_entry.txout().clone());
+                let collateral_input = collateral_entry.as_input();
+                let option_input = option_entry.as_input();
+
+                // Re-selects when the estimated fee outgrows what the selected inputs leave over
+                let ((settlement_input, fee_inputs), actual_fee) = select_with_fee(
+                    *fee,
+                    async |required_fee| {
+                        if settlement_is_lbtc {
+                            let combined_filter = UtxoFilter::new()
+                                .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                                .script_pubkey(script_pubkey.clone())
+                                .required_value(settlement_required + required_fee);
+
+                            let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[combined_filter]).await?;
+                            let entries = extract_entries_from_result(&results[0]);
+
+                            if entries.is_empty() {
+                                return Err(Error::Config(format!(
+                                    "No LBTC UTXOs found for settlement + fee. Need {} sats",
+                                    settlement_required + required_fee
+                                )));
+                            }
+
+                            let utxo = &entries[0];
+                            let fee_capacity = utxo.value().unwrap_or(0).saturating_sub(settlement_required);
+                            Ok(((utxo.as_input(), Vec::new()), fee_capacity))
+                        } else {
+                            // Separate queries for different assets
+                            let fee_filter = config.selection.for_contract(
+                                UtxoFilter::new()
+                                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                                    .script_pubkey(script_pubkey.clone())
+                                    .required_value(required_fee),
+                                &taproot_pubkey_gen,
+                            );
+
+                            let settlement_filter = UtxoFilter::new()
+                                .asset_id(settlement_asset_id)
+                                .script_pubkey(script_pubkey.clone())
+                                .required_value(settlement_required);
+
+                            let results =
+                                <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter, settlement_filter]).await?;
+                            let mut fee_entries = extract_entries_from_result(&results[0]);
+                            let settlement_entries = extract_entries_from_result(&results[1]);
+                            if let Some(parent) = &cpfp_parent {
+                                prefer_outputs_of(&mut fee_entries, parent.txid);
+                            }
+
+                            if fee_entries.is_empty() {
+                                // The first round only carries the placeholder fee, so estimate instead
+                                let known_fee = Some(required_fee).filter(|required| *required > PLACEHOLDER_FEE);
+                                return Err(
+                                    lbtc_fee_shortfall(wallet.store(), &script_pubkey, known_fee, config.get_fee_rate())
+                                        .await,
+                                );
+                            }
+                            if settlement_entries.is_empty() {
+                                return Err(Error::Config(format!(
+                                    "No settlement asset UTXOs found. Need {settlement_required} of {settlement_asset_id}"
+                                )));
+                            }
+
+                            // Several small LBTC outputs can pay the fee together
+                            let fee_inputs = select_inputs(&fee_entries, required_fee, None, "LBTC fee")?;
+                            ensure_wallet_fee_inputs(&fee_inputs, &script_pubkey)?;
+                            let fee_capacity: u64 = fee_inputs
+                                .iter()
+                                .filter_map(|(_, txout)| txout.value.explicit())
+                                .sum();
+                            let settlement_utxo = &settlement_entries[0];
+
+                            Ok(((settlement_utxo.as_input(), fee_inputs), fee_capacity))
+                        }
+                    },
+                    |(settlement_input, fee_inputs)| {
+                        if let Some(f) = fee {
+                            return Ok(*f);
+                        }
 
-                let actual_fee = if let Some(f) = fee {
-                    *f
-                } else {
-                    let (pst, branch) = contracts::sdk::build_option_exercise(
-                        collateral_input.clone(),
-                        option_input.clone(),
-                        settlement_input.clone(),
-                        fee_input.clone(),
-                        amount_to_burn,
-                        PLACEHOLDER_FEE,
-                        &option_arguments,
-                    )?;
-                    let mut tx = pst.extract_tx()?;
-                    let mut utxos = vec![
-                        collateral_input.1.clone(),
-                        option_input.1.clone(),
-                        settlement_input.1.clone(),
-                    ];
-                    if let Some(ref fi) = fee_input {
-                        utxos.push(fi.1.clone());
-                    }
-                    let options_program = get_options_program(&option_arguments)?;
-                    tx = finalize_options_transaction(
-                        tx,
-                        &taproot_pubkey_gen.get_x_only_pubkey(),
-                        &options_program,
-                        &utxos,
-                        0,
-                        &branch,
-                        config.address_params(),
-                        *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
-                    )?;
-                    let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
-                    let signed_weight = tx.weight();
-                    let fee_rate = config.get_fee_rate();
-                    let estimated = crate::fee::calculate_fee(signed_weight, fee_rate);
-                    println!(
-                        "Estimated fee: {estimated} sats (signed weight: {signed_weight}, rate: {fee_rate} sats/kvb)"
-                    );
-                    estimated
-                };
+                        let fee_input = (!fee_inputs.is_empty())
+                            .then(|| aggregate_inputs(fee_inputs, "LBTC fee"))
+                            .transpose()?;
+                        let (mut pst, branch) = contracts::sdk::build_option_exercise(
+                            collateral_input.clone(),
+                            option_input.clone(),
+                            settlement_input.clone(),
+                            fee_input,
+                            amount_to_burn,
+                            PLACEHOLDER_FEE,
+                            &option_arguments,
+                        )?;
+                        spread_inputs(&mut pst, EXERCISE_FEE_INPUT_INDEX, fee_inputs);
+                        let mut tx = pst.extract_tx()?;
+                        remove_empty_fee_change(&mut tx, &script_pubkey);
+                        let mut utxos = vec![
+                            collateral_input.1.clone(),
+                            option_input.1.clone(),
+                            settlement_input.1.clone(),
+                        ];
+                        utxos.extend(fee_inputs.iter().map(|(_, txout)| txout.clone()));
+                        let options_program = get_options_program(&option_arguments)?;
+                        tx = finalize_contract_input(
+                            tx,
+                            &taproot_pubkey_gen.get_x_only_pubkey(),
+                            &options_program,
+                            &utxos,
+                            0,
+                            &branch,
+                            config.address_params(),
+                            *LIQUID_TESTNET_GENESIS,
+                            self.tracker_log.level(),
+                        )?;
+                        let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
+                        let signed_weight = tx.weight();
+                        let fee_rate = config.get_fee_rate();
+                        let estimated = crate::fee::calculate_fee(signed_weight, fee_rate);
+                        println!(
+                            "Estimated fee: {estimated} sats (signed weight: {signed_weight}, rate: {fee_rate} sats/kvb)"
+                        );
+                        Ok(cpfp_parent.map_or(estimated, |parent| parent.child_fee(signed_weight, fee_rate)))
+                    },
+                )
+                .await?;
 
                 println!("  Fee: {actual_fee} sats");
 
-                let (pst, option_branch) = contracts::sdk::build_option_exercise(
+                if let Some(parent) = &cpfp_parent {
+                    parent.ensure_spent_by(
+                        [collateral_input.0, option_input.0, settlement_input.0]
+                            .into_iter()
+                            .chain(fee_inputs.iter().map(|(outpoint, _)| *outpoint)),
+                    )?;
+                }
+
+                let fee_input = (!fee_inputs.is_empty())
+                    .then(|| aggregate_inputs(&fee_inputs, "LBTC fee"))
+                    .transpose()?;
+                if fee_inputs.len() > 1 {
+                    println!("  Fee inputs: {}", fee_inputs.len());
+                }
+
+                let (mut pst, option_branch) = contracts::sdk::build_option_exercise(
                     collateral_input.clone(),
                     option_input.clone(),
                     settlement_input.clone(),
-                    fee_input.clone(),
+                    fee_input,
                     amount_to_burn,
                     actual_fee,
                     &option_arguments,
                 )?;
+                spread_inputs(&mut pst, EXERCISE_FEE_INPUT_INDEX, &fee_inputs);
 
                 let mut tx = pst.extract_tx()?;
+                remove_empty_fee_change(&mut tx, &script_pubkey);
                 let mut utxos = vec![collateral_input.1, option_input.1, settlement_input.1];
-                if let Some(fi) = fee_input {
-                    utxos.push(fi.1);
-                }
+                utxos.extend(fee_inputs.into_iter().map(|(_, txout)| txout));
 
                 let options_program = get_options_program(&option_arguments)?;
-                tx = finalize_options_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &options_program,
@@ -610,8 +636,20 @@ impl Cli {
                     &option_branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &options_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "exercise",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
 
@@ -619,33 +657,41 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(metadata) =
+                    let published = if let Some(metadata) =
                         crate::sync::get_contract_metadata(wallet.store(), &taproot_pubkey_gen).await?
                         && let Some(ref nostr_event_id) = metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::OptionExercised,
                             OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published action to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
-
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::OptionExercised.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    add_history_entry(wallet.store(), &taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        &taproot_pubkey_gen,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -656,10 +702,11 @@ impl Cli {
                 grantor_token,
                 fee,
                 broadcast,
+                force,
             } => {
                 println!("Expiring option...");
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let grantor_entries = get_grantor_tokens_from_wallet(&wallet, OPTION_SOURCE, &script_pubkey).await?;
                 if grantor_entries.is_empty() {
                     return Err(Error::Config("No grantor tokens found".to_string()));
@@ -724,8 +771,10 @@ impl Cli {
                         })?
                 } else {
                     println!("  (Showing one entry per contract with collateral available)");
+                    let states = get_contract_states(&wallet, &entries_with_collateral).await?;
                     select_enriched_token_interactive(
                         &entries_with_collateral,
+                        &states,
                         "Select contract to expire options from",
                     )?
                 };
@@ -753,16 +802,19 @@ impl Cli {
                 println!("  Burning: {amount_to_burn} grantor tokens");
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(initial_fee);
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee),
+                    &taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
                 let fee_entries = extract_entries_from_result(&results[0]);
 
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
                 let fee_utxo = &fee_entries[0];
@@ -785,9 +837,9 @@ impl Cli {
 
                 let collateral_entry = &collateral_entries[0];
 
-                let collateral_input = (*collateral_entry.outpoint(), collateral_entry.txout().clone());
-                let grantor_input = (*grantor_entry.outpoint(), grantor_entry.txout().clone());
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let collateral_input = collateral_entry.as_input();
+                let grantor_input = grantor_entry.as_input();
+                let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
 
                 let actual_fee = if let Some(f) = fee {
                     *f
@@ -803,7 +855,7 @@ impl Cli {
                     let mut tx = pst.extract_tx()?;
                     let utxos = vec![collateral_input.1.clone(), grantor_input.1.clone(), fee_input.1.clone()];
                     let options_program = get_options_program(&option_arguments)?;
-                    tx = finalize_options_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
                         &options_program,
@@ -812,7 +864,7 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
                     let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
                     let signed_weight = tx.weight();
@@ -826,6 +878,14 @@ impl Cli {
 
                 println!("  Fee: {actual_fee} sats");
 
+                let collateral_reclaimed = amount_to_burn.saturating_mul(option_arguments.collateral_per_contract());
+                check_fee_ratio(
+                    actual_fee,
+                    &[(option_arguments.get_collateral_asset_id(), collateral_reclaimed)],
+                    config.safety.max_fee_ratio,
+                    *force,
+                )?;
+
                 let (pst, option_branch) = contracts::sdk::build_option_expiry(
                     collateral_input.clone(),
                     grantor_input.clone(),
@@ -839,7 +899,7 @@ impl Cli {
                 let utxos = vec![collateral_input.1, grantor_input.1, fee_input.1];
 
                 let options_program = get_options_program(&option_arguments)?;
-                tx = finalize_options_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &options_program,
@@ -848,8 +908,20 @@ impl Cli {
                     &option_branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &options_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "expiry",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
 
@@ -857,30 +929,38 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(metadata) =
+                    let published = if let Some(metadata) =
                         crate::sync::get_contract_metadata(wallet.store(), &taproot_pubkey_gen).await?
                         && let Some(ref nostr_event_id) = metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event =
                             ActionCompletedEvent::new(event_id, ActionType::OptionExpired, OutPoint::new(tx.txid(), 0));
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published action to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
-
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::OptionExpired.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    add_history_entry(wallet.store(), &taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        &taproot_pubkey_gen,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -891,10 +971,11 @@ impl Cli {
                 grantor_token,
                 fee,
                 broadcast,
+                force,
             } => {
                 println!("Claiming settlement...");
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let grantor_entries = get_grantor_tokens_from_wallet(&wallet, OPTION_SOURCE, &script_pubkey).await?;
                 if grantor_entries.is_empty() {
                     return Err(Error::Config("No grantor tokens found".to_string()));
@@ -959,8 +1040,10 @@ impl Cli {
                         })?
                 } else {
                     println!("  (Showing one entry per contract with settlement available)");
+                    let states = get_contract_states(&wallet, &entries_with_settlement).await?;
                     select_enriched_token_interactive(
                         &entries_with_settlement,
+                        &states,
                         "Select contract to claim settlement from",
                     )?
                 };
@@ -989,16 +1072,19 @@ impl Cli {
                 println!("  Burning: {amount_to_burn} grantor tokens");
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(initial_fee);
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee),
+                    &taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
                 let fee_entries = extract_entries_from_result(&results[0]);
 
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
                 let fee_utxo = &fee_entries[0];
@@ -1033,9 +1119,9 @@ impl Cli {
                     )));
                 }
 
-                let settlement_input = (*settlement_entry.outpoint(), settlement_entry.txout().clone());
-                let grantor_input = (*grantor_entry.outpoint(), grantor_entry.txout().clone());
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let settlement_input = settlement_entry.as_input();
+                let grantor_input = grantor_entry.as_input();
+                let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
 
                 let actual_fee = if let Some(f) = fee {
                     *f
@@ -1051,7 +1137,7 @@ impl Cli {
                     let mut tx = pst.extract_tx()?;
                     let utxos = vec![settlement_input.1.clone(), grantor_input.1.clone(), fee_input.1.clone()];
                     let options_program = get_options_program(&option_arguments)?;
-                    tx = finalize_options_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
                         &options_program,
@@ -1060,7 +1146,7 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
                     let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
                     let signed_weight = tx.weight();
@@ -1074,6 +1160,13 @@ impl Cli {
 
                 println!("  Fee: {actual_fee} sats");
 
+                check_fee_ratio(
+                    actual_fee,
+                    &[(settlement_asset_id, settlement_needed)],
+                    config.safety.max_fee_ratio,
+                    *force,
+                )?;
+
                 let (pst, option_branch) = contracts::sdk::build_option_settlement(
                     settlement_input.clone(),
                     grantor_input.clone(),
@@ -1087,7 +1180,7 @@ impl Cli {
                 let utxos = vec![settlement_input.1, grantor_input.1, fee_input.1];
 
                 let options_program = get_options_program(&option_arguments)?;
-                tx = finalize_options_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &options_program,
@@ -1096,8 +1189,20 @@ impl Cli {
                     &option_branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &options_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "settlement",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
 
@@ -1105,33 +1210,41 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(metadata) =
+                    let published = if let Some(metadata) =
                         crate::sync::get_contract_metadata(wallet.store(), &taproot_pubkey_gen).await?
                         && let Some(ref nostr_event_id) = metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::SettlementClaimed,
                             OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published action to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
-
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::SettlementClaimed.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    add_history_entry(wallet.store(), &taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        &taproot_pubkey_gen,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -1145,7 +1258,7 @@ impl Cli {
             } => {
                 println!("Cancelling option...");
 
-                let user_script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let token_entries = get_option_tokens_from_wallet(&wallet, OPTION_SOURCE, &user_script_pubkey).await?;
                 if token_entries.is_empty() {
                     return Err(Error::Config("No option tokens found".to_string()));
@@ -1156,7 +1269,10 @@ impl Cli {
                         .iter()
                         .find(|e| e.entry.outpoint() == outpoint)
                         .ok_or_else(|| Error::Config("Option token not found in wallet".to_string()))?,
-                    None => select_enriched_token_interactive(&token_entries, "Select option token to cancel")?,
+                    None => {
+                        let states = get_contract_states(&wallet, &token_entries).await?;
+                        select_enriched_token_interactive(&token_entries, &states, "Select option token to cancel")?
+                    }
                 };
 
                 let option_entry = &enriched_entry.entry;
@@ -1205,17 +1321,20 @@ impl Cli {
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(initial_fee);
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee),
+                    &taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
                 let fee_entries = extract_entries_from_result(&results[0]);
 
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
                 let fee_utxo = &fee_entries[0];
@@ -1233,10 +1352,10 @@ impl Cli {
 
                 let collateral_entry = &collateral_entries[0];
 
-                let collateral_input = (*collateral_entry.outpoint(), collateral_entry.txout().clone());
-                let option_input = (*option_entry.outpoint(), option_entry.txout().clone());
-                let grantor_input = (*grantor_entry.outpoint(), grantor_entry.txout().clone());
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let collateral_input = collateral_entry.as_input();
+                let option_input = option_entry.as_input();
+                let grantor_input = grantor_entry.as_input();
+                let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
 
                 let actual_fee = if let Some(f) = fee {
                     *f
@@ -1258,7 +1377,7 @@ impl Cli {
                         fee_input.1.clone(),
                     ];
                     let options_program = get_options_program(&option_arguments)?;
-                    tx = finalize_options_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
                         &options_program,
@@ -1267,7 +1386,7 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
                     let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
                     let signed_weight = tx.weight();
@@ -1295,7 +1414,7 @@ impl Cli {
                 let utxos = vec![collateral_input.1, option_input.1, grantor_input.1, fee_input.1];
 
                 let options_program = get_options_program(&option_arguments)?;
-                tx = finalize_options_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &options_program,
@@ -1304,8 +1423,20 @@ impl Cli {
                     &option_branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &options_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "cancel",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
 
@@ -1313,33 +1444,41 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(metadata) =
+                    let published = if let Some(metadata) =
                         crate::sync::get_contract_metadata(wallet.store(), &taproot_pubkey_gen).await?
                         && let Some(ref nostr_event_id) = metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::OptionCancelled,
                             OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published action to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
-
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::OptionCancelled.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    add_history_entry(wallet.store(), &taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        &taproot_pubkey_gen,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -1348,4 +1487,419 @@ impl Cli {
             }
         }
     }
+
+    /// Store the funding transaction, announce the funded option on NOSTR and complete its local history.
+    ///
+    /// With `relays` set the announcement only goes to those relays, for options shared privately.
+    async fn publish_option_funding(
+        &self,
+        wallet: &Wallet,
+        config: &Config,
+        args: &OptionsArguments,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        funding_tx: &Transaction,
+        relays: &[String],
+    ) -> Result<(), Error> {
+        let blinding_keypair = derive_public_blinder_key();
+        let blinder_keys = HashMap::from([(0, blinding_keypair), (1, blinding_keypair)]);
+        wallet.store().insert_transaction(funding_tx, blinder_keys).await?;
+
+        let publishing_client = self.get_publishing_client(config).await?;
+        let funding_outpoint = OutPoint::new(funding_tx.txid(), 0);
+        let option_event = OptionCreatedEvent::new(args.clone(), funding_outpoint, taproot_pubkey_gen.clone());
+        let nostr_event_id = if relays.is_empty() {
+            publishing_client.publish_option_created(&option_event).await?
+        } else {
+            publishing_client
+                .publish_option_created_to(&option_event, relays)
+                .await?
+        };
+        println!("Published option creation event to NOSTR: {nostr_event_id}");
+
+        let funded_action = ActionCompletedEvent::new(nostr_event_id, ActionType::OptionFunded, funding_outpoint);
+        let funded_event_id = if relays.is_empty() {
+            publishing_client.publish_action_completed(&funded_action).await?
+        } else {
+            publishing_client
+                .publish_action_completed_to(&funded_action, relays)
+                .await?
+        };
+        println!("Published funding action: {funded_event_id}");
+
+        let mut metadata = crate::sync::get_contract_metadata(wallet.store(), taproot_pubkey_gen)
+            .await?
+            .unwrap_or_default();
+        metadata.nostr_event_id = Some(nostr_event_id.to_hex());
+        metadata.nostr_author = Some(publishing_client.public_key().await?.to_hex());
+        if let Some(created) = metadata
+            .history
+            .iter_mut()
+            .find(|entry| entry.action == ActionType::OptionCreated.as_str())
+        {
+            created.nostr_event_id = Some(nostr_event_id.to_hex());
+        }
+        metadata.add_history(HistoryEntry::with_txid_and_nostr(
+            ActionType::OptionFunded.as_str(),
+            &funding_tx.txid().to_string(),
+            &funded_event_id.to_hex(),
+            current_timestamp(),
+        ));
+        crate::sync::update_contract_metadata(wallet.store(), taproot_pubkey_gen, &metadata).await?;
+
+        let (option_token_id, _) = args.get_option_token_ids();
+        let (grantor_token_id, _) = args.get_grantor_token_ids();
+
+        println!("  Option token: {option_token_id}");
+        println!("  Grantor token: {grantor_token_id}");
+        println!("  Contract address: {}", taproot_pubkey_gen.address);
+
+        publishing_client.disconnect().await;
+
+        Ok(())
+    }
+}
+
+/// Record a broadcast creation transaction and its token outputs so funding can be resumed from local state.
+///
+/// The total collateral is kept in the creation history entry, since it is not part of the contract arguments.
+async fn record_option_creation(
+    store: &Store,
+    args: &OptionsArguments,
+    taproot_pubkey_gen: &TaprootPubkeyGen,
+    creation_tx: &Transaction,
+    total_collateral: u64,
+    created_at: i64,
+) -> Result<(), Error> {
+    let mut created = HistoryEntry::with_txid(
+        ActionType::OptionCreated.as_str(),
+        &creation_tx.txid().to_string(),
+        created_at,
+    );
+    created.details = Some(pending_funding_details(total_collateral));
+
+    let metadata = ContractMetadata {
+        created_at: Some(created_at),
+        history: vec![created],
+        ..ContractMetadata::default()
+    };
+    let metadata_bytes = metadata.to_bytes()?;
+
+    store
+        .add_contract(
+            OPTION_SOURCE,
+            args.build_option_arguments(),
+            taproot_pubkey_gen.clone(),
+            Some(&metadata_bytes),
+            None,
+        )
+        .await?;
+
+    // The secret key is persisted per output in `blinder_keys`, so the token outputs stay
+    // unblindable after a restart without re-deriving the keypair.
+    let blinding_keypair = derive_public_blinder_key();
+    let blinder_keys = HashMap::from([(0, blinding_keypair), (1, blinding_keypair)]);
+    store.insert_transaction(creation_tx, blinder_keys).await?;
+
+    let (option_token_id, _) = args.get_option_token_ids();
+    let (grantor_token_id, _) = args.get_grantor_token_ids();
+
+    store
+        .insert_contract_token(taproot_pubkey_gen, option_token_id, OPTION_TOKEN_TAG)
+        .await?;
+    store
+        .insert_contract_token(taproot_pubkey_gen, grantor_token_id, GRANTOR_TOKEN_TAG)
+        .await?;
+
+    Ok(())
+}
+
+/// A recorded option creation whose funding transaction has not been broadcast yet.
+struct PendingFunding {
+    args: OptionsArguments,
+    taproot_pubkey_gen: TaprootPubkeyGen,
+    option_token: UtxoEntry,
+    grantor_token: UtxoEntry,
+    total_collateral: u64,
+}
+
+/// Look up the token outputs and collateral amount recorded by `record_option_creation`.
+async fn find_pending_funding(
+    store: &Store,
+    script_pubkey: &Script,
+    params: &'static AddressParams,
+    creation_txid: Txid,
+) -> Result<PendingFunding, Error> {
+    let option_filter = UtxoFilter::new()
+        .token_tag(OPTION_TOKEN_TAG)
+        .script_pubkey(script_pubkey.clone());
+    let option_results = <_ as UtxoStore>::query_utxos(store, &[option_filter]).await?;
+    let option_token = extract_entries_from_results(option_results)
+        .into_iter()
+        .find(|e| *e.outpoint() == OutPoint::new(creation_txid, 0))
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "No unfunded option token from {creation_txid} found. \
+                It was never recorded locally or has already been funded."
+            ))
+        })?;
+
+    let grantor_filter = UtxoFilter::new()
+        .token_tag(GRANTOR_TOKEN_TAG)
+        .script_pubkey(script_pubkey.clone());
+    let grantor_results = <_ as UtxoStore>::query_utxos(store, &[grantor_filter]).await?;
+    let grantor_token = extract_entries_from_results(grantor_results)
+        .into_iter()
+        .find(|e| *e.outpoint() == OutPoint::new(creation_txid, 1))
+        .ok_or_else(|| Error::Config(format!("Grantor token from {creation_txid} is not in the wallet")))?;
+
+    let (Some(taproot_pubkey_gen_str), Some(args)) = (
+        option_token.taproot_pubkey_gen(),
+        option_token
+            .arguments()
+            .and_then(|arguments| OptionsArguments::from_arguments(arguments).ok()),
+    ) else {
+        return Err(Error::Config(format!(
+            "Option token from {creation_txid} is not linked to an option contract"
+        )));
+    };
+    let taproot_pubkey_gen = TaprootPubkeyGen::build_from_str(
+        taproot_pubkey_gen_str,
+        &args,
+        params,
+        &contracts::options::get_options_address,
+    )?;
+
+    let total_collateral = crate::sync::get_contract_metadata(store, &taproot_pubkey_gen)
+        .await?
+        .and_then(|metadata| pending_total_collateral(&metadata))
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "No recorded collateral amount for option creation {creation_txid}"
+            ))
+        })?;
+
+    Ok(PendingFunding {
+        args,
+        taproot_pubkey_gen,
+        option_token,
+        grantor_token,
+        total_collateral,
+    })
+}
+
+/// Build and sign the transaction that locks collateral and mints the option and grantor tokens.
+#[allow(clippy::too_many_arguments)]
+fn build_funding_tx(
+    wallet: &Wallet,
+    config: &Config,
+    args: &OptionsArguments,
+    taproot_pubkey_gen: &TaprootPubkeyGen,
+    option_token_utxo: (OutPoint, TxOut, TxOutSecrets),
+    grantor_token_utxo: (OutPoint, TxOut, TxOutSecrets),
+    collateral_utxo: (OutPoint, TxOut),
+    funding_fee_utxo: Option<(OutPoint, TxOut)>,
+    total_collateral: u64,
+    fee: Option<u64>,
+    tracker_log: TrackerLog,
+) -> Result<Transaction, Error> {
+    let blinding_keypair = derive_public_blinder_key();
+    let options_program = get_options_program(args)?;
+
+    let mut utxos: Vec<TxOut> = vec![
+        option_token_utxo.1.clone(),
+        grantor_token_utxo.1.clone(),
+        collateral_utxo.1.clone(),
+    ];
+    if let Some((_, fee_txout)) = &funding_fee_utxo {
+        utxos.push(fee_txout.clone());
+    }
+
+    let build = |funding_fee: u64| -> Result<Transaction, Error> {
+        let (pst, branch) = contracts::sdk::build_option_funding(
+            &blinding_keypair,
+            option_token_utxo.clone(),
+            grantor_token_utxo.clone(),
+            collateral_utxo.clone(),
+            funding_fee_utxo.as_ref(),
+            args,
+            total_collateral,
+            funding_fee,
+        )?;
+
+        let mut tx = pst.extract_tx()?;
+        for i in 0..2 {
+            tx = finalize_contract_input(
+                tx,
+                &taproot_pubkey_gen.get_x_only_pubkey(),
+                &options_program,
+                &utxos,
+                i,
+                &branch,
+                config.address_params(),
+                *LIQUID_TESTNET_GENESIS,
+                tracker_log.level(),
+            )?;
+            if config.safety.verify_finalized {
+                verify_finalized_input(
+                    &tx,
+                    &options_program,
+                    &taproot_pubkey_gen.get_x_only_pubkey(),
+                    &utxos,
+                    i,
+                    "funding",
+                    config.address_params(),
+                    *LIQUID_TESTNET_GENESIS,
+                )?;
+            }
+        }
+
+        sign_p2pk_inputs(tx, &utxos, wallet, config.address_params(), 2)
+    };
+
+    let funding_fee = if let Some(f) = fee {
+        f
+    } else {
+        let signed_weight = build(PLACEHOLDER_FEE)?.weight();
+        let fee_rate = config.get_fee_rate();
+        let estimated = crate::fee::calculate_fee(signed_weight, fee_rate);
+        println!("Estimated funding fee: {estimated} sats (signed weight: {signed_weight}, rate: {fee_rate} sats/kvb)");
+        estimated
+    };
+
+    println!("  Funding fee: {funding_fee} sats");
+
+    build(funding_fee)
+}
+
+/// The unblinded input for a token output created by the option creation transaction.
+fn token_input(entry: &UtxoEntry) -> Result<(OutPoint, TxOut, TxOutSecrets), Error> {
+    let (outpoint, txout, secrets) = entry.as_input_with_secrets();
+    let secrets = secrets.ok_or_else(|| Error::Config(format!("Token output {outpoint} is not unblinded")))?;
+    Ok((outpoint, txout, secrets))
+}
+
+const PENDING_FUNDING_PREFIX: &str = "total_collateral=";
+
+fn pending_funding_details(total_collateral: u64) -> String {
+    format!("{PENDING_FUNDING_PREFIX}{total_collateral}")
+}
+
+/// The total collateral recorded when the option was created, used to rebuild its funding transaction.
+fn pending_total_collateral(metadata: &ContractMetadata) -> Option<u64> {
+    metadata
+        .history
+        .iter()
+        .find(|entry| entry.action == ActionType::OptionCreated.as_str())
+        .and_then(|entry| {
+            entry
+                .details
+                .as_deref()?
+                .strip_prefix(PENDING_FUNDING_PREFIX)?
+                .parse()
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::TxOutWitness;
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::hashes::Hash;
+
+    const PARAMS: &AddressParams = &AddressParams::LIQUID_TESTNET;
+
+    fn lbtc_input(seed: u8, value: u64) -> (OutPoint, TxOut) {
+        let txout = TxOut {
+            asset: Asset::Explicit(*LIQUID_TESTNET_BITCOIN_ASSET),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: Script::from(vec![0x51]),
+            witness: TxOutWitness::default(),
+        };
+        (OutPoint::new(Txid::from_byte_array([seed; 32]), 0), txout)
+    }
+
+    #[test]
+    fn test_pending_total_collateral_roundtrip() {
+        let mut created = HistoryEntry::with_txid(ActionType::OptionCreated.as_str(), "00", 1_704_067_200);
+        created.details = Some(pending_funding_details(150_000));
+        let metadata = ContractMetadata {
+            history: vec![created],
+            ..ContractMetadata::default()
+        };
+
+        assert_eq!(pending_total_collateral(&metadata), Some(150_000));
+        assert_eq!(pending_total_collateral(&ContractMetadata::default()), None);
+    }
+
+    /// A creation recorded before a failed funding broadcast must be enough to rebuild the funding.
+    #[tokio::test]
+    async fn test_resume_funding_after_failed_broadcast() {
+        let path = "/tmp/test_cli_resume_funding.db";
+        let _ = std::fs::remove_file(path);
+        let store = Store::create(path).await.unwrap();
+
+        let asset = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let (first_fee, second_fee) = (lbtc_input(1, 5_000), lbtc_input(2, 5_000));
+        let args = OptionsArguments::new(
+            1_704_067_200,
+            1_706_745_600,
+            10,
+            100,
+            asset,
+            asset,
+            [3; 32],
+            (first_fee.0, false),
+            (second_fee.0, false),
+        );
+        let (pst, taproot_pubkey_gen) = contracts::sdk::build_option_creation(
+            &derive_public_blinder_key().public_key(),
+            first_fee,
+            second_fee,
+            &args,
+            [3; 32],
+            500,
+            PARAMS,
+        )
+        .unwrap();
+        let creation_tx = pst.extract_tx().unwrap();
+        let creation_txid = creation_tx.txid();
+        let script_pubkey = creation_tx.output[0].script_pubkey.clone();
+
+        // The creation is broadcast and recorded; the funding broadcast then fails, so nothing else is stored.
+        record_option_creation(&store, &args, &taproot_pubkey_gen, &creation_tx, 1_000, 1_704_067_200)
+            .await
+            .unwrap();
+
+        let pending = find_pending_funding(&store, &script_pubkey, PARAMS, creation_txid)
+            .await
+            .unwrap();
+        assert_eq!(*pending.option_token.outpoint(), OutPoint::new(creation_txid, 0));
+        assert_eq!(*pending.grantor_token.outpoint(), OutPoint::new(creation_txid, 1));
+        assert_eq!(pending.taproot_pubkey_gen.to_string(), taproot_pubkey_gen.to_string());
+        assert_eq!(pending.total_collateral, 1_000);
+
+        let funding = contracts::sdk::build_option_funding(
+            &derive_public_blinder_key(),
+            token_input(&pending.option_token).unwrap(),
+            token_input(&pending.grantor_token).unwrap(),
+            lbtc_input(4, 10_000),
+            None,
+            &pending.args,
+            pending.total_collateral,
+            500,
+        );
+        assert!(funding.is_ok());
+
+        let unknown = Txid::from_byte_array([9; 32]);
+        assert!(
+            find_pending_funding(&store, &script_pubkey, PARAMS, unknown)
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
 }
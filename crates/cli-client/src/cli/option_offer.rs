@@ -1,32 +1,142 @@
+use crate::cli::inputs::{
+    aggregate_inputs, ensure_wallet_fee_inputs, prefer_outputs_of, remove_empty_fee_change, select_inputs,
+    spread_inputs, wallet_fee_input,
+};
 use crate::cli::interactive::{
-    current_timestamp, extract_entries_from_result, format_relative_time, format_settlement_asset, get_wallet_assets,
-    parse_expiry, prompt_amount, select_asset_interactive, truncate_with_ellipsis,
+    Clock, GRANTOR_TOKEN_TAG, OPTION_TOKEN_TAG, SystemClock, current_timestamp, extract_entries_from_result,
+    format_relative_time, format_relative_time_at, format_settlement_asset, get_wallet_assets, is_offer_expired,
+    prompt_amount, prompt_confirm, resolve_expiry, select_asset_interactive, truncate_with_ellipsis,
 };
 use crate::cli::tables::{
-    display_active_option_offers_table, display_cancellable_option_offers_table,
+    display_active_option_offers_table, display_cancellable_option_offers_table, display_hidden_rows_notice,
     display_withdrawable_option_offers_table,
 };
-use crate::cli::{Cli, OptionOfferCommand};
+use crate::cli::{AmountRounding, Cli, OptionOfferCommand, TrackerLog};
 use crate::config::Config;
 use crate::error::Error;
-use crate::fee::{PLACEHOLDER_FEE, estimate_fee_signed};
+use crate::fee::{
+    CpfpParent, PLACEHOLDER_FEE, calculate_fee, check_fee_ratio, estimate_fee_signed, lbtc_fee_shortfall,
+};
 use crate::metadata::{ContractMetadata, HistoryEntry};
-use crate::signing::sign_p2pk_inputs;
+use crate::signing::{finalize_contract_input, sign_p2pk_inputs, verify_finalized_input};
+use crate::sync::decode_contract_arguments;
 
 use std::collections::HashMap;
-
-use coin_store::{UtxoFilter, UtxoQueryResult, UtxoStore};
-use contracts::option_offer::{
-    OPTION_OFFER_SOURCE, OptionOfferArguments, finalize_option_offer_transaction, get_option_offer_program,
-};
+use std::path::Path;
+use std::str::FromStr;
+
+use coin_store::{NewContract, SelectionStrategy, UtxoEntry, UtxoFilter, UtxoQueryResult, UtxoStore};
+use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments, get_option_offer_program};
+use contracts::options::{OPTION_SOURCE, OptionsArguments};
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use nostr::PublicKey;
 use options_relay::{ActionCompletedEvent, ActionType, OptionOfferCreatedEvent};
+use serde::Deserialize;
+use simplicityhl::elements::pset::PartiallySignedTransaction;
 use simplicityhl::elements::pset::serialize::Serialize;
+use simplicityhl::elements::{AssetId, OutPoint, Transaction, TxOut};
 use simplicityhl::simplicity::hex::DisplayHex;
-use simplicityhl::tracker::TrackerLogLevel;
 use simplicityhl_core::{LIQUID_TESTNET_BITCOIN_ASSET, LIQUID_TESTNET_GENESIS};
 
 pub const OPTION_OFFER_COLLATERAL_TAG: &str = "option_offer_collateral";
 
+/// Position of the settlement input in an option offer exercise transaction.
+const SETTLEMENT_INPUT_INDEX: usize = 2;
+
+/// Position of the fee input in an option offer exercise transaction.
+const FEE_INPUT_INDEX: usize = 3;
+
+/// Position of the fee input in an option offer roll-over transaction.
+const ROLLOVER_FEE_INPUT_INDEX: usize = 2;
+
+/// Rough signed weight of an option offer creation transaction (3 P2PK inputs), used for capacity planning.
+const OPTION_OFFER_CREATE_WEIGHT_ESTIMATE: usize = 4_000;
+
+/// What bounds the number of option offers the wallet can fund.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapacityLimit {
+    /// Balance of the collateral asset, including the premium when it is the same asset.
+    Collateral,
+    /// Balance of the premium asset.
+    Premium,
+    /// LBTC balance, covering fees and any collateral or premium paid in LBTC.
+    LbtcBalance,
+    LbtcUtxoCount,
+}
+
+impl std::fmt::Display for CapacityLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Collateral => write!(f, "collateral balance"),
+            Self::Premium => write!(f, "premium balance"),
+            Self::LbtcBalance => write!(f, "LBTC balance"),
+            Self::LbtcUtxoCount => write!(f, "LBTC UTXO count"),
+        }
+    }
+}
+
+/// What funding a single option offer costs the wallet.
+struct OfferCost {
+    collateral_asset: AssetId,
+    collateral_amount: u64,
+    premium_asset: AssetId,
+    premium_amount: u64,
+    fee: u64,
+}
+
+/// How many offers of `cost` each balance and the LBTC UTXO count allow, one entry per bound.
+///
+/// Each offer spends one collateral, one premium and one fee input, and the fee is always paid in
+/// LBTC. Offers are created one after another, so the change of each fee input is an LBTC UTXO the
+/// next offer can spend.
+fn capacity_limits(
+    cost: &OfferCost,
+    balances: &HashMap<AssetId, u64>,
+    lbtc_utxo_count: u64,
+) -> Vec<(CapacityLimit, u64)> {
+    let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+    let balance_of = |asset| balances.get(&asset).copied().unwrap_or(0);
+
+    let mut needs: Vec<(CapacityLimit, AssetId, u64)> = Vec::new();
+    for (limit, asset, amount) in [
+        (CapacityLimit::Collateral, cost.collateral_asset, cost.collateral_amount),
+        (CapacityLimit::Premium, cost.premium_asset, cost.premium_amount),
+        (CapacityLimit::LbtcBalance, lbtc, cost.fee),
+    ] {
+        let limit = if asset == lbtc {
+            CapacityLimit::LbtcBalance
+        } else {
+            limit
+        };
+        match needs.iter_mut().find(|(_, needed, _)| *needed == asset) {
+            Some((_, _, need)) => *need = need.saturating_add(amount),
+            None => needs.push((limit, asset, amount)),
+        }
+    }
+
+    let mut limits: Vec<(CapacityLimit, u64)> = needs
+        .into_iter()
+        .filter(|(_, _, need)| *need > 0)
+        .map(|(limit, asset, need)| (limit, balance_of(asset) / need))
+        .collect();
+
+    let lbtc_inputs: u64 = 1 + u64::from(cost.collateral_asset == lbtc) + u64::from(cost.premium_asset == lbtc);
+    // The fee change replaces one spent LBTC UTXO per offer
+    let spent_per_offer = lbtc_inputs - 1;
+    let utxo_bound = if lbtc_utxo_count < lbtc_inputs {
+        Some(0)
+    } else if spent_per_offer == 0 {
+        None
+    } else {
+        Some((lbtc_utxo_count - lbtc_inputs) / spent_per_offer + 1)
+    };
+    if let Some(bound) = utxo_bound {
+        limits.push((CapacityLimit::LbtcUtxoCount, bound));
+    }
+
+    limits
+}
+
 pub struct LocalOptionOfferData {
     pub(crate) option_offer_args: OptionOfferArguments,
     pub(crate) taproot_pubkey_gen: contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
@@ -35,6 +145,18 @@ pub struct LocalOptionOfferData {
     pub(crate) current_value: u64,
 }
 
+impl LocalOptionOfferData {
+    /// Collateral to show as on offer.
+    ///
+    /// Collateral only ever decreases, so the lower of the stored UTXO value and the remaining
+    /// collateral last recorded from a take is the more recent one.
+    pub(crate) fn available_collateral(&self) -> u64 {
+        self.metadata
+            .remaining_collateral
+            .map_or(self.current_value, |remaining| remaining.min(self.current_value))
+    }
+}
+
 pub struct LocalCancellableOptionOffer {
     pub(crate) option_offer_args: OptionOfferArguments,
     pub(crate) taproot_pubkey_gen: contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
@@ -89,12 +211,33 @@ impl Cli {
                 settlement_asset,
                 settlement_amount,
                 expiry,
+                collateral_from_contract,
+                label,
+                round,
                 fee,
+                relays,
                 broadcast,
             } => {
+                if let Some(event_id) = collateral_from_contract {
+                    return self
+                        .run_option_offer_rollover(
+                            &wallet,
+                            &config,
+                            event_id,
+                            *settlement_asset,
+                            *settlement_amount,
+                            expiry.as_deref(),
+                            label.as_deref(),
+                            fee.as_ref(),
+                            relays,
+                            *broadcast,
+                        )
+                        .await;
+                }
+
                 println!("Creating option offer...");
 
-                let user_script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
                 let wallet_assets = get_wallet_assets(&wallet, &user_script_pubkey).await?;
 
@@ -105,6 +248,8 @@ impl Cli {
                     selected.asset_id
                 };
 
+                preflight_contract_token_collateral(&wallet, collateral_asset_id, &user_script_pubkey).await?;
+
                 let collateral_amt = if let Some(amt) = collateral_amount {
                     *amt
                 } else {
@@ -122,24 +267,24 @@ impl Cli {
                     selected.asset_id
                 };
 
-                let total_premium = if let Some(amt) = premium_amount {
+                let requested_premium = if let Some(amt) = premium_amount {
                     *amt
                 } else {
                     prompt_amount("Enter total premium amount").map_err(Error::Io)?
                 };
 
-                let premium_per_collateral = if total_premium == 0 {
-                    0
-                } else {
-                    if total_premium % collateral_amt != 0 {
+                let total_premium = match round {
+                    Some(mode) => round_to_multiple(requested_premium, collateral_amt, *mode)?,
+                    None if requested_premium % collateral_amt != 0 => {
                         return Err(Error::Config(format!(
-                            "Premium amount ({total_premium}) must be evenly divisible by collateral amount ({collateral_amt}). \
-                             Remainder: {}",
-                            total_premium % collateral_amt
+                            "Premium amount ({requested_premium}) must be evenly divisible by collateral amount ({collateral_amt}). \
+                             Remainder: {} (use --round to adjust it)",
+                            requested_premium % collateral_amt
                         )));
                     }
-                    total_premium / collateral_amt
+                    None => requested_premium,
                 };
+                let premium_per_collateral = total_premium / collateral_amt;
 
                 let settlement_asset_id = if let Some(asset) = settlement_asset {
                     *asset
@@ -148,27 +293,49 @@ impl Cli {
                     selected.asset_id
                 };
 
-                let settlement_amt = if let Some(amt) = settlement_amount {
+                let requested_settlement = if let Some(amt) = settlement_amount {
                     *amt
                 } else {
                     prompt_amount("Enter total settlement amount expected").map_err(Error::Io)?
                 };
 
-                let collateral_per_contract = if settlement_amt == 0 {
-                    return Err(Error::Config("Settlement amount must be greater than 0".to_string()));
-                } else {
-                    if settlement_amt % collateral_amt != 0 {
+                let settlement_amt = match round {
+                    Some(mode) => round_to_multiple(requested_settlement, collateral_amt, *mode)?,
+                    None if requested_settlement % collateral_amt != 0 => {
                         return Err(Error::Config(format!(
-                            "Settlement amount ({settlement_amt}) must be evenly divisible by collateral amount ({collateral_amt}). \
-                             Remainder: {}",
-                            settlement_amt % collateral_amt
+                            "Settlement amount ({requested_settlement}) must be evenly divisible by collateral amount ({collateral_amt}). \
+                             Remainder: {} (use --round to adjust it)",
+                            requested_settlement % collateral_amt
                         )));
                     }
-                    settlement_amt / collateral_amt
+                    None => requested_settlement,
                 };
+                if settlement_amt == 0 {
+                    return Err(Error::Config("Settlement amount must be greater than 0".to_string()));
+                }
+                let collateral_per_contract = settlement_amt / collateral_amt;
+
+                if total_premium != requested_premium || settlement_amt != requested_settlement {
+                    println!();
+                    println!("Amounts rounded to a multiple of the collateral ({collateral_amt}):");
+                    if total_premium != requested_premium {
+                        println!(
+                            "  Premium: {requested_premium} -> {total_premium} (rate: {premium_per_collateral} per collateral)"
+                        );
+                    }
+                    if settlement_amt != requested_settlement {
+                        println!(
+                            "  Settlement: {requested_settlement} -> {settlement_amt} (rate: {collateral_per_contract} per collateral)"
+                        );
+                    }
+                    if !prompt_confirm("Continue with the rounded amounts?").map_err(Error::Io)? {
+                        println!("Aborted.");
+                        return Ok(());
+                    }
+                }
 
                 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-                let offer_expiry: u32 = parse_expiry(expiry)? as u32;
+                let offer_expiry: u32 = resolve_expiry(expiry.as_deref(), &config)? as u32;
 
                 println!();
                 println!(
@@ -184,7 +351,10 @@ impl Cli {
                     settlement_amt,
                     format_settlement_asset(&settlement_asset_id)
                 );
-                println!("  Expiry: {}", format_relative_time(i64::from(offer_expiry)));
+                println!(
+                    "  Expiry: {offer_expiry} ({})",
+                    format_relative_time(i64::from(offer_expiry))
+                );
 
                 let option_offer_args = OptionOfferArguments::new(
                     collateral_asset_id,
@@ -193,211 +363,163 @@ impl Cli {
                     collateral_per_contract,
                     premium_per_collateral,
                     offer_expiry,
-                    wallet.signer().public_key().serialize(),
+                    wallet.public_key().serialize(),
                 );
 
-                let collateral_filter = UtxoFilter::new()
-                    .asset_id(collateral_asset_id)
-                    .script_pubkey(user_script_pubkey.clone())
-                    .required_value(collateral_amt);
-
-                let premium_filter = UtxoFilter::new()
-                    .asset_id(premium_asset_id)
-                    .script_pubkey(user_script_pubkey.clone())
-                    .required_value(total_premium);
-
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(user_script_pubkey.clone())
-                    .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
-
-                let results =
-                    <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter, premium_filter, fee_filter])
-                        .await?;
-
-                let collateral_entries = extract_entries_from_result(&results[0]);
-                let premium_entries = extract_entries_from_result(&results[1]);
-                let fee_entries = extract_entries_from_result(&results[2]);
-
-                if collateral_entries.is_empty() {
-                    return Err(Error::Config(format!(
-                        "No collateral UTXOs found for asset {}",
-                        format_settlement_asset(&collateral_asset_id)
-                    )));
-                }
-                if premium_entries.is_empty() {
-                    return Err(Error::Config(format!(
-                        "No premium UTXOs found for asset {}. Need {total_premium}",
-                        format_settlement_asset(&premium_asset_id)
-                    )));
-                }
-                if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
-                }
-
-                let collateral_utxo = &collateral_entries[0];
-                let premium_utxo = &premium_entries[0];
-                let fee_utxo = &fee_entries[0];
-
-                let collateral_input = (*collateral_utxo.outpoint(), collateral_utxo.txout().clone());
-                let premium_input = (*premium_utxo.outpoint(), premium_utxo.txout().clone());
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
-
-                let actual_fee = estimate_fee_signed(
-                    fee.as_ref(),
-                    config.get_fee_rate(),
-                    |f| {
-                        let (pst, _) = contracts::sdk::build_option_offer_deposit(
-                            collateral_input.clone(),
-                            premium_input.clone(),
-                            fee_input.clone(),
-                            collateral_amt,
-                            f,
-                            &option_offer_args,
-                            config.address_params(),
-                        )?;
-                        Ok((
-                            pst,
-                            vec![collateral_input.1.clone(), premium_input.1.clone(), fee_input.1.clone()],
-                        ))
-                    },
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
-                )?;
-
-                println!("  Fee: {actual_fee} sats");
-
-                let (pst, taproot_pubkey_gen) = contracts::sdk::build_option_offer_deposit(
-                    collateral_input.clone(),
-                    premium_input.clone(),
-                    fee_input.clone(),
-                    collateral_amt,
-                    actual_fee,
+                let (tx, taproot_pubkey_gen, actual_fee) = build_offer_deposit(
+                    &wallet,
+                    &config,
                     &option_offer_args,
-                    config.address_params(),
-                )?;
-
-                let tx = pst.extract_tx()?;
-                let utxos = vec![collateral_input.1.clone(), premium_input.1, fee_input.1];
+                    collateral_amt,
+                    total_premium,
+                    *fee,
+                )
+                .await?;
 
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                println!("  Fee: {actual_fee} sats");
 
                 if *broadcast {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    let offer_outpoint = simplicityhl::elements::OutPoint::new(tx.txid(), 0);
-
-                    let publishing_client = self.get_publishing_client(&config).await?;
+                    self.publish_and_record_offer(
+                        &wallet,
+                        &config,
+                        &tx,
+                        &option_offer_args,
+                        &taproot_pubkey_gen,
+                        label.as_deref(),
+                        relays,
+                    )
+                    .await?;
+                } else {
+                    println!("{}", tx.serialize().to_lower_hex_string());
+                }
 
-                    let offer_event = OptionOfferCreatedEvent::new(
-                        option_offer_args.clone(),
-                        offer_outpoint,
-                        taproot_pubkey_gen.clone(),
-                    );
+                Ok(())
+            }
+            OptionOfferCommand::CreateBatch {
+                file,
+                label,
+                fee,
+                relays,
+                broadcast,
+            } => {
+                self.run_option_offer_create_batch(&wallet, &config, file, label.as_deref(), *fee, relays, *broadcast)
+                    .await
+            }
+            #[cfg(feature = "dev-commands")]
+            OptionOfferCommand::Roundtrip {
+                offer_event,
+                fee,
+                poll_interval,
+            } => {
+                self.run_option_offer_roundtrip(&wallet, config, offer_event, *fee, *poll_interval)
+                    .await
+            }
+            OptionOfferCommand::Analyze { offer_event, spot } => {
+                self.run_offer_analyze(config, offer_event, *spot).await
+            }
+            OptionOfferCommand::Capacity {
+                collateral_asset,
+                collateral_amount,
+                premium_asset,
+                premium_amount,
+                fee,
+            } => {
+                if *collateral_amount == 0 {
+                    return Err(Error::Config("Collateral amount must be greater than 0".to_string()));
+                }
 
-                    let event_id = publishing_client.publish_option_offer_created(&offer_event).await?;
-                    println!("Published to NOSTR: {event_id}");
+                let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+                let collateral_asset_id = collateral_asset.unwrap_or(lbtc);
+                let premium_asset_id = premium_asset.unwrap_or(lbtc);
+                let fee_per_offer =
+                    fee.unwrap_or_else(|| calculate_fee(OPTION_OFFER_CREATE_WEIGHT_ESTIMATE, config.get_fee_rate()));
 
-                    let now = current_timestamp();
-                    let history = vec![HistoryEntry::with_txid_and_nostr(
-                        ActionType::OptionOfferCreated.as_str(),
-                        &tx.txid().to_string(),
-                        &event_id.to_hex(),
-                        now,
-                    )];
+                let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
+                let filter = UtxoFilter::new().script_pubkey(user_script_pubkey);
+                let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await?;
 
-                    let metadata = ContractMetadata::from_nostr_with_history(
-                        event_id.to_hex(),
-                        publishing_client.public_key().await?.to_hex(),
-                        now,
-                        history,
-                    );
-                    let metadata_bytes = metadata.to_bytes()?;
+                let mut balances: HashMap<simplicityhl::elements::AssetId, u64> = HashMap::new();
+                let mut lbtc_utxo_count: u64 = 0;
+                for entry in extract_entries_from_result(&results[0]) {
+                    let (Some(asset), Some(value)) = (entry.asset(), entry.value()) else {
+                        continue;
+                    };
+                    *balances.entry(asset).or_insert(0) += value;
+                    if asset == lbtc {
+                        lbtc_utxo_count += 1;
+                    }
+                }
+                let balance_of = |asset| balances.get(&asset).copied().unwrap_or(0);
+
+                let cost = OfferCost {
+                    collateral_asset: collateral_asset_id,
+                    collateral_amount: *collateral_amount,
+                    premium_asset: premium_asset_id,
+                    premium_amount: *premium_amount,
+                    fee: fee_per_offer,
+                };
+                let limits = capacity_limits(&cost, &balances, lbtc_utxo_count);
 
-                    wallet
-                        .store()
-                        .add_contract(
-                            OPTION_OFFER_SOURCE,
-                            option_offer_args.build_arguments(),
-                            taproot_pubkey_gen.clone(),
-                            Some(&metadata_bytes),
-                        )
-                        .await?;
+                println!("Option offer capacity:");
+                println!(
+                    "  Per offer: {collateral_amount} of {} collateral, {premium_amount} of {} premium, ~{fee_per_offer} sats fee",
+                    format_settlement_asset(&collateral_asset_id),
+                    format_settlement_asset(&premium_asset_id)
+                );
+                println!(
+                    "  Balances: {} collateral, {} premium, {} LBTC in {lbtc_utxo_count} UTXOs",
+                    balance_of(collateral_asset_id),
+                    balance_of(premium_asset_id),
+                    balance_of(lbtc)
+                );
+                println!();
+                for (limit, count) in &limits {
+                    println!("  Bounded by {limit}: {count}");
+                }
 
-                    wallet
-                        .store()
-                        .insert_contract_token(&taproot_pubkey_gen, collateral_asset_id, OPTION_OFFER_COLLATERAL_TAG)
-                        .await?;
+                if let Some((limit, count)) = limits.iter().min_by_key(|(_, count)| *count) {
+                    println!();
+                    println!("Maximum offers: {count} (limited by {limit})");
+                }
 
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                Ok(())
+            }
+            OptionOfferCommand::List { label, limit } => {
+                let offer_contracts = list_option_offer_contracts(&wallet, label.as_deref()).await?;
+                let mut active_offers = collect_active_option_offers(&wallet, &config, offer_contracts).await?;
 
-                    publishing_client.disconnect().await;
-                } else {
-                    println!("{}", tx.serialize().to_lower_hex_string());
+                if active_offers.is_empty() {
+                    println!("No active option offers found.");
+                    return Ok(());
                 }
 
+                sort_active_offers(&mut active_offers);
+                display_active_option_offers(&active_offers, config.ui.row_limit(*limit));
+
                 Ok(())
             }
             OptionOfferCommand::Take {
                 offer_event,
+                label,
+                limit,
                 fee,
+                cpfp_parent,
                 broadcast,
             } => {
                 println!("Taking option offer...");
 
-                let offer_contracts =
-                    <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE)
-                        .await?;
-
-                let mut active_offers: Vec<LocalOptionOfferData> = Vec::new();
-                for (args_bytes, tpg_str, metadata_bytes) in offer_contracts {
-                    let Ok((arguments, _)): Result<(simplicityhl::Arguments, usize), _> =
-                        bincode::serde::decode_from_slice(&args_bytes, bincode::config::standard())
-                    else {
-                        continue;
-                    };
-                    let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&arguments) else {
-                        continue;
-                    };
-
-                    let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
-                        &tpg_str,
-                        &option_offer_args,
-                        config.address_params(),
-                        &contracts::option_offer::get_option_offer_address,
-                    ) else {
-                        continue;
-                    };
+                let cpfp_parent = match cpfp_parent {
+                    Some(txid) => Some(CpfpParent::fetch(*txid).await?),
+                    None => None,
+                };
 
-                    let metadata = metadata_bytes
-                        .as_ref()
-                        .and_then(|b| ContractMetadata::from_bytes(b).ok())
-                        .unwrap_or_default();
+                let offer_contracts = list_option_offer_contracts(&wallet, label.as_deref()).await?;
 
-                    let collateral_asset = option_offer_args.get_collateral_asset_id();
-                    let filter = UtxoFilter::new()
-                        .taproot_pubkey_gen(taproot_pubkey_gen.clone())
-                        .asset_id(collateral_asset);
-
-                    if let Ok(results) = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await
-                        && let Some((outpoint, value)) = match &results[0] {
-                            UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) => {
-                                entries
-                                    .first()
-                                    .and_then(|entry| entry.value().map(|value| (*entry.outpoint(), value)))
-                            }
-                            UtxoQueryResult::Empty => None,
-                        }
-                    {
-                        active_offers.push(LocalOptionOfferData {
-                            option_offer_args,
-                            taproot_pubkey_gen,
-                            metadata,
-                            current_outpoint: outpoint,
-                            current_value: value,
-                        });
-                    }
-                }
+                let mut active_offers = collect_active_option_offers(&wallet, &config, offer_contracts).await?;
+                sort_active_offers(&mut active_offers);
 
                 let selected_offer = if let Some(event_id_str) = offer_event {
                     active_offers
@@ -420,14 +542,12 @@ impl Cli {
                         ));
                     }
 
-                    let active_offer_displays = build_active_option_offers_displays(&active_offers);
-                    display_active_option_offers_table(&active_offer_displays);
+                    let shown = display_active_option_offers(&active_offers, config.ui.row_limit(*limit));
                     println!();
 
-                    let selection =
-                        crate::cli::interactive::prompt_selection("Select option offer to take", active_offers.len())
-                            .map_err(Error::Io)?
-                            .ok_or_else(|| Error::Config("Selection cancelled".to_string()))?;
+                    let selection = crate::cli::interactive::prompt_selection("Select option offer to take", shown)
+                        .map_err(Error::Io)?
+                        .ok_or_else(|| Error::Config("Selection cancelled".to_string()))?;
 
                     active_offers
                         .into_iter()
@@ -463,31 +583,29 @@ impl Cli {
 
                 println!("  Settlement required: {settlement_required}");
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
                 let settlement_asset = args.get_settlement_asset_id();
 
+                // Fetch every settlement UTXO so several small ones can be combined
                 let settlement_filter = UtxoFilter::new()
                     .asset_id(settlement_asset)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(settlement_required);
-
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
+                    .script_pubkey(script_pubkey.clone());
+
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(fee.unwrap_or(PLACEHOLDER_FEE)),
+                    &selected_offer.taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[settlement_filter, fee_filter]).await?;
 
-                let settlement_entries = match &results[0] {
-                    UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) => entries,
-                    UtxoQueryResult::Empty => {
-                        return Err(Error::Config(format!(
-                            "No settlement UTXOs found for asset {settlement_asset}"
-                        )));
-                    }
-                };
-
-                let fee_entries = extract_entries_from_result(&results[1]);
+                let settlement_entries = extract_entries_from_result(&results[0]);
+                let mut fee_entries = extract_entries_from_result(&results[1]);
+                if let Some(parent) = &cpfp_parent {
+                    prefer_outputs_of(&mut fee_entries, parent.txid);
+                }
 
                 if settlement_entries.is_empty() {
                     return Err(Error::Config(format!(
@@ -495,40 +613,86 @@ impl Cli {
                     )));
                 }
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
-                let settlement_utxo = &settlement_entries[0];
-                let fee_utxo = if settlement_asset == *LIQUID_TESTNET_BITCOIN_ASSET {
-                    fee_entries
-                        .iter()
-                        .find(|entry| entry.outpoint() != settlement_utxo.outpoint())
-                        .ok_or_else(|| {
-                            Error::Config(
-                                "Need two separate LBTC UTXOs: one for settlement and one for fee. \
-                                 Please split your LBTC UTXO or fund with additional LBTC."
-                                    .to_string(),
-                            )
-                        })?
+                // When settling in LBTC, keep the smallest LBTC UTXO aside for the fee
+                let fee_inputs = if settlement_asset == *LIQUID_TESTNET_BITCOIN_ASSET {
+                    if settlement_entries.len() < 2 {
+                        return Err(Error::Config(
+                            "Need two separate LBTC UTXOs: one for settlement and one for fee. \
+                             Please split your LBTC UTXO or fund with additional LBTC."
+                                .to_string(),
+                        ));
+                    }
+                    vec![settlement_entries[settlement_entries.len() - 1].as_input()]
                 } else {
-                    &fee_entries[0]
+                    select_inputs(&fee_entries, fee.unwrap_or(PLACEHOLDER_FEE), None, "LBTC fee")?
                 };
+                ensure_wallet_fee_inputs(&fee_inputs, &script_pubkey)?;
+                if fee_inputs.len() > 1 {
+                    println!("  Fee inputs: {}", fee_inputs.len());
+                }
 
-                let collateral_txout = cli_helper::explorer::fetch_utxo(current_offer_outpoint).await?;
+                let settlement_inputs = select_inputs(
+                    &settlement_entries,
+                    settlement_required,
+                    Some(fee_inputs[0].0),
+                    "settlement",
+                )?;
+                if settlement_inputs.len() > 1 {
+                    println!("  Settlement inputs: {}", settlement_inputs.len());
+                }
 
                 let premium_outpoint =
                     simplicityhl::elements::OutPoint::new(current_offer_outpoint.txid, current_offer_outpoint.vout + 1);
-                let premium_txout = cli_helper::explorer::fetch_utxo(premium_outpoint).await?;
+
+                let fetched: Result<_, Error> = async {
+                    Ok((
+                        fetch_offer_utxo(current_offer_outpoint).await?,
+                        fetch_offer_utxo(premium_outpoint).await?,
+                    ))
+                }
+                .await;
+                let (collateral_txout, premium_txout) = match fetched {
+                    Ok(txouts) => txouts,
+                    Err(e @ Error::OfferNoLongerAvailable { .. }) => {
+                        // Hide the stale offer from take lists until the next sync confirms who spent it
+                        for outpoint in [current_offer_outpoint, premium_outpoint] {
+                            <_ as UtxoStore>::mark_as_spent(wallet.store(), outpoint).await?;
+                        }
+                        return Err(e);
+                    }
+                    Err(e) => return Err(e),
+                };
 
                 let collateral_input = (current_offer_outpoint, collateral_txout.clone());
                 let premium_input = (premium_outpoint, premium_txout.clone());
-                let settlement_input = (*settlement_utxo.outpoint(), settlement_utxo.txout().clone());
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let settlement_input = aggregate_inputs(&settlement_inputs, "settlement")?;
+                let fee_input = aggregate_inputs(&fee_inputs, "LBTC fee")?;
+
+                let mut utxos = vec![
+                    collateral_txout.clone(),
+                    premium_txout.clone(),
+                    settlement_inputs[0].1.clone(),
+                    fee_inputs[0].1.clone(),
+                ];
+                utxos.extend(settlement_inputs[1..].iter().map(|(_, txout)| txout.clone()));
+                utxos.extend(fee_inputs[1..].iter().map(|(_, txout)| txout.clone()));
+
+                if let Some(parent) = &cpfp_parent {
+                    parent.ensure_spent_by(
+                        [current_offer_outpoint, premium_outpoint]
+                            .into_iter()
+                            .chain(settlement_inputs.iter().map(|(outpoint, _)| *outpoint))
+                            .chain(fee_inputs.iter().map(|(outpoint, _)| *outpoint)),
+                    )?;
+                }
 
                 let actual_fee = if let Some(f) = fee {
                     *f
                 } else {
-                    let (pst, branch) = contracts::sdk::build_option_offer_exercise(
+                    let (mut pst, branch) = contracts::sdk::build_option_offer_exercise(
                         collateral_input.clone(),
                         premium_input.clone(),
                         settlement_input.clone(),
@@ -538,15 +702,12 @@ impl Cli {
                         args,
                         script_pubkey.clone(),
                     )?;
+                    spread_inputs(&mut pst, SETTLEMENT_INPUT_INDEX, &settlement_inputs);
+                    spread_inputs(&mut pst, FEE_INPUT_INDEX, &fee_inputs);
                     let mut tx = pst.extract_tx()?;
-                    let utxos = vec![
-                        collateral_txout.clone(),
-                        premium_txout.clone(),
-                        settlement_input.1.clone(),
-                        fee_input.1.clone(),
-                    ];
+                    remove_empty_fee_change(&mut tx, &script_pubkey);
                     let offer_program = get_option_offer_program(args)?;
-                    tx = finalize_option_offer_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
                         &offer_program,
@@ -555,9 +716,9 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
-                    tx = finalize_option_offer_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
                         &offer_program,
@@ -566,7 +727,7 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
                     let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 2)?;
                     let signed_weight = tx.weight();
@@ -575,12 +736,15 @@ impl Cli {
                     println!(
                         "Estimated fee: {estimated} sats (signed weight: {signed_weight}, rate: {fee_rate} sats/kvb)"
                     );
-                    estimated
+                    cpfp_parent.map_or(estimated, |parent| parent.child_fee(signed_weight, fee_rate))
                 };
 
                 println!("  Fee: {actual_fee} sats");
 
-                let (pst, branch) = contracts::sdk::build_option_offer_exercise(
+                // Change stays explicit: the SDK builder has no blinding option, and blinding it here
+                // would not hide the amount. With every input explicit, a lone blinded output gets a
+                // value blinding factor that cancels its asset factor (see `transfer_output` in tx.rs).
+                let (mut pst, branch) = contracts::sdk::build_option_offer_exercise(
                     collateral_input.clone(),
                     premium_input.clone(),
                     settlement_input.clone(),
@@ -591,16 +755,13 @@ impl Cli {
                     script_pubkey.clone(),
                 )?;
 
+                spread_inputs(&mut pst, SETTLEMENT_INPUT_INDEX, &settlement_inputs);
+                spread_inputs(&mut pst, FEE_INPUT_INDEX, &fee_inputs);
                 let mut tx = pst.extract_tx()?;
-                let utxos = vec![
-                    collateral_txout.clone(),
-                    premium_txout.clone(),
-                    settlement_input.1.clone(),
-                    fee_input.1.clone(),
-                ];
+                remove_empty_fee_change(&mut tx, &script_pubkey);
 
                 let offer_program = get_option_offer_program(args)?;
-                tx = finalize_option_offer_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
                     &offer_program,
@@ -609,10 +770,22 @@ impl Cli {
                     &branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &offer_program,
+                        &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "exercise",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
-                tx = finalize_option_offer_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
                     &offer_program,
@@ -621,8 +794,20 @@ impl Cli {
                     &branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &offer_program,
+                        &selected_offer.taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        1,
+                        "exercise",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 2)?;
 
@@ -630,31 +815,40 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(ref nostr_event_id) = selected_offer.metadata.nostr_event_id
+                    let published = if let Some(ref nostr_event_id) = selected_offer.metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::OptionOfferExercised,
                             simplicityhl::elements::OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published action to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
-
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::OptionOfferExercised.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    crate::sync::add_history_entry(wallet.store(), &selected_offer.taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_option_offer_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        &selected_offer.taproot_pubkey_gen,
+                        args,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -663,14 +857,26 @@ impl Cli {
             }
             OptionOfferCommand::Cancel {
                 offer_event,
+                label,
+                limit,
                 fee,
                 broadcast,
+                force,
             } => {
                 println!("Cancelling option offer (reclaiming collateral + premium after expiry)...");
 
-                let offer_contracts =
-                    <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE)
-                        .await?;
+                // Only expired offers can be cancelled, so without a label the store skips the rest
+                let offer_contracts = match label {
+                    Some(label) => list_option_offer_contracts(&wallet, Some(label.as_str())).await?,
+                    None => {
+                        <_ as UtxoStore>::list_contracts_by_source_filtered(
+                            wallet.store(),
+                            OPTION_OFFER_SOURCE,
+                            Some(true),
+                        )
+                        .await?
+                    }
+                };
 
                 if offer_contracts.is_empty() {
                     return Err(Error::Config(
@@ -681,20 +887,18 @@ impl Cli {
 
                 println!("Checking offer status...");
 
+                let offer_utxos = option_offer_utxos(&wallet).await?;
                 let mut cancellable_offers: Vec<LocalCancellableOptionOffer> = Vec::new();
 
                 for (args_bytes, tpg_str, metadata_bytes) in offer_contracts {
-                    let Ok((arguments, _)): Result<(simplicityhl::Arguments, usize), _> =
-                        bincode::serde::decode_from_slice(&args_bytes, bincode::config::standard())
-                    else {
+                    let Some(arguments) = decode_contract_arguments(&args_bytes, &tpg_str) else {
                         continue;
                     };
                     let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&arguments) else {
                         continue;
                     };
 
-                    let is_expired = current_timestamp() > i64::from(option_offer_args.expiry_time());
-                    if !is_expired {
+                    if !is_offer_expired(&SystemClock, option_offer_args.expiry_time()) {
                         continue; // Skip non-expired offers
                     }
 
@@ -712,19 +916,10 @@ impl Cli {
                         .and_then(|b| ContractMetadata::from_bytes(b).ok())
                         .unwrap_or_default();
 
-                    let collateral_asset = option_offer_args.get_collateral_asset_id();
-                    let filter = UtxoFilter::new()
-                        .taproot_pubkey_gen(taproot_pubkey_gen.clone())
-                        .asset_id(collateral_asset);
-
-                    if let Ok(results) = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await
-                        && let UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) =
-                            &results[0]
-                        && let Some(entry) = entries.first()
+                    if let Some(entry) = offer_collateral_utxo(&offer_utxos, &taproot_pubkey_gen, &option_offer_args)
                         && let Some(collateral_value) = entry.value()
                     {
-                        // Calculate premium: collateral * premium_per_collateral rate
-                        let premium_amount = collateral_value * option_offer_args.premium_per_collateral();
+                        let premium_amount = remaining_premium(&option_offer_args, collateral_value);
                         cancellable_offers.push(LocalCancellableOptionOffer {
                             option_offer_args,
                             taproot_pubkey_gen,
@@ -741,10 +936,16 @@ impl Cli {
                     ));
                 }
 
-                let cancellable_offer_displays = build_cancellable_option_offers_displays(&cancellable_offers);
-                display_cancellable_option_offers_table(&cancellable_offer_displays);
+                cancellable_offers.sort_by_key(|offer| offer.option_offer_args.expiry_time());
+                let shown = display_cancellable_option_offers(&cancellable_offers, config.ui.row_limit(*limit));
                 println!();
 
+                if let (Some(label), None) = (label, offer_event) {
+                    return self
+                        .cancel_option_offer_batch(config, label, cancellable_offers, *fee, *broadcast, *force)
+                        .await;
+                }
+
                 let selected = if let Some(event_id_str) = offer_event {
                     cancellable_offers
                         .into_iter()
@@ -756,12 +957,9 @@ impl Cli {
                         })
                         .ok_or_else(|| Error::Config(format!("Offer event not found: {event_id_str}")))?
                 } else {
-                    let selection = crate::cli::interactive::prompt_selection(
-                        "Select option offer to cancel",
-                        cancellable_offers.len(),
-                    )
-                    .map_err(Error::Io)?
-                    .ok_or_else(|| Error::Config("Selection cancelled".to_string()))?;
+                    let selection = crate::cli::interactive::prompt_selection("Select option offer to cancel", shown)
+                        .map_err(Error::Io)?
+                        .ok_or_else(|| Error::Config("Selection cancelled".to_string()))?;
 
                     cancellable_offers
                         .into_iter()
@@ -778,21 +976,24 @@ impl Cli {
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(initial_fee);
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee),
+                    taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
                 let fee_entries = extract_entries_from_result(&results[0]);
 
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
                 let fee_utxo = &fee_entries[0];
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
 
                 let collateral_asset = args.get_collateral_asset_id();
                 let filter = UtxoFilter::new()
@@ -818,7 +1019,17 @@ impl Cli {
 
                 let premium_outpoint =
                     simplicityhl::elements::OutPoint::new(current_outpoint.txid, current_outpoint.vout + 1);
-                let premium_txout = cli_helper::explorer::fetch_utxo(premium_outpoint).await?;
+                let premium_txout = fetch_offer_utxo(premium_outpoint).await?;
+
+                // After partial takes the premium left is prorated to the remaining collateral
+                let expected_premium = remaining_premium(args, offer_entry.value().unwrap_or_default());
+                if let Some(premium_value) = premium_txout.value.explicit()
+                    && premium_value != expected_premium
+                {
+                    return Err(Error::Config(format!(
+                        "Premium output {premium_outpoint} holds {premium_value}, expected {expected_premium} for the remaining collateral. Run `sync utxos` to update."
+                    )));
+                }
 
                 let collateral_input = (current_outpoint, collateral_txout.clone());
                 let premium_input = (premium_outpoint, premium_txout.clone());
@@ -837,7 +1048,7 @@ impl Cli {
                     let mut tx = pst.extract_tx()?;
                     let utxos = vec![collateral_txout.clone(), premium_txout.clone(), fee_input.1.clone()];
                     let offer_program = get_option_offer_program(args)?;
-                    let signature = wallet.signer().sign_contract(
+                    let signature = wallet.signer()?.sign_contract(
                         &tx,
                         &offer_program,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
@@ -849,7 +1060,7 @@ impl Cli {
                     let branch = contracts::option_offer::build_witness::OptionOfferBranch::Expiry {
                         schnorr_signature: signature,
                     };
-                    tx = finalize_option_offer_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
                         &offer_program,
@@ -858,9 +1069,9 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
-                    let signature = wallet.signer().sign_contract(
+                    let signature = wallet.signer()?.sign_contract(
                         &tx,
                         &offer_program,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
@@ -872,7 +1083,7 @@ impl Cli {
                     let branch = contracts::option_offer::build_witness::OptionOfferBranch::Expiry {
                         schnorr_signature: signature,
                     };
-                    tx = finalize_option_offer_transaction(
+                    tx = finalize_contract_input(
                         tx,
                         &taproot_pubkey_gen.get_x_only_pubkey(),
                         &offer_program,
@@ -881,7 +1092,7 @@ impl Cli {
                         &branch,
                         config.address_params(),
                         *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        self.tracker_log.level(),
                     )?;
                     let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 2)?;
                     let signed_weight = tx.weight();
@@ -895,6 +1106,16 @@ impl Cli {
 
                 println!("  Fee: {actual_fee} sats");
 
+                check_fee_ratio(
+                    actual_fee,
+                    &[
+                        (args.get_collateral_asset_id(), selected.collateral_amount),
+                        (args.get_premium_asset_id(), selected.premium_amount),
+                    ],
+                    config.safety.max_fee_ratio,
+                    *force,
+                )?;
+
                 let pst = contracts::sdk::build_option_offer_expiry(
                     collateral_input.clone(),
                     premium_input.clone(),
@@ -908,7 +1129,7 @@ impl Cli {
                 let utxos = vec![collateral_txout.clone(), premium_txout.clone(), fee_input.1.clone()];
                 let offer_program = get_option_offer_program(args)?;
 
-                let signature = wallet.signer().sign_contract(
+                let signature = wallet.signer()?.sign_contract(
                     &tx,
                     &offer_program,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
@@ -922,7 +1143,7 @@ impl Cli {
                     schnorr_signature: signature,
                 };
 
-                tx = finalize_option_offer_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &offer_program,
@@ -931,16 +1152,28 @@ impl Cli {
                     &branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
-
-                let signature = wallet.signer().sign_contract(
-                    &tx,
-                    &offer_program,
-                    &taproot_pubkey_gen.get_x_only_pubkey(),
-                    &utxos,
-                    1,
-                    config.address_params(),
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &offer_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        0,
+                        "expiry",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
+
+                let signature = wallet.signer()?.sign_contract(
+                    &tx,
+                    &offer_program,
+                    &taproot_pubkey_gen.get_x_only_pubkey(),
+                    &utxos,
+                    1,
+                    config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
                 )?;
 
@@ -948,7 +1181,7 @@ impl Cli {
                     schnorr_signature: signature,
                 };
 
-                tx = finalize_option_offer_transaction(
+                tx = finalize_contract_input(
                     tx,
                     &taproot_pubkey_gen.get_x_only_pubkey(),
                     &offer_program,
@@ -957,8 +1190,20 @@ impl Cli {
                     &branch,
                     config.address_params(),
                     *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    self.tracker_log.level(),
                 )?;
+                if config.safety.verify_finalized {
+                    verify_finalized_input(
+                        &tx,
+                        &offer_program,
+                        &taproot_pubkey_gen.get_x_only_pubkey(),
+                        &utxos,
+                        1,
+                        "expiry",
+                        config.address_params(),
+                        *LIQUID_TESTNET_GENESIS,
+                    )?;
+                }
 
                 let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 2)?;
 
@@ -966,31 +1211,40 @@ impl Cli {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(ref nostr_event_id) = selected.metadata.nostr_event_id
+                    let published = if let Some(ref nostr_event_id) = selected.metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::OptionOfferCancelled,
                             simplicityhl::elements::OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published cancellation to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
-
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    let entry = HistoryEntry::with_txid(
+                    let mut entry = HistoryEntry::with_txid(
                         ActionType::OptionOfferCancelled.as_str(),
                         &tx.txid().to_string(),
                         current_timestamp(),
                     );
-                    crate::sync::add_history_entry(wallet.store(), taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_option_offer_interaction(
+                        wallet.store(),
+                        &tx,
+                        HashMap::default(),
+                        taproot_pubkey_gen,
+                        args,
+                        entry,
+                    )
+                    .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -1001,6 +1255,7 @@ impl Cli {
                 offer_event,
                 fee,
                 broadcast,
+                force,
             } => {
                 println!("Withdrawing settlement from option offer (claiming payment after offer was taken)...");
 
@@ -1017,55 +1272,13 @@ impl Cli {
 
                 println!("Checking offer status...");
 
-                let mut withdrawable_offers: Vec<LocalWithdrawableOptionOffer> = Vec::new();
-
-                for (args_bytes, tpg_str, metadata_bytes) in offer_contracts {
-                    let Ok((arguments, _)): Result<(simplicityhl::Arguments, usize), _> =
-                        bincode::serde::decode_from_slice(&args_bytes, bincode::config::standard())
-                    else {
-                        continue;
-                    };
-                    let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&arguments) else {
-                        continue;
-                    };
-
-                    let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
-                        &tpg_str,
-                        &option_offer_args,
-                        config.address_params(),
-                        &contracts::option_offer::get_option_offer_address,
-                    ) else {
-                        continue;
-                    };
-
-                    let metadata = metadata_bytes
-                        .as_ref()
-                        .and_then(|b| ContractMetadata::from_bytes(b).ok())
-                        .unwrap_or_default();
-
-                    let settlement_asset = option_offer_args.get_settlement_asset_id();
-                    let filter = UtxoFilter::new()
-                        .taproot_pubkey_gen(taproot_pubkey_gen.clone())
-                        .asset_id(settlement_asset);
-
-                    if let Ok(results) = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await
-                        && let UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) =
-                            &results[0]
-                        && let Some(entry) = entries.first()
-                        && let Some(value) = entry.value()
-                    {
-                        let wallet_pubkey = wallet.signer().public_key();
-                        let contract_user_pubkey = option_offer_args.user_pubkey();
-                        if wallet_pubkey.serialize() == contract_user_pubkey {
-                            withdrawable_offers.push(LocalWithdrawableOptionOffer {
-                                option_offer_args,
-                                taproot_pubkey_gen,
-                                metadata,
-                                settlement_amount: value,
-                            });
-                        }
-                    }
-                }
+                let withdrawable_offers = collect_withdrawable_option_offers(
+                    wallet.store(),
+                    wallet.public_key(),
+                    config.address_params(),
+                    offer_contracts,
+                )
+                .await;
 
                 if withdrawable_offers.is_empty() {
                     return Err(Error::Config(
@@ -1111,21 +1324,24 @@ impl Cli {
 
                 let initial_fee = fee.unwrap_or(PLACEHOLDER_FEE);
 
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
-                let fee_filter = UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(initial_fee);
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+                let fee_filter = config.selection.for_contract(
+                    UtxoFilter::new()
+                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                        .script_pubkey(script_pubkey.clone())
+                        .required_value(initial_fee),
+                    taproot_pubkey_gen,
+                );
 
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
                 let fee_entries = extract_entries_from_result(&results[0]);
 
                 if fee_entries.is_empty() {
-                    return Err(Error::Config("No LBTC UTXOs found for fee".to_string()));
+                    return Err(lbtc_fee_shortfall(wallet.store(), &script_pubkey, *fee, config.get_fee_rate()).await);
                 }
 
                 let fee_utxo = &fee_entries[0];
-                let fee_input = (*fee_utxo.outpoint(), fee_utxo.txout().clone());
+                let fee_input = wallet_fee_input(fee_utxo, &script_pubkey)?;
 
                 let settlement_asset = args.get_settlement_asset_id();
                 let filter = UtxoFilter::new()
@@ -1162,33 +1378,17 @@ impl Cli {
                         args,
                         script_pubkey.clone(),
                     )?;
-                    let mut tx = pst.extract_tx()?;
                     let utxos = vec![offer_txout.clone(), fee_input.1.clone()];
-                    let offer_program = get_option_offer_program(args)?;
-                    let signature = wallet.signer().sign_contract(
-                        &tx,
-                        &offer_program,
-                        &taproot_pubkey_gen.get_x_only_pubkey(),
-                        &utxos,
-                        0,
-                        config.address_params(),
-                        *LIQUID_TESTNET_GENESIS,
-                    )?;
-                    let branch = contracts::option_offer::build_witness::OptionOfferBranch::Withdraw {
-                        schnorr_signature: signature,
-                    };
-                    tx = finalize_option_offer_transaction(
-                        tx,
-                        &taproot_pubkey_gen.get_x_only_pubkey(),
-                        &offer_program,
+                    let tx = finalize_option_offer_withdraw(
+                        pst.extract_tx()?,
                         &utxos,
-                        0,
-                        &branch,
+                        args,
+                        taproot_pubkey_gen,
+                        &wallet,
                         config.address_params(),
-                        *LIQUID_TESTNET_GENESIS,
-                        TrackerLogLevel::None,
+                        false,
+                        self.tracker_log,
                     )?;
-                    let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
                     let signed_weight = tx.weight();
                     let fee_rate = config.get_fee_rate();
                     let estimated = crate::fee::calculate_fee(signed_weight, fee_rate);
@@ -1200,6 +1400,13 @@ impl Cli {
 
                 println!("  Fee: {actual_fee} sats");
 
+                check_fee_ratio(
+                    actual_fee,
+                    &[(args.get_settlement_asset_id(), selected.settlement_amount)],
+                    config.safety.max_fee_ratio,
+                    *force,
+                )?;
+
                 let pst = contracts::sdk::build_option_offer_withdraw(
                     offer_input.clone(),
                     fee_input.clone(),
@@ -1208,64 +1415,46 @@ impl Cli {
                     script_pubkey.clone(),
                 )?;
 
-                let mut tx = pst.extract_tx()?;
                 let utxos = vec![offer_txout.clone(), fee_input.1.clone()];
-                let offer_program = get_option_offer_program(args)?;
-
-                let signature = wallet.signer().sign_contract(
-                    &tx,
-                    &offer_program,
-                    &taproot_pubkey_gen.get_x_only_pubkey(),
+                let tx = finalize_option_offer_withdraw(
+                    pst.extract_tx()?,
                     &utxos,
-                    0,
-                    config.address_params(),
-                    *LIQUID_TESTNET_GENESIS,
-                )?;
-
-                let branch = contracts::option_offer::build_witness::OptionOfferBranch::Withdraw {
-                    schnorr_signature: signature,
-                };
-
-                tx = finalize_option_offer_transaction(
-                    tx,
-                    &taproot_pubkey_gen.get_x_only_pubkey(),
-                    &offer_program,
-                    &utxos,
-                    0,
-                    &branch,
+                    args,
+                    taproot_pubkey_gen,
+                    &wallet,
                     config.address_params(),
-                    *LIQUID_TESTNET_GENESIS,
-                    TrackerLogLevel::None,
+                    config.safety.verify_finalized,
+                    self.tracker_log,
                 )?;
 
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 1)?;
-
                 if *broadcast {
                     cli_helper::explorer::broadcast_tx(&tx).await?;
                     println!("Broadcasted: {}", tx.txid());
 
-                    if let Some(ref nostr_event_id) = selected.metadata.nostr_event_id
+                    let published = if let Some(ref nostr_event_id) = selected.metadata.nostr_event_id
                         && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
                     {
-                        let publishing_client = self.get_publishing_client(&config).await?;
-
                         let action_event = ActionCompletedEvent::new(
                             event_id,
                             ActionType::SettlementClaimed,
                             simplicityhl::elements::OutPoint::new(tx.txid(), 0),
                         );
 
-                        let published_id = publishing_client.publish_action_completed(&action_event).await?;
+                        let (published_id, author) = self.publish_action(&config, &action_event).await?;
                         println!("Published withdrawal to NOSTR: {published_id}");
 
-                        publishing_client.disconnect().await;
-                    }
-
-                    wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                        Some((published_id, author))
+                    } else {
+                        None
+                    };
 
-                    let entry =
+                    let mut entry =
                         HistoryEntry::with_txid("option_offer_withdrawn", &tx.txid().to_string(), current_timestamp());
-                    crate::sync::add_history_entry(wallet.store(), taproot_pubkey_gen, entry).await?;
+                    if let Some((published_id, author)) = published {
+                        entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+                    }
+                    crate::sync::record_interaction(wallet.store(), &tx, HashMap::default(), taproot_pubkey_gen, entry)
+                        .await?;
                 } else {
                     println!("{}", tx.serialize().to_lower_hex_string());
                 }
@@ -1276,6 +1465,1290 @@ impl Cli {
     }
 }
 
+impl Cli {
+    /// Cancel every expired offer of a labeled batch, one reclaim transaction per offer.
+    async fn cancel_option_offer_batch(
+        &self,
+        config: Config,
+        label: &str,
+        offers: Vec<LocalCancellableOptionOffer>,
+        fee: Option<u64>,
+        broadcast: bool,
+        force: bool,
+    ) -> Result<(), Error> {
+        println!("Cancelling {} offer(s) labeled \"{label}\"...", offers.len());
+
+        let mut cancelled = 0usize;
+        for offer in offers {
+            let Some(event_id) = offer.metadata.nostr_event_id else {
+                println!("  Skipping offer {}: no NOSTR event ID", offer.taproot_pubkey_gen);
+                continue;
+            };
+
+            println!();
+            let command = OptionOfferCommand::Cancel {
+                offer_event: Some(event_id.clone()),
+                label: Some(label.to_string()),
+                limit: None,
+                fee,
+                broadcast,
+                force,
+            };
+            match Box::pin(self.run_option_offer(config.clone(), &command)).await {
+                Ok(()) => cancelled += 1,
+                Err(e) => println!("  Cancel failed for {event_id}: {e}"),
+            }
+        }
+
+        println!();
+        println!("Batch cancel complete: {cancelled} offer(s) cancelled");
+
+        Ok(())
+    }
+
+    /// Fund every offer in a spec file, then publish them over one relay connection and record
+    /// them in a single store transaction.
+    ///
+    /// Funding stops at the first offer that fails; offers already broadcast are still published
+    /// and recorded, so their deposits stay visible locally.
+    #[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+    async fn run_option_offer_create_batch(
+        &self,
+        wallet: &crate::wallet::Wallet,
+        config: &Config,
+        file: &Path,
+        default_label: Option<&str>,
+        fee: Option<u64>,
+        relays: &[String],
+        broadcast: bool,
+    ) -> Result<(), Error> {
+        let content = std::fs::read_to_string(file)?;
+        let specs: Vec<OfferSpec> = serde_json::from_str(&content)
+            .map_err(|e| Error::Config(format!("Invalid offer batch file {}: {e}", file.display())))?;
+
+        if specs.is_empty() {
+            return Err(Error::Config(format!("No offers in {}", file.display())));
+        }
+
+        let maker_pubkey = wallet.public_key().serialize();
+        let planned = specs
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let expiry = resolve_expiry(spec.expiry.as_deref(), config)? as u32;
+                let option_offer_args = spec
+                    .to_arguments(expiry, maker_pubkey)
+                    .map_err(|e| Error::Config(format!("Offer #{}: {e}", index + 1)))?;
+                let label = spec.label.as_deref().or(default_label).map(str::to_string);
+
+                Ok((option_offer_args, spec.collateral_amount, spec.premium_amount, label))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        println!("Offer batch: {} offer(s)", planned.len());
+        for (index, (args, collateral_amt, total_premium, label)) in planned.iter().enumerate() {
+            println!(
+                "  #{}: {collateral_amt} of {} for {} of {}, premium {total_premium} of {}, expires {}{}",
+                index + 1,
+                format_settlement_asset(&args.get_collateral_asset_id()),
+                collateral_amt * args.collateral_per_contract(),
+                format_settlement_asset(&args.get_settlement_asset_id()),
+                format_settlement_asset(&args.get_premium_asset_id()),
+                format_relative_time(i64::from(args.expiry_time())),
+                label.as_ref().map_or_else(String::new, |label| format!(" [{label}]")),
+            );
+        }
+
+        if !broadcast {
+            println!();
+            println!("Dry run: pass --broadcast to fund, publish and record these offers.");
+            return Ok(());
+        }
+
+        let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
+
+        let mut funded = Vec::with_capacity(planned.len());
+        let mut funding_error = None;
+        for (index, (option_offer_args, collateral_amt, total_premium, label)) in planned.into_iter().enumerate() {
+            let deposit = async {
+                preflight_contract_token_collateral(
+                    wallet,
+                    option_offer_args.get_collateral_asset_id(),
+                    &user_script_pubkey,
+                )
+                .await?;
+
+                let (tx, taproot_pubkey_gen, actual_fee) =
+                    build_offer_deposit(wallet, config, &option_offer_args, collateral_amt, total_premium, fee).await?;
+
+                cli_helper::explorer::broadcast_tx(&tx).await?;
+
+                // Record the deposit right away so the next offer does not select the same coins.
+                wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+
+                Ok::<_, Error>((tx, taproot_pubkey_gen, actual_fee))
+            };
+
+            match deposit.await {
+                Ok((tx, taproot_pubkey_gen, actual_fee)) => {
+                    println!("  #{}: broadcast {} (fee: {actual_fee} sats)", index + 1, tx.txid());
+                    funded.push((tx, option_offer_args, taproot_pubkey_gen, label));
+                }
+                Err(e) => {
+                    println!("  #{}: funding failed: {e}", index + 1);
+                    funding_error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if funded.is_empty() {
+            return Err(funding_error.unwrap_or_else(|| Error::Config("No offers were funded".to_string())));
+        }
+
+        let publishing_client = self.get_publishing_client(config).await?;
+        let author = publishing_client.public_key().await?.to_hex();
+
+        let mut contracts = Vec::with_capacity(funded.len());
+        let mut published = 0usize;
+        for (index, (tx, option_offer_args, taproot_pubkey_gen, label)) in funded.iter().enumerate() {
+            let offer_event = OptionOfferCreatedEvent::new(
+                option_offer_args.clone(),
+                OutPoint::new(tx.txid(), 0),
+                taproot_pubkey_gen.clone(),
+            );
+
+            let result = if relays.is_empty() {
+                publishing_client.publish_option_offer_created(&offer_event).await
+            } else {
+                publishing_client
+                    .publish_option_offer_created_to(&offer_event, relays)
+                    .await
+            };
+
+            let now = current_timestamp();
+            let metadata = match result {
+                Ok(event_id) => {
+                    println!("  #{}: published {event_id}", index + 1);
+                    published += 1;
+
+                    ContractMetadata::from_nostr_with_history(
+                        event_id.to_hex(),
+                        author.clone(),
+                        now,
+                        vec![HistoryEntry::with_txid_and_nostr(
+                            ActionType::OptionOfferCreated.as_str(),
+                            &tx.txid().to_string(),
+                            &event_id.to_hex(),
+                            now,
+                        )],
+                    )
+                }
+                Err(e) => {
+                    println!("  #{}: publishing failed, recording locally only: {e}", index + 1);
+
+                    ContractMetadata {
+                        created_at: Some(now),
+                        history: vec![HistoryEntry::with_txid(
+                            ActionType::OptionOfferCreated.as_str(),
+                            &tx.txid().to_string(),
+                            now,
+                        )],
+                        ..ContractMetadata::default()
+                    }
+                }
+            };
+
+            contracts.push(NewContract {
+                source: OPTION_OFFER_SOURCE.to_string(),
+                arguments: option_offer_args.build_arguments(),
+                taproot_pubkey_gen: taproot_pubkey_gen.clone(),
+                app_metadata: Some(metadata.to_bytes()?),
+                label: label.clone(),
+                tokens: vec![(
+                    option_offer_args.get_collateral_asset_id(),
+                    OPTION_OFFER_COLLATERAL_TAG.to_string(),
+                )],
+            });
+        }
+
+        publishing_client.disconnect().await;
+
+        wallet.store().add_contracts(&contracts).await?;
+
+        println!();
+        println!(
+            "Batch complete: {} offer(s) funded, {published} published, {} recorded",
+            funded.len(),
+            contracts.len()
+        );
+
+        match funding_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Publish a freshly broadcast offer deposit to NOSTR and record the contract locally.
+    ///
+    /// With `relays` set the offer only goes to those relays, e.g. for private OTC offers.
+    #[allow(clippy::too_many_arguments)]
+    async fn publish_and_record_offer(
+        &self,
+        wallet: &crate::wallet::Wallet,
+        config: &Config,
+        tx: &Transaction,
+        option_offer_args: &OptionOfferArguments,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        label: Option<&str>,
+        relays: &[String],
+    ) -> Result<(), Error> {
+        let offer_outpoint = simplicityhl::elements::OutPoint::new(tx.txid(), 0);
+
+        let publishing_client = self.get_publishing_client(config).await?;
+
+        let offer_event =
+            OptionOfferCreatedEvent::new(option_offer_args.clone(), offer_outpoint, taproot_pubkey_gen.clone());
+
+        let event_id = if relays.is_empty() {
+            publishing_client.publish_option_offer_created(&offer_event).await?
+        } else {
+            publishing_client
+                .publish_option_offer_created_to(&offer_event, relays)
+                .await?
+        };
+        println!("Published to NOSTR: {event_id}");
+
+        let now = current_timestamp();
+        let history = vec![HistoryEntry::with_txid_and_nostr(
+            ActionType::OptionOfferCreated.as_str(),
+            &tx.txid().to_string(),
+            &event_id.to_hex(),
+            now,
+        )];
+
+        let metadata = ContractMetadata::from_nostr_with_history(
+            event_id.to_hex(),
+            publishing_client.public_key().await?.to_hex(),
+            now,
+            history,
+        );
+        let metadata_bytes = metadata.to_bytes()?;
+
+        wallet
+            .store()
+            .add_contract(
+                OPTION_OFFER_SOURCE,
+                option_offer_args.build_arguments(),
+                taproot_pubkey_gen.clone(),
+                Some(&metadata_bytes),
+                label,
+            )
+            .await?;
+
+        wallet
+            .store()
+            .insert_contract_token(
+                taproot_pubkey_gen,
+                option_offer_args.get_collateral_asset_id(),
+                OPTION_OFFER_COLLATERAL_TAG,
+            )
+            .await?;
+
+        wallet.store().insert_transaction(tx, HashMap::default()).await?;
+
+        publishing_client.disconnect().await;
+
+        Ok(())
+    }
+
+    /// Reclaim an expired offer's collateral + premium and deposit them into a new offer.
+    ///
+    /// The old contract is spent through its expiry branch straight into the new contract's outputs,
+    /// so the roll-over is a single transaction paying a single fee.
+    #[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+    async fn run_option_offer_rollover(
+        &self,
+        wallet: &crate::wallet::Wallet,
+        config: &Config,
+        offer_event: &str,
+        settlement_asset: Option<simplicityhl::elements::AssetId>,
+        settlement_amount: Option<u64>,
+        expiry: Option<&str>,
+        label: Option<&str>,
+        fee: Option<&u64>,
+        relays: &[String],
+        broadcast: bool,
+    ) -> Result<(), Error> {
+        println!("Rolling expired option offer collateral into a new offer...");
+
+        let offer_contracts =
+            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
+
+        let (old_args, old_tpg, old_metadata) = offer_contracts
+            .into_iter()
+            .find_map(|(args_bytes, tpg_str, metadata_bytes)| {
+                let metadata = ContractMetadata::from_bytes(metadata_bytes.as_ref()?).ok()?;
+                if !metadata.nostr_event_id.as_ref()?.starts_with(offer_event) {
+                    return None;
+                }
+                let arguments = decode_contract_arguments(&args_bytes, &tpg_str)?;
+                let args = OptionOfferArguments::from_arguments(&arguments).ok()?;
+                let tpg = TaprootPubkeyGen::build_from_str(
+                    &tpg_str,
+                    &args,
+                    config.address_params(),
+                    &contracts::option_offer::get_option_offer_address,
+                )
+                .ok()?;
+                Some((args, tpg, metadata))
+            })
+            .ok_or_else(|| Error::Config(format!("Offer event not found: {offer_event}")))?;
+
+        if !is_offer_expired(&SystemClock, old_args.expiry_time()) {
+            return Err(Error::Config(
+                "Offer has not expired yet; its collateral cannot be reclaimed".to_string(),
+            ));
+        }
+
+        let collateral_asset_id = old_args.get_collateral_asset_id();
+        let premium_asset_id = old_args.get_premium_asset_id();
+
+        let collateral_filter = UtxoFilter::new()
+            .taproot_pubkey_gen(old_tpg.clone())
+            .asset_id(collateral_asset_id);
+        let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter]).await?;
+        let offer_entry = *extract_entries_from_result(&results[0]).first().ok_or_else(|| {
+            Error::Config(
+                "No collateral UTXO found at contract address. Offer may have been taken. Run `sync utxos` to update."
+                    .to_string(),
+            )
+        })?;
+
+        let collateral_amt = offer_entry
+            .value()
+            .ok_or_else(|| Error::Config("Collateral UTXO value is confidential".to_string()))?;
+        let total_premium = collateral_amt * old_args.premium_per_collateral();
+
+        let old_collateral_input = offer_entry.as_input();
+        let old_collateral_outpoint = old_collateral_input.0;
+
+        // The premium sits next to the collateral in the transaction that funded (or last took from) the offer
+        let funding_tx = match <_ as UtxoStore>::get_transaction(wallet.store(), old_collateral_outpoint.txid).await? {
+            Some(tx) => tx,
+            None => {
+                crate::explorer::run_blocking(move || crate::explorer::fetch_transaction(old_collateral_outpoint.txid))
+                    .await?
+            }
+        };
+        let old_premium_outpoint = find_premium_outpoint(
+            &funding_tx,
+            old_collateral_outpoint,
+            &old_tpg.address.script_pubkey(),
+            premium_asset_id,
+            total_premium,
+        )?;
+        let old_premium_input = (old_premium_outpoint, fetch_offer_utxo(old_premium_outpoint).await?);
+
+        let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
+
+        let fee_filter = UtxoFilter::new()
+            .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+            .script_pubkey(user_script_pubkey.clone())
+            .required_value(fee.copied().unwrap_or(PLACEHOLDER_FEE));
+        let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
+        let fee_entries = extract_entries_from_result(&results[0]);
+        let fee_inputs = select_inputs(&fee_entries, fee.copied().unwrap_or(PLACEHOLDER_FEE), None, "LBTC fee")?;
+        ensure_wallet_fee_inputs(&fee_inputs, &user_script_pubkey)?;
+        let fee_input = aggregate_inputs(&fee_inputs, "LBTC fee")?;
+
+        let settlement_asset_id = match settlement_asset {
+            Some(asset) => asset,
+            None => {
+                let wallet_assets = get_wallet_assets(wallet, &user_script_pubkey).await?;
+                select_asset_interactive(&wallet_assets, "Select settlement asset", true)?.asset_id
+            }
+        };
+
+        let settlement_amt = match settlement_amount {
+            Some(amt) => amt,
+            None => prompt_amount("Enter total settlement amount expected").map_err(Error::Io)?,
+        };
+
+        if settlement_amt == 0 || settlement_amt % collateral_amt != 0 {
+            return Err(Error::Config(format!(
+                "Settlement amount ({settlement_amt}) must be a non-zero multiple of the rolled collateral ({collateral_amt})"
+            )));
+        }
+        let collateral_per_contract = settlement_amt / collateral_amt;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let offer_expiry: u32 = resolve_expiry(expiry, config)? as u32;
+
+        println!();
+        println!(
+            "  Collateral: {collateral_amt} of {} (from {})",
+            format_settlement_asset(&collateral_asset_id),
+            truncate_with_ellipsis(&old_collateral_outpoint.to_string(), 20)
+        );
+        println!(
+            "  Premium: {total_premium} of {}",
+            format_settlement_asset(&premium_asset_id)
+        );
+        println!(
+            "  Settlement: {settlement_amt} of {} (rate: {collateral_per_contract} per collateral)",
+            format_settlement_asset(&settlement_asset_id)
+        );
+        println!(
+            "  Expiry: {offer_expiry} ({})",
+            format_relative_time(i64::from(offer_expiry))
+        );
+
+        let option_offer_args = OptionOfferArguments::new(
+            collateral_asset_id,
+            premium_asset_id,
+            settlement_asset_id,
+            collateral_per_contract,
+            old_args.premium_per_collateral(),
+            offer_expiry,
+            wallet.public_key().serialize(),
+        );
+
+        let mut utxos = vec![
+            old_collateral_input.1.clone(),
+            old_premium_input.1.clone(),
+            fee_inputs[0].1.clone(),
+        ];
+        utxos.extend(fee_inputs[1..].iter().map(|(_, txout)| txout.clone()));
+
+        // The expiry branch spends the old contract; the deposit builder lays out the new one
+        let build_rollover = |f: u64| -> Result<(PartiallySignedTransaction, TaprootPubkeyGen), Error> {
+            let reclaim = contracts::sdk::build_option_offer_expiry(
+                old_collateral_input.clone(),
+                old_premium_input.clone(),
+                fee_input.clone(),
+                f,
+                &old_args,
+                user_script_pubkey.clone(),
+            )?;
+            let (deposit, taproot_pubkey_gen) = contracts::sdk::build_option_offer_deposit(
+                old_collateral_input.clone(),
+                old_premium_input.clone(),
+                fee_input.clone(),
+                collateral_amt,
+                f,
+                &option_offer_args,
+                config.address_params(),
+            )?;
+            let mut rollover = combine_rollover(&reclaim, &deposit);
+            spread_inputs(&mut rollover, ROLLOVER_FEE_INPUT_INDEX, &fee_inputs);
+            Ok((rollover, taproot_pubkey_gen))
+        };
+        let finalize = |tx: Transaction, utxos: &[TxOut], verify: bool| {
+            finalize_option_offer_expiry(
+                tx,
+                utxos,
+                &old_args,
+                &old_tpg,
+                wallet,
+                config.address_params(),
+                verify,
+                self.tracker_log,
+            )
+        };
+
+        let actual_fee = estimate_fee_signed(
+            fee,
+            config.get_fee_rate(),
+            |f| Ok((build_rollover(f)?.0, utxos.clone())),
+            |tx, utxos| finalize(tx, utxos, false),
+        )?;
+        println!("  Fee: {actual_fee} sats");
+
+        let (pst, taproot_pubkey_gen) = build_rollover(actual_fee)?;
+        let tx = finalize(pst.extract_tx()?, &utxos, config.safety.verify_finalized)?;
+
+        if !broadcast {
+            println!("{}", tx.serialize().to_lower_hex_string());
+            return Ok(());
+        }
+
+        cli_helper::explorer::broadcast_tx(&tx).await?;
+        println!("Broadcasted roll-over: {}", tx.txid());
+
+        let published = if let Some(ref nostr_event_id) = old_metadata.nostr_event_id
+            && let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id)
+        {
+            let action_event = ActionCompletedEvent::new(
+                event_id,
+                ActionType::OptionOfferCancelled,
+                simplicityhl::elements::OutPoint::new(tx.txid(), 0),
+            );
+
+            let (published_id, author) = self.publish_action(config, &action_event).await?;
+            println!("Published cancellation to NOSTR: {published_id}");
+
+            Some((published_id, author))
+        } else {
+            None
+        };
+
+        let mut entry = HistoryEntry::with_txid(
+            ActionType::OptionOfferCancelled.as_str(),
+            &tx.txid().to_string(),
+            current_timestamp(),
+        );
+        if let Some((published_id, author)) = published {
+            entry = entry.published_as(&published_id.to_hex(), &author.to_hex());
+        }
+        crate::sync::record_interaction(wallet.store(), &tx, HashMap::default(), &old_tpg, entry).await?;
+
+        self.publish_and_record_offer(
+            wallet,
+            config,
+            &tx,
+            &option_offer_args,
+            &taproot_pubkey_gen,
+            label,
+            relays,
+        )
+        .await
+    }
+}
+
+/// Find the premium output funded alongside an offer's collateral output.
+///
+/// Looked up by contract script, asset and value rather than position, so the collateral output
+/// itself is never mistaken for it when both use the same asset.
+fn find_premium_outpoint(
+    funding_tx: &Transaction,
+    collateral_outpoint: OutPoint,
+    contract_script: &simplicityhl::elements::Script,
+    premium_asset: AssetId,
+    premium_amount: u64,
+) -> Result<OutPoint, Error> {
+    funding_tx
+        .output
+        .iter()
+        .zip(0u32..)
+        .find(|(out, vout)| {
+            *vout != collateral_outpoint.vout
+                && out.script_pubkey == *contract_script
+                && out.asset.explicit() == Some(premium_asset)
+                && out.value.explicit() == Some(premium_amount)
+        })
+        .map(|(_, vout)| OutPoint::new(funding_tx.txid(), vout))
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "No premium output of {premium_amount} found at the contract in {}",
+                funding_tx.txid()
+            ))
+        })
+}
+
+/// Spend the old offer's inputs into the new offer's outputs, as one transaction.
+fn combine_rollover(
+    reclaim: &PartiallySignedTransaction,
+    deposit: &PartiallySignedTransaction,
+) -> PartiallySignedTransaction {
+    let mut combined = PartiallySignedTransaction::new_v2();
+    combined.global.tx_data.fallback_locktime = reclaim.global.tx_data.fallback_locktime;
+
+    for input in reclaim.inputs() {
+        combined.add_input(input.clone());
+    }
+    for output in deposit.outputs() {
+        combined.add_output(output.clone());
+    }
+
+    combined
+}
+
+#[cfg(feature = "dev-commands")]
+impl Cli {
+    /// Take an offer the wallet created itself, wait for the take to confirm, then withdraw the settlement.
+    ///
+    /// Reuses the `take` and `withdraw` code paths, syncing UTXOs in between.
+    async fn run_option_offer_roundtrip(
+        &self,
+        wallet: &crate::wallet::Wallet,
+        config: Config,
+        offer_event: &str,
+        fee: Option<u64>,
+        poll_interval: u64,
+    ) -> Result<(), Error> {
+        let offer_contracts =
+            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
+
+        let wallet_pubkey = wallet.public_key().serialize();
+
+        let (args, taproot_pubkey_gen) = offer_contracts
+            .into_iter()
+            .find_map(|(args_bytes, tpg_str, metadata_bytes)| {
+                let metadata = ContractMetadata::from_bytes(metadata_bytes.as_ref()?).ok()?;
+                if !metadata.nostr_event_id.as_ref()?.starts_with(offer_event) {
+                    return None;
+                }
+                let arguments = decode_contract_arguments(&args_bytes, &tpg_str)?;
+                let args = OptionOfferArguments::from_arguments(&arguments).ok()?;
+                let tpg = TaprootPubkeyGen::build_from_str(
+                    &tpg_str,
+                    &args,
+                    config.address_params(),
+                    &contracts::option_offer::get_option_offer_address,
+                )
+                .ok()?;
+                Some((args, tpg))
+            })
+            .ok_or_else(|| Error::Config(format!("Offer event not found: {offer_event}")))?;
+
+        if args.user_pubkey() != wallet_pubkey {
+            return Err(Error::Config(
+                "Roundtrip requires an offer created by this wallet".to_string(),
+            ));
+        }
+
+        // The take spends one of these, which identifies its transaction afterwards
+        let collateral_filter = UtxoFilter::new()
+            .taproot_pubkey_gen(taproot_pubkey_gen)
+            .asset_id(args.get_collateral_asset_id());
+        let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter]).await?;
+        let offer_outpoints: Vec<OutPoint> = extract_entries_from_result(&results[0])
+            .iter()
+            .map(|entry| *entry.outpoint())
+            .collect();
+        if offer_outpoints.is_empty() {
+            return Err(Error::Config("Offer holds no collateral to take".to_string()));
+        }
+
+        println!("[1/3] Taking offer...");
+        Box::pin(self.run_option_offer(
+            config.clone(),
+            &OptionOfferCommand::Take {
+                offer_event: Some(offer_event.to_string()),
+                label: None,
+                limit: None,
+                fee,
+                cpfp_parent: None,
+                broadcast: true,
+            },
+        ))
+        .await?;
+
+        let take_txid = find_spending_txid(&offer_outpoints)
+            .await?
+            .ok_or_else(|| Error::Config("No transaction spending the offer collateral was found".to_string()))?;
+
+        println!("[2/3] Waiting for {take_txid} to confirm...");
+        loop {
+            match crate::explorer::run_blocking(move || crate::explorer::fetch_tx_status(take_txid)).await {
+                Ok(status) if status.confirmed => break,
+                Ok(_) => {}
+                Err(e) => eprintln!("  Status check failed: {e}"),
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(poll_interval)).await;
+        }
+
+        self.run_sync(config.clone(), &crate::cli::SyncCommand::Spent, false)
+            .await?;
+        self.run_sync(config.clone(), &crate::cli::SyncCommand::Utxos, false)
+            .await?;
+
+        println!("[3/3] Withdrawing settlement...");
+        Box::pin(self.run_option_offer(
+            config,
+            &OptionOfferCommand::Withdraw {
+                offer_event: Some(offer_event.to_string()),
+                fee,
+                broadcast: true,
+                force: false,
+            },
+        ))
+        .await
+    }
+}
+
+/// The transaction that spent the first of `outpoints` Esplora reports as spent.
+#[cfg(feature = "dev-commands")]
+async fn find_spending_txid(outpoints: &[OutPoint]) -> Result<Option<simplicityhl::elements::Txid>, Error> {
+    for &outpoint in outpoints {
+        let statuses = crate::explorer::run_blocking(move || crate::explorer::fetch_outspends(outpoint.txid)).await?;
+        let spending = usize::try_from(outpoint.vout)
+            .ok()
+            .and_then(|vout| statuses.get(vout))
+            .filter(|status| status.spent)
+            .and_then(|status| status.txid.as_deref());
+
+        if let Some(txid) = spending {
+            return txid
+                .parse()
+                .map(Some)
+                .map_err(|_| crate::explorer::EsploraError::InvalidTxid(txid.to_string()).into());
+        }
+    }
+
+    Ok(None)
+}
+
+/// Sign and finalize both contract inputs of an option offer expiry transaction, then the P2PK fee input.
+///
+/// With `verify`, each contract input is executed locally right after it is finalized.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn finalize_option_offer_expiry(
+    mut tx: Transaction,
+    utxos: &[TxOut],
+    args: &OptionOfferArguments,
+    taproot_pubkey_gen: &TaprootPubkeyGen,
+    wallet: &crate::wallet::Wallet,
+    params: &'static simplicityhl::elements::AddressParams,
+    verify: bool,
+    tracker_log: TrackerLog,
+) -> Result<Transaction, Error> {
+    let offer_program = get_option_offer_program(args)?;
+
+    for input_index in 0..2 {
+        let signature = wallet.signer()?.sign_contract(
+            &tx,
+            &offer_program,
+            &taproot_pubkey_gen.get_x_only_pubkey(),
+            utxos,
+            input_index,
+            params,
+            *LIQUID_TESTNET_GENESIS,
+        )?;
+
+        let branch = contracts::option_offer::build_witness::OptionOfferBranch::Expiry {
+            schnorr_signature: signature,
+        };
+
+        tx = finalize_contract_input(
+            tx,
+            &taproot_pubkey_gen.get_x_only_pubkey(),
+            &offer_program,
+            utxos,
+            input_index,
+            &branch,
+            params,
+            *LIQUID_TESTNET_GENESIS,
+            tracker_log.level(),
+        )?;
+        if verify {
+            verify_finalized_input(
+                &tx,
+                &offer_program,
+                &taproot_pubkey_gen.get_x_only_pubkey(),
+                utxos,
+                input_index,
+                "expiry",
+                params,
+                *LIQUID_TESTNET_GENESIS,
+            )?;
+        }
+    }
+
+    sign_p2pk_inputs(tx, utxos, wallet, params, 2)
+}
+
+/// Sign and finalize the settlement input of an option offer withdraw transaction, then the P2PK fee input.
+///
+/// With `verify`, the contract input is executed locally right after it is finalized.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn finalize_option_offer_withdraw(
+    tx: Transaction,
+    utxos: &[TxOut],
+    args: &OptionOfferArguments,
+    taproot_pubkey_gen: &TaprootPubkeyGen,
+    wallet: &crate::wallet::Wallet,
+    params: &'static simplicityhl::elements::AddressParams,
+    verify: bool,
+    tracker_log: TrackerLog,
+) -> Result<Transaction, Error> {
+    let offer_program = get_option_offer_program(args)?;
+
+    let signature = wallet.signer()?.sign_contract(
+        &tx,
+        &offer_program,
+        &taproot_pubkey_gen.get_x_only_pubkey(),
+        utxos,
+        0,
+        params,
+        *LIQUID_TESTNET_GENESIS,
+    )?;
+
+    let branch = contracts::option_offer::build_witness::OptionOfferBranch::Withdraw {
+        schnorr_signature: signature,
+    };
+
+    let tx = finalize_contract_input(
+        tx,
+        &taproot_pubkey_gen.get_x_only_pubkey(),
+        &offer_program,
+        utxos,
+        0,
+        &branch,
+        params,
+        *LIQUID_TESTNET_GENESIS,
+        tracker_log.level(),
+    )?;
+    if verify {
+        verify_finalized_input(
+            &tx,
+            &offer_program,
+            &taproot_pubkey_gen.get_x_only_pubkey(),
+            utxos,
+            0,
+            "withdraw",
+            params,
+            *LIQUID_TESTNET_GENESIS,
+        )?;
+    }
+
+    sign_p2pk_inputs(tx, utxos, wallet, params, 1)
+}
+
+/// One offer of an `option offer create-batch` file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OfferSpec {
+    /// Asset IDs are given as hex.
+    collateral_asset: String,
+    collateral_amount: u64,
+    premium_asset: String,
+    premium_amount: u64,
+    settlement_asset: String,
+    settlement_amount: u64,
+    /// Unix timestamp or duration (e.g., +30d); defaults to `defaults.offer_expiry` from config.
+    #[serde(default)]
+    expiry: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+impl OfferSpec {
+    /// The offer's contract arguments, rejecting amounts that are not exact multiples of the collateral.
+    fn to_arguments(&self, expiry: u32, maker_pubkey: [u8; 32]) -> Result<OptionOfferArguments, Error> {
+        if self.collateral_amount == 0 {
+            return Err(Error::Config("Collateral amount must be greater than 0".to_string()));
+        }
+        if self.settlement_amount == 0 {
+            return Err(Error::Config("Settlement amount must be greater than 0".to_string()));
+        }
+
+        for (name, amount) in [("Premium", self.premium_amount), ("Settlement", self.settlement_amount)] {
+            if amount % self.collateral_amount != 0 {
+                return Err(Error::Config(format!(
+                    "{name} amount ({amount}) must be evenly divisible by collateral amount ({}). Remainder: {}",
+                    self.collateral_amount,
+                    amount % self.collateral_amount
+                )));
+            }
+        }
+
+        let parse_asset = |name: &str, hex: &str| {
+            AssetId::from_str(hex).map_err(|e| Error::Config(format!("Invalid {name} asset ID '{hex}': {e}")))
+        };
+
+        Ok(OptionOfferArguments::new(
+            parse_asset("collateral", &self.collateral_asset)?,
+            parse_asset("premium", &self.premium_asset)?,
+            parse_asset("settlement", &self.settlement_asset)?,
+            self.settlement_amount / self.collateral_amount,
+            self.premium_amount / self.collateral_amount,
+            expiry,
+            maker_pubkey,
+        ))
+    }
+}
+
+/// Select coins for an offer's collateral, premium and fee, then build and sign its deposit.
+///
+/// Returns the signed transaction, the offer's taproot pubkey gen and the fee paid.
+async fn build_offer_deposit(
+    wallet: &crate::wallet::Wallet,
+    config: &Config,
+    option_offer_args: &OptionOfferArguments,
+    collateral_amt: u64,
+    total_premium: u64,
+    fee: Option<u64>,
+) -> Result<(Transaction, TaprootPubkeyGen, u64), Error> {
+    let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
+
+    let collateral_filter = UtxoFilter::new()
+        .asset_id(option_offer_args.get_collateral_asset_id())
+        .script_pubkey(user_script_pubkey.clone())
+        .required_value(collateral_amt)
+        .selection_strategy(SelectionStrategy::MinimizeChange);
+
+    let premium_filter = UtxoFilter::new()
+        .asset_id(option_offer_args.get_premium_asset_id())
+        .script_pubkey(user_script_pubkey.clone())
+        .required_value(total_premium)
+        .selection_strategy(SelectionStrategy::MinimizeChange);
+
+    let fee_filter = UtxoFilter::new()
+        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+        .script_pubkey(user_script_pubkey.clone())
+        .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
+
+    let results =
+        <_ as UtxoStore>::query_utxos(wallet.store(), &[collateral_filter, premium_filter, fee_filter]).await?;
+
+    let collateral_entries = extract_entries_from_result(&results[0]);
+    let premium_entries = extract_entries_from_result(&results[1]);
+    let fee_entries = extract_entries_from_result(&results[2]);
+
+    if collateral_entries.is_empty() {
+        return Err(Error::Config(format!(
+            "No collateral UTXOs found for asset {}",
+            format_settlement_asset(&option_offer_args.get_collateral_asset_id())
+        )));
+    }
+    if premium_entries.is_empty() {
+        return Err(Error::Config(format!(
+            "No premium UTXOs found for asset {}. Need {total_premium}",
+            format_settlement_asset(&option_offer_args.get_premium_asset_id())
+        )));
+    }
+    if fee_entries.is_empty() {
+        return Err(lbtc_fee_shortfall(wallet.store(), &user_script_pubkey, fee, config.get_fee_rate()).await);
+    }
+
+    let collateral_utxo = &collateral_entries[0];
+    let premium_utxo = &premium_entries[0];
+    let fee_utxo = &fee_entries[0];
+
+    let collateral_input = collateral_utxo.as_input();
+    let premium_input = premium_utxo.as_input();
+    let fee_input = wallet_fee_input(fee_utxo, &user_script_pubkey)?;
+
+    let actual_fee = estimate_fee_signed(
+        fee.as_ref(),
+        config.get_fee_rate(),
+        |f| {
+            let (pst, _) = contracts::sdk::build_option_offer_deposit(
+                collateral_input.clone(),
+                premium_input.clone(),
+                fee_input.clone(),
+                collateral_amt,
+                f,
+                option_offer_args,
+                config.address_params(),
+            )?;
+            Ok((
+                pst,
+                vec![collateral_input.1.clone(), premium_input.1.clone(), fee_input.1.clone()],
+            ))
+        },
+        |tx, utxos| sign_p2pk_inputs(tx, utxos, wallet, config.address_params(), 0),
+    )?;
+
+    let (pst, taproot_pubkey_gen) = contracts::sdk::build_option_offer_deposit(
+        collateral_input.clone(),
+        premium_input.clone(),
+        fee_input.clone(),
+        collateral_amt,
+        actual_fee,
+        option_offer_args,
+        config.address_params(),
+    )?;
+
+    let tx = pst.extract_tx()?;
+    let utxos = vec![collateral_input.1.clone(), premium_input.1, fee_input.1];
+
+    let tx = sign_p2pk_inputs(tx, &utxos, wallet, config.address_params(), 0)?;
+
+    Ok((tx, taproot_pubkey_gen, actual_fee))
+}
+
+/// Check that a contract token used as offer collateral belongs to a known, still-active option.
+///
+/// Fails if the token maps to an option contract that is not stored locally, and warns if the
+/// option has already expired or if a grantor token is offered without the matching option tokens.
+async fn preflight_contract_token_collateral(
+    wallet: &crate::wallet::Wallet,
+    collateral_asset_id: simplicityhl::elements::AssetId,
+    user_script_pubkey: &simplicityhl::elements::Script,
+) -> Result<(), Error> {
+    let warnings =
+        contract_token_collateral_warnings(wallet.store(), &SystemClock, collateral_asset_id, user_script_pubkey)
+            .await?;
+    for warning in warnings {
+        println!("  Warning: {warning}");
+    }
+
+    Ok(())
+}
+
+/// The checks behind [`preflight_contract_token_collateral`], returning its warnings instead of printing them.
+async fn contract_token_collateral_warnings(
+    store: &coin_store::Store,
+    clock: &impl Clock,
+    collateral_asset_id: simplicityhl::elements::AssetId,
+    user_script_pubkey: &simplicityhl::elements::Script,
+) -> Result<Vec<String>, Error> {
+    let mut warnings = Vec::new();
+
+    let Some((tpg_str, tag)) = <_ as UtxoStore>::get_contract_by_token(store, collateral_asset_id).await? else {
+        return Ok(warnings);
+    };
+
+    if tag != OPTION_TOKEN_TAG && tag != GRANTOR_TOKEN_TAG {
+        return Ok(warnings);
+    }
+
+    let contracts = <_ as UtxoStore>::list_contracts_by_source(store, OPTION_SOURCE).await?;
+    let option_arguments = contracts
+        .iter()
+        .find(|(_, contract_tpg)| *contract_tpg == tpg_str)
+        .and_then(|(args_bytes, contract_tpg)| decode_contract_arguments(args_bytes, contract_tpg))
+        .and_then(|args| OptionsArguments::from_arguments(&args).ok())
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "Collateral {tag} does not belong to a known option contract. Run 'sync nostr' to fetch it."
+            ))
+        })?;
+
+    let expiry_time = i64::from(option_arguments.expiry_time());
+    if clock.now() >= expiry_time {
+        warnings.push(format!(
+            "the underlying option expired {}; this {tag} may be worthless",
+            format_relative_time_at(clock, expiry_time)
+        ));
+    }
+
+    if tag == GRANTOR_TOKEN_TAG {
+        let option_token = <_ as UtxoStore>::list_tokens_by_tag(store, OPTION_TOKEN_TAG)
+            .await?
+            .into_iter()
+            .find(|(_, contract_tpg)| *contract_tpg == tpg_str)
+            .map(|(asset_id, _)| asset_id);
+
+        let holds_option_tokens = match option_token {
+            Some(asset_id) => {
+                let filter = UtxoFilter::new()
+                    .asset_id(asset_id)
+                    .script_pubkey(user_script_pubkey.clone());
+                let results = <_ as UtxoStore>::query_utxos(store, &[filter]).await?;
+                !extract_entries_from_result(&results[0]).is_empty()
+            }
+            None => false,
+        };
+
+        if !holds_option_tokens {
+            warnings.push(format!(
+                "wallet holds no matching {OPTION_TOKEN_TAG} for this {GRANTOR_TOKEN_TAG}"
+            ));
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Fetch an offer output from Esplora for spending.
+///
+/// Fails with `Error::OfferNoLongerAvailable` when Esplora reports the output spent, instead of
+/// surfacing a raw explorer error. A transaction Esplora does not know yet may still be propagating,
+/// so that case is left to the fetch below rather than reported as a spent offer.
+pub(crate) async fn fetch_offer_utxo(outpoint: OutPoint) -> Result<TxOut, Error> {
+    let txid = outpoint.txid;
+    match crate::explorer::run_blocking(move || crate::explorer::fetch_outspends(txid)).await {
+        Ok(statuses) => {
+            let spent = usize::try_from(outpoint.vout)
+                .ok()
+                .and_then(|vout| statuses.get(vout))
+                .is_none_or(|status| status.spent);
+            if spent {
+                return Err(Error::OfferNoLongerAvailable { outpoint });
+            }
+        }
+        // The fetch below reports its own error if Esplora is unreachable or does not know the transaction
+        Err(e) => tracing::debug!("Could not check whether {outpoint} is spent: {e}"),
+    }
+
+    Ok(cli_helper::explorer::fetch_utxo(outpoint).await?)
+}
+
+/// List stored option offer contracts, optionally scoped to a local label.
+pub(crate) async fn list_option_offer_contracts(
+    wallet: &crate::wallet::Wallet,
+    label: Option<&str>,
+) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Error> {
+    let offer_contracts = match label {
+        Some(label) => <_ as UtxoStore>::list_contracts_by_label(wallet.store(), OPTION_OFFER_SOURCE, label).await?,
+        None => <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?,
+    };
+
+    Ok(offer_contracts)
+}
+
+/// Unspent outputs of every stored option offer, keyed by taproot pubkey gen, fetched in one query.
+async fn option_offer_utxos(wallet: &crate::wallet::Wallet) -> Result<HashMap<String, Vec<UtxoEntry>>, Error> {
+    let contracts = wallet.store().contracts_with_utxos(OPTION_OFFER_SOURCE).await?;
+
+    Ok(contracts
+        .into_iter()
+        .map(|(contract, entries)| (contract.taproot_pubkey_gen, entries))
+        .collect())
+}
+
+/// Largest collateral output still locked at an offer's address.
+fn offer_collateral_utxo<'a>(
+    offer_utxos: &'a HashMap<String, Vec<UtxoEntry>>,
+    taproot_pubkey_gen: &TaprootPubkeyGen,
+    option_offer_args: &OptionOfferArguments,
+) -> Option<&'a UtxoEntry> {
+    let script_pubkey = taproot_pubkey_gen.address.script_pubkey();
+    let collateral_asset = option_offer_args.get_collateral_asset_id();
+
+    // Entries are ordered by value, largest first
+    offer_utxos
+        .get(&taproot_pubkey_gen.to_string())?
+        .iter()
+        .find(|entry| entry.txout().script_pubkey == script_pubkey && entry.asset() == Some(collateral_asset))
+}
+
+/// Premium still locked in an offer for its remaining collateral.
+///
+/// Takes release premium in proportion to the collateral taken, so this is the amount a cancel reclaims.
+fn remaining_premium(args: &OptionOfferArguments, remaining_collateral: u64) -> u64 {
+    remaining_collateral.saturating_mul(args.premium_per_collateral())
+}
+
+/// Find option offers whose contract still holds collateral available to take.
+pub(crate) async fn collect_active_option_offers(
+    wallet: &crate::wallet::Wallet,
+    config: &Config,
+    offer_contracts: Vec<(Vec<u8>, String, Option<Vec<u8>>)>,
+) -> Result<Vec<LocalOptionOfferData>, Error> {
+    let offer_utxos = option_offer_utxos(wallet).await?;
+
+    let mut active_offers: Vec<LocalOptionOfferData> = Vec::new();
+    for (args_bytes, tpg_str, metadata_bytes) in offer_contracts {
+        let Some(arguments) = decode_contract_arguments(&args_bytes, &tpg_str) else {
+            continue;
+        };
+        let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&arguments) else {
+            continue;
+        };
+
+        let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
+            &tpg_str,
+            &option_offer_args,
+            config.address_params(),
+            &contracts::option_offer::get_option_offer_address,
+        ) else {
+            continue;
+        };
+
+        let metadata = metadata_bytes
+            .as_ref()
+            .and_then(|b| ContractMetadata::from_bytes(b).ok())
+            .unwrap_or_default();
+
+        // Offers synced before an author was blocked stay in the store, so the policy is applied here too
+        if let Some(author) = metadata.nostr_author.as_deref()
+            && let Ok(author) = PublicKey::from_hex(author)
+            && !config.relay.accepts_author(&author)
+        {
+            continue;
+        }
+
+        if let Some(entry) = offer_collateral_utxo(&offer_utxos, &taproot_pubkey_gen, &option_offer_args)
+            && let Some(value) = entry.value()
+        {
+            let outpoint = *entry.outpoint();
+            active_offers.push(LocalOptionOfferData {
+                option_offer_args,
+                taproot_pubkey_gen,
+                metadata,
+                current_outpoint: outpoint,
+                current_value: value,
+            });
+        }
+    }
+
+    Ok(active_offers)
+}
+
+/// Find option offers owned by this wallet whose contract holds a taker's settlement.
+pub(crate) async fn collect_withdrawable_option_offers(
+    store: &coin_store::Store,
+    wallet_pubkey: simplicityhl::simplicity::bitcoin::XOnlyPublicKey,
+    params: &'static simplicityhl::elements::AddressParams,
+    offer_contracts: Vec<(Vec<u8>, String, Option<Vec<u8>>)>,
+) -> Vec<LocalWithdrawableOptionOffer> {
+    let mut withdrawable_offers: Vec<LocalWithdrawableOptionOffer> = Vec::new();
+
+    for (args_bytes, tpg_str, metadata_bytes) in offer_contracts {
+        let Some(arguments) = decode_contract_arguments(&args_bytes, &tpg_str) else {
+            continue;
+        };
+        let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&arguments) else {
+            continue;
+        };
+
+        let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
+            &tpg_str,
+            &option_offer_args,
+            params,
+            &contracts::option_offer::get_option_offer_address,
+        ) else {
+            continue;
+        };
+
+        let metadata = metadata_bytes
+            .as_ref()
+            .and_then(|b| ContractMetadata::from_bytes(b).ok())
+            .unwrap_or_default();
+
+        let settlement_asset = option_offer_args.get_settlement_asset_id();
+        let filter = UtxoFilter::new()
+            .taproot_pubkey_gen(taproot_pubkey_gen.clone())
+            .asset_id(settlement_asset);
+
+        if let Ok(results) = <_ as UtxoStore>::query_utxos(store, &[filter]).await
+            && let UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) = &results[0]
+            && let Some(entry) = entries.first()
+            && let Some(value) = entry.value()
+            && wallet_pubkey.serialize() == option_offer_args.user_pubkey()
+        {
+            withdrawable_offers.push(LocalWithdrawableOptionOffer {
+                option_offer_args,
+                taproot_pubkey_gen,
+                metadata,
+                settlement_amount: value,
+            });
+        }
+    }
+
+    withdrawable_offers
+}
+
+/// Order offers for a taker: cheapest price first, then the ones expiring soonest.
+fn sort_active_offers(offers: &mut [LocalOptionOfferData]) {
+    offers.sort_by_key(|offer| {
+        let args = &offer.option_offer_args;
+        (args.collateral_per_contract(), args.expiry_time())
+    });
+}
+
+/// Print up to `limit` active offers, returning how many rows were shown.
+fn display_active_option_offers(offers: &[LocalOptionOfferData], limit: Option<usize>) -> usize {
+    let shown = limit.map_or(offers.len(), |limit| limit.min(offers.len()));
+    display_active_option_offers_table(&build_active_option_offers_displays(&offers[..shown]));
+    display_hidden_rows_notice(offers.len() - shown);
+    shown
+}
+
+/// Print up to `limit` cancellable offers, returning how many rows were shown.
+fn display_cancellable_option_offers(offers: &[LocalCancellableOptionOffer], limit: Option<usize>) -> usize {
+    let shown = limit.map_or(offers.len(), |limit| limit.min(offers.len()));
+    display_cancellable_option_offers_table(&build_cancellable_option_offers_displays(&offers[..shown]));
+    display_hidden_rows_notice(offers.len() - shown);
+    shown
+}
+
 fn build_active_option_offers_displays(active_offers: &[LocalOptionOfferData]) -> Vec<ActiveOptionOfferDisplay> {
     active_offers
         .iter()
@@ -1285,7 +2758,7 @@ fn build_active_option_offers_displays(active_offers: &[LocalOptionOfferData]) -
             let price = offer.option_offer_args.collateral_per_contract();
             ActiveOptionOfferDisplay {
                 index: idx + 1,
-                offering: offer.current_value.to_string(),
+                offering: offer.available_collateral().to_string(),
                 price: price.to_string(),
                 wants: format_settlement_asset(&offer.option_offer_args.get_settlement_asset_id()),
                 expires: format_relative_time(i64::from(offer.option_offer_args.expiry_time())),
@@ -1348,3 +2821,772 @@ fn build_withdrawable_option_offers_displays(
         })
         .collect()
 }
+
+/// Round `amount` to a multiple of `unit` (the collateral amount) as the offer contract requires.
+///
+/// `Nearest` rounds halfway amounts up.
+fn round_to_multiple(amount: u64, unit: u64, mode: AmountRounding) -> Result<u64, Error> {
+    let down = amount - amount % unit;
+    if down == amount {
+        return Ok(amount);
+    }
+
+    let round_up = match mode {
+        AmountRounding::Up => true,
+        AmountRounding::Down => false,
+        AmountRounding::Nearest => amount - down >= unit - (amount - down),
+    };
+    if !round_up {
+        return Ok(down);
+    }
+
+    down.checked_add(unit)
+        .ok_or_else(|| Error::Config(format!("Rounding {amount} up to a multiple of {unit} overflows")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{self, Asset, Nonce};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{AssetId, Script, TxOutWitness, Txid};
+
+    fn settlement_entry(seed: u8, value: u64) -> UtxoEntry {
+        let txout = TxOut {
+            asset: Asset::Explicit(AssetId::from_slice(&[9; 32]).unwrap()),
+            value: confidential::Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: Script::new(),
+            witness: TxOutWitness::default(),
+        };
+        UtxoEntry::new_explicit(OutPoint::new(Txid::from_byte_array([seed; 32]), 0), txout)
+    }
+
+    #[tokio::test]
+    async fn test_contract_token_collateral_warnings() {
+        use crate::cli::interactive::FixedClock;
+
+        let path = "/tmp/test_cli_contract_token_collateral.db";
+        let _ = std::fs::remove_file(path);
+        let store = coin_store::Store::create(path).await.unwrap();
+
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let expiry: u32 = 1_706_745_600;
+        let outpoint = |seed: u8| OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+        let args = OptionsArguments::new(
+            1_704_067_200,
+            expiry,
+            10,
+            100,
+            lbtc,
+            lbtc,
+            [3; 32],
+            (outpoint(1), false),
+            (outpoint(2), false),
+        );
+        let tpg = TaprootPubkeyGen::from(
+            &args,
+            &simplicityhl::elements::AddressParams::LIQUID_TESTNET,
+            &contracts::options::get_options_address,
+        )
+        .unwrap();
+        store
+            .add_contract(OPTION_SOURCE, args.build_option_arguments(), tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        let (option_token, _) = args.get_option_token_ids();
+        let (grantor_token, _) = args.get_grantor_token_ids();
+        store
+            .insert_contract_token(&tpg, option_token, OPTION_TOKEN_TAG)
+            .await
+            .unwrap();
+        store
+            .insert_contract_token(&tpg, grantor_token, GRANTOR_TOKEN_TAG)
+            .await
+            .unwrap();
+
+        let script_pubkey = Script::from(vec![0x51]);
+        let before = FixedClock(i64::from(expiry) - 1);
+        let after = FixedClock(i64::from(expiry));
+
+        // Plain assets and live option tokens pass silently
+        assert!(
+            contract_token_collateral_warnings(&store, &before, lbtc, &script_pubkey)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            contract_token_collateral_warnings(&store, &before, option_token, &script_pubkey)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            contract_token_collateral_warnings(&store, &after, option_token, &script_pubkey)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // A grantor token without the matching option tokens is flagged until the wallet holds them
+        assert_eq!(
+            contract_token_collateral_warnings(&store, &before, grantor_token, &script_pubkey)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        store
+            .insert(
+                outpoint(3),
+                explicit_txout(option_token, 10, script_pubkey.clone()),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(
+            contract_token_collateral_warnings(&store, &before, grantor_token, &script_pubkey)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_rollover_spends_old_offer_into_new_one_in_one_transaction() {
+        let params = &simplicityhl::elements::AddressParams::LIQUID_TESTNET;
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let collateral_asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium_asset = AssetId::from_slice(&[2; 32]).unwrap();
+        let user_script = Script::from(vec![0x51]);
+        let user_pubkey = simplicityhl::elements::secp256k1_zkp::Keypair::from_seckey_slice(
+            simplicityhl::elements::secp256k1_zkp::SECP256K1,
+            &[7; 32],
+        )
+        .unwrap()
+        .x_only_public_key()
+        .0
+        .serialize();
+        let outpoint = |seed: u8| OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+
+        let old_args = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            AssetId::from_slice(&[3; 32]).unwrap(),
+            100,
+            2,
+            1_704_067_200,
+            user_pubkey,
+        );
+        let (funding, old_tpg) = contracts::sdk::build_option_offer_deposit(
+            (
+                outpoint(1),
+                explicit_txout(collateral_asset, 10_000, user_script.clone()),
+            ),
+            (outpoint(2), explicit_txout(premium_asset, 20_000, user_script.clone())),
+            (outpoint(3), explicit_txout(lbtc, 5_000, user_script.clone())),
+            10_000,
+            500,
+            &old_args,
+            params,
+        )
+        .unwrap();
+        let funding = funding.extract_tx().unwrap();
+
+        let collateral_outpoint = OutPoint::new(funding.txid(), 0);
+        let contract_script = old_tpg.address.script_pubkey();
+        let premium_outpoint =
+            find_premium_outpoint(&funding, collateral_outpoint, &contract_script, premium_asset, 20_000).unwrap();
+        assert_eq!(
+            funding.output[premium_outpoint.vout as usize].value.explicit(),
+            Some(20_000)
+        );
+        assert!(find_premium_outpoint(&funding, collateral_outpoint, &contract_script, premium_asset, 19_999).is_err());
+        // The collateral output is never returned as the premium, even when the asset and value match
+        assert!(
+            find_premium_outpoint(
+                &funding,
+                collateral_outpoint,
+                &contract_script,
+                collateral_asset,
+                10_000
+            )
+            .is_err()
+        );
+
+        let new_args = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            AssetId::from_slice(&[4; 32]).unwrap(),
+            50,
+            2,
+            1_706_745_600,
+            user_pubkey,
+        );
+        let old_collateral = (collateral_outpoint, funding.output[0].clone());
+        let old_premium = (premium_outpoint, funding.output[premium_outpoint.vout as usize].clone());
+        let fee_input = (outpoint(6), explicit_txout(lbtc, 5_000, user_script.clone()));
+
+        let reclaim = contracts::sdk::build_option_offer_expiry(
+            old_collateral.clone(),
+            old_premium.clone(),
+            fee_input.clone(),
+            500,
+            &old_args,
+            user_script.clone(),
+        )
+        .unwrap();
+        let (deposit, new_tpg) = contracts::sdk::build_option_offer_deposit(
+            old_collateral,
+            old_premium,
+            fee_input,
+            10_000,
+            500,
+            &new_args,
+            params,
+        )
+        .unwrap();
+
+        let rollover = combine_rollover(&reclaim, &deposit).extract_tx().unwrap();
+        let reclaim = reclaim.extract_tx().unwrap();
+
+        let spent: Vec<OutPoint> = rollover.input.iter().map(|input| input.previous_output).collect();
+        assert_eq!(spent, vec![collateral_outpoint, premium_outpoint, outpoint(6)]);
+        assert_eq!(rollover.lock_time, reclaim.lock_time);
+
+        // Everything lands in the new offer; nothing is paid out to the maker in between
+        let new_script = new_tpg.address.script_pubkey();
+        assert_eq!(output_value(&rollover, collateral_asset, &new_script), 10_000);
+        assert_eq!(output_value(&rollover, premium_asset, &new_script), 20_000);
+        assert_eq!(output_value(&rollover, collateral_asset, &user_script), 0);
+        assert_eq!(rollover.fee_in(lbtc), 500);
+    }
+
+    #[tokio::test]
+    async fn test_withdrawable_offers_are_ours_with_settlement() {
+        let path = "/tmp/test_cli_withdrawable_offers.db";
+        let _ = std::fs::remove_file(path);
+        let store = coin_store::Store::create(path).await.unwrap();
+        let params = &simplicityhl::elements::AddressParams::LIQUID_TESTNET;
+
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let settlement = AssetId::from_slice(&[9; 32]).unwrap();
+        let ours = simplicityhl::simplicity::bitcoin::XOnlyPublicKey::from_slice(
+            &simplicityhl_core::derive_public_blinder_key()
+                .x_only_public_key()
+                .0
+                .serialize(),
+        )
+        .unwrap();
+
+        let mut offers = Vec::new();
+        for (user_pubkey, expiry) in [(ours.serialize(), 1_000), (ours.serialize(), 2_000), ([2; 32], 1_000)] {
+            let args = OptionOfferArguments::new(lbtc, lbtc, settlement, 100, 1, expiry, user_pubkey);
+            let tpg =
+                TaprootPubkeyGen::from(&args, params, &contracts::option_offer::get_option_offer_address).unwrap();
+            store
+                .add_contract(OPTION_OFFER_SOURCE, args.build_arguments(), tpg.clone(), None, None)
+                .await
+                .unwrap();
+            offers.push(tpg);
+        }
+
+        // Taken: our first offer and the other party's offer; our second offer is untouched
+        for (seed, tpg) in [(1, &offers[0]), (2, &offers[2])] {
+            let outpoint = OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+            let txout = explicit_txout(settlement, 5_000, tpg.address.script_pubkey());
+            store.insert(outpoint, txout, None).await.unwrap();
+        }
+
+        let offer_contracts = <_ as UtxoStore>::list_contracts_by_source_with_metadata(&store, OPTION_OFFER_SOURCE)
+            .await
+            .unwrap();
+        let withdrawable = collect_withdrawable_option_offers(&store, ours, params, offer_contracts).await;
+
+        assert_eq!(withdrawable.len(), 1);
+        assert_eq!(withdrawable[0].taproot_pubkey_gen.to_string(), offers[0].to_string());
+        assert_eq!(withdrawable[0].settlement_amount, 5_000);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_capacity_limits_pool_lbtc_needs_under_one_label() {
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let cost = OfferCost {
+            collateral_asset: lbtc,
+            collateral_amount: 1_000,
+            premium_asset: lbtc,
+            premium_amount: 100,
+            fee: 100,
+        };
+        let balances = HashMap::from([(lbtc, 6_000)]);
+
+        // 1_200 sats per offer; 3 LBTC inputs per offer, of which the fee change comes back
+        assert_eq!(
+            capacity_limits(&cost, &balances, 7),
+            vec![(CapacityLimit::LbtcBalance, 5), (CapacityLimit::LbtcUtxoCount, 3)]
+        );
+        assert_eq!(
+            capacity_limits(&cost, &balances, 2),
+            vec![(CapacityLimit::LbtcBalance, 5), (CapacityLimit::LbtcUtxoCount, 0)]
+        );
+    }
+
+    #[test]
+    fn test_capacity_limits_with_separate_assets() {
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let collateral = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium = AssetId::from_slice(&[2; 32]).unwrap();
+        let cost = OfferCost {
+            collateral_asset: collateral,
+            collateral_amount: 1_000,
+            premium_asset: premium,
+            premium_amount: 100,
+            fee: 100,
+        };
+        let balances = HashMap::from([(collateral, 10_000), (premium, 250), (lbtc, 500)]);
+
+        // Only the fee is paid in LBTC and its change is reused, so one LBTC UTXO is enough
+        assert_eq!(
+            capacity_limits(&cost, &balances, 1),
+            vec![
+                (CapacityLimit::Collateral, 10),
+                (CapacityLimit::Premium, 2),
+                (CapacityLimit::LbtcBalance, 5),
+            ]
+        );
+
+        let shared = OfferCost {
+            premium_asset: collateral,
+            ..cost
+        };
+        assert_eq!(
+            capacity_limits(&shared, &balances, 0),
+            vec![
+                (CapacityLimit::Collateral, 9),
+                (CapacityLimit::LbtcBalance, 5),
+                (CapacityLimit::LbtcUtxoCount, 0),
+            ]
+        );
+    }
+
+    /// A funded offer selling 10_000 collateral for a premium of 2 per unit, settled at 100 per unit.
+    fn funded_offer(
+        settlement_asset: AssetId,
+        user_script: &Script,
+    ) -> (OptionOfferArguments, Transaction, TaprootPubkeyGen) {
+        let keypair = simplicityhl::elements::secp256k1_zkp::Keypair::from_seckey_slice(
+            simplicityhl::elements::secp256k1_zkp::SECP256K1,
+            &[7; 32],
+        )
+        .unwrap();
+        let collateral_asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium_asset = AssetId::from_slice(&[2; 32]).unwrap();
+        let args = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            settlement_asset,
+            100,
+            2,
+            1_704_067_200,
+            keypair.x_only_public_key().0.serialize(),
+        );
+
+        let outpoint = |seed: u8| OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+        let (deposit, taproot_pubkey_gen) = contracts::sdk::build_option_offer_deposit(
+            (
+                outpoint(20),
+                explicit_txout(collateral_asset, 10_000, user_script.clone()),
+            ),
+            (outpoint(21), explicit_txout(premium_asset, 20_000, user_script.clone())),
+            (
+                outpoint(22),
+                explicit_txout(*LIQUID_TESTNET_BITCOIN_ASSET, 5_000, user_script.clone()),
+            ),
+            10_000,
+            500,
+            &args,
+            &simplicityhl::elements::AddressParams::LIQUID_TESTNET,
+        )
+        .unwrap();
+
+        (args, deposit.extract_tx().unwrap(), taproot_pubkey_gen)
+    }
+
+    /// Build a take the way `option-offer take` does, then finalize and run both contract inputs.
+    #[allow(clippy::too_many_arguments)]
+    fn finalized_take(
+        args: &OptionOfferArguments,
+        deposit: &Transaction,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        settlement_inputs: &[(OutPoint, TxOut)],
+        fee_inputs: &[(OutPoint, TxOut)],
+        collateral_amount: u64,
+        fee: u64,
+        user_script: &Script,
+    ) -> Transaction {
+        let params = &simplicityhl::elements::AddressParams::LIQUID_TESTNET;
+        let collateral_input = (OutPoint::new(deposit.txid(), 0), deposit.output[0].clone());
+        let premium_input = (OutPoint::new(deposit.txid(), 1), deposit.output[1].clone());
+
+        let (mut pst, branch) = contracts::sdk::build_option_offer_exercise(
+            collateral_input.clone(),
+            premium_input.clone(),
+            aggregate_inputs(settlement_inputs, "settlement").unwrap(),
+            aggregate_inputs(fee_inputs, "LBTC fee").unwrap(),
+            collateral_amount,
+            fee,
+            args,
+            user_script.clone(),
+        )
+        .unwrap();
+        spread_inputs(&mut pst, SETTLEMENT_INPUT_INDEX, settlement_inputs);
+        spread_inputs(&mut pst, FEE_INPUT_INDEX, fee_inputs);
+        let mut tx = pst.extract_tx().unwrap();
+        remove_empty_fee_change(&mut tx, user_script);
+
+        let mut utxos = vec![
+            collateral_input.1,
+            premium_input.1,
+            settlement_inputs[0].1.clone(),
+            fee_inputs[0].1.clone(),
+        ];
+        utxos.extend(settlement_inputs[1..].iter().map(|(_, txout)| txout.clone()));
+        utxos.extend(fee_inputs[1..].iter().map(|(_, txout)| txout.clone()));
+
+        let program = get_option_offer_program(args).unwrap();
+        let x_only_public_key = taproot_pubkey_gen.get_x_only_pubkey();
+        for index in [0, 1] {
+            tx = finalize_contract_input(
+                tx,
+                &x_only_public_key,
+                &program,
+                &utxos,
+                index,
+                &branch,
+                params,
+                *LIQUID_TESTNET_GENESIS,
+                simplicityhl::tracker::TrackerLogLevel::None,
+            )
+            .unwrap();
+        }
+        for index in [0, 1] {
+            verify_finalized_input(
+                &tx,
+                &program,
+                &x_only_public_key,
+                &utxos,
+                index,
+                "exercise",
+                params,
+                *LIQUID_TESTNET_GENESIS,
+            )
+            .unwrap();
+        }
+
+        tx
+    }
+
+    #[test]
+    fn test_take_with_three_small_settlement_utxos() {
+        let settlement_asset = AssetId::from_slice(&[9; 32]).unwrap();
+        let user_script = Script::new();
+        let (args, deposit, taproot_pubkey_gen) = funded_offer(settlement_asset, &user_script);
+
+        let entries = [
+            settlement_entry(1, 400),
+            settlement_entry(2, 300),
+            settlement_entry(3, 350),
+        ];
+        let refs: Vec<&UtxoEntry> = entries.iter().collect();
+
+        // No single UTXO covers 1000, all three together do
+        let inputs = select_inputs(&refs, 1000, None, "settlement").unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[0].0, *entries[0].outpoint());
+
+        let (outpoint, aggregate) = aggregate_inputs(&inputs, "settlement").unwrap();
+        assert_eq!(outpoint, inputs[0].0);
+        assert_eq!(aggregate.value.explicit(), Some(1050));
+
+        let fee_input = (
+            OutPoint::new(Txid::from_byte_array([12; 32]), 0),
+            explicit_txout(*LIQUID_TESTNET_BITCOIN_ASSET, 5_000, user_script.clone()),
+        );
+        let tx = finalized_take(
+            &args,
+            &deposit,
+            &taproot_pubkey_gen,
+            &inputs,
+            std::slice::from_ref(&fee_input),
+            10,
+            500,
+            &user_script,
+        );
+
+        // The builder's inputs keep their positions; the extra settlement coins follow the fee input
+        let spent: Vec<OutPoint> = tx.input.iter().map(|input| input.previous_output).collect();
+        assert_eq!(
+            spent,
+            vec![
+                OutPoint::new(deposit.txid(), 0),
+                OutPoint::new(deposit.txid(), 1),
+                inputs[0].0,
+                fee_input.0,
+                inputs[1].0,
+                inputs[2].0,
+            ]
+        );
+
+        // 1000 goes to the offer and the 50 left over returns to the taker
+        let offer_script = taproot_pubkey_gen.address.script_pubkey();
+        assert_eq!(output_value(&tx, settlement_asset, &offer_script), 1000);
+        assert_eq!(output_value(&tx, settlement_asset, &user_script), 50);
+    }
+
+    #[test]
+    fn test_take_pays_fee_from_several_lbtc_outputs() {
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let settlement_asset = AssetId::from_slice(&[9; 32]).unwrap();
+        let user_script = Script::new();
+        let (args, deposit, taproot_pubkey_gen) = funded_offer(settlement_asset, &user_script);
+
+        let fee_entries = [
+            UtxoEntry::new_explicit(
+                OutPoint::new(Txid::from_byte_array([13; 32]), 0),
+                explicit_txout(lbtc, 300, user_script.clone()),
+            ),
+            UtxoEntry::new_explicit(
+                OutPoint::new(Txid::from_byte_array([14; 32]), 0),
+                explicit_txout(lbtc, 200, user_script.clone()),
+            ),
+        ];
+        let refs: Vec<&UtxoEntry> = fee_entries.iter().collect();
+        let fee_inputs = select_inputs(&refs, 500, None, "LBTC fee").unwrap();
+        assert_eq!(fee_inputs.len(), 2);
+
+        let settlement = settlement_entry(1, 1_200);
+        let tx = finalized_take(
+            &args,
+            &deposit,
+            &taproot_pubkey_gen,
+            &[settlement.as_input()],
+            &fee_inputs,
+            10,
+            500,
+            &user_script,
+        );
+
+        assert_eq!(tx.input.len(), 5);
+        assert_eq!(tx.input[FEE_INPUT_INDEX].previous_output, fee_inputs[0].0);
+        assert_eq!(tx.input[4].previous_output, fee_inputs[1].0);
+
+        // The two coins cover the fee exactly, so the empty LBTC change is dropped
+        assert_eq!(tx.fee_in(lbtc), 500);
+        assert!(
+            tx.output
+                .iter()
+                .all(|out| out.is_fee() || out.value.explicit() != Some(0))
+        );
+    }
+
+    fn explicit_txout(asset: AssetId, value: u64, script_pubkey: Script) -> TxOut {
+        TxOut {
+            asset: Asset::Explicit(asset),
+            value: confidential::Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey,
+            witness: TxOutWitness::default(),
+        }
+    }
+
+    fn output_value(tx: &Transaction, asset: AssetId, script_pubkey: &Script) -> u64 {
+        tx.output
+            .iter()
+            .filter(|out| out.asset.explicit() == Some(asset) && out.script_pubkey == *script_pubkey)
+            .filter_map(|out| out.value.explicit())
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_partial_take_reclaims_remaining_collateral_and_premium() {
+        let path = "/tmp/test_cli_cancel_partial_take.db";
+        let _ = std::fs::remove_file(path);
+        let store = coin_store::Store::create(path).await.unwrap();
+
+        let params = &simplicityhl::elements::AddressParams::LIQUID_TESTNET;
+        let lbtc = *LIQUID_TESTNET_BITCOIN_ASSET;
+        let collateral_asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium_asset = AssetId::from_slice(&[2; 32]).unwrap();
+        let settlement_asset = AssetId::from_slice(&[3; 32]).unwrap();
+        let user_script = Script::from(vec![0x51]);
+        let keypair = simplicityhl::elements::secp256k1_zkp::Keypair::from_seckey_slice(
+            simplicityhl::elements::secp256k1_zkp::SECP256K1,
+            &[7; 32],
+        )
+        .unwrap();
+
+        let premium_per_collateral = 2;
+        let args = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            settlement_asset,
+            100,
+            premium_per_collateral,
+            1_704_067_200,
+            keypair.x_only_public_key().0.serialize(),
+        );
+
+        let outpoint = |seed: u8| OutPoint::new(Txid::from_byte_array([seed; 32]), 0);
+
+        // Create: lock 10_000 collateral and 20_000 premium at the offer address
+        let (deposit, taproot_pubkey_gen) = contracts::sdk::build_option_offer_deposit(
+            (
+                outpoint(1),
+                explicit_txout(collateral_asset, 10_000, user_script.clone()),
+            ),
+            (outpoint(2), explicit_txout(premium_asset, 20_000, user_script.clone())),
+            (outpoint(3), explicit_txout(lbtc, 5_000, user_script.clone())),
+            10_000,
+            500,
+            &args,
+            params,
+        )
+        .unwrap();
+        let deposit = deposit.extract_tx().unwrap();
+
+        store
+            .add_contract(
+                OPTION_OFFER_SOURCE,
+                args.build_arguments(),
+                taproot_pubkey_gen.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        store.insert_transaction(&deposit, HashMap::new()).await.unwrap();
+
+        // Partial take: 4_000 of the 10_000 collateral
+        let (take, _) = contracts::sdk::build_option_offer_exercise(
+            (OutPoint::new(deposit.txid(), 0), deposit.output[0].clone()),
+            (OutPoint::new(deposit.txid(), 1), deposit.output[1].clone()),
+            (
+                outpoint(4),
+                explicit_txout(settlement_asset, 1_000_000, user_script.clone()),
+            ),
+            (outpoint(5), explicit_txout(lbtc, 5_000, user_script.clone())),
+            4_000,
+            500,
+            &args,
+            user_script.clone(),
+        )
+        .unwrap();
+        let take = take.extract_tx().unwrap();
+        store.insert_transaction(&take, HashMap::new()).await.unwrap();
+
+        // Cancel selects the successor collateral UTXO, not the original deposit
+        let offer_utxos: HashMap<String, Vec<UtxoEntry>> = store
+            .contracts_with_utxos(OPTION_OFFER_SOURCE)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(contract, entries)| (contract.taproot_pubkey_gen, entries))
+            .collect();
+        let collateral_entry = offer_collateral_utxo(&offer_utxos, &taproot_pubkey_gen, &args).unwrap();
+        assert_eq!(collateral_entry.outpoint().txid, take.txid());
+        assert_eq!(collateral_entry.value(), Some(6_000));
+        assert_eq!(
+            crate::sync::option_offer_collateral_after(&take, &taproot_pubkey_gen, &args),
+            6_000
+        );
+
+        let collateral_outpoint = *collateral_entry.outpoint();
+        let premium_outpoint = OutPoint::new(collateral_outpoint.txid, collateral_outpoint.vout + 1);
+        let premium_txout = take.output[premium_outpoint.vout as usize].clone();
+        assert_eq!(premium_txout.value.explicit(), Some(remaining_premium(&args, 6_000)));
+
+        // Expire and cancel: the maker gets the remaining collateral and prorated premium back
+        let cancel = contracts::sdk::build_option_offer_expiry(
+            (collateral_outpoint, collateral_entry.txout().clone()),
+            (premium_outpoint, premium_txout),
+            (outpoint(6), explicit_txout(lbtc, 5_000, user_script.clone())),
+            500,
+            &args,
+            user_script.clone(),
+        )
+        .unwrap();
+        let cancel = cancel.extract_tx().unwrap();
+
+        assert_eq!(output_value(&cancel, collateral_asset, &user_script), 6_000);
+        assert_eq!(output_value(&cancel, premium_asset, &user_script), 12_000);
+        // Nothing is left at the offer, so its remaining collateral resets
+        assert_eq!(
+            crate::sync::option_offer_collateral_after(&cancel, &taproot_pubkey_gen, &args),
+            0
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_select_settlement_inputs_insufficient() {
+        let entries = [settlement_entry(1, 400), settlement_entry(2, 300)];
+        let refs: Vec<&UtxoEntry> = entries.iter().collect();
+
+        assert!(select_inputs(&refs, 1000, None, "settlement").is_err());
+        // Excluded outpoint (e.g. the fee UTXO) is never selected
+        assert!(select_inputs(&refs, 600, Some(*entries[0].outpoint()), "settlement").is_err());
+    }
+
+    #[test]
+    fn round_to_multiple_modes() {
+        assert_eq!(round_to_multiple(1050, 100, AmountRounding::Up).unwrap(), 1100);
+        assert_eq!(round_to_multiple(1050, 100, AmountRounding::Down).unwrap(), 1000);
+        assert_eq!(round_to_multiple(1050, 100, AmountRounding::Nearest).unwrap(), 1100);
+        assert_eq!(round_to_multiple(1049, 100, AmountRounding::Nearest).unwrap(), 1000);
+        assert_eq!(round_to_multiple(1200, 100, AmountRounding::Up).unwrap(), 1200);
+        assert_eq!(round_to_multiple(40, 100, AmountRounding::Down).unwrap(), 0);
+        assert!(round_to_multiple(u64::MAX, 100, AmountRounding::Up).is_err());
+    }
+
+    #[test]
+    fn offer_spec_requires_exact_multiples() {
+        let collateral = AssetId::from_slice(&[1; 32]).unwrap();
+        let settlement = AssetId::from_slice(&[3; 32]).unwrap();
+        let json = format!(
+            r#"[{{"collateral_asset": "{collateral}", "collateral_amount": 100, "premium_asset": "{collateral}",
+                "premium_amount": 500, "settlement_asset": "{settlement}", "settlement_amount": 20000,
+                "label": "grid-1"}}]"#
+        );
+
+        let specs: Vec<OfferSpec> = serde_json::from_str(&json).unwrap();
+        let args = specs[0].to_arguments(1_700_000_000, [7; 32]).unwrap();
+
+        assert_eq!(args.get_collateral_asset_id(), collateral);
+        assert_eq!(args.get_settlement_asset_id(), settlement);
+        assert_eq!(args.premium_per_collateral(), 5);
+        assert_eq!(args.collateral_per_contract(), 200);
+        assert_eq!(args.expiry_time(), 1_700_000_000);
+        assert_eq!(specs[0].label.as_deref(), Some("grid-1"));
+
+        let uneven = OfferSpec {
+            premium_amount: 550,
+            ..specs[0].clone()
+        };
+        assert!(uneven.to_arguments(1_700_000_000, [7; 32]).is_err());
+
+        let bad_asset = OfferSpec {
+            settlement_asset: "not-hex".to_string(),
+            ..specs[0].clone()
+        };
+        assert!(bad_asset.to_arguments(1_700_000_000, [7; 32]).is_err());
+    }
+}
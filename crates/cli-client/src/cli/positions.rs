@@ -8,6 +8,7 @@ use crate::cli::tables::{display_collateral_table, display_token_table, display_
 use crate::config::Config;
 use crate::error::Error;
 use crate::metadata::ContractMetadata;
+use crate::sync::decode_contract_arguments;
 
 use coin_store::{Store, UtxoEntry, UtxoFilter, UtxoQueryResult, UtxoStore};
 use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments, get_option_offer_address};
@@ -26,7 +27,7 @@ impl Cli {
         println!("===============");
         println!();
 
-        let user_script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+        let user_script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
         let options_filter = UtxoFilter::new().source(OPTION_SOURCE);
         let options_results = <_ as UtxoStore>::query_utxos(wallet.store(), &[options_filter]).await?;
@@ -75,10 +76,7 @@ impl Cli {
                 && let Ok(metadata) = ContractMetadata::from_bytes(bytes)
                 && !metadata.history.is_empty()
             {
-                let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                    args_bytes,
-                    bincode::config::standard(),
-                ) else {
+                let Some(args) = decode_contract_arguments(args_bytes, tpg_str) else {
                     continue;
                 };
                 let Ok(opt_args) = OptionsArguments::from_arguments(&args) else {
@@ -99,10 +97,7 @@ impl Cli {
                 && let Ok(metadata) = ContractMetadata::from_bytes(bytes)
                 && !metadata.history.is_empty()
             {
-                let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                    args_bytes,
-                    bincode::config::standard(),
-                ) else {
+                let Some(args) = decode_contract_arguments(args_bytes, tpg_str) else {
                     continue;
                 };
                 let Ok(option_offer_args) = OptionOfferArguments::from_arguments(&args) else {
@@ -208,9 +203,7 @@ async fn extract_collateral_info(
 ) -> Option<(String, String, String, String)> {
     let (_metadata, args_bytes, tpg_str) = contract_info.ok().flatten()?;
 
-    let (args, _) =
-        bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(&args_bytes, bincode::config::standard())
-            .ok()?;
+    let args = decode_contract_arguments(&args_bytes, &tpg_str)?;
 
     let opt_args = OptionsArguments::from_arguments(&args).ok()?;
 
@@ -343,11 +336,9 @@ async fn extract_option_offer_display_info_with_tags(
     contract_info: ContractInfoResult,
     entry: &UtxoEntry,
 ) -> Option<(String, String, bool, u64)> {
-    let (_metadata, args_bytes, _tpg) = contract_info.ok().flatten()?;
+    let (_metadata, args_bytes, tpg_str) = contract_info.ok().flatten()?;
 
-    let (args, _) =
-        bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(&args_bytes, bincode::config::standard())
-            .ok()?;
+    let args = decode_contract_arguments(&args_bytes, &tpg_str)?;
 
     let option_offer_args = OptionOfferArguments::from_arguments(&args).ok()?;
 
@@ -0,0 +1,185 @@
+use crate::cli::Cli;
+use crate::cli::best_offer::is_offer_for_pair;
+use crate::config::Config;
+use crate::error::Error;
+use crate::explorer::{EsploraError, fetch_transaction, run_blocking};
+
+use std::collections::HashMap;
+
+use nostr::{EventId, Timestamp};
+use options_relay::{ActionCompletedEvent, ActionType, OptionOfferCreatedEvent};
+use simplicityhl::elements::{AssetId, OutPoint, Script, Transaction, Txid};
+
+/// A take of an option offer, as reconstructed from the relay event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Trade {
+    timestamp: u64,
+    /// Settlement paid per unit of collateral.
+    price: u64,
+    offer_event: EventId,
+    take_outpoint: OutPoint,
+}
+
+impl Cli {
+    pub(crate) async fn run_price_history(
+        &self,
+        config: Config,
+        collateral_asset: AssetId,
+        settlement_asset: AssetId,
+        since: Option<u64>,
+    ) -> Result<(), Error> {
+        let client = self.get_read_only_client(&config).await?;
+
+        let offers: HashMap<EventId, OptionOfferCreatedEvent> = client
+            .fetch_option_offer_history(config.address_params())
+            .await?
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|offer| is_offer_for_pair(&offer.option_offer_args, collateral_asset, settlement_asset))
+            .map(|offer| (offer.event_id, offer))
+            .collect();
+
+        let actions: Vec<ActionCompletedEvent> = client
+            .fetch_actions(since.map(Timestamp::from))
+            .await?
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+
+        client.disconnect().await;
+
+        let prices: HashMap<EventId, u64> = offers
+            .iter()
+            .map(|(event_id, offer)| (*event_id, offer.option_offer_args.collateral_per_contract()))
+            .collect();
+        let trades = match_takes(&prices, &actions, since);
+
+        if trades.is_empty() {
+            eprintln!("No executed trades found for this pair.");
+            return Ok(());
+        }
+
+        // Size is left empty when the take transaction cannot be fetched
+        let take_txids: Vec<Txid> = trades.iter().map(|trade| trade.take_outpoint.txid).collect();
+        let take_txs = run_blocking(move || {
+            Ok::<_, EsploraError>(
+                take_txids
+                    .into_iter()
+                    .filter_map(|txid| Some((txid, fetch_transaction(txid).ok()?)))
+                    .collect::<HashMap<Txid, Transaction>>(),
+            )
+        })
+        .await
+        .unwrap_or_default();
+
+        println!("timestamp,price,size");
+        for trade in trades {
+            let offer = &offers[&trade.offer_event];
+            let offer_script = offer.taproot_pubkey_gen.address.script_pubkey();
+
+            let size = take_txs
+                .get(&trade.take_outpoint.txid)
+                .map(|tx| taken_collateral(tx, &offer_script, collateral_asset).to_string())
+                .unwrap_or_default();
+
+            println!("{},{},{size}", trade.timestamp, trade.price);
+        }
+
+        Ok(())
+    }
+}
+
+/// Correlate take actions with the offers they reference, oldest first.
+fn match_takes(prices: &HashMap<EventId, u64>, actions: &[ActionCompletedEvent], since: Option<u64>) -> Vec<Trade> {
+    let mut trades: Vec<Trade> = actions
+        .iter()
+        .filter(|action| action.action == ActionType::OptionOfferExercised)
+        .filter(|action| since.is_none_or(|since| action.created_at.as_secs() >= since))
+        .filter_map(|action| {
+            let price = *prices.get(&action.original_event_id)?;
+            Some(Trade {
+                timestamp: action.created_at.as_secs(),
+                price,
+                offer_event: action.original_event_id,
+                take_outpoint: action.outpoint,
+            })
+        })
+        .collect();
+
+    trades.sort_by_key(|trade| trade.timestamp);
+    trades
+}
+
+/// Collateral a take moved out of the offer: every explicit collateral output not paid back to the offer.
+pub(crate) fn taken_collateral(tx: &Transaction, offer_script: &Script, collateral_asset: AssetId) -> u64 {
+    tx.output
+        .iter()
+        .filter(|out| out.asset.explicit() == Some(collateral_asset) && out.script_pubkey != *offer_script)
+        .filter_map(|out| out.value.explicit())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{LockTime, TxOut, TxOutWitness, Txid};
+
+    fn take(offer: EventId, action: ActionType, created_at: u64, seed: u8) -> ActionCompletedEvent {
+        let mut event = ActionCompletedEvent::new(offer, action, OutPoint::new(Txid::from_byte_array([seed; 32]), 0));
+        event.created_at = Timestamp::from(created_at);
+        event
+    }
+
+    #[test]
+    fn test_match_takes_keeps_known_offer_takes_in_time_order() {
+        let offer_a = EventId::from_byte_array([1; 32]);
+        let offer_b = EventId::from_byte_array([2; 32]);
+        let unknown = EventId::from_byte_array([3; 32]);
+        let prices = HashMap::from([(offer_a, 100), (offer_b, 120)]);
+
+        let actions = [
+            take(offer_b, ActionType::OptionOfferExercised, 300, 1),
+            take(offer_a, ActionType::OptionOfferExercised, 200, 2),
+            take(offer_a, ActionType::OptionOfferCancelled, 250, 3),
+            take(unknown, ActionType::OptionOfferExercised, 260, 4),
+            take(offer_a, ActionType::OptionOfferExercised, 100, 5),
+        ];
+
+        let trades = match_takes(&prices, &actions, Some(150));
+        let rows: Vec<(u64, u64)> = trades.iter().map(|trade| (trade.timestamp, trade.price)).collect();
+
+        assert_eq!(rows, vec![(200, 100), (300, 120)]);
+    }
+
+    #[test]
+    fn test_taken_collateral_excludes_remainder_at_offer() {
+        let collateral = AssetId::from_slice(&[1; 32]).unwrap();
+        let settlement = AssetId::from_slice(&[2; 32]).unwrap();
+        let offer_script = Script::from(vec![0x51]);
+        let taker_script = Script::from(vec![0x52]);
+
+        let output = |asset: AssetId, value: u64, script_pubkey: &Script| TxOut {
+            asset: Asset::Explicit(asset),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        };
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![
+                output(collateral, 6_000, &offer_script),
+                output(settlement, 400_000, &offer_script),
+                output(collateral, 4_000, &taker_script),
+            ],
+        };
+
+        assert_eq!(taken_collateral(&tx, &offer_script, collateral), 4_000);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::cli::interactive::current_timestamp;
+use crate::cli::{Cli, RegtestCommand};
+use crate::config::Config;
+use crate::error::Error;
+use crate::regtest::RegtestNode;
+
+/// Blocks needed for the median time past to reach the timestamp of newly mined blocks.
+const MEDIAN_TIME_SPAN: u32 = 11;
+
+impl Cli {
+    pub(crate) fn run_regtest(config: &Config, command: &RegtestCommand) -> Result<(), Error> {
+        match command {
+            RegtestCommand::Advance { blocks, time } => {
+                let node = RegtestNode::new(&config.regtest);
+                let before = node.ensure_regtest()?;
+
+                if let Some(time) = time {
+                    let secs =
+                        i64::try_from(time.as_secs()).map_err(|_| Error::Config("Duration too large".to_string()))?;
+                    let target = before.mediantime.max(current_timestamp()) + secs;
+                    node.set_mock_time(target)?;
+                    println!("Node clock set to {target}");
+
+                    if *blocks < MEDIAN_TIME_SPAN {
+                        println!(
+                            "Note: timelocks use median time past; mine at least {MEDIAN_TIME_SPAN} blocks for it to reach {target}"
+                        );
+                    }
+                }
+
+                let hashes = node.generate(*blocks)?;
+                let after = node.blockchain_info()?;
+
+                println!(
+                    "Mined {} blocks on {}: height {} -> {}, median time past {} -> {}",
+                    hashes.len(),
+                    after.chain,
+                    before.blocks,
+                    after.blocks,
+                    before.mediantime,
+                    after.mediantime
+                );
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::error::Error;
+
+use std::fmt;
+
+use options_relay::{RelayError, RelayProbe};
+use serde::Deserialize;
+
+/// Timeout for the NIP-11 relay information request, in seconds.
+const RELAY_INFO_TIMEOUT_SECS: u64 = 10;
+
+/// Relay information document (NIP-11), reduced to the fields we report.
+#[derive(Debug, Default, Deserialize)]
+struct RelayInfo {
+    #[serde(default)]
+    software: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    supported_nips: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelayHealth {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl RelayHealth {
+    /// The worse of two outcomes.
+    const fn max_with(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Fail, _) | (_, Self::Fail) => Self::Fail,
+            (Self::Warn, _) | (_, Self::Warn) => Self::Warn,
+            _ => Self::Ok,
+        }
+    }
+}
+
+impl fmt::Display for RelayHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Warn => write!(f, "warn"),
+            Self::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+impl Cli {
+    /// Check every configured relay: latency, test publishes of our event kinds and NIP-11 info.
+    pub(crate) async fn run_relay_status(&self, config: Config) -> Result<(), Error> {
+        let client = self.get_publishing_client(&config).await?;
+        let relay_config = config.relay.get_nostr_relay_config();
+
+        for url in relay_config.all_relays() {
+            // The NIP-11 request blocks, so it runs on the blocking pool while the probe is in flight
+            let info_url = url.to_string();
+            let info = tokio::task::spawn_blocking(move || fetch_relay_info(&info_url));
+            let probe = client.probe_relay(url).await;
+            let info = info.await.unwrap_or_else(|e| Err(e.to_string()));
+
+            let (health, notes) = assess_relay(&probe, &info);
+            let latency = probe
+                .as_ref()
+                .map_or_else(|_| "-".to_string(), |probe| format!("{}ms", probe.latency.as_millis()));
+
+            println!("[{health:<4}] {url} ({latency})");
+            for note in notes {
+                println!("         {note}");
+            }
+        }
+
+        client.disconnect().await;
+
+        Ok(())
+    }
+}
+
+/// Classify a relay: unreachable or rejecting any of our kinds fails, missing cleanup or info warns.
+fn assess_relay(
+    probe: &Result<RelayProbe, RelayError>,
+    info: &Result<RelayInfo, String>,
+) -> (RelayHealth, Vec<String>) {
+    let mut health = RelayHealth::Ok;
+    let mut notes = Vec::new();
+
+    match probe {
+        Err(e) => {
+            health = RelayHealth::Fail;
+            notes.push(format!("unreachable: {e}"));
+        }
+        Ok(probe) => {
+            for (kind, message) in &probe.rejected_kinds {
+                health = RelayHealth::Fail;
+                notes.push(format!("rejected kind {}: {message}", kind.as_u16()));
+            }
+            if !probe.cleaned_up {
+                health = health.max_with(RelayHealth::Warn);
+                notes.push("test events were not deleted (they expire on their own)".to_string());
+            }
+        }
+    }
+
+    match info {
+        Err(e) => {
+            health = health.max_with(RelayHealth::Warn);
+            notes.push(format!("no NIP-11 relay info: {e}"));
+        }
+        Ok(info) => {
+            let software = match (&info.software, &info.version) {
+                (Some(software), Some(version)) => format!("{software} {version}"),
+                (Some(software), None) => software.clone(),
+                _ => "unknown software".to_string(),
+            };
+            let nips = info
+                .supported_nips
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            notes.push(format!("{software}, NIPs: [{nips}]"));
+        }
+    }
+
+    (health, notes)
+}
+
+/// Fetch the NIP-11 relay information document served over HTTP at the relay URL.
+fn fetch_relay_info(url: &str) -> Result<RelayInfo, String> {
+    let response = minreq::get(relay_info_url(url))
+        .with_header("Accept", "application/nostr+json")
+        .with_timeout(RELAY_INFO_TIMEOUT_SECS)
+        .send()
+        .map_err(|e| e.to_string())?;
+
+    if response.status_code != 200 {
+        return Err(format!("HTTP {}: {}", response.status_code, response.reason_phrase));
+    }
+
+    response.json().map_err(|e| e.to_string())
+}
+
+fn relay_info_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("wss://") {
+        format!("https://{rest}")
+    } else if let Some(rest) = url.strip_prefix("ws://") {
+        format!("http://{rest}")
+    } else {
+        url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use options_relay::OPTION_OFFER_CREATED;
+
+    #[test]
+    fn test_relay_info_url_maps_websocket_schemes() {
+        assert_eq!(relay_info_url("wss://relay.damus.io"), "https://relay.damus.io");
+        assert_eq!(relay_info_url("ws://127.0.0.1:7000"), "http://127.0.0.1:7000");
+    }
+
+    #[test]
+    fn test_assess_relay_fails_on_rejected_kind_and_warns_on_missing_info() {
+        let info = Ok(RelayInfo {
+            software: Some("strfry".to_string()),
+            version: None,
+            supported_nips: vec![1, 9, 11, 40],
+        });
+        let healthy = Ok(RelayProbe {
+            latency: Duration::from_millis(40),
+            rejected_kinds: Vec::new(),
+            cleaned_up: true,
+        });
+        let rejecting = Ok(RelayProbe {
+            latency: Duration::from_millis(40),
+            rejected_kinds: vec![(OPTION_OFFER_CREATED, "blocked: kind not allowed".to_string())],
+            cleaned_up: true,
+        });
+
+        assert_eq!(assess_relay(&healthy, &info).0, RelayHealth::Ok);
+        assert_eq!(assess_relay(&healthy, &Err("timeout".to_string())).0, RelayHealth::Warn);
+        assert_eq!(assess_relay(&rejecting, &info).0, RelayHealth::Fail);
+        assert_eq!(
+            assess_relay(&Err(RelayError::NoRelaysConfigured), &info).0,
+            RelayHealth::Fail
+        );
+    }
+}
@@ -3,20 +3,22 @@ use std::collections::{HashMap, HashSet};
 use coin_store::UtxoStore;
 use contracts::option_offer::OPTION_OFFER_SOURCE;
 use contracts::options::OPTION_SOURCE;
-use options_relay::{OptionCreatedEvent, OptionOfferCreatedEvent};
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use options_relay::{ActionCompletedEvent, ActionType, OptionCreatedEvent, OptionOfferCreatedEvent};
 use simplicityhl::elements::hex::ToHex;
 use simplicityhl::elements::{OutPoint, Txid};
-use simplicityhl_core::derive_public_blinder_key;
 
 use crate::cli::Cli;
-use crate::cli::SyncCommand;
+use crate::cli::option_offer::collect_withdrawable_option_offers;
+use crate::cli::{OptionOfferCommand, SyncCommand};
 use crate::config::Config;
 use crate::error::Error;
 use crate::explorer::{
-    esplora_utxo_to_outpoint, fetch_address_utxos, fetch_outspends, fetch_scripthash_utxos, fetch_tip_height,
-    fetch_transaction,
+    EsploraUtxo, esplora_utxo_to_outpoint, fetch_address_utxos, fetch_outspends, fetch_scripthash_utxos,
+    fetch_tip_height, fetch_transaction,
 };
-use crate::sync::{sync_option_event, sync_option_offer_event};
+use crate::metadata::{ContractMetadata, HistoryEntry};
+use crate::sync::{import_transaction, sync_option_event, sync_option_offer_event};
 use options_relay::ReadOnlyClient;
 
 #[derive(Default)]
@@ -59,14 +61,92 @@ impl SyncStats {
 }
 
 impl Cli {
-    pub(crate) async fn run_sync(&self, config: Config, command: &SyncCommand) -> Result<(), Error> {
+    pub(crate) async fn run_sync(&self, config: Config, command: &SyncCommand, confirmed: bool) -> Result<(), Error> {
         match command {
-            SyncCommand::Full => self.run_sync_full(config).await,
-            SyncCommand::Spent => self.run_sync_spent(config).await,
-            SyncCommand::Utxos => self.run_sync_utxos(config).await,
-            SyncCommand::Nostr => self.run_sync_nostr(config).await,
-            SyncCommand::History => self.run_sync_history(config).await,
+            SyncCommand::Full => self.run_sync_full(config.clone()).await?,
+            SyncCommand::Spent => return self.run_sync_spent(config).await,
+            SyncCommand::Utxos => self.run_sync_utxos(config.clone()).await?,
+            SyncCommand::Nostr => return self.run_sync_nostr(config).await,
+            SyncCommand::History => return self.run_sync_history(config).await,
+            SyncCommand::Check => return self.run_sync_check(config).await,
         }
+
+        // Settlement only shows up after UTXO discovery, so automation runs after `full` and `utxos`.
+        if config.automation.auto_withdraw {
+            self.run_auto_withdraw(config, confirmed).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Withdraw settlement from every option offer of ours that has been taken.
+    ///
+    /// Without `confirmed` (`sync --yes`) only lists what would be withdrawn.
+    async fn run_auto_withdraw(&self, config: Config, confirmed: bool) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let offer_contracts =
+            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
+        let withdrawable_offers = collect_withdrawable_option_offers(
+            wallet.store(),
+            wallet.public_key(),
+            config.address_params(),
+            offer_contracts,
+        )
+        .await;
+
+        if withdrawable_offers.is_empty() {
+            return Ok(());
+        }
+
+        println!();
+        println!(
+            "Auto-withdraw: {} taken offer(s) with settlement ready",
+            withdrawable_offers.len()
+        );
+
+        if !confirmed {
+            for offer in &withdrawable_offers {
+                println!(
+                    "  {} ({} sats)",
+                    offer.metadata.nostr_event_id.as_deref().unwrap_or("<local>"),
+                    offer.settlement_amount
+                );
+            }
+            println!("  Re-run with `sync --yes` to withdraw automatically.");
+            return Ok(());
+        }
+
+        let mut withdrawn = 0usize;
+        for offer in withdrawable_offers {
+            let Some(event_id) = offer.metadata.nostr_event_id else {
+                println!(
+                    "  Skipping offer {}: no NOSTR event ID, use `option-offer withdraw`",
+                    offer.taproot_pubkey_gen
+                );
+                continue;
+            };
+
+            println!();
+            println!(
+                "Auto-withdrawing offer {event_id} ({} sats)...",
+                offer.settlement_amount
+            );
+            let command = OptionOfferCommand::Withdraw {
+                offer_event: Some(event_id.clone()),
+                fee: None,
+                broadcast: true,
+                force: false,
+            };
+            match Box::pin(self.run_option_offer(config.clone(), &command)).await {
+                Ok(()) => withdrawn += 1,
+                Err(e) => println!("  Auto-withdraw failed for {event_id}: {e}"),
+            }
+        }
+
+        println!();
+        println!("Auto-withdraw complete: {withdrawn} offer(s) withdrawn");
+
+        Ok(())
     }
 
     /// Full sync: mark spent UTXOs + discover new UTXOs + sync NOSTR events + sync history
@@ -141,7 +221,6 @@ impl Cli {
     }
 
     /// Only sync action history for existing contracts from NOSTR (no UTXOs)
-    #[allow(clippy::too_many_lines)]
     async fn run_sync_history(&self, config: Config) -> Result<(), Error> {
         println!("Syncing action history for existing contracts...");
         println!();
@@ -153,75 +232,17 @@ impl Cli {
         let mut contracts_checked = 0;
         let mut errors: Vec<String> = Vec::new();
 
-        let option_contracts =
-            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_SOURCE).await?;
-        let option_offer_contracts =
-            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
-
-        println!(
-            "  Found {} option contracts and {} option offer contracts",
-            option_contracts.len(),
-            option_offer_contracts.len()
-        );
-
-        // Process option contracts
-        for (args_bytes, tpg_str, metadata_bytes) in &option_contracts {
-            let Some(meta_bytes) = metadata_bytes else {
-                continue;
-            };
-
-            let Ok(metadata) = crate::metadata::ContractMetadata::from_bytes(meta_bytes) else {
-                continue;
-            };
-
-            let Some(nostr_event_id_str) = &metadata.nostr_event_id else {
-                continue;
-            };
-
-            let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id_str) else {
-                errors.push(format!("Invalid event ID: {nostr_event_id_str}"));
-                continue;
-            };
-
-            let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                args_bytes,
-                bincode::config::standard(),
-            ) else {
-                continue;
-            };
-
-            let Ok(options_args) = contracts::options::OptionsArguments::from_arguments(&args) else {
-                continue;
-            };
-
-            let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
-                tpg_str,
-                &options_args,
-                config.address_params(),
-                &contracts::options::get_options_address,
-            ) else {
-                errors.push(format!(
-                    "Invalid taproot pubkey gen: {}",
-                    &tpg_str[..tpg_str.len().min(20)]
-                ));
-                continue;
-            };
-
+        for contract in announced_contracts(wallet.store(), &config, &mut errors).await? {
             contracts_checked += 1;
 
-            if let Ok(actions) = client.fetch_actions_for_event(event_id).await {
+            if let Ok(actions) = client.fetch_actions_for_event(contract.event_id).await {
                 for action in actions.into_iter().flatten() {
-                    #[allow(clippy::cast_possible_wrap)]
-                    let timestamp = action.created_at.as_secs() as i64;
-                    let entry = crate::metadata::HistoryEntry::with_txid_and_nostr(
-                        action.action.as_str(),
-                        &action.outpoint.txid.to_string(),
-                        &action.event_id.to_hex(),
-                        timestamp,
-                    );
-
-                    if let Ok(added) =
-                        crate::sync::add_history_entry_if_new(wallet.store(), &taproot_pubkey_gen, entry).await
+                    if let Ok(added) = crate::sync::add_history_entry_if_new(
+                        wallet.store(),
+                        &contract.taproot_pubkey_gen,
+                        action_history_entry(&action),
+                    )
+                    .await
                         && added
                     {
                         actions_synced += 1;
@@ -230,78 +251,81 @@ impl Cli {
             }
         }
 
-        // Process option offer contracts
-        for (args_bytes, tpg_str, metadata_bytes) in &option_offer_contracts {
-            let Some(meta_bytes) = metadata_bytes else {
-                continue;
-            };
+        client.disconnect().await;
 
-            let Ok(metadata) = crate::metadata::ContractMetadata::from_bytes(meta_bytes) else {
-                continue;
-            };
+        println!();
+        println!("=== History Sync Summary ===");
+        println!("Contracts checked:    {contracts_checked}");
+        println!("Actions synced:       {actions_synced}");
 
-            let Some(nostr_event_id_str) = &metadata.nostr_event_id else {
-                continue;
-            };
+        if !errors.is_empty() {
+            println!();
+            println!("Warnings/Errors ({}):", errors.len());
+            for (i, error) in errors.iter().enumerate().take(10) {
+                println!("  {}. {}", i + 1, error);
+            }
+            if errors.len() > 10 {
+                println!("  ... and {} more", errors.len() - 10);
+            }
+        }
 
-            let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id_str) else {
-                errors.push(format!("Invalid event ID: {nostr_event_id_str}"));
-                continue;
-            };
+        Ok(())
+    }
 
-            let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                args_bytes,
-                bincode::config::standard(),
-            ) else {
-                continue;
-            };
+    /// Compare each contract's local history with the actions relays report for it, without
+    /// changing anything.
+    async fn run_sync_check(&self, config: Config) -> Result<(), Error> {
+        println!("Comparing local contract history with NOSTR relays...");
+        println!();
 
-            let Ok(option_offer_args) = contracts::option_offer::OptionOfferArguments::from_arguments(&args) else {
-                continue;
-            };
+        let wallet = self.get_wallet(&config).await?;
+        let client = self.get_read_only_client(&config).await?;
 
-            let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
-                tpg_str,
-                &option_offer_args,
-                config.address_params(),
-                &contracts::option_offer::get_option_offer_address,
-            ) else {
-                errors.push(format!(
-                    "Invalid taproot pubkey gen: {}",
-                    &tpg_str[..tpg_str.len().min(20)]
-                ));
-                continue;
-            };
+        let mut contracts_checked = 0;
+        let mut contracts_differing = 0;
+        let mut errors: Vec<String> = Vec::new();
 
+        for contract in announced_contracts(wallet.store(), &config, &mut errors).await? {
+            let actions = match client.fetch_actions_for_event(contract.event_id).await {
+                Ok(actions) => actions,
+                Err(e) => {
+                    errors.push(format!("Could not fetch actions for event {}: {e}", contract.event_id));
+                    continue;
+                }
+            };
             contracts_checked += 1;
 
-            if let Ok(actions) = client.fetch_actions_for_event(event_id).await {
-                for action in actions.into_iter().flatten() {
-                    #[allow(clippy::cast_possible_wrap)]
-                    let timestamp = action.created_at.as_secs() as i64;
-                    let entry = crate::metadata::HistoryEntry::with_txid_and_nostr(
-                        action.action.as_str(),
-                        &action.outpoint.txid.to_string(),
-                        &action.event_id.to_hex(),
-                        timestamp,
-                    );
-
-                    if let Ok(added) =
-                        crate::sync::add_history_entry_if_new(wallet.store(), &taproot_pubkey_gen, entry).await
-                        && added
-                    {
-                        actions_synced += 1;
-                    }
-                }
+            let relay_actions: Vec<_> = actions
+                .into_iter()
+                .flatten()
+                .map(|a| action_history_entry(&a))
+                .collect();
+            let discrepancies = contract.metadata.history_discrepancies(&relay_actions);
+            if discrepancies.is_empty() {
+                continue;
+            }
+
+            contracts_differing += 1;
+            println!(
+                "  Contract {} (event {}):",
+                contract.taproot_pubkey_gen, contract.event_id
+            );
+            for discrepancy in &discrepancies {
+                println!("    - {discrepancy}");
             }
         }
 
         client.disconnect().await;
 
         println!();
-        println!("=== History Sync Summary ===");
+        println!("=== Sync Check Summary ===");
         println!("Contracts checked:    {contracts_checked}");
-        println!("Actions synced:       {actions_synced}");
+        println!("Contracts differing:  {contracts_differing}");
+
+        if contracts_differing > 0 {
+            println!();
+            println!("Run `sync history` to import the actions relays show that are missing locally.");
+        }
 
         if !errors.is_empty() {
             println!();
@@ -393,7 +417,7 @@ impl Cli {
         }
 
         println!("  Checking wallet address...");
-        let wallet_address = wallet.signer().p2pk_address(config.address_params())?;
+        let wallet_address = wallet.p2pk_address()?;
 
         match fetch_address_utxos(&wallet_address) {
             Ok(utxos) => {
@@ -408,19 +432,20 @@ impl Cli {
                                     .import_transaction_from_esplora(wallet.store(), outpoint.txid)
                                     .await
                                 {
-                                    Ok(true) => {
-                                        stats.new_utxos_imported += 1;
-                                        imported_txids.insert(outpoint.txid);
-                                        tracing::debug!("Imported transaction: {}", outpoint.txid);
-                                    }
-                                    Ok(false) => {
+                                    Ok(inserted) => {
+                                        stats.new_utxos_imported += inserted;
                                         imported_txids.insert(outpoint.txid);
+                                        tracing::debug!(
+                                            "Imported transaction {} ({inserted} new outputs)",
+                                            outpoint.txid
+                                        );
                                     }
                                     Err(e) => {
                                         stats.errors.push(format!("Failed to import tx {}: {e}", outpoint.txid));
                                     }
                                 }
                             }
+                            record_confirmation_height(wallet.store(), outpoint, &utxo, stats).await;
                         }
                         Err(e) => {
                             stats.errors.push(format!("Invalid UTXO from Esplora: {e}"));
@@ -452,19 +477,20 @@ impl Cli {
                                         .import_transaction_from_esplora(wallet.store(), outpoint.txid)
                                         .await
                                     {
-                                        Ok(true) => {
-                                            stats.new_utxos_imported += 1;
-                                            imported_txids.insert(outpoint.txid);
-                                            tracing::debug!("Imported transaction: {}", outpoint.txid);
-                                        }
-                                        Ok(false) => {
+                                        Ok(inserted) => {
+                                            stats.new_utxos_imported += inserted;
                                             imported_txids.insert(outpoint.txid);
+                                            tracing::debug!(
+                                                "Imported transaction {} ({inserted} new outputs)",
+                                                outpoint.txid
+                                            );
                                         }
                                         Err(e) => {
                                             stats.errors.push(format!("Failed to import tx {}: {e}", outpoint.txid));
                                         }
                                     }
                                 }
+                                record_confirmation_height(wallet.store(), outpoint, &utxo, stats).await;
                             }
                             Err(e) => {
                                 stats.errors.push(format!("Invalid UTXO from Esplora: {e}"));
@@ -485,28 +511,15 @@ impl Cli {
         Ok(())
     }
 
-    async fn import_transaction_from_esplora(&self, store: &coin_store::Store, txid: Txid) -> Result<bool, Error> {
+    /// Fetch a transaction from Esplora and store it, returning how many of its outputs were new.
+    pub(crate) async fn import_transaction_from_esplora(
+        &self,
+        store: &coin_store::Store,
+        txid: Txid,
+    ) -> Result<usize, Error> {
         let tx = fetch_transaction(txid)?;
 
-        let blinder_keypair = derive_public_blinder_key();
-        let blinder_keys: HashMap<usize, _> = tx
-            .output
-            .iter()
-            .enumerate()
-            .filter(|(_, out)| !out.is_fee())
-            .filter(|(_, out)| out.asset.is_confidential())
-            .map(|(i, _)| (i, blinder_keypair))
-            .collect();
-
-        match store.insert_transaction(&tx, blinder_keys).await {
-            Ok(()) => Ok(true),
-            Err(
-                coin_store::StoreError::UtxoAlreadyExists(_)
-                | coin_store::StoreError::MissingBlinderKey(_)
-                | coin_store::StoreError::Unblind(_),
-            ) => Ok(false),
-            Err(e) => Err(e.into()),
-        }
+        import_transaction(store, &tx).await
     }
 
     /// Sync options and option offers from NOSTR relay (creates its own client).
@@ -528,25 +541,30 @@ impl Cli {
 
         println!("  Fetching options from NOSTR...");
         let options_results = client.fetch_options(config.address_params()).await?;
-        let valid_options: Vec<OptionCreatedEvent> = options_results.into_iter().filter_map(Result::ok).collect();
+        let valid_options: Vec<OptionCreatedEvent> = options_results
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|event| config.relay.accepts_author(&event.pubkey))
+            .collect();
 
         println!("    Found {} valid options", valid_options.len());
 
         let mut options_already_synced = 0;
         for event in &valid_options {
+            if wallet.store().get_contract_by_outpoint(event.utxo).await?.is_some() {
+                options_already_synced += 1;
+                continue;
+            }
+
             let arguments = event.options_args.build_option_arguments();
             match sync_option_event(wallet.store(), event, OPTION_SOURCE, arguments).await {
                 Ok(()) => {
                     stats.nostr_options_synced += 1;
                 }
                 Err(e) => {
-                    if e.to_string().contains("UNIQUE constraint") {
-                        options_already_synced += 1;
-                    } else {
-                        stats
-                            .errors
-                            .push(format!("Failed to sync option {}: {}", event.event_id, e));
-                    }
+                    stats
+                        .errors
+                        .push(format!("Failed to sync option {}: {}", event.event_id, e));
                 }
             }
         }
@@ -556,24 +574,27 @@ impl Cli {
 
         println!("  Fetching option offers from NOSTR...");
         let offers_results = client.fetch_option_offers(config.address_params()).await?;
-        let valid_offers: Vec<OptionOfferCreatedEvent> = offers_results.into_iter().filter_map(Result::ok).collect();
+        let valid_offers: Vec<OptionOfferCreatedEvent> = offers_results
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|offer| config.relay.accepts_author(&offer.pubkey))
+            .collect();
 
         println!("    Found {} valid option offers", valid_offers.len());
 
         let mut actions_synced = 0;
         let mut offers_already_synced = 0;
         for offer in &valid_offers {
-            // First sync the option offer contract itself
-            let arguments = offer.option_offer_args.build_arguments();
-            match sync_option_offer_event(wallet.store(), offer, OPTION_OFFER_SOURCE, arguments, None).await {
-                Ok(()) => {
-                    stats.nostr_option_offers_synced += 1;
-                }
-                Err(e) => {
-                    // Ignore duplicate errors (already synced)
-                    if e.to_string().contains("UNIQUE constraint") {
-                        offers_already_synced += 1;
-                    } else {
+            // First sync the option offer contract itself, unless its funding output is already known
+            if wallet.store().get_contract_by_outpoint(offer.utxo).await?.is_some() {
+                offers_already_synced += 1;
+            } else {
+                let arguments = offer.option_offer_args.build_arguments();
+                match sync_option_offer_event(wallet.store(), offer, OPTION_OFFER_SOURCE, arguments, None).await {
+                    Ok(()) => {
+                        stats.nostr_option_offers_synced += 1;
+                    }
+                    Err(e) => {
                         stats
                             .errors
                             .push(format!("Failed to sync option offer {}: {}", offer.event_id, e));
@@ -583,20 +604,31 @@ impl Cli {
 
             if let Ok(actions) = client.fetch_actions_for_event(offer.event_id).await {
                 for action in actions.into_iter().flatten() {
-                    #[allow(clippy::cast_possible_wrap)]
-                    let timestamp = action.created_at.as_secs() as i64;
-                    let entry = crate::metadata::HistoryEntry::with_txid_and_nostr(
-                        action.action.as_str(),
-                        &action.outpoint.txid.to_string(),
-                        &action.event_id.to_hex(),
-                        timestamp,
-                    );
-
-                    if let Ok(added) =
-                        crate::sync::add_history_entry_if_new(wallet.store(), &offer.taproot_pubkey_gen, entry).await
+                    if let Ok(added) = crate::sync::add_history_entry_if_new(
+                        wallet.store(),
+                        &offer.taproot_pubkey_gen,
+                        action_history_entry(&action),
+                    )
+                    .await
                         && added
                     {
                         actions_synced += 1;
+
+                        if action.action == ActionType::OptionOfferExercised
+                            && let Err(e) = crate::sync::refresh_remaining_collateral(
+                                wallet.store(),
+                                &offer.taproot_pubkey_gen,
+                                &offer.option_offer_args,
+                                action.outpoint.txid,
+                            )
+                            .await
+                        {
+                            tracing::debug!(
+                                "Could not update remaining collateral from take {}: {} (soft failure)",
+                                action.outpoint.txid,
+                                e
+                            );
+                        }
                     }
 
                     if let Err(e) = crate::sync::sync_utxo_with_public_blinder(wallet.store(), action.outpoint).await {
@@ -628,7 +660,6 @@ impl Cli {
     }
 
     /// Sync action history for existing contracts from NOSTR using provided client.
-    #[allow(clippy::too_many_lines)]
     async fn sync_history_with_client(
         &self,
         config: &Config,
@@ -637,75 +668,17 @@ impl Cli {
     ) -> Result<(), Error> {
         let wallet = self.get_wallet(config).await?;
 
-        let option_contracts =
-            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_SOURCE).await?;
-        let option_offer_contracts =
-            <_ as UtxoStore>::list_contracts_by_source_with_metadata(wallet.store(), OPTION_OFFER_SOURCE).await?;
-
-        println!(
-            "  Found {} option contracts and {} option offer contracts",
-            option_contracts.len(),
-            option_offer_contracts.len()
-        );
-
-        // Process option contracts
-        for (args_bytes, tpg_str, metadata_bytes) in &option_contracts {
-            let Some(meta_bytes) = metadata_bytes else {
-                continue;
-            };
-
-            let Ok(metadata) = crate::metadata::ContractMetadata::from_bytes(meta_bytes) else {
-                continue;
-            };
-
-            let Some(nostr_event_id_str) = &metadata.nostr_event_id else {
-                continue;
-            };
-
-            let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id_str) else {
-                stats.errors.push(format!("Invalid event ID: {nostr_event_id_str}"));
-                continue;
-            };
-
-            let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                args_bytes,
-                bincode::config::standard(),
-            ) else {
-                continue;
-            };
-
-            let Ok(options_args) = contracts::options::OptionsArguments::from_arguments(&args) else {
-                continue;
-            };
-
-            let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
-                tpg_str,
-                &options_args,
-                config.address_params(),
-                &contracts::options::get_options_address,
-            ) else {
-                stats.errors.push(format!(
-                    "Invalid taproot pubkey gen: {}",
-                    &tpg_str[..tpg_str.len().min(20)]
-                ));
-                continue;
-            };
-
+        for contract in announced_contracts(wallet.store(), config, &mut stats.errors).await? {
             stats.history_contracts_checked += 1;
 
-            if let Ok(actions) = client.fetch_actions_for_event(event_id).await {
+            if let Ok(actions) = client.fetch_actions_for_event(contract.event_id).await {
                 for action in actions.into_iter().flatten() {
-                    #[allow(clippy::cast_possible_wrap)]
-                    let timestamp = action.created_at.as_secs() as i64;
-                    let entry = crate::metadata::HistoryEntry::with_txid_and_nostr(
-                        action.action.as_str(),
-                        &action.outpoint.txid.to_string(),
-                        &action.event_id.to_hex(),
-                        timestamp,
-                    );
-
-                    if let Ok(added) =
-                        crate::sync::add_history_entry_if_new(wallet.store(), &taproot_pubkey_gen, entry).await
+                    if let Ok(added) = crate::sync::add_history_entry_if_new(
+                        wallet.store(),
+                        &contract.taproot_pubkey_gen,
+                        action_history_entry(&action),
+                    )
+                    .await
                         && added
                     {
                         stats.history_actions_synced += 1;
@@ -714,77 +687,147 @@ impl Cli {
             }
         }
 
-        // Process option offer contracts
-        for (args_bytes, tpg_str, metadata_bytes) in &option_offer_contracts {
-            let Some(meta_bytes) = metadata_bytes else {
-                continue;
-            };
+        println!(
+            "  Checked {} contracts, synced {} actions.",
+            stats.history_contracts_checked, stats.history_actions_synced
+        );
 
-            let Ok(metadata) = crate::metadata::ContractMetadata::from_bytes(meta_bytes) else {
-                continue;
-            };
+        Ok(())
+    }
+}
 
-            let Some(nostr_event_id_str) = &metadata.nostr_event_id else {
-                continue;
-            };
+/// A stored option or option offer contract together with the NOSTR event that announced it.
+struct AnnouncedContract {
+    taproot_pubkey_gen: TaprootPubkeyGen,
+    event_id: nostr::EventId,
+    metadata: ContractMetadata,
+}
 
-            let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id_str) else {
-                stats.errors.push(format!("Invalid event ID: {nostr_event_id_str}"));
+/// Stored option and option offer contracts that were announced on NOSTR, options first.
+///
+/// Contracts without an announcement event are skipped; ones whose records cannot be decoded
+/// are skipped and reported in `errors`.
+async fn announced_contracts(
+    store: &coin_store::Store,
+    config: &Config,
+    errors: &mut Vec<String>,
+) -> Result<Vec<AnnouncedContract>, Error> {
+    let option_contracts = <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, OPTION_SOURCE).await?;
+    let option_offer_contracts =
+        <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, OPTION_OFFER_SOURCE).await?;
+
+    println!(
+        "  Found {} option contracts and {} option offer contracts",
+        option_contracts.len(),
+        option_offer_contracts.len()
+    );
+
+    let rows = option_contracts
+        .iter()
+        .map(|row| (OPTION_SOURCE, row))
+        .chain(option_offer_contracts.iter().map(|row| (OPTION_OFFER_SOURCE, row)));
+
+    let mut announced = Vec::new();
+    for (source, (args_bytes, tpg_str, metadata_bytes)) in rows {
+        let Some(meta_bytes) = metadata_bytes else {
+            continue;
+        };
+
+        let Ok(metadata) = ContractMetadata::from_bytes(meta_bytes) else {
+            continue;
+        };
+
+        let Some(nostr_event_id_str) = &metadata.nostr_event_id else {
+            continue;
+        };
+
+        let Ok(event_id) = nostr::EventId::from_hex(nostr_event_id_str) else {
+            errors.push(format!("Invalid event ID: {nostr_event_id_str}"));
+            continue;
+        };
+
+        let args = match coin_store::decode_arguments(args_bytes) {
+            Ok(args) => args,
+            Err(e) => {
+                let tpg_prefix: String = tpg_str.chars().take(20).collect();
+                errors.push(format!("Skipping contract {tpg_prefix}: {e}"));
                 continue;
-            };
+            }
+        };
 
-            let Ok((args, _)) = bincode::serde::decode_from_slice::<simplicityhl::Arguments, _>(
-                args_bytes,
-                bincode::config::standard(),
-            ) else {
+        let taproot_pubkey_gen = if source == OPTION_SOURCE {
+            let Ok(options_args) = contracts::options::OptionsArguments::from_arguments(&args) else {
                 continue;
             };
-
+            TaprootPubkeyGen::build_from_str(
+                tpg_str,
+                &options_args,
+                config.address_params(),
+                &contracts::options::get_options_address,
+            )
+            .ok()
+        } else {
             let Ok(option_offer_args) = contracts::option_offer::OptionOfferArguments::from_arguments(&args) else {
                 continue;
             };
-
-            let Ok(taproot_pubkey_gen) = contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen::build_from_str(
+            TaprootPubkeyGen::build_from_str(
                 tpg_str,
                 &option_offer_args,
                 config.address_params(),
                 &contracts::option_offer::get_option_offer_address,
-            ) else {
-                stats.errors.push(format!(
-                    "Invalid taproot pubkey gen: {}",
-                    &tpg_str[..tpg_str.len().min(20)]
-                ));
-                continue;
-            };
-
-            stats.history_contracts_checked += 1;
+            )
+            .ok()
+        };
+
+        let Some(taproot_pubkey_gen) = taproot_pubkey_gen else {
+            let tpg_prefix: String = tpg_str.chars().take(20).collect();
+            errors.push(format!("Invalid taproot pubkey gen: {tpg_prefix}"));
+            continue;
+        };
+
+        announced.push(AnnouncedContract {
+            taproot_pubkey_gen,
+            event_id,
+            metadata,
+        });
+    }
 
-            if let Ok(actions) = client.fetch_actions_for_event(event_id).await {
-                for action in actions.into_iter().flatten() {
-                    #[allow(clippy::cast_possible_wrap)]
-                    let timestamp = action.created_at.as_secs() as i64;
-                    let entry = crate::metadata::HistoryEntry::with_txid_and_nostr(
-                        action.action.as_str(),
-                        &action.outpoint.txid.to_string(),
-                        &action.event_id.to_hex(),
-                        timestamp,
-                    );
-
-                    if let Ok(added) =
-                        crate::sync::add_history_entry_if_new(wallet.store(), &taproot_pubkey_gen, entry).await
-                        && added
-                    {
-                        stats.history_actions_synced += 1;
-                    }
-                }
-            }
-        }
+    Ok(announced)
+}
 
-        println!(
-            "  Checked {} contracts, synced {} actions.",
-            stats.history_contracts_checked, stats.history_actions_synced
-        );
+/// History entry recording a relay's action event.
+fn action_history_entry(action: &ActionCompletedEvent) -> HistoryEntry {
+    #[allow(clippy::cast_possible_wrap)]
+    let timestamp = action.created_at.as_secs() as i64;
+
+    HistoryEntry::with_txid_and_nostr(
+        action.action.as_str(),
+        &action.outpoint.txid.to_string(),
+        &action.event_id.to_hex(),
+        timestamp,
+    )
+}
 
-        Ok(())
+/// Store the confirmation height Esplora reports for an output we track.
+async fn record_confirmation_height(
+    store: &coin_store::Store,
+    outpoint: OutPoint,
+    utxo: &EsploraUtxo,
+    stats: &mut SyncStats,
+) {
+    let Some(height) = utxo.status.block_height.filter(|_| utxo.status.confirmed) else {
+        return;
+    };
+    let Ok(height) = u32::try_from(height) else {
+        stats
+            .errors
+            .push(format!("Invalid block height {height} for {outpoint}"));
+        return;
+    };
+
+    if let Err(e) = store.set_height(outpoint, height).await {
+        stats
+            .errors
+            .push(format!("Failed to record height for {outpoint}: {e}"));
     }
 }
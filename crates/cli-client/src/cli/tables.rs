@@ -152,6 +152,30 @@ impl TableData for UtxoDisplay {
     }
 }
 
+pub struct BalanceDisplay {
+    pub asset: String,
+    pub spendable: String,
+    pub in_contracts: String,
+    pub total: String,
+}
+
+impl TableData for BalanceDisplay {
+    fn get_header() -> Vec<String> {
+        vec!["Asset", "Spendable", "In Contracts", "Total"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+    fn to_row(&self) -> Vec<String> {
+        vec![
+            self.asset.clone(),
+            self.spendable.clone(),
+            self.in_contracts.clone(),
+            self.total.clone(),
+        ]
+    }
+}
+
 fn render_table<T: TableData>(items: &[T], empty_msg: &str) {
     if items.is_empty() {
         println!("  ({empty_msg})");
@@ -189,6 +213,13 @@ pub fn display_user_token_table(displays: &[UserTokenDisplay]) {
     render_table(displays, "No option/grantor tokens found");
 }
 
+/// Note rows left out of a table by a row limit.
+pub fn display_hidden_rows_notice(hidden: usize) {
+    if hidden > 0 {
+        println!("  ... and {hidden} more (use --limit to show more)");
+    }
+}
+
 pub fn display_active_option_offers_table(active_offers: &[ActiveOptionOfferDisplay]) {
     render_table(active_offers, "No option offers found");
 }
@@ -205,6 +236,10 @@ pub fn display_utxo_table(utxos: &[UtxoDisplay]) {
     render_table(utxos, "No UTXOs found");
 }
 
+pub fn display_balance_table(balances: &[BalanceDisplay]) {
+    render_table(balances, "No UTXOs found");
+}
+
 pub fn display_wallet_assets_table(assets: &[WalletAssetDisplay]) {
     render_table(assets, "No assets found in wallet");
 }
@@ -0,0 +1,291 @@
+use crate::cli::interactive::{format_relative_time, format_settlement_asset, truncate_with_ellipsis};
+use crate::cli::option_offer::{LocalOptionOfferData, collect_active_option_offers, list_option_offer_contracts};
+use crate::cli::{Cli, OptionOfferCommand};
+use crate::config::Config;
+use crate::error::Error;
+use crate::sync::sync_option_offer_event;
+use crate::wallet::Wallet;
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use contracts::option_offer::OPTION_OFFER_SOURCE;
+use options_relay::{OptionOfferCreatedEvent, ReadOnlyClient};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+/// How often the order book is re-fetched from the relays.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long to wait for a key press before redrawing.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+struct OrderBook {
+    offers: Vec<LocalOptionOfferData>,
+    table: TableState,
+    confirming_take: bool,
+    status: String,
+}
+
+impl OrderBook {
+    fn selected(&self) -> Option<&LocalOptionOfferData> {
+        self.table.selected().and_then(|index| self.offers.get(index))
+    }
+
+    /// Replace the offers, keeping the same offer selected if it is still listed.
+    fn replace_offers(&mut self, offers: Vec<LocalOptionOfferData>) {
+        let selected_outpoint = self.selected().map(|offer| offer.current_outpoint);
+        self.offers = offers;
+
+        let index = selected_outpoint
+            .and_then(|outpoint| self.offers.iter().position(|offer| offer.current_outpoint == outpoint))
+            .or_else(|| (!self.offers.is_empty()).then_some(0));
+        self.table.select(index);
+    }
+}
+
+enum Action {
+    Quit,
+    Take(String),
+}
+
+impl Cli {
+    /// Interactive order book: browse active option offers and take one.
+    pub(crate) async fn run_tui(&self, config: Config) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+        let client = self.get_read_only_client(&config).await?;
+
+        let mut book = OrderBook {
+            offers: Vec::new(),
+            table: TableState::default(),
+            confirming_take: false,
+            status: String::new(),
+        };
+
+        loop {
+            let mut terminal = ratatui::init();
+            let action = run_order_book(&mut terminal, &wallet, &config, &client, &mut book).await;
+            ratatui::restore();
+
+            match action? {
+                Action::Quit => break,
+                Action::Take(offer_event) => {
+                    // Take runs in the regular terminal so its prompts work as usual
+                    let result = Box::pin(self.run_option_offer(
+                        config.clone(),
+                        &OptionOfferCommand::Take {
+                            offer_event: Some(offer_event),
+                            label: None,
+                            limit: None,
+                            fee: None,
+                            cpfp_parent: None,
+                            broadcast: true,
+                        },
+                    ))
+                    .await;
+
+                    if let Err(e) = result {
+                        eprintln!("Take failed: {e}");
+                    }
+
+                    print!("Press Enter to return to the order book...");
+                    io::stdout().flush()?;
+                    io::stdin().read_line(&mut String::new())?;
+                }
+            }
+        }
+
+        client.disconnect().await;
+
+        Ok(())
+    }
+}
+
+async fn run_order_book(
+    terminal: &mut DefaultTerminal,
+    wallet: &Wallet,
+    config: &Config,
+    client: &ReadOnlyClient,
+    book: &mut OrderBook,
+) -> Result<Action, Error> {
+    let mut last_refresh: Option<Instant> = None;
+
+    loop {
+        if last_refresh.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL) {
+            book.status = "Refreshing from relays...".to_string();
+            terminal.draw(|frame| draw(frame, book))?;
+
+            let synced = sync_offers_from_relay(wallet, config, client).await;
+            let offers =
+                collect_active_option_offers(wallet, config, list_option_offer_contracts(wallet, None).await?).await?;
+            book.replace_offers(offers);
+            book.status = match synced {
+                Ok(count) => format!("{} active offers ({count} on relays)", book.offers.len()),
+                Err(e) => format!("{} active offers (relay refresh failed: {e})", book.offers.len()),
+            };
+            last_refresh = Some(Instant::now());
+        }
+
+        terminal.draw(|frame| draw(frame, book))?;
+
+        if !event::poll(INPUT_POLL_INTERVAL)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        if book.confirming_take {
+            book.confirming_take = false;
+            if matches!(key.code, KeyCode::Char('y' | 'Y'))
+                && let Some(offer_event) = book.selected().and_then(|offer| offer.metadata.nostr_event_id.clone())
+            {
+                return Ok(Action::Take(offer_event));
+            }
+            book.status = "Take cancelled".to_string();
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Action::Quit),
+            KeyCode::Down | KeyCode::Char('j') => book.table.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => book.table.select_previous(),
+            KeyCode::Char('r') => last_refresh = None,
+            KeyCode::Char('t') | KeyCode::Enter => match book.selected() {
+                Some(offer) if offer.metadata.nostr_event_id.is_some() => book.confirming_take = true,
+                Some(_) => book.status = "Only offers published to NOSTR can be taken".to_string(),
+                None => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Store every valid option offer on the relays, returning how many were fetched.
+async fn sync_offers_from_relay(wallet: &Wallet, config: &Config, client: &ReadOnlyClient) -> Result<usize, Error> {
+    let offers: Vec<OptionOfferCreatedEvent> = client
+        .fetch_option_offers(config.address_params())
+        .await?
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|offer| config.relay.accepts_author(&offer.pubkey))
+        .collect();
+
+    for offer in &offers {
+        let arguments = offer.option_offer_args.build_arguments();
+        // Offers synced earlier fail on the unique constraint, which is fine here
+        let _ = sync_option_offer_event(wallet.store(), offer, OPTION_OFFER_SOURCE, arguments, None).await;
+    }
+
+    Ok(offers.len())
+}
+
+fn draw(frame: &mut Frame, book: &mut OrderBook) {
+    let [table_area, details_area, status_area] =
+        Layout::vertical([Constraint::Min(5), Constraint::Length(9), Constraint::Length(1)]).areas(frame.area());
+
+    let rows = book.offers.iter().enumerate().map(|(index, offer)| {
+        let args = &offer.option_offer_args;
+        Row::new(vec![
+            (index + 1).to_string(),
+            offer.available_collateral().to_string(),
+            args.collateral_per_contract().to_string(),
+            format_settlement_asset(&args.get_settlement_asset_id()),
+            format_relative_time(i64::from(args.expiry_time())),
+            truncate_with_ellipsis(offer.metadata.nostr_author.as_deref().unwrap_or("unknown"), 12),
+        ])
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Length(14),
+            Constraint::Min(18),
+            Constraint::Length(14),
+        ],
+    )
+    .header(Row::new(vec!["#", "Offering", "Price", "Wants", "Expires", "Seller"]).bold())
+    .block(Block::bordered().title(" Option Offers "))
+    .row_highlight_style(Style::new().reversed());
+
+    frame.render_stateful_widget(table, table_area, &mut book.table);
+
+    let details = book.selected().map_or_else(
+        || vec![Line::from("No offer selected")],
+        |offer| {
+            let args = &offer.option_offer_args;
+            let settlement_for_all = offer
+                .available_collateral()
+                .saturating_mul(args.collateral_per_contract());
+            let premium_for_all = offer
+                .available_collateral()
+                .saturating_mul(args.premium_per_collateral());
+            vec![
+                Line::from(format!(
+                    "Offer event: {}",
+                    offer.metadata.nostr_event_id.as_deref().unwrap_or("local")
+                )),
+                Line::from(format!("Collateral available: {}", offer.available_collateral())),
+                Line::from(format!(
+                    "Price: {} {} per collateral",
+                    args.collateral_per_contract(),
+                    format_settlement_asset(&args.get_settlement_asset_id())
+                )),
+                Line::from(format!(
+                    "Premium: {} {} per collateral",
+                    args.premium_per_collateral(),
+                    format_settlement_asset(&args.get_premium_asset_id())
+                )),
+                Line::from(format!(
+                    "Taking all: pay {settlement_for_all}, receive {} collateral + {premium_for_all} premium",
+                    offer.available_collateral()
+                )),
+                Line::from(format!(
+                    "Expiry: {}",
+                    format_relative_time(i64::from(args.expiry_time()))
+                )),
+            ]
+        },
+    );
+    frame.render_widget(
+        Paragraph::new(details).block(Block::bordered().title(" Details ")),
+        details_area,
+    );
+
+    frame.render_widget(
+        Line::from(format!(
+            "[↑/↓] select  [t] take  [r] refresh  [q] quit   {}",
+            book.status
+        )),
+        status_area,
+    );
+
+    if book.confirming_take {
+        let area = centered(frame.area(), 50, 3);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new("Take the selected offer? [y/N]").block(Block::bordered().title(" Confirm ")),
+            area,
+        );
+    }
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
@@ -1,21 +1,37 @@
+use crate::address::{describe_address, validate_address};
+use crate::cli::inputs::wallet_fee_input;
+use crate::cli::interactive::prompt_text;
 use crate::cli::{Cli, TxCommand};
 use crate::config::Config;
 use crate::error::Error;
-use crate::fee::{PLACEHOLDER_FEE, estimate_fee_signed};
-use crate::signing::sign_p2pk_inputs;
+use crate::fee::{PLACEHOLDER_FEE, estimate_fee_signed, select_with_fee};
+use crate::signing::{sign_wallet_transaction, verify_finalized_transaction};
+use crate::sync::decode_contract_arguments;
 
 use std::collections::HashMap;
 
-use coin_store::{UtxoQueryResult, UtxoStore};
+use coin_store::{CoinSelector, SelectionStrategy, StoreError, UtxoQueryResult, UtxoStore};
+use contracts::option_offer::{OptionOfferArguments, get_option_offer_address, get_option_offer_program};
+use contracts::options::{OptionsArguments, get_options_address, get_options_program};
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
 
-use simplicityhl::elements::TxOut;
-use simplicityhl::elements::hashes::Hash;
+use simplicityhl::CompiledProgram;
+use simplicityhl::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+use simplicityhl::elements::hashes::{Hash, HashEngine, sha256};
 use simplicityhl::elements::issuance::ContractHash;
 use simplicityhl::elements::pset::serialize::Serialize;
 use simplicityhl::elements::pset::{Input, Output, PartiallySignedTransaction};
 use simplicityhl::elements::secp256k1_zkp::{self as secp256k1, Keypair};
+use simplicityhl::elements::{
+    AddressParams, AssetId, OutPoint, Script, Transaction, TxOut, TxOutSecrets, Txid, encode,
+};
+use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
 use simplicityhl::simplicity::hex::DisplayHex;
-use simplicityhl_core::LIQUID_TESTNET_BITCOIN_ASSET;
+use simplicityhl_core::{LIQUID_TESTNET_BITCOIN_ASSET, derive_public_blinder_key};
+
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::SeedableRng;
+use signer::Signer;
 
 impl Cli {
     #[allow(clippy::too_many_lines)]
@@ -26,7 +42,7 @@ impl Cli {
 
                 let filter = coin_store::UtxoFilter::new()
                     .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(wallet.signer().p2pk_address(config.address_params())?.script_pubkey());
+                    .script_pubkey(wallet.p2pk_address()?.script_pubkey());
 
                 let results: Vec<UtxoQueryResult> = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await?;
 
@@ -43,7 +59,7 @@ impl Cli {
                     })
                     .ok_or_else(|| Error::Config("No native UTXO found".to_string()))?;
 
-                let fee_utxo = (*native_entry.outpoint(), native_entry.txout().clone());
+                let fee_utxo = native_entry.as_input();
 
                 let actual_fee = estimate_fee_signed(
                     fee.as_ref(),
@@ -52,14 +68,14 @@ impl Cli {
                         let pst = contracts::sdk::split_native_any(fee_utxo.clone(), *count, f)?;
                         Ok((pst, vec![fee_utxo.1.clone()]))
                     },
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
+                    |tx, utxos| sign_wallet_transaction(tx, utxos, &wallet, config.address_params(), config.fee.rbf),
                 )?;
 
                 let pst = contracts::sdk::split_native_any(fee_utxo.clone(), *count, actual_fee)?;
                 let tx = pst.extract_tx()?;
                 let utxos = vec![fee_utxo.1];
 
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                let tx = sign_wallet_transaction(tx, &utxos, &wallet, config.address_params(), config.fee.rbf)?;
 
                 match broadcast {
                     false => {
@@ -77,6 +93,7 @@ impl Cli {
             TxCommand::Merge {
                 asset_id,
                 count,
+                keep,
                 fee,
                 broadcast,
             } => {
@@ -85,32 +102,53 @@ impl Cli {
                 }
 
                 let wallet = self.get_wallet(&config).await?;
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
                 let target_asset = asset_id.unwrap_or(*LIQUID_TESTNET_BITCOIN_ASSET);
                 let is_native = target_asset == *LIQUID_TESTNET_BITCOIN_ASSET;
 
-                #[allow(clippy::cast_possible_wrap)]
-                let asset_filter = coin_store::UtxoFilter::new()
-                    .asset_id(target_asset)
-                    .script_pubkey(script_pubkey.clone())
-                    .limit(*count as i64);
+                let limit = count
+                    .checked_add(*keep)
+                    .and_then(|limit| i64::try_from(limit).ok())
+                    .ok_or_else(|| Error::Config("--count plus --keep is too large".to_string()))?;
+                let keep_limit = i64::try_from(*keep).map_err(|_| Error::Config("--keep is too large".to_string()))?;
 
-                let results: Vec<UtxoQueryResult> =
-                    <_ as UtxoStore>::query_utxos(wallet.store(), &[asset_filter]).await?;
+                // The smallest `count + keep` coins always contain the `count` smallest ones outside the kept set
+                let mut filters = vec![
+                    coin_store::UtxoFilter::new()
+                        .asset_id(target_asset)
+                        .script_pubkey(script_pubkey.clone())
+                        .order_by(coin_store::UtxoOrder::ValueAsc)
+                        .limit(limit),
+                ];
+                if *keep > 0 {
+                    filters.push(
+                        coin_store::UtxoFilter::new()
+                            .asset_id(target_asset)
+                            .script_pubkey(script_pubkey.clone())
+                            .order_by(coin_store::UtxoOrder::ValueDesc)
+                            .limit(keep_limit),
+                    );
+                }
 
-                let entries: Vec<_> = results
+                let mut results = <_ as UtxoStore>::query_utxos(wallet.store(), &filters)
+                    .await?
                     .into_iter()
-                    .next()
-                    .and_then(|r| match r {
-                        UtxoQueryResult::Found(entries, _) => Some(entries),
-                        UtxoQueryResult::InsufficientValue(entries, _) => {
-                            eprintln!("Only found {} UTXOs for merge.", entries.len());
-                            Some(entries)
-                        }
-                        UtxoQueryResult::Empty => None,
-                    })
-                    .ok_or_else(|| Error::Config(format!("No UTXOs found for asset {target_asset}")))?;
+                    .map(|result| match result {
+                        UtxoQueryResult::Found(entries, _) | UtxoQueryResult::InsufficientValue(entries, _) => entries,
+                        UtxoQueryResult::Empty => Vec::new(),
+                    });
+                let smallest = results.next().unwrap_or_default();
+                let largest = results.next().unwrap_or_default();
+
+                if smallest.is_empty() {
+                    return Err(Error::Config(format!("No UTXOs found for asset {target_asset}")));
+                }
+
+                let entries = merge_candidates(smallest, &largest, *count);
+                if entries.len() < *count {
+                    eprintln!("Only found {} UTXOs for merge.", entries.len());
+                }
 
                 if entries.len() < 2 {
                     return Err(Error::Config(format!(
@@ -149,10 +187,11 @@ impl Cli {
                         let Some(fee_input_value) = fee_e.value() else {
                             return Err(Error::Config("Unexpected confidential value".to_string()));
                         };
-                        let mut fee_input = Input::from_prevout(*fee_e.outpoint());
-                        fee_input.witness_utxo = Some(fee_e.txout().clone());
+                        let (fee_outpoint, fee_txout) = wallet_fee_input(fee_e, &script_pubkey)?;
+                        let mut fee_input = Input::from_prevout(fee_outpoint);
+                        fee_input.witness_utxo = Some(fee_txout.clone());
                         pst.add_input(fee_input);
-                        utxos.push(fee_e.txout().clone());
+                        utxos.push(fee_txout);
 
                         pst.add_output(Output::new_explicit(
                             script_pubkey.clone(),
@@ -210,7 +249,7 @@ impl Cli {
                     fee.as_ref(),
                     config.get_fee_rate(),
                     |f| build_merge_pset(f, fee_entry_opt.as_ref()),
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
+                    |tx, utxos| sign_wallet_transaction(tx, utxos, &wallet, config.address_params(), config.fee.rbf),
                 )?;
 
                 if !is_native && let Some(ref fee_e) = fee_entry_opt {
@@ -243,7 +282,7 @@ impl Cli {
                 }
 
                 let tx = pst.extract_tx()?;
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                let tx = sign_wallet_transaction(tx, &utxos, &wallet, config.address_params(), config.fee.rbf)?;
 
                 match broadcast {
                     false => {
@@ -265,47 +304,31 @@ impl Cli {
                 fee,
                 broadcast,
             } => {
+                let to = validate_address(to, config.address_params())?;
+                println!("Recipient: {to} ({})", describe_address(&to));
+
                 let wallet = self.get_wallet(&config).await?;
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
                 let target_asset = asset_id.unwrap_or(*LIQUID_TESTNET_BITCOIN_ASSET);
                 let is_native = target_asset == *LIQUID_TESTNET_BITCOIN_ASSET;
 
-                let required_amount = if is_native {
-                    *amount + fee.unwrap_or(PLACEHOLDER_FEE)
+                // A single blinded output would get a zero value blinding factor, so when the
+                // recipient is confidential our change is blinded too (to the public blinder key).
+                let blinder_keypair = derive_public_blinder_key();
+                let change_blinder = to.blinding_pubkey.map(|_| blinder_keypair.public_key());
+                let blinding_seed = if self.deterministic_blinding {
+                    Some(self.parse_seed()?)
                 } else {
-                    *amount
+                    None
                 };
 
-                let asset_filter = coin_store::UtxoFilter::new()
-                    .asset_id(target_asset)
-                    .script_pubkey(script_pubkey.clone())
-                    .required_value(required_amount);
-
-                let results: Vec<UtxoQueryResult> =
-                    <_ as UtxoStore>::query_utxos(wallet.store(), &[asset_filter]).await?;
-
-                let entries: Vec<_> = results
-                    .into_iter()
-                    .next()
-                    .and_then(|r| match r {
-                        UtxoQueryResult::Found(entries, _) => Some(entries),
-                        UtxoQueryResult::InsufficientValue(entries, _) => {
-                            let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
-                            eprintln!(
-                                "Insufficient funds: have {available} sats, need {required_amount} sats. Try using 'merge' command first."
-                            );
-                            None
-                        }
-                        UtxoQueryResult::Empty => None,
-                    })
-                    .ok_or_else(|| Error::Config(format!("No UTXOs found for asset {target_asset}")))?;
-
-                let total_asset_value: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
-
                 let build_transfer_pset = |actual_fee: u64,
+                                           entries: &[coin_store::UtxoEntry],
                                            fee_entry: Option<&coin_store::UtxoEntry>|
                  -> Result<(PartiallySignedTransaction, Vec<TxOut>), Error> {
+                    let total_asset_value: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
+
                     let mut pst = PartiallySignedTransaction::new_v2();
                     let mut utxos: Vec<TxOut> = entries
                         .iter()
@@ -316,13 +339,14 @@ impl Cli {
                             e.txout().clone()
                         })
                         .collect();
+                    let mut spent_entries: Vec<&coin_store::UtxoEntry> = entries.iter().collect();
 
                     if is_native {
-                        pst.add_output(Output::new_explicit(
+                        pst.add_output(transfer_output(
                             to.script_pubkey(),
                             *amount,
                             *LIQUID_TESTNET_BITCOIN_ASSET,
-                            None,
+                            to.blinding_pubkey,
                         ));
 
                         let change = total_asset_value
@@ -330,11 +354,11 @@ impl Cli {
                             .ok_or_else(|| Error::Config("Fee + amount exceeds total UTXO value".to_string()))?;
 
                         if change > 0 {
-                            pst.add_output(Output::new_explicit(
+                            pst.add_output(transfer_output(
                                 script_pubkey.clone(),
                                 change,
                                 *LIQUID_TESTNET_BITCOIN_ASSET,
-                                None,
+                                change_blinder,
                             ));
                         }
                     } else if let Some(fee_e) = fee_entry {
@@ -342,29 +366,36 @@ impl Cli {
                             return Err(Error::Config("Unexpected confidential value".to_string()));
                         };
 
-                        let mut fee_input = Input::from_prevout(*fee_e.outpoint());
-                        fee_input.witness_utxo = Some(fee_e.txout().clone());
+                        let (fee_outpoint, fee_txout) = wallet_fee_input(fee_e, &script_pubkey)?;
+                        let mut fee_input = Input::from_prevout(fee_outpoint);
+                        fee_input.witness_utxo = Some(fee_txout.clone());
                         pst.add_input(fee_input);
-                        utxos.push(fee_e.txout().clone());
+                        utxos.push(fee_txout);
+                        spent_entries.push(fee_e);
 
-                        pst.add_output(Output::new_explicit(to.script_pubkey(), *amount, target_asset, None));
+                        pst.add_output(transfer_output(
+                            to.script_pubkey(),
+                            *amount,
+                            target_asset,
+                            to.blinding_pubkey,
+                        ));
 
                         let asset_change = total_asset_value - *amount;
                         if asset_change > 0 {
-                            pst.add_output(Output::new_explicit(
+                            pst.add_output(transfer_output(
                                 script_pubkey.clone(),
                                 asset_change,
                                 target_asset,
-                                None,
+                                change_blinder,
                             ));
                         }
 
                         if fee_input_value > actual_fee {
-                            pst.add_output(Output::new_explicit(
+                            pst.add_output(transfer_output(
                                 script_pubkey.clone(),
                                 fee_input_value - actual_fee,
                                 *LIQUID_TESTNET_BITCOIN_ASSET,
-                                None,
+                                change_blinder,
                             ));
                         }
                     }
@@ -373,63 +404,92 @@ impl Cli {
                         actual_fee,
                         *LIQUID_TESTNET_BITCOIN_ASSET,
                     )));
-                    Ok((pst, utxos))
-                };
-
-                let fee_entry_opt = if is_native {
-                    None
-                } else {
-                    let fee_filter = coin_store::UtxoFilter::new()
-                        .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                        .script_pubkey(script_pubkey.clone())
-                        .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
 
-                    let fee_results: Vec<UtxoQueryResult> =
-                        <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
+                    if to.is_blinded() {
+                        blind_transfer_outputs(&mut pst, &spent_entries, blinding_seed.as_ref())?;
+                    }
 
-                    Some(fee_results
-                        .into_iter()
-                        .next()
-                        .and_then(|r| match r {
-                            UtxoQueryResult::Found(entries, _) => entries.into_iter().next(),
-                            UtxoQueryResult::InsufficientValue(entries, _) => {
-                                let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
-                                eprintln!(
-                                    "Insufficient LBTC for fee: have {available} sats. Try using 'merge' command first."
-                                );
-                                None
-                            }
-                            UtxoQueryResult::Empty => None,
-                        })
-                        .ok_or_else(|| Error::Config("No LBTC UTXO found to pay fee".to_string()))?)
+                    Ok((pst, utxos))
                 };
 
-                let actual_fee = estimate_fee_signed(
-                    fee.as_ref(),
-                    config.get_fee_rate(),
-                    |f| build_transfer_pset(f, fee_entry_opt.as_ref()),
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
-                )?;
+                // Re-selects when the estimated fee outgrows what the selected inputs leave over
+                let ((entries, fee_entry_opt), actual_fee) = select_with_fee(
+                    *fee,
+                    async |required_fee| {
+                        let required_amount = if is_native { *amount + required_fee } else { *amount };
+
+                        let asset_filter = coin_store::UtxoFilter::new()
+                            .asset_id(target_asset)
+                            .script_pubkey(script_pubkey.clone())
+                            .required_value(required_amount);
+
+                        let results: Vec<UtxoQueryResult> =
+                            <_ as UtxoStore>::query_utxos(wallet.store(), &[asset_filter]).await?;
+
+                        let entries: Vec<_> = results
+                            .into_iter()
+                            .next()
+                            .and_then(|r| match r {
+                                UtxoQueryResult::Found(entries, _) => Some(entries),
+                                UtxoQueryResult::InsufficientValue(entries, _) => {
+                                    let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
+                                    eprintln!(
+                                        "Insufficient funds: have {available} sats, need {required_amount} sats. Try using 'merge' command first."
+                                    );
+                                    None
+                                }
+                                UtxoQueryResult::Empty => None,
+                            })
+                            .ok_or_else(|| Error::Config(format!("No UTXOs found for asset {target_asset}")))?;
+
+                        if is_native {
+                            let total_asset_value: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
+                            let fee_capacity = total_asset_value.saturating_sub(*amount);
+                            return Ok(((entries, None), fee_capacity));
+                        }
 
-                if is_native && total_asset_value < *amount + actual_fee {
-                    return Err(Error::Config(format!(
-                        "Insufficient funds: have {total_asset_value} sats, need {} sats (amount + fee)",
-                        *amount + actual_fee
-                    )));
-                }
+                        let fee_filter = coin_store::UtxoFilter::new()
+                            .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
+                            .script_pubkey(script_pubkey.clone())
+                            .required_value(required_fee);
+
+                        let fee_results: Vec<UtxoQueryResult> =
+                            <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
+
+                        let fee_entry = fee_results
+                            .into_iter()
+                            .next()
+                            .and_then(|r| match r {
+                                UtxoQueryResult::Found(entries, _) => entries.into_iter().next(),
+                                UtxoQueryResult::InsufficientValue(entries, _) => {
+                                    let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
+                                    eprintln!(
+                                        "Insufficient LBTC for fee: have {available} sats. Try using 'merge' command first."
+                                    );
+                                    None
+                                }
+                                UtxoQueryResult::Empty => None,
+                            })
+                            .ok_or_else(|| Error::Config("No LBTC UTXO found to pay fee".to_string()))?;
+
+                        let Some(fee_capacity) = fee_entry.value() else {
+                            return Err(Error::Config("Unexpected confidential value".to_string()));
+                        };
 
-                if !is_native && let Some(ref fee_e) = fee_entry_opt {
-                    let Some(fee_input_value) = fee_e.value() else {
-                        return Err(Error::Config("Unexpected confidential value".to_string()));
-                    };
-                    if fee_input_value < actual_fee {
-                        return Err(Error::Config(format!(
-                            "Fee UTXO value ({fee_input_value} sats) is less than required fee ({actual_fee} sats)"
-                        )));
-                    }
-                }
+                        Ok(((entries, Some(fee_entry)), fee_capacity))
+                    },
+                    |(entries, fee_entry)| {
+                        estimate_fee_signed(
+                            fee.as_ref(),
+                            config.get_fee_rate(),
+                            |f| build_transfer_pset(f, entries, fee_entry.as_ref()),
+                            |tx, utxos| sign_wallet_transaction(tx, utxos, &wallet, config.address_params(), config.fee.rbf),
+                        )
+                    },
+                )
+                .await?;
 
-                let (pst, utxos) = build_transfer_pset(actual_fee, fee_entry_opt.as_ref())?;
+                let (pst, utxos) = build_transfer_pset(actual_fee, &entries, fee_entry_opt.as_ref())?;
 
                 if is_native {
                     println!("Transferring {amount} sats LBTC to {to}");
@@ -438,7 +498,7 @@ impl Cli {
                 }
 
                 let tx = pst.extract_tx()?;
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                let tx = sign_wallet_transaction(tx, &utxos, &wallet, config.address_params(), config.fee.rbf)?;
 
                 match broadcast {
                     false => {
@@ -449,38 +509,27 @@ impl Cli {
 
                         println!("Broadcasted: {}", tx.txid());
 
-                        wallet.store().insert_transaction(&tx, HashMap::default()).await?;
+                        // Only our change is blinded to the blinder key; output 0 is the recipient's
+                        let change_blinder_keys: HashMap<usize, Keypair> = if to.is_blinded() {
+                            tx.output
+                                .iter()
+                                .enumerate()
+                                .skip(1)
+                                .filter(|(_, output)| output.script_pubkey == script_pubkey)
+                                .map(|(index, _)| (index, blinder_keypair))
+                                .collect()
+                        } else {
+                            HashMap::default()
+                        };
+                        wallet.store().insert_transaction(&tx, change_blinder_keys).await?;
                     }
                 }
             }
             TxCommand::IssueAsset { amount, fee, broadcast } => {
                 let wallet = self.get_wallet(&config).await?;
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
-
-                let fee_filter = coin_store::UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey)
-                    .required_value(fee.unwrap_or(PLACEHOLDER_FEE));
-
-                let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[fee_filter]).await?;
-
-                let fee_entry = results
-                    .into_iter()
-                    .next()
-                    .and_then(|r| match r {
-                        UtxoQueryResult::Found(entries, _) => entries.into_iter().next(),
-                        UtxoQueryResult::InsufficientValue(entries, _) => {
-                            let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
-                            eprintln!(
-                                "Insufficient LBTC for fee: have {available} sats. Try using 'merge' command first."
-                            );
-                            None
-                        }
-                        UtxoQueryResult::Empty => None,
-                    })
-                    .ok_or_else(|| Error::Config("No LBTC UTXO found to pay fee".to_string()))?;
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
-                let fee_utxo = (*fee_entry.outpoint(), fee_entry.txout().clone());
+                let fee_utxo = select_fee_coin(wallet.store(), &script_pubkey, fee.unwrap_or(PLACEHOLDER_FEE)).await?;
 
                 let blinding_keypair = Keypair::new(secp256k1::SECP256K1, &mut secp256k1::rand::thread_rng());
 
@@ -492,10 +541,10 @@ impl Cli {
                             contracts::sdk::issue_asset(&blinding_keypair.public_key(), fee_utxo.clone(), *amount, f)?;
                         Ok((pst, vec![fee_utxo.1.clone()]))
                     },
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
+                    |tx, utxos| sign_wallet_transaction(tx, utxos, &wallet, config.address_params(), config.fee.rbf),
                 )?;
 
-                if let Some(fee_input_value) = fee_entry.value()
+                if let Some(fee_input_value) = fee_utxo.1.value.explicit()
                     && fee_input_value < actual_fee
                 {
                     return Err(Error::Config(format!(
@@ -511,15 +560,13 @@ impl Cli {
                     .issuance_asset_entropy
                     .ok_or_else(|| Error::Config("Missing asset entropy in PST".to_string()))?;
                 let contract_hash = ContractHash::from_byte_array(asset_entropy_bytes);
-                let entropy = simplicityhl::elements::issuance::AssetId::generate_asset_entropy(
-                    *fee_entry.outpoint(),
-                    contract_hash,
-                );
+                let entropy =
+                    simplicityhl::elements::issuance::AssetId::generate_asset_entropy(fee_utxo.0, contract_hash);
 
                 let tx = pst.extract_tx()?;
                 let utxos = vec![fee_utxo.1];
 
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                let tx = sign_wallet_transaction(tx, &utxos, &wallet, config.address_params(), config.fee.rbf)?;
 
                 println!("Asset ID: {asset_id}");
                 println!("Reissuance Token ID: {token_id}");
@@ -540,6 +587,107 @@ impl Cli {
                     }
                 }
             }
+            TxCommand::Verify { hex: tx_hex, utxos } => {
+                let wallet = self.get_wallet(&config).await?;
+
+                let tx_bytes = hex::decode(tx_hex)?;
+                let tx: Transaction = encode::deserialize(&tx_bytes)
+                    .map_err(|e| Error::Config(format!("Invalid Elements transaction: {e}")))?;
+
+                let spent = parse_spent_txouts(&std::fs::read_to_string(utxos)?)?;
+                if spent.len() != tx.input.len() {
+                    return Err(Error::Config(format!(
+                        "Transaction has {} inputs but {} spent outputs were supplied",
+                        tx.input.len(),
+                        spent.len()
+                    )));
+                }
+
+                println!("Verifying transaction {}...", tx.txid());
+
+                let mut checked = 0usize;
+                let mut failed = 0usize;
+                for (index, utxo) in spent.iter().enumerate() {
+                    let Some((name, program, x_only_public_key)) =
+                        contract_program_for_script(wallet.store(), &utxo.script_pubkey, config.address_params())
+                            .await?
+                    else {
+                        println!("  Input {index}: not a known contract, skipped");
+                        continue;
+                    };
+
+                    checked += 1;
+                    match verify_finalized_transaction(
+                        &tx,
+                        &spent,
+                        index,
+                        &program,
+                        &x_only_public_key,
+                        config.address_params(),
+                    ) {
+                        Ok(()) => println!("  Input {index}: {name} PASS"),
+                        Err(e) => {
+                            failed += 1;
+                            println!("  Input {index}: {name} FAIL ({e})");
+                        }
+                    }
+                }
+
+                if checked == 0 {
+                    println!("No inputs spend a contract known to this wallet. Run 'sync nostr' to fetch contracts.");
+                    return Ok(());
+                }
+
+                if failed > 0 {
+                    return Err(Error::Config(format!(
+                        "{failed} of {checked} contract inputs failed verification"
+                    )));
+                }
+
+                println!("All {checked} contract inputs passed verification");
+                Ok(())
+            }
+            TxCommand::Broadcast { hex: tx_hex, blinder } => {
+                let wallet = self.get_wallet(&config).await?;
+
+                let tx_bytes = hex::decode(tx_hex)?;
+                let tx: Transaction = encode::deserialize(&tx_bytes)
+                    .map_err(|e| Error::Config(format!("Invalid Elements transaction: {e}")))?;
+
+                let has_confidential = tx.output.iter().any(|out| !out.is_fee() && out.asset.is_confidential());
+
+                let blinder_hex = match blinder {
+                    Some(key_hex) => Some(key_hex.clone()),
+                    None if has_confidential => {
+                        let input = prompt_text("Enter blinding key hex for confidential outputs (empty to skip)")
+                            .map_err(Error::Io)?;
+                        (!input.is_empty()).then_some(input)
+                    }
+                    None => None,
+                };
+
+                let blinder_keys: HashMap<usize, Keypair> = match blinder_hex {
+                    Some(key_hex) => {
+                        let key_bytes = hex::decode(key_hex)
+                            .map_err(|e| Error::Config(format!("Invalid blinding key hex: {e}")))?;
+                        let keypair = Keypair::from_seckey_slice(secp256k1::SECP256K1, &key_bytes)
+                            .map_err(|e| Error::Config(format!("Invalid blinding key: {e}")))?;
+
+                        tx.output
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, out)| !out.is_fee() && out.asset.is_confidential())
+                            .map(|(i, _)| (i, keypair))
+                            .collect()
+                    }
+                    None => HashMap::new(),
+                };
+
+                cli_helper::explorer::broadcast_tx(&tx).await?;
+                println!("Broadcasted: {}", tx.txid());
+
+                wallet.store().insert_transaction(&tx, blinder_keys).await?;
+            }
             TxCommand::ReissueAsset {
                 asset_id,
                 amount,
@@ -547,7 +695,7 @@ impl Cli {
                 broadcast,
             } => {
                 let wallet = self.get_wallet(&config).await?;
-                let script_pubkey = wallet.signer().p2pk_address(config.address_params())?.script_pubkey();
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
 
                 let asset_filter = coin_store::UtxoFilter::new()
                     .asset_id(*asset_id)
@@ -584,13 +732,7 @@ impl Cli {
                     .script_pubkey(script_pubkey.clone())
                     .limit(1);
 
-                let fee_filter = coin_store::UtxoFilter::new()
-                    .asset_id(*LIQUID_TESTNET_BITCOIN_ASSET)
-                    .script_pubkey(script_pubkey)
-                    .required_value(fee.unwrap_or(PLACEHOLDER_FEE))
-                    .limit(1);
-
-                let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[token_filter, fee_filter]).await?;
+                let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[token_filter]).await?;
 
                 let token_entry = match &results[0] {
                     UtxoQueryResult::Found(entries, _) => &entries[0],
@@ -602,20 +744,9 @@ impl Cli {
                     .secrets()
                     .ok_or_else(|| Error::Config("Reissuance token must be confidential".to_string()))?;
 
-                let fee_entry = match &results[1] {
-                    UtxoQueryResult::Found(entries, _) => &entries[0],
-                    UtxoQueryResult::InsufficientValue(entries, _) => {
-                        let available: u64 = entries.iter().filter_map(coin_store::UtxoEntry::value).sum();
-                        eprintln!("Insufficient LBTC for fee: have {available} sats. Try using 'merge' command first.");
-                        return Err(Error::Config("No LBTC UTXO found to pay fee".to_string()));
-                    }
-                    UtxoQueryResult::Empty => {
-                        return Err(Error::Config("No LBTC UTXO found to pay fee".to_string()));
-                    }
-                };
+                let fee_utxo = select_fee_coin(wallet.store(), &script_pubkey, fee.unwrap_or(PLACEHOLDER_FEE)).await?;
 
-                let token_utxo = (*token_entry.outpoint(), token_entry.txout().clone());
-                let fee_utxo = (*fee_entry.outpoint(), fee_entry.txout().clone());
+                let token_utxo = token_entry.as_input();
 
                 let blinding_keypair = Keypair::new(secp256k1::SECP256K1, &mut secp256k1::rand::thread_rng());
 
@@ -634,10 +765,10 @@ impl Cli {
                         )?;
                         Ok((pst, vec![token_utxo.1.clone(), fee_utxo.1.clone()]))
                     },
-                    |tx, utxos| sign_p2pk_inputs(tx, utxos, &wallet, config.address_params(), 0),
+                    |tx, utxos| sign_wallet_transaction(tx, utxos, &wallet, config.address_params(), config.fee.rbf),
                 )?;
 
-                if let Some(fee_input_value) = fee_entry.value()
+                if let Some(fee_input_value) = fee_utxo.1.value.explicit()
                     && fee_input_value < actual_fee
                 {
                     return Err(Error::Config(format!(
@@ -658,7 +789,7 @@ impl Cli {
                 let tx = pst.extract_tx()?;
                 let utxos = vec![token_utxo.1, fee_utxo.1];
 
-                let tx = sign_p2pk_inputs(tx, &utxos, &wallet, config.address_params(), 0)?;
+                let tx = sign_wallet_transaction(tx, &utxos, &wallet, config.address_params(), config.fee.rbf)?;
 
                 println!("Reissuing {amount} units of asset {asset_id}");
 
@@ -681,3 +812,321 @@ impl Cli {
         Ok(())
     }
 }
+
+/// Pick one LBTC coin covering `amount`, for SDK builders that take a single fee input.
+///
+/// Without `--fee`, `amount` is only the placeholder fee, so the largest coin is taken to leave
+/// room for the real fee estimated afterwards.
+async fn select_fee_coin(
+    store: &coin_store::Store,
+    script_pubkey: &Script,
+    amount: u64,
+) -> Result<(OutPoint, TxOut), Error> {
+    let coins = CoinSelector::new()
+        .strategy(SelectionStrategy::LargestFirst)
+        .select(store, *LIQUID_TESTNET_BITCOIN_ASSET, script_pubkey, amount)
+        .await
+        .map_err(|e| match e {
+            StoreError::NoCoins { .. } => Error::Config("No LBTC UTXO found to pay fee".to_string()),
+            StoreError::InsufficientCoins { available, .. } => {
+                Error::Config(format!("Insufficient LBTC for fee: have {available} sats"))
+            }
+            e => e.into(),
+        })?;
+
+    let entry = coins.single().ok_or_else(|| {
+        Error::Config(format!(
+            "No single LBTC UTXO covers the {amount} sat fee. Try using 'merge' command first."
+        ))
+    })?;
+
+    wallet_fee_input(entry, script_pubkey)
+}
+
+/// Pick the `count` smallest coins to merge, leaving out the `largest` coins kept by `--keep`.
+///
+/// `smallest` must be sorted by ascending value.
+fn merge_candidates(
+    smallest: Vec<coin_store::UtxoEntry>,
+    largest: &[coin_store::UtxoEntry],
+    count: usize,
+) -> Vec<coin_store::UtxoEntry> {
+    smallest
+        .into_iter()
+        .filter(|entry| !largest.iter().any(|kept| kept.outpoint() == entry.outpoint()))
+        .take(count)
+        .collect()
+}
+
+/// Explicit transfer output, marked for blinding to `blinding_key` when one is given.
+fn transfer_output(
+    script_pubkey: Script,
+    amount: u64,
+    asset: AssetId,
+    blinding_key: Option<secp256k1::PublicKey>,
+) -> Output {
+    let mut output = Output::new_explicit(script_pubkey, amount, asset, blinding_key);
+    if blinding_key.is_some() {
+        output.blinder_index = Some(0);
+    }
+    output
+}
+
+/// Blind every output marked by [`transfer_output`], attaching range and surjection proofs.
+///
+/// `spent_entries` must be in the same order as the PSET inputs. With a `deterministic_seed` the
+/// blinding randomness comes from [`deterministic_blinding_rng`] rather than the OS.
+fn blind_transfer_outputs(
+    pst: &mut PartiallySignedTransaction,
+    spent_entries: &[&coin_store::UtxoEntry],
+    deterministic_seed: Option<&[u8; Signer::SEED_LEN]>,
+) -> Result<(), Error> {
+    let input_secrets = spent_entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| Ok((index, input_secrets(entry)?)))
+        .collect::<Result<HashMap<_, _>, Error>>()?;
+
+    match deterministic_seed {
+        Some(seed) => {
+            let unblinded = pst.extract_tx()?;
+            let mut rng = deterministic_blinding_rng(seed, &unblinded.txid());
+            pst.blind_last(&mut rng, secp256k1::SECP256K1, &input_secrets)?;
+        }
+        None => pst.blind_last(&mut rand::thread_rng(), secp256k1::SECP256K1, &input_secrets)?,
+    }
+
+    Ok(())
+}
+
+/// Blinding randomness derived from the wallet seed and the unblinded transaction's txid.
+///
+/// Rebuilding the same transaction reproduces its blinding factors and ECDH nonces, and
+/// different transactions still get unrelated ones. The cost is that the blinding no longer
+/// depends only on the recipient: whoever learns the seed can recompute it for every transaction
+/// built this way. So this is meant for test fixtures, never for funds that matter.
+fn deterministic_blinding_rng(seed: &[u8; Signer::SEED_LEN], txid: &Txid) -> ChaCha20Rng {
+    let mut engine = sha256::Hash::engine();
+    engine.input(b"simplicity-dex/deterministic-blinding");
+    engine.input(seed);
+    engine.input(txid.as_byte_array());
+
+    ChaCha20Rng::from_seed(sha256::Hash::from_engine(engine).to_byte_array())
+}
+
+/// Blinding secrets of a spent wallet UTXO; explicit outputs use zero blinding factors.
+fn input_secrets(entry: &coin_store::UtxoEntry) -> Result<TxOutSecrets, Error> {
+    if let Some(secrets) = entry.secrets() {
+        return Ok(*secrets);
+    }
+
+    match (entry.asset(), entry.value()) {
+        (Some(asset), Some(value)) => Ok(TxOutSecrets::new(
+            asset,
+            AssetBlindingFactor::zero(),
+            value,
+            ValueBlindingFactor::zero(),
+        )),
+        _ => Err(Error::Config(format!(
+            "Cannot blind transfer: input {} is confidential and could not be unblinded",
+            entry.outpoint()
+        ))),
+    }
+}
+
+/// Parse spent outputs supplied for offline verification: one consensus-encoded `TxOut` hex per line.
+///
+/// Blank lines and lines starting with `#` are ignored.
+fn parse_spent_txouts(contents: &str) -> Result<Vec<TxOut>, Error> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let bytes = hex::decode(line)?;
+            encode::deserialize(&bytes).map_err(|e| Error::Config(format!("Invalid spent output '{line}': {e}")))
+        })
+        .collect()
+}
+
+/// Look up the contract a script belongs to and compile its program.
+///
+/// Returns the contract kind, its program and the internal key of its taproot output, or `None`
+/// if the script is not a contract known to the store.
+async fn contract_program_for_script(
+    store: &coin_store::Store,
+    script_pubkey: &Script,
+    params: &'static AddressParams,
+) -> Result<Option<(&'static str, CompiledProgram, XOnlyPublicKey)>, Error> {
+    let Some((_, args_bytes, tpg_str)) = <_ as UtxoStore>::get_contract_by_script_pubkey(store, script_pubkey).await?
+    else {
+        return Ok(None);
+    };
+
+    let Some(arguments) = decode_contract_arguments(&args_bytes, &tpg_str) else {
+        return Ok(None);
+    };
+
+    if let Ok(args) = OptionsArguments::from_arguments(&arguments) {
+        let taproot_pubkey_gen = TaprootPubkeyGen::build_from_str(&tpg_str, &args, params, &get_options_address)?;
+        return Ok(Some((
+            "option",
+            get_options_program(&args)?,
+            taproot_pubkey_gen.get_x_only_pubkey(),
+        )));
+    }
+
+    if let Ok(args) = OptionOfferArguments::from_arguments(&arguments) {
+        let taproot_pubkey_gen = TaprootPubkeyGen::build_from_str(&tpg_str, &args, params, &get_option_offer_address)?;
+        return Ok(Some((
+            "option offer",
+            get_option_offer_program(&args)?,
+            taproot_pubkey_gen.get_x_only_pubkey(),
+        )));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::{Address, AddressParams, TxOutWitness, Txid};
+
+    #[test]
+    fn test_transfer_to_confidential_address_is_blinded() {
+        let recipient_key = secp256k1::SecretKey::from_slice(&[3; 32]).unwrap();
+        let recipient_pubkey = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &recipient_key);
+        let to = Address::p2sh(&Script::new(), Some(recipient_pubkey), &AddressParams::LIQUID_TESTNET);
+        let change_blinder = derive_public_blinder_key().public_key();
+
+        let prevout = TxOut {
+            asset: Asset::Explicit(*LIQUID_TESTNET_BITCOIN_ASSET),
+            value: Value::Explicit(5000),
+            nonce: Nonce::Null,
+            script_pubkey: Script::new(),
+            witness: TxOutWitness::default(),
+        };
+        let entry =
+            coin_store::UtxoEntry::new_explicit(OutPoint::new(Txid::from_byte_array([1; 32]), 0), prevout.clone());
+
+        let mut pst = PartiallySignedTransaction::new_v2();
+        let mut input = Input::from_prevout(*entry.outpoint());
+        input.witness_utxo = Some(prevout);
+        pst.add_input(input);
+        pst.add_output(transfer_output(
+            to.script_pubkey(),
+            1000,
+            *LIQUID_TESTNET_BITCOIN_ASSET,
+            to.blinding_pubkey,
+        ));
+        pst.add_output(transfer_output(
+            Script::new(),
+            3900,
+            *LIQUID_TESTNET_BITCOIN_ASSET,
+            Some(change_blinder),
+        ));
+        pst.add_output(Output::from_txout(TxOut::new_fee(100, *LIQUID_TESTNET_BITCOIN_ASSET)));
+
+        blind_transfer_outputs(&mut pst, &[&entry], None).unwrap();
+        let tx = pst.extract_tx().unwrap();
+
+        let output = &tx.output[0];
+        assert!(output.value.is_confidential());
+        assert!(output.asset.is_confidential());
+        assert!(output.witness.rangeproof.is_some());
+
+        let secrets = output.unblind(secp256k1::SECP256K1, recipient_key).unwrap();
+        assert_eq!(secrets.value, 1000);
+        assert_eq!(secrets.asset, *LIQUID_TESTNET_BITCOIN_ASSET);
+
+        // Fee output stays explicit
+        assert_eq!(tx.output[2].value.explicit(), Some(100));
+    }
+
+    #[test]
+    fn test_merge_candidates_skip_kept_and_take_smallest() {
+        let entry = |byte: u8, value: u64| {
+            coin_store::UtxoEntry::new_explicit(
+                OutPoint::new(Txid::from_byte_array([byte; 32]), 0),
+                TxOut::new_fee(value, *LIQUID_TESTNET_BITCOIN_ASSET),
+            )
+        };
+
+        // Ascending, as the `ValueAsc` query returns them
+        let smallest = || {
+            vec![
+                entry(1, 100),
+                entry(2, 200),
+                entry(3, 300),
+                entry(4, 400),
+                entry(5, 500),
+            ]
+        };
+        let largest = vec![entry(5, 500), entry(4, 400)];
+
+        let merged = merge_candidates(smallest(), &largest, 2);
+        let values: Vec<_> = merged.iter().filter_map(coin_store::UtxoEntry::value).collect();
+        assert_eq!(values, vec![100, 200]);
+
+        // Asking for more than is left never pulls in a kept coin
+        let merged = merge_candidates(smallest(), &largest, 4);
+        let values: Vec<_> = merged.iter().filter_map(coin_store::UtxoEntry::value).collect();
+        assert_eq!(values, vec![100, 200, 300]);
+    }
+
+    #[test]
+    fn test_deterministic_blinding_is_reproducible() {
+        let recipient_pubkey = secp256k1::PublicKey::from_secret_key(
+            secp256k1::SECP256K1,
+            &secp256k1::SecretKey::from_slice(&[3; 32]).unwrap(),
+        );
+        let to = Address::p2sh(&Script::new(), Some(recipient_pubkey), &AddressParams::LIQUID_TESTNET);
+        let prevout = TxOut::new_fee(5000, *LIQUID_TESTNET_BITCOIN_ASSET);
+        let entry =
+            coin_store::UtxoEntry::new_explicit(OutPoint::new(Txid::from_byte_array([1; 32]), 0), prevout.clone());
+
+        let blinded = |seed: Option<&[u8; Signer::SEED_LEN]>| {
+            let mut pst = PartiallySignedTransaction::new_v2();
+            let mut input = Input::from_prevout(*entry.outpoint());
+            input.witness_utxo = Some(prevout.clone());
+            pst.add_input(input);
+            pst.add_output(transfer_output(
+                Script::new(),
+                1000,
+                *LIQUID_TESTNET_BITCOIN_ASSET,
+                to.blinding_pubkey,
+            ));
+            pst.add_output(transfer_output(
+                Script::new(),
+                3900,
+                *LIQUID_TESTNET_BITCOIN_ASSET,
+                Some(derive_public_blinder_key().public_key()),
+            ));
+            pst.add_output(Output::from_txout(TxOut::new_fee(100, *LIQUID_TESTNET_BITCOIN_ASSET)));
+
+            blind_transfer_outputs(&mut pst, &[&entry], seed).unwrap();
+            pst.extract_tx().unwrap()
+        };
+
+        let seed = [7; Signer::SEED_LEN];
+        assert_eq!(blinded(Some(&seed)), blinded(Some(&seed)));
+        assert_ne!(blinded(Some(&seed)), blinded(Some(&[8; Signer::SEED_LEN])));
+        assert_ne!(blinded(None), blinded(None));
+    }
+
+    #[test]
+    fn test_parse_spent_txouts() {
+        let txout = TxOut::new_fee(1500, *LIQUID_TESTNET_BITCOIN_ASSET);
+        let line = encode::serialize(&txout).to_lower_hex_string();
+        let contents = format!("# input 0\n{line}\n\n  {line}  \n");
+
+        let parsed = parse_spent_txouts(&contents).unwrap();
+        assert_eq!(parsed, vec![txout.clone(), txout]);
+
+        assert!(parse_spent_txouts("zz").is_err());
+        assert!(parse_spent_txouts("00").is_err());
+    }
+}
@@ -2,10 +2,16 @@ use crate::cli::tables::{UtxoDisplay, display_utxo_table};
 use crate::cli::{Cli, WalletCommand};
 use crate::config::Config;
 use crate::error::Error;
+use crate::sync::{Esplora, WalletEvent, watch};
 use crate::wallet::Wallet;
 
+use std::time::Duration;
+
 use coin_store::UtxoStore;
+use futures::StreamExt;
+use signer::{Signer, SigningBackend};
 use simplicityhl::elements::bitcoin::secp256k1;
+use simplicityhl::simplicity::bitcoin::bip32::DerivationPath;
 
 impl Cli {
     pub(crate) async fn run_wallet(&self, config: Config, command: &WalletCommand) -> Result<(), Error> {
@@ -15,7 +21,8 @@ impl Cli {
                 let db_path = config.database_path();
 
                 std::fs::create_dir_all(&config.storage.data_dir)?;
-                Wallet::create(&seed, &db_path, config.address_params()).await?;
+                let db_passphrase = self.db_passphrase(&config)?;
+                Wallet::create(&seed, &db_path, db_passphrase.as_deref(), config.address_params()).await?;
 
                 println!("Wallet initialized at {}", db_path.display());
 
@@ -24,18 +31,38 @@ impl Cli {
             WalletCommand::Address => {
                 let wallet = self.get_wallet(&config).await?;
 
-                wallet.signer().print_details()?;
+                if wallet.is_watch_only() {
+                    println!("X Only Public Key: {}", wallet.public_key());
+                    println!("P2PK Address: {}", wallet.p2pk_address()?);
+                } else {
+                    wallet.signer()?.print_details()?;
+                }
+
+                Ok(())
+            }
+            WalletCommand::Xpub { account } => {
+                let path: DerivationPath = format!("m/{account}'")
+                    .parse()
+                    .map_err(|e| Error::Config(format!("Invalid account index {account}: {e}")))?;
+                let signer = Signer::from_seed_with_path(&self.parse_seed()?, &path)?;
+
+                println!("Account: {path}");
+                println!("Xpub: {}", signer.xpub());
+                println!(
+                    "First address ({path}/0): {}",
+                    signer.derive_child(0)?.p2pk_address(config.address_params())?
+                );
 
                 Ok(())
             }
             WalletCommand::Balance => {
                 let wallet = self.get_wallet(&config).await?;
 
-                let filter = coin_store::UtxoFilter::new()
-                    .script_pubkey(wallet.signer().p2pk_address(config.address_params())?.script_pubkey());
+                let filter = coin_store::UtxoFilter::new().script_pubkey(wallet.p2pk_address()?.script_pubkey());
                 let results = <_ as UtxoStore>::query_utxos(wallet.store(), &[filter]).await?;
 
-                let mut balances: std::collections::HashMap<simplicityhl::elements::AssetId, u64> =
+                // (confirmed, pending) per asset
+                let mut balances: std::collections::HashMap<simplicityhl::elements::AssetId, (u64, u64)> =
                     std::collections::HashMap::new();
 
                 if let Some(coin_store::UtxoQueryResult::Found(entries, _)) = results.into_iter().next() {
@@ -44,31 +71,54 @@ impl Cli {
                             continue;
                         };
 
-                        *balances.entry(asset).or_insert(0) += value;
+                        let balance = balances.entry(asset).or_insert((0, 0));
+                        if entry.is_confirmed() {
+                            balance.0 += value;
+                        } else {
+                            balance.1 += value;
+                        }
                     }
                 }
 
                 if balances.is_empty() {
                     println!("No UTXOs found");
                 } else {
-                    for (asset, value) in &balances {
-                        println!("{asset}: {value}");
+                    let assets = self.asset_registry(&config)?;
+                    for (asset, (confirmed, pending)) in &balances {
+                        let ticker = assets.ticker(asset);
+                        let confirmed = assets.format_amount(asset, *confirmed);
+                        if *pending == 0 {
+                            println!("{ticker}: {confirmed}");
+                        } else {
+                            let pending = assets.format_amount(asset, *pending);
+                            println!("{ticker}: {confirmed} confirmed, {pending} pending");
+                        }
                     }
                 }
                 Ok(())
             }
-            WalletCommand::Utxos => {
+            WalletCommand::Utxos { page, page_size } => {
                 let wallet = self.get_wallet(&config).await?;
 
                 let filter = coin_store::UtxoFilter::new();
-                let results = wallet.store().query_utxos(&[filter]).await?;
+                let (entries, has_more) = if let Some(page_size) = page_size {
+                    let offset = page.saturating_sub(1).saturating_mul(*page_size);
+                    wallet.store().query_utxos_paged(&filter, *page_size, offset).await?
+                } else {
+                    let results = wallet.store().query_utxos(&[filter]).await?;
+                    match results.into_iter().next() {
+                        Some(coin_store::UtxoQueryResult::Found(entries, _)) => (entries, false),
+                        _ => (Vec::new(), false),
+                    }
+                };
+                let assets = self.asset_registry(&config)?;
 
-                if let Some(coin_store::UtxoQueryResult::Found(entries, _)) = results.into_iter().next() {
+                if !entries.is_empty() {
                     let displays: Vec<UtxoDisplay> = entries
                         .iter()
                         .map(|entry| {
                             let (asset, value) = match (entry.asset(), entry.value()) {
-                                (Some(a), Some(v)) => (a.to_string(), v.to_string()),
+                                (Some(a), Some(v)) => (assets.ticker(&a), assets.format_amount(&a, v)),
                                 _ => ("Confidential".to_string(), "Confidential".to_string()),
                             };
                             UtxoDisplay {
@@ -80,10 +130,17 @@ impl Cli {
                         .collect();
 
                     display_utxo_table(&displays);
-                    println!("Total: {} UTXOs", entries.len());
+                    if page_size.is_some() {
+                        println!("Page {page}: {} UTXOs", entries.len());
+                    } else {
+                        println!("Total: {} UTXOs", entries.len());
+                    }
                 } else {
                     display_utxo_table(&[]);
                 }
+                if has_more {
+                    println!("More UTXOs follow; run again with --page {}", page.saturating_add(1));
+                }
                 Ok(())
             }
             WalletCommand::Import { outpoint, blinding_key } => {
@@ -115,6 +172,33 @@ impl Cli {
 
                 println!("Marked {outpoint} as spent");
 
+                Ok(())
+            }
+            WalletCommand::Watch { interval } => {
+                let wallet = self.get_wallet(&config).await?;
+                let script_pubkey = wallet.p2pk_address()?.script_pubkey();
+
+                println!("Watching wallet and tracked contracts every {interval}s (Ctrl+C to stop)");
+
+                let events = watch(wallet.store(), Esplora, script_pubkey, Duration::from_secs(*interval));
+                let mut events = std::pin::pin!(events);
+
+                while let Some(event) = events.next().await {
+                    match event {
+                        WalletEvent::Received {
+                            outpoint,
+                            asset,
+                            value,
+                            height,
+                        } => println!("  + {outpoint}: received {value} of {asset} (block {height})"),
+                        WalletEvent::Spent {
+                            outpoint,
+                            amount: Some((asset, value)),
+                        } => println!("  - {outpoint}: spent {value} of {asset}"),
+                        WalletEvent::Spent { outpoint, amount: None } => println!("  - {outpoint}: spent"),
+                    }
+                }
+
                 Ok(())
             }
         }
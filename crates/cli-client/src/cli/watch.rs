@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use coin_store::UtxoStore;
+use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments};
+use contracts::options::{OPTION_SOURCE, OptionsArguments};
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use simplicityhl::elements::{OutPoint, Script};
+
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::error::Error;
+use crate::explorer::{EsploraUtxo, esplora_utxo_to_outpoint, fetch_scripthash_utxos, fetch_transaction, run_blocking};
+use crate::metadata::ContractMetadata;
+use crate::sync::{decode_contract_arguments, import_transaction};
+
+/// Contract resolved from a watch target.
+struct WatchedContract {
+    source: &'static str,
+    taproot_pubkey_gen: TaprootPubkeyGen,
+    script_pubkey: Script,
+}
+
+/// Changes in a contract's UTXO set between two polls.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UtxoSetDiff {
+    added: Vec<(OutPoint, String)>,
+    removed: Vec<(OutPoint, String)>,
+}
+
+impl UtxoSetDiff {
+    const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Cli {
+    /// Poll a contract address and report whenever its UTXO set changes.
+    pub(crate) async fn run_watch(
+        &self,
+        config: Config,
+        target: &str,
+        interval: u64,
+        hook: Option<&str>,
+    ) -> Result<(), Error> {
+        let wallet = self.get_wallet(&config).await?;
+
+        let contract = resolve_watch_target(wallet.store(), &config, target).await?;
+
+        println!(
+            "Watching {} contract {} every {interval}s (Ctrl+C to stop)",
+            contract.source, contract.taproot_pubkey_gen.address
+        );
+
+        let mut current = fetch_contract_utxos(&contract.script_pubkey).await?;
+        println!("  {} UTXO(s) at contract address", current.len());
+
+        loop {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+
+            let next = match fetch_contract_utxos(&contract.script_pubkey).await {
+                Ok(next) => next,
+                Err(e) => {
+                    eprintln!("  Poll failed: {e}");
+                    continue;
+                }
+            };
+
+            let diff = diff_utxo_sets(&current, &next);
+            if diff.is_empty() {
+                continue;
+            }
+
+            println!();
+            println!("Contract UTXO set changed:");
+            for (outpoint, amount) in &diff.removed {
+                println!("  - {outpoint} ({amount}) consumed");
+            }
+            for (outpoint, amount) in &diff.added {
+                println!("  + {outpoint} ({amount}) appeared");
+            }
+
+            for (outpoint, _) in &diff.removed {
+                if let Err(e) = wallet.store().mark_as_spent(*outpoint).await {
+                    tracing::debug!("Failed to mark {outpoint} as spent: {e}");
+                }
+            }
+            for (outpoint, _) in &diff.added {
+                let txid = outpoint.txid;
+                let imported = match run_blocking(move || fetch_transaction(txid)).await {
+                    Ok(tx) => import_transaction(wallet.store(), &tx).await,
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = imported {
+                    tracing::debug!("Failed to import {txid}: {e}");
+                }
+            }
+
+            if let Some(hook) = hook {
+                run_watch_hook(hook, target, &diff).await;
+            }
+
+            current = next;
+        }
+    }
+}
+
+/// Find a locally known option or option offer by taproot pubkey gen or NOSTR event ID prefix.
+async fn resolve_watch_target(
+    store: &coin_store::Store,
+    config: &Config,
+    target: &str,
+) -> Result<WatchedContract, Error> {
+    for source in [OPTION_SOURCE, OPTION_OFFER_SOURCE] {
+        let contracts = <_ as UtxoStore>::list_contracts_by_source_with_metadata(store, source).await?;
+
+        for (args_bytes, tpg_str, metadata_bytes) in contracts {
+            let matches_event = metadata_bytes
+                .as_ref()
+                .and_then(|b| ContractMetadata::from_bytes(b).ok())
+                .and_then(|m| m.nostr_event_id)
+                .is_some_and(|id| id.starts_with(target));
+
+            if tpg_str != target && !matches_event {
+                continue;
+            }
+
+            let arguments = decode_contract_arguments(&args_bytes, &tpg_str)
+                .ok_or_else(|| Error::Config(format!("Failed to decode arguments for contract {tpg_str}")))?;
+
+            let taproot_pubkey_gen = if source == OPTION_SOURCE {
+                let args = OptionsArguments::from_arguments(&arguments)
+                    .map_err(|e| Error::Config(format!("Invalid option arguments: {e}")))?;
+                TaprootPubkeyGen::build_from_str(
+                    &tpg_str,
+                    &args,
+                    config.address_params(),
+                    &contracts::options::get_options_address,
+                )
+            } else {
+                let args = OptionOfferArguments::from_arguments(&arguments)
+                    .map_err(|e| Error::Config(format!("Invalid option offer arguments: {e}")))?;
+                TaprootPubkeyGen::build_from_str(
+                    &tpg_str,
+                    &args,
+                    config.address_params(),
+                    &contracts::option_offer::get_option_offer_address,
+                )
+            }
+            .map_err(|e| Error::Config(format!("Invalid taproot pubkey gen: {e}")))?;
+
+            let script_pubkey = taproot_pubkey_gen.address.script_pubkey();
+
+            return Ok(WatchedContract {
+                source,
+                taproot_pubkey_gen,
+                script_pubkey,
+            });
+        }
+    }
+
+    Err(Error::Config(format!(
+        "No local contract matches {target}. Run `sync nostr` to import contracts first."
+    )))
+}
+
+async fn fetch_contract_utxos(script_pubkey: &Script) -> Result<BTreeMap<OutPoint, String>, Error> {
+    let script_pubkey = script_pubkey.clone();
+    let utxos = run_blocking(move || fetch_scripthash_utxos(&script_pubkey))
+        .await
+        .map_err(|e| Error::Config(e.to_string()))?;
+
+    let mut set = BTreeMap::new();
+    for utxo in &utxos {
+        let outpoint = esplora_utxo_to_outpoint(utxo).map_err(|e| Error::Config(e.to_string()))?;
+        set.insert(outpoint, describe_utxo(utxo));
+    }
+
+    Ok(set)
+}
+
+fn describe_utxo(utxo: &EsploraUtxo) -> String {
+    match (&utxo.value, &utxo.asset) {
+        (Some(value), Some(asset)) => format!("{value} of {asset}"),
+        _ => "confidential".to_string(),
+    }
+}
+
+fn diff_utxo_sets(previous: &BTreeMap<OutPoint, String>, next: &BTreeMap<OutPoint, String>) -> UtxoSetDiff {
+    UtxoSetDiff {
+        added: next
+            .iter()
+            .filter(|(outpoint, _)| !previous.contains_key(outpoint))
+            .map(|(outpoint, amount)| (*outpoint, amount.clone()))
+            .collect(),
+        removed: previous
+            .iter()
+            .filter(|(outpoint, _)| !next.contains_key(outpoint))
+            .map(|(outpoint, amount)| (*outpoint, amount.clone()))
+            .collect(),
+    }
+}
+
+/// Run the user's shell hook, exposing the change through environment variables.
+///
+/// The change is only ever passed in the environment, never spliced into the command line, so
+/// outpoints and targets cannot inject shell syntax. The hook runs on the blocking thread pool
+/// so a slow hook does not stall the runtime.
+async fn run_watch_hook(hook: &str, target: &str, diff: &UtxoSetDiff) {
+    let join = |entries: &[(OutPoint, String)]| {
+        entries
+            .iter()
+            .map(|(outpoint, _)| outpoint.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    let mut command = std::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(hook)
+        .env("DEX_WATCH_TARGET", target)
+        .env("DEX_WATCH_ADDED", join(&diff.added))
+        .env("DEX_WATCH_REMOVED", join(&diff.removed));
+
+    let status = tokio::task::spawn_blocking(move || command.status())
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match status {
+        Ok(status) if !status.success() => eprintln!("  Hook exited with {status}"),
+        Err(e) => eprintln!("  Failed to run hook: {e}"),
+        Ok(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::Txid;
+    use simplicityhl::elements::hashes::Hash;
+
+    #[test]
+    fn test_diff_utxo_sets_reports_taken_offer() {
+        let collateral = OutPoint::new(Txid::from_byte_array([1; 32]), 0);
+        let settlement = OutPoint::new(Txid::from_byte_array([2; 32]), 3);
+
+        let previous = BTreeMap::from([(collateral, "1000 of collateral".to_string())]);
+        let next = BTreeMap::from([(settlement, "5000 of settlement".to_string())]);
+
+        let diff = diff_utxo_sets(&previous, &next);
+        assert_eq!(diff.removed, vec![(collateral, "1000 of collateral".to_string())]);
+        assert_eq!(diff.added, vec![(settlement, "5000 of settlement".to_string())]);
+
+        assert!(diff_utxo_sets(&next, &next).is_empty());
+    }
+}
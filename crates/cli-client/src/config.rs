@@ -1,18 +1,28 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use crate::assets::AssetInfo;
 use crate::error::Error;
 use crate::explorer;
 use crate::fee::DEFAULT_FEE_RATE;
-use options_relay::NostrRelayConfig;
+use coin_store::UtxoFilter;
+use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
+use nostr::PublicKey;
+use options_relay::{ContentEncoding, NostrRelayConfig};
 use serde::{Deserialize, Serialize};
 use simplicityhl::elements::AddressParams;
 
 const DEFAULT_CONFIG_PATH: &str = "config.toml";
 const DEFAULT_DATA_DIR: &str = ".data";
 const DEFAULT_DATABASE_FILENAME: &str = "coins.db";
+const DEFAULT_NOSTR_KEY_FILENAME: &str = "nostr.key";
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 const DEFAULT_RELAY: &str = "wss://relay.damus.io";
+const DEFAULT_OFFER_EXPIRY: &str = "30d";
+const DEFAULT_MAX_FEE_RATIO: f64 = 0.5;
+const DEFAULT_MAX_LIST_ROWS: usize = 50;
+const DEFAULT_REGTEST_RPC_URL: &str = "http://127.0.0.1:7041";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -24,6 +34,21 @@ pub struct Config {
     pub storage: StorageConfig,
     #[serde(default)]
     pub fee: FeeConfig,
+    #[serde(default)]
+    pub defaults: DefaultsConfig,
+    #[serde(default)]
+    pub automation: AutomationConfig,
+    #[serde(default)]
+    pub safety: SafetyConfig,
+    #[serde(default)]
+    pub selection: SelectionConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub regtest: RegtestConfig,
+    /// Per-asset display overrides keyed by asset ID hex, applied over the asset registry.
+    #[serde(default)]
+    pub assets: BTreeMap<String, AssetInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +66,14 @@ pub enum NetworkName {
 }
 
 impl NetworkName {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Testnet => "testnet",
+            Self::Mainnet => "mainnet",
+        }
+    }
+
     #[must_use]
     pub const fn address_params(self) -> &'static AddressParams {
         match self {
@@ -56,12 +89,26 @@ pub struct RelayConfig {
     pub urls: Vec<String>,
     #[serde(default = "default_timeout")]
     pub timeout_secs: u64,
+    /// When non-empty, only options and offers published by these NOSTR authors are imported or listed.
+    #[serde(default)]
+    pub author_allowlist: Vec<PublicKey>,
+    /// Options and offers published by these NOSTR authors are always ignored.
+    #[serde(default)]
+    pub author_blocklist: Vec<PublicKey>,
+    /// Publish options and offers in the compact binary content encoding instead of hex tags.
+    /// Clients that predate the compact encoding cannot read such events.
+    #[serde(default)]
+    pub compact_events: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     #[serde(default = "default_data_dir")]
     pub data_dir: PathBuf,
+    /// Encrypt the wallet database with SQLCipher (requires the `sqlcipher` feature).
+    /// Applies when the database is created; an existing plain database is not converted.
+    #[serde(default)]
+    pub encrypted: bool,
 }
 
 /// Fee estimation configuration.
@@ -76,6 +123,69 @@ pub struct FeeConfig {
     /// Default: 100.0 sats/kvb (0.10 sat/vB) to meet Liquid minimum relay fee.
     #[serde(default = "default_fallback_rate")]
     pub fallback_rate: f32,
+    /// Signal BIP125 replaceability on wallet-only transactions (transfer, split, merge, issue,
+    /// reissue) so they can be fee-bumped with `helper bump-fee`. Contract transactions are not affected.
+    #[serde(default)]
+    pub rbf: bool,
+}
+
+/// Defaults applied when optional command arguments are omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultsConfig {
+    /// Expiry duration for created options and offers when `--expiry` is omitted (e.g., "30d", "2w").
+    #[serde(default = "default_offer_expiry")]
+    pub offer_expiry: String,
+}
+
+/// Opt-in actions performed automatically during `sync`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AutomationConfig {
+    /// Withdraw settlement from our option offers once a taker's settlement appears at the contract.
+    /// Only broadcasts when `sync` is run with `--yes`.
+    #[serde(default)]
+    pub auto_withdraw: bool,
+}
+
+/// Guards against uneconomical transactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyConfig {
+    /// Maximum share of the LBTC value claimed that the fee may consume before a claim
+    /// (settlement, withdraw, expiry reclaim) is refused without `--force`. 0 disables the check.
+    #[serde(default = "default_max_fee_ratio")]
+    pub max_fee_ratio: f64,
+    /// Execute each finalized contract input locally before the transaction is signed and broadcast.
+    #[serde(default)]
+    pub verify_finalized: bool,
+}
+
+/// Wallet coin selection preferences.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SelectionConfig {
+    /// When interacting with a contract, spend change from earlier transactions with the same
+    /// contract first, keeping related coins together.
+    #[serde(default)]
+    pub prefer_contract_change: bool,
+}
+
+/// Terminal output preferences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Maximum number of rows printed in offer tables before the rest are summarized.
+    /// Overridden per command with `--limit`. 0 shows every row.
+    #[serde(default = "default_max_list_rows")]
+    pub max_list_rows: usize,
+}
+
+/// Local regtest node used by the `regtest` development commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegtestConfig {
+    /// Elements node JSON-RPC endpoint.
+    #[serde(default = "default_regtest_rpc_url")]
+    pub rpc_url: String,
+    #[serde(default)]
+    pub rpc_user: Option<String>,
+    #[serde(default)]
+    pub rpc_password: Option<String>,
 }
 
 impl Config {
@@ -89,11 +199,24 @@ impl Config {
         Self::load(path).unwrap_or_default()
     }
 
+    /// Write the configuration back to disk. Comments in the original file are not preserved.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
     #[must_use]
     pub fn database_path(&self) -> PathBuf {
         self.storage.data_dir.join(DEFAULT_DATABASE_FILENAME)
     }
 
+    /// File holding the NOSTR secret key managed by `key rotate`.
+    #[must_use]
+    pub fn nostr_key_path(&self) -> PathBuf {
+        self.storage.data_dir.join(DEFAULT_NOSTR_KEY_FILENAME)
+    }
+
     #[must_use]
     pub const fn address_params(&self) -> &'static AddressParams {
         self.network.name.address_params()
@@ -110,15 +233,51 @@ impl Config {
     }
 }
 
+impl SelectionConfig {
+    /// Apply the configured preferences to a wallet coin filter used in an interaction with a contract.
+    #[must_use]
+    pub fn for_contract(&self, filter: UtxoFilter, taproot_pubkey_gen: &TaprootPubkeyGen) -> UtxoFilter {
+        if self.prefer_contract_change {
+            filter.prefer_from_contract(taproot_pubkey_gen.clone())
+        } else {
+            filter
+        }
+    }
+}
+
+impl UiConfig {
+    /// Resolve the number of rows to show from a `--limit` override, `None` meaning no limit.
+    #[must_use]
+    pub fn row_limit(&self, limit: Option<usize>) -> Option<usize> {
+        Some(limit.unwrap_or(self.max_list_rows)).filter(|rows| *rows > 0)
+    }
+}
+
 impl RelayConfig {
     pub fn get_nostr_relay_config(&self) -> NostrRelayConfig {
         let mut urls = self.urls.iter();
 
         let primary = urls.next().map_or("wss://relay.damus.io", String::as_str);
 
+        let content_encoding = if self.compact_events {
+            ContentEncoding::CompactV1
+        } else {
+            ContentEncoding::Tags
+        };
+
         NostrRelayConfig::new(primary)
             .add_backup_relays(urls.map(String::as_str))
             .with_timeout(Duration::from_secs(self.timeout_secs))
+            .with_content_encoding(content_encoding)
+    }
+
+    /// Whether events signed by `author` pass the allowlist and blocklist.
+    ///
+    /// Only call this on events whose signature has already been verified.
+    #[must_use]
+    pub fn accepts_author(&self, author: &PublicKey) -> bool {
+        !self.author_blocklist.contains(author)
+            && (self.author_allowlist.is_empty() || self.author_allowlist.contains(author))
     }
 }
 
@@ -135,6 +294,9 @@ impl Default for RelayConfig {
         Self {
             urls: default_relays(),
             timeout_secs: default_timeout(),
+            author_allowlist: Vec::new(),
+            author_blocklist: Vec::new(),
+            compact_events: false,
         }
     }
 }
@@ -143,6 +305,42 @@ impl Default for StorageConfig {
     fn default() -> Self {
         Self {
             data_dir: default_data_dir(),
+            encrypted: false,
+        }
+    }
+}
+
+impl Default for DefaultsConfig {
+    fn default() -> Self {
+        Self {
+            offer_expiry: default_offer_expiry(),
+        }
+    }
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            max_fee_ratio: default_max_fee_ratio(),
+            verify_finalized: false,
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            max_list_rows: default_max_list_rows(),
+        }
+    }
+}
+
+impl Default for RegtestConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: default_regtest_rpc_url(),
+            rpc_user: None,
+            rpc_password: None,
         }
     }
 }
@@ -152,6 +350,7 @@ impl Default for FeeConfig {
         Self {
             confirmation_target: 0,
             fallback_rate: default_fallback_rate(),
+            rbf: false,
         }
     }
 }
@@ -172,6 +371,22 @@ const fn default_fallback_rate() -> f32 {
     DEFAULT_FEE_RATE
 }
 
+const fn default_max_fee_ratio() -> f64 {
+    DEFAULT_MAX_FEE_RATIO
+}
+
+const fn default_max_list_rows() -> usize {
+    DEFAULT_MAX_LIST_ROWS
+}
+
+fn default_regtest_rpc_url() -> String {
+    DEFAULT_REGTEST_RPC_URL.to_string()
+}
+
+fn default_offer_expiry() -> String {
+    DEFAULT_OFFER_EXPIRY.to_string()
+}
+
 fn default_data_dir() -> PathBuf {
     PathBuf::from(DEFAULT_DATA_DIR)
 }
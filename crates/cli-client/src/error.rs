@@ -11,9 +11,15 @@ pub enum Error {
     #[error("TOML parse error: {0}")]
     TomlParse(#[from] toml::de::Error),
 
+    #[error("TOML serialize error: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
+
     #[error("Signer error: {0}")]
     Signer(#[from] signer::SignerError),
 
+    #[error("watch-only wallet cannot sign")]
+    WatchOnly,
+
     #[error("Store error: {0}")]
     Store(#[from] coin_store::StoreError),
 
@@ -29,9 +35,34 @@ pub enum Error {
     #[error("Program error: {0}")]
     Program(#[from] simplicityhl_core::ProgramError),
 
+    #[error("Input {input} failed verification: {message}")]
+    Verification { input: usize, message: String },
+
+    #[error("Input {input} ({branch} branch) failed verification: {message}")]
+    BranchVerification {
+        input: usize,
+        branch: String,
+        message: String,
+    },
+
+    #[error("Invalid control proof: {0}")]
+    ControlProof(String),
+
+    #[error(
+        "Offer output {outpoint} is no longer available; someone else may have taken the offer. \
+         Run `sync spent` to refresh the local order book."
+    )]
+    OfferNoLongerAvailable { outpoint: simplicityhl::elements::OutPoint },
+
     #[error("PSET error: {0}")]
     Pset(#[from] simplicityhl::elements::pset::Error),
 
+    #[error("PSET blinding error: {0}")]
+    PsetBlind(#[from] simplicityhl::elements::pset::PsetBlindError),
+
+    #[error("Address error: {0}")]
+    Address(#[from] crate::address::AddrError),
+
     #[error("Hex error: {0}")]
     Hex(#[from] hex::FromHexError),
 
@@ -47,6 +78,10 @@ pub enum Error {
     #[error("Metadata decode error: {0}")]
     MetadataDecode(bincode::error::DecodeError),
 
+    #[cfg(feature = "dev-commands")]
+    #[error("Regtest node error: {0}")]
+    Regtest(#[from] crate::regtest::RpcError),
+
     #[error("Relay error: {0}")]
     Relay(#[from] options_relay::RelayError),
 
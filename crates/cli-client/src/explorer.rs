@@ -27,10 +27,27 @@ pub enum EsploraError {
 
     #[error("Invalid txid format: {0}")]
     InvalidTxid(String),
+
+    #[error("Transaction not found: {0}")]
+    NotFound(Txid),
 }
 
 pub type FetchTransactionError = EsploraError;
 
+/// Run blocking explorer requests on tokio's blocking thread pool.
+///
+/// The fetch functions here use `minreq`, which blocks the calling thread, so async callers go
+/// through this instead of calling them directly on the runtime.
+pub async fn run_blocking<T, E>(f: impl FnOnce() -> Result<T, E> + Send + 'static) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<EsploraError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| EsploraError::Request(format!("Blocking request task failed: {e}")))?
+}
+
 /// Spending status of a transaction output.
 #[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
@@ -98,6 +115,28 @@ pub fn fetch_transaction(txid: Txid) -> Result<Transaction, EsploraError> {
     Ok(tx)
 }
 
+/// Fetch confirmation status of a transaction.
+///
+/// Uses the `GET /tx/:txid/status` endpoint.
+#[cfg(feature = "dev-commands")]
+pub fn fetch_tx_status(txid: Txid) -> Result<UtxoStatus, EsploraError> {
+    let url = format!("{ESPLORA_URL}/tx/{}/status", txid.to_hex());
+    let response = minreq::get(&url)
+        .send()
+        .map_err(|e| EsploraError::Request(e.to_string()))?;
+
+    if response.status_code != 200 {
+        return Err(EsploraError::Request(format!(
+            "HTTP {}: {}",
+            response.status_code, response.reason_phrase
+        )));
+    }
+
+    let status: UtxoStatus = response.json().map_err(|e| EsploraError::Deserialize(e.to_string()))?;
+
+    Ok(status)
+}
+
 /// Check spending status of all outputs in a transaction.
 ///
 /// Uses the `GET /tx/:txid/outspends` endpoint. More efficient than
@@ -108,6 +147,9 @@ pub fn fetch_outspends(txid: Txid) -> Result<Vec<OutspendStatus>, EsploraError>
         .send()
         .map_err(|e| EsploraError::Request(e.to_string()))?;
 
+    if response.status_code == 404 {
+        return Err(EsploraError::NotFound(txid));
+    }
     if response.status_code != 200 {
         return Err(EsploraError::Request(format!(
             "HTTP {}: {}",
@@ -1,5 +1,9 @@
 use simplicityhl::elements::pset::PartiallySignedTransaction;
-use simplicityhl::elements::{Transaction, TxOut};
+use simplicityhl::elements::{AssetId, OutPoint, Script, Transaction, TxOut, Txid};
+use simplicityhl_core::LIQUID_TESTNET_BITCOIN_ASSET;
+
+use crate::error::Error;
+use crate::explorer::{fetch_transaction, fetch_tx_status, run_blocking};
 
 /// Witness scale factor for weight-to-vsize conversion.
 /// In segwit, weight = 4 * `base_size` + `witness_size`, so vsize = weight / 4.
@@ -10,6 +14,13 @@ pub const WITNESS_SCALE_FACTOR: usize = 4;
 /// calculating the real fee.
 pub const PLACEHOLDER_FEE: u64 = 1;
 
+/// Upper bound on select → estimate rounds in [`select_with_fee`].
+pub const MAX_FEE_SELECTION_ROUNDS: usize = 4;
+
+/// Signed weight assumed for a contract transaction when no fee input exists to build it with.
+/// Generous for the Simplicity spends we make, so the reported shortfall is enough to proceed.
+pub const FEE_PREFLIGHT_WEIGHT: usize = 20_000;
+
 /// Default fallback fee rate in sats/kvb (0.10 sat/vB).
 /// Higher than LWK default to meet Liquid minimum relay fee requirements.
 pub const DEFAULT_FEE_RATE: f32 = 100.0;
@@ -58,6 +69,54 @@ where
     Ok(estimated)
 }
 
+/// Select inputs and estimate the fee until the selection covers the fee it produces.
+///
+/// The first selection only has to cover `fee` (or [`PLACEHOLDER_FEE`]), so the real fee
+/// estimated from it can exceed what the selected inputs leave over. When that happens
+/// selection is re-run for the real fee, which may pull in more inputs and raise the fee
+/// again, for at most [`MAX_FEE_SELECTION_ROUNDS`] rounds.
+///
+/// # Arguments
+///
+/// * `fee` - Optional user-provided fee in satoshis
+/// * `select` - Selects inputs able to pay the given fee, returning them with the largest fee they can cover
+/// * `estimate` - Estimates the fee of the transaction built from a selection (see [`estimate_fee_signed`])
+///
+/// # Returns
+///
+/// The settled selection and its fee.
+///
+/// # Errors
+///
+/// Returns an error if selection or estimation fails, or the selection does not settle within the round limit.
+pub async fn select_with_fee<T, Sel, Est>(
+    fee: Option<u64>,
+    mut select: Sel,
+    mut estimate: Est,
+) -> Result<(T, u64), Error>
+where
+    Sel: AsyncFnMut(u64) -> Result<(T, u64), Error>,
+    Est: FnMut(&T) -> Result<u64, Error>,
+{
+    let mut required_fee = fee.unwrap_or(PLACEHOLDER_FEE);
+
+    for _ in 0..MAX_FEE_SELECTION_ROUNDS {
+        let (selection, fee_capacity) = select(required_fee).await?;
+        let actual_fee = estimate(&selection)?;
+
+        if actual_fee <= fee_capacity {
+            return Ok((selection, actual_fee));
+        }
+
+        println!("  Selected inputs cover {fee_capacity} sats of fee, {actual_fee} needed; re-selecting");
+        required_fee = actual_fee;
+    }
+
+    Err(Error::Config(format!(
+        "Coin selection did not settle on a fee after {MAX_FEE_SELECTION_ROUNDS} rounds. Try `tx merge` or pass --fee."
+    )))
+}
+
 /// Calculate fee from weight and fee rate (sats/kvb).
 ///
 /// Formula: `fee = ceil(vsize * fee_rate / 1000)`
@@ -81,3 +140,241 @@ pub fn calculate_fee(weight: usize, fee_rate: f32) -> u64 {
     let vsize = weight.div_ceil(WITNESS_SCALE_FACTOR);
     (vsize as f32 * fee_rate / 1000.0).ceil() as u64
 }
+
+/// Fee a child must pay for the parent + child package to reach `fee_rate` (child-pays-for-parent).
+///
+/// Never less than the child's own fee at `fee_rate`, so an overpaying parent does not
+/// leave the child underpaying on its own.
+#[must_use]
+pub fn cpfp_child_fee(parent_fee: u64, parent_weight: usize, child_weight: usize, fee_rate: f32) -> u64 {
+    let package_fee = calculate_fee(parent_weight + child_weight, fee_rate);
+    package_fee
+        .saturating_sub(parent_fee)
+        .max(calculate_fee(child_weight, fee_rate))
+}
+
+/// An unconfirmed transaction whose fee is topped up by the child spending one of its outputs.
+///
+/// Only the parent itself is counted; unconfirmed ancestors further up are not bumped.
+#[derive(Debug, Clone, Copy)]
+pub struct CpfpParent {
+    pub txid: Txid,
+    pub fee: u64,
+    pub weight: usize,
+}
+
+impl CpfpParent {
+    /// Fetch the parent from the explorer, refusing one that has already confirmed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the explorer request fails or the parent is confirmed.
+    pub async fn fetch(txid: Txid) -> Result<Self, Error> {
+        if run_blocking(move || fetch_tx_status(txid)).await?.confirmed {
+            return Err(Error::Config(format!(
+                "CPFP parent {txid} is already confirmed; drop --cpfp-parent"
+            )));
+        }
+
+        let tx = run_blocking(move || fetch_transaction(txid)).await?;
+        let fee = tx
+            .output
+            .iter()
+            .filter(|txout| txout.is_fee())
+            .filter_map(|txout| txout.value.explicit())
+            .sum();
+
+        Ok(Self {
+            txid,
+            fee,
+            weight: tx.weight(),
+        })
+    }
+
+    /// Fail unless one of the child's `inputs` spends an output of the parent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child does not spend the parent.
+    pub fn ensure_spent_by(&self, inputs: impl IntoIterator<Item = OutPoint>) -> Result<(), Error> {
+        if inputs.into_iter().any(|outpoint| outpoint.txid == self.txid) {
+            return Ok(());
+        }
+
+        Err(Error::Config(format!(
+            "Transaction does not spend any output of CPFP parent {}; a child can only bump a parent it spends",
+            self.txid
+        )))
+    }
+
+    /// Fee the child of `child_weight` must pay to confirm the parent along with it.
+    #[must_use]
+    pub fn child_fee(&self, child_weight: usize, fee_rate: f32) -> u64 {
+        let fee = cpfp_child_fee(self.fee, self.weight, child_weight, fee_rate);
+        println!(
+            "CPFP: parent {} pays {} sats (weight: {}), child pays {fee} sats for the package",
+            self.txid, self.fee, self.weight
+        );
+        fee
+    }
+}
+
+/// Explain why no L-BTC fee input could be selected, with the exact amount the wallet is short.
+///
+/// Without a user-provided `fee`, the requirement is estimated from [`FEE_PREFLIGHT_WEIGHT`].
+pub async fn lbtc_fee_shortfall(
+    store: &coin_store::Store,
+    script_pubkey: &Script,
+    fee: Option<u64>,
+    fee_rate: f32,
+) -> Error {
+    let required = fee.unwrap_or_else(|| calculate_fee(FEE_PREFLIGHT_WEIGHT, fee_rate));
+
+    match store.balance_detailed(script_pubkey, &[]).await {
+        Ok(balances) => {
+            let available = balances
+                .get(&*LIQUID_TESTNET_BITCOIN_ASSET)
+                .map_or(0, |balance| balance.spendable);
+            fee_shortfall_error(required, available)
+        }
+        Err(e) => e.into(),
+    }
+}
+
+fn fee_shortfall_error(required: u64, available: u64) -> Error {
+    if available < required {
+        return Error::Config(format!(
+            "Not enough L-BTC for fees: need {required} sats, wallet has {available} sats. \
+             Send at least {} sats of L-BTC to the wallet address; Liquid fees can only be paid in L-BTC.",
+            required - available
+        ));
+    }
+
+    Error::Config(format!(
+        "Wallet holds {available} sats of L-BTC but none of it could be selected for the {required} sats fee. \
+         Run `sync full` to refresh the wallet's UTXOs."
+    ))
+}
+
+/// Refuse a claim whose fee would eat more than `max_ratio` of the value it moves.
+///
+/// Only amounts in the fee asset (LBTC) are comparable, so claims moving other assets pass.
+/// With `force` the check only warns. A `max_ratio` of 0 or less disables it.
+///
+/// # Errors
+///
+/// Returns an error if the ratio is exceeded and `force` is not set.
+#[allow(clippy::cast_precision_loss)]
+pub fn check_fee_ratio(fee: u64, moved: &[(AssetId, u64)], max_ratio: f64, force: bool) -> Result<(), Error> {
+    let native_value: u64 = moved
+        .iter()
+        .filter(|(asset, _)| *asset == *LIQUID_TESTNET_BITCOIN_ASSET)
+        .map(|(_, amount)| *amount)
+        .sum();
+
+    if max_ratio <= 0.0 || native_value == 0 {
+        return Ok(());
+    }
+
+    let ratio = fee as f64 / native_value as f64;
+    if ratio <= max_ratio {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Fee of {fee} sats is {:.0}% of the {native_value} sats claimed (limit: {:.0}%, `safety.max_fee_ratio`)",
+        ratio * 100.0,
+        max_ratio * 100.0
+    );
+
+    if force {
+        eprintln!("Warning: {message}");
+        return Ok(());
+    }
+
+    Err(Error::Config(format!(
+        "{message}. Re-run with --force to proceed anyway."
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simplicityhl::elements::hashes::Hash;
+
+    /// UTXO values in selection order; selection takes a prefix covering `amount + fee`.
+    const UTXOS: [u64; 4] = [600, 300, 200, 500];
+    const AMOUNT: u64 = 800;
+
+    fn select_prefix(required_fee: u64) -> Result<(usize, u64), Error> {
+        let mut total = 0;
+        for (count, value) in UTXOS.iter().enumerate() {
+            total += value;
+            if total >= AMOUNT + required_fee {
+                return Ok((count + 1, total - AMOUNT));
+            }
+        }
+        Err(Error::Config("Insufficient funds".to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_select_with_fee_reselects_when_fee_grows() {
+        // Each input adds 60 sats of fee: 2 inputs cover the placeholder but leave only
+        // 100 sats for a 120 sat fee, so selection must grow to 3 inputs (180 sats).
+        let mut rounds = 0;
+        let (count, fee) = select_with_fee(
+            None,
+            async |required_fee| {
+                rounds += 1;
+                select_prefix(required_fee)
+            },
+            |count| Ok(*count as u64 * 60),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!((count, fee), (3, 180));
+        assert_eq!(rounds, 2);
+    }
+
+    #[test]
+    fn test_fee_shortfall_error_reports_missing_amount() {
+        let message = fee_shortfall_error(500, 120).to_string();
+        assert!(message.contains("need 500 sats, wallet has 120 sats"));
+        assert!(message.contains("at least 380 sats"));
+
+        let message = fee_shortfall_error(500, 0).to_string();
+        assert!(message.contains("at least 500 sats"));
+    }
+
+    #[test]
+    fn test_cpfp_child_fee_covers_package() {
+        // 100 sats/kvb: 4000 WU is 1000 vB, so 100 sats for the package
+        assert_eq!(cpfp_child_fee(10, 2000, 2000, 100.0), 90);
+        // A parent already paying for the package still leaves the child its own fee
+        assert_eq!(cpfp_child_fee(500, 2000, 2000, 100.0), 50);
+    }
+
+    #[test]
+    fn test_cpfp_parent_must_be_spent() {
+        let parent = CpfpParent {
+            txid: Txid::from_byte_array([1; 32]),
+            fee: 0,
+            weight: 0,
+        };
+        let other = Txid::from_byte_array([2; 32]);
+
+        assert!(parent.ensure_spent_by([OutPoint::new(other, 0)]).is_err());
+        assert!(
+            parent
+                .ensure_spent_by([OutPoint::new(other, 0), OutPoint::new(parent.txid, 1)])
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_with_fee_gives_up_after_max_rounds() {
+        let result = select_with_fee(None, async |required_fee| Ok(((), required_fee)), |()| Ok(u64::MAX)).await;
+        assert!(result.is_err());
+    }
+}
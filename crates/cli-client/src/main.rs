@@ -1,5 +1,7 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+mod address;
+mod assets;
 mod cli;
 mod config;
 mod error;
@@ -7,6 +9,8 @@ mod explorer;
 mod fee;
 mod logging;
 mod metadata;
+#[cfg(feature = "dev-commands")]
+mod regtest;
 mod signing;
 mod sync;
 mod wallet;
@@ -1,9 +1,12 @@
+use std::fmt;
+
+use options_relay::{ACTION_OPTION_CREATED, ACTION_OPTION_OFFER_CREATED};
 use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 
 /// Represents a single action in the contract's history.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HistoryEntry {
     /// The action type (e.g., "created", "funded", "`swap_created`", "`swap_taken`", etc.)
     pub action: String,
@@ -41,6 +44,59 @@ impl HistoryEntry {
             details: None,
         }
     }
+
+    /// Record the NOSTR event this action was published as and the public key that signed it.
+    ///
+    /// The signing key is kept in `details` so actions stay verifiable after the NOSTR key is rotated.
+    #[must_use]
+    pub fn published_as(mut self, nostr_event_id: &str, author: &str) -> Self {
+        self.nostr_event_id = Some(nostr_event_id.to_string());
+        self.details = Some(format!("signed by {author}"));
+        self
+    }
+
+    /// Whether both entries record the same action: the same NOSTR event, or the same action in the same transaction.
+    fn records_same_action(&self, other: &Self) -> bool {
+        if let (Some(a), Some(b)) = (&self.nostr_event_id, &other.nostr_event_id)
+            && a == b
+        {
+            return true;
+        }
+
+        self.action == other.action && matches!((&self.txid, &other.txid), (Some(a), Some(b)) if a == b)
+    }
+}
+
+/// A disagreement between a contract's local history and the actions relays report for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryDiscrepancy {
+    /// The relays report an action the local history has no entry for.
+    MissingLocally(HistoryEntry),
+    /// The local history has an action the relays do not report.
+    MissingOnRelays(HistoryEntry),
+}
+
+impl fmt::Display for HistoryDiscrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (entry, side) = match self {
+            Self::MissingLocally(entry) => (entry, "relay shows"),
+            Self::MissingOnRelays(entry) => (entry, "local has"),
+        };
+
+        write!(f, "{side} {}", entry.action)?;
+        if let Some(event_id) = &entry.nostr_event_id {
+            write!(f, " at event {event_id}")?;
+        }
+        if let Some(txid) = &entry.txid {
+            write!(f, " (tx {txid})")?;
+        }
+
+        match self {
+            Self::MissingLocally(_) => write!(f, ", local has no record"),
+            Self::MissingOnRelays(_) if entry.nostr_event_id.is_some() => write!(f, ", relays do not return it"),
+            Self::MissingOnRelays(_) => write!(f, ", never published to relays"),
+        }
+    }
 }
 
 /// Metadata for contracts stored in the database.
@@ -61,6 +117,34 @@ pub struct ContractMetadata {
     /// Full history of actions taken on this contract
     #[serde(default)]
     pub history: Vec<HistoryEntry>,
+    /// Collateral an option offer still holds after partial takes, as last seen in a taking transaction
+    #[serde(default)]
+    pub remaining_collateral: Option<u64>,
+}
+
+/// Layout of [`ContractMetadata`] before `remaining_collateral`, so records written then still decode.
+///
+/// bincode is not self-describing, so a field missing from the end of a record cannot be defaulted.
+#[derive(Deserialize)]
+struct ContractMetadataV1 {
+    nostr_event_id: Option<String>,
+    nostr_author: Option<String>,
+    created_at: Option<i64>,
+    parent_event_id: Option<String>,
+    history: Vec<HistoryEntry>,
+}
+
+impl From<ContractMetadataV1> for ContractMetadata {
+    fn from(legacy: ContractMetadataV1) -> Self {
+        Self {
+            nostr_event_id: legacy.nostr_event_id,
+            nostr_author: legacy.nostr_author,
+            created_at: legacy.created_at,
+            parent_event_id: legacy.parent_event_id,
+            history: legacy.history,
+            remaining_collateral: None,
+        }
+    }
 }
 
 impl ContractMetadata {
@@ -78,6 +162,7 @@ impl ContractMetadata {
             created_at: Some(created_at),
             parent_event_id: None,
             history,
+            remaining_collateral: None,
         }
     }
 
@@ -95,6 +180,7 @@ impl ContractMetadata {
             created_at: Some(created_at),
             parent_event_id: Some(parent_event_id),
             history: Vec::new(),
+            remaining_collateral: None,
         }
     }
 
@@ -130,14 +216,55 @@ impl ContractMetadata {
         }
     }
 
+    /// Point the metadata at the event the contract was republished under.
+    /// Returns the event ID it replaced.
+    pub fn relink(&mut self, event_id: String, author: String) -> Option<String> {
+        self.nostr_author = Some(author);
+        self.nostr_event_id.replace(event_id)
+    }
+
+    /// Record the collateral an option offer holds after a transaction that spent it.
+    ///
+    /// A transaction that leaves nothing, e.g. a full take or a cancel, resets it to `None`: the
+    /// offer then has no collateral UTXO and is no longer listed, so there is nothing to override.
+    pub fn set_remaining_collateral(&mut self, remaining: u64) {
+        self.remaining_collateral = (remaining > 0).then_some(remaining);
+    }
+
+    /// Compare the local history with the actions relays report for this contract.
+    ///
+    /// Entries match on NOSTR event ID or on action and txid. Creation entries are left out of
+    /// the local side, since the contract announcement rather than an action event covers them.
+    #[must_use]
+    pub fn history_discrepancies(&self, relay_actions: &[HistoryEntry]) -> Vec<HistoryDiscrepancy> {
+        let missing_locally = relay_actions
+            .iter()
+            .filter(|observed| !self.history.iter().any(|local| local.records_same_action(observed)))
+            .cloned()
+            .map(HistoryDiscrepancy::MissingLocally);
+
+        let missing_on_relays = self
+            .history
+            .iter()
+            .filter(|local| local.action != ACTION_OPTION_CREATED && local.action != ACTION_OPTION_OFFER_CREATED)
+            .filter(|local| !relay_actions.iter().any(|observed| observed.records_same_action(local)))
+            .cloned()
+            .map(HistoryDiscrepancy::MissingOnRelays);
+
+        missing_locally.chain(missing_on_relays).collect()
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         bincode::serde::encode_to_vec(self, bincode::config::standard()).map_err(Error::MetadataEncode)
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let (metadata, _): (Self, usize) =
-            bincode::serde::decode_from_slice(bytes, bincode::config::standard()).map_err(Error::MetadataDecode)?;
-        Ok(metadata)
+        match bincode::serde::decode_from_slice::<Self, _>(bytes, bincode::config::standard()) {
+            Ok((metadata, _)) => Ok(metadata),
+            Err(e) => bincode::serde::decode_from_slice::<ContractMetadataV1, _>(bytes, bincode::config::standard())
+                .map(|(legacy, _)| legacy.into())
+                .map_err(|_| Error::MetadataDecode(e)),
+        }
     }
 }
 
@@ -211,6 +338,90 @@ mod tests {
         assert_eq!(entry.nostr_event_id, Some("event456".to_string()));
     }
 
+    #[test]
+    fn test_metadata_written_before_remaining_collateral_still_decodes() {
+        #[derive(Serialize)]
+        struct Legacy {
+            nostr_event_id: Option<String>,
+            nostr_author: Option<String>,
+            created_at: Option<i64>,
+            parent_event_id: Option<String>,
+            history: Vec<HistoryEntry>,
+        }
+
+        let legacy = Legacy {
+            nostr_event_id: Some("offer1".to_string()),
+            nostr_author: None,
+            created_at: Some(1_704_067_200),
+            parent_event_id: None,
+            history: vec![HistoryEntry::with_txid("option_offer_created", "tx0", 1_704_067_200)],
+        };
+        let bytes = bincode::serde::encode_to_vec(&legacy, bincode::config::standard()).unwrap();
+
+        let restored = ContractMetadata::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.nostr_event_id, Some("offer1".to_string()));
+        assert_eq!(restored.history, legacy.history);
+        assert_eq!(restored.remaining_collateral, None);
+
+        let mut metadata = restored;
+        metadata.set_remaining_collateral(4_000);
+        let restored = ContractMetadata::from_bytes(&metadata.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.remaining_collateral, Some(4_000));
+
+        metadata.set_remaining_collateral(0);
+        assert_eq!(metadata.remaining_collateral, None);
+    }
+
+    #[test]
+    fn test_history_discrepancies_in_both_directions() {
+        let mut metadata = ContractMetadata::from_nostr_with_history(
+            "offer1".to_string(),
+            "npub1abc".to_string(),
+            1_704_067_200,
+            vec![HistoryEntry::with_txid_and_nostr(
+                ACTION_OPTION_OFFER_CREATED,
+                "tx0",
+                "offer1",
+                1_704_067_200,
+            )],
+        );
+        metadata.add_history(HistoryEntry::with_txid("option_offer_cancelled", "tx1", 1_704_067_300));
+        metadata.add_history(
+            HistoryEntry::with_txid("option_offer_exercised", "tx2", 1_704_067_400).published_as("event2", "abcdef"),
+        );
+
+        let relay_actions = [
+            HistoryEntry::with_txid_and_nostr("option_offer_exercised", "tx2", "event2", 1_704_067_400),
+            HistoryEntry::with_txid_and_nostr("option_offer_exercised", "tx3", "event3", 1_704_067_500),
+        ];
+
+        let discrepancies = metadata.history_discrepancies(&relay_actions);
+        assert_eq!(
+            discrepancies,
+            vec![
+                HistoryDiscrepancy::MissingLocally(relay_actions[1].clone()),
+                HistoryDiscrepancy::MissingOnRelays(metadata.history[1].clone()),
+            ]
+        );
+        assert_eq!(
+            discrepancies[0].to_string(),
+            "relay shows option_offer_exercised at event event3 (tx tx3), local has no record"
+        );
+        assert_eq!(
+            discrepancies[1].to_string(),
+            "local has option_offer_cancelled (tx tx1), never published to relays"
+        );
+    }
+
+    #[test]
+    fn test_history_entry_published_as_records_author() {
+        let entry =
+            HistoryEntry::with_txid("option_expired", "tx123", 1_704_067_200).published_as("event456", "abcdef");
+
+        assert_eq!(entry.nostr_event_id, Some("event456".to_string()));
+        assert_eq!(entry.details, Some("signed by abcdef".to_string()));
+    }
+
     #[test]
     fn test_add_history() {
         let mut metadata = ContractMetadata::default();
@@ -222,4 +433,20 @@ mod tests {
         metadata.add_history(HistoryEntry::with_txid("action2", "tx2", 1_704_067_300));
         assert_eq!(metadata.history.len(), 2);
     }
+
+    #[test]
+    fn test_relink_keeps_history() {
+        let mut metadata = ContractMetadata::from_nostr_with_history(
+            "old_event".to_string(),
+            "author".to_string(),
+            1_704_067_200,
+            vec![HistoryEntry::with_txid("option_offer_created", "tx1", 1_704_067_200)],
+        );
+
+        let previous = metadata.relink("new_event".to_string(), "author".to_string());
+
+        assert_eq!(previous, Some("old_event".to_string()));
+        assert_eq!(metadata.nostr_event_id, Some("new_event".to_string()));
+        assert_eq!(metadata.history.len(), 1);
+    }
 }
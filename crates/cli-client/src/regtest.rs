@@ -0,0 +1,140 @@
+//! Minimal Elements JSON-RPC client for driving a local regtest node during development.
+
+use crate::config::RegtestConfig;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+
+/// Error type for regtest node RPC calls.
+#[derive(thiserror::Error, Debug)]
+pub enum RpcError {
+    #[error("HTTP request failed: {0}")]
+    Request(String),
+
+    #[error("Failed to deserialize response: {0}")]
+    Deserialize(String),
+
+    #[error("RPC error {code}: {message}")]
+    Node { code: i64, message: String },
+
+    #[error("Node is on chain '{0}', refusing to run regtest commands")]
+    NotRegtest(String),
+}
+
+/// Subset of `getblockchaininfo` used to guard and report regtest commands.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainInfo {
+    pub chain: String,
+    pub blocks: u64,
+    pub mediantime: i64,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RpcParam {
+    Int(i64),
+    Str(String),
+}
+
+#[derive(Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: &'static str,
+    method: &'a str,
+    params: &'a [RpcParam],
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+pub struct RegtestNode<'a> {
+    config: &'a RegtestConfig,
+}
+
+impl<'a> RegtestNode<'a> {
+    #[must_use]
+    pub const fn new(config: &'a RegtestConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn blockchain_info(&self) -> Result<ChainInfo, RpcError> {
+        self.call("getblockchaininfo", &[])
+    }
+
+    /// Fetch the chain info, failing unless the node runs a regtest chain.
+    pub fn ensure_regtest(&self) -> Result<ChainInfo, RpcError> {
+        let info = self.blockchain_info()?;
+
+        // Elements reports e.g. "elementsregtest" or "liquidregtest"
+        if !info.chain.ends_with("regtest") {
+            return Err(RpcError::NotRegtest(info.chain));
+        }
+
+        Ok(info)
+    }
+
+    /// Pin the node clock; blocks mined afterwards carry this timestamp.
+    pub fn set_mock_time(&self, timestamp: i64) -> Result<(), RpcError> {
+        self.call::<IgnoredAny>("setmocktime", &[RpcParam::Int(timestamp)])?;
+        Ok(())
+    }
+
+    /// Mine `blocks` blocks to a fresh node wallet address, returning their hashes.
+    pub fn generate(&self, blocks: u32) -> Result<Vec<String>, RpcError> {
+        let address: String = self.call("getnewaddress", &[])?;
+        self.call(
+            "generatetoaddress",
+            &[RpcParam::Int(i64::from(blocks)), RpcParam::Str(address)],
+        )
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: &[RpcParam]) -> Result<T, RpcError> {
+        let mut request = minreq::post(&self.config.rpc_url)
+            .with_json(&RpcRequest {
+                jsonrpc: "1.0",
+                id: "simplicity-dex",
+                method,
+                params,
+            })
+            .map_err(|e| RpcError::Request(e.to_string()))?;
+
+        if let Some(user) = &self.config.rpc_user {
+            let password = self.config.rpc_password.as_deref().unwrap_or_default();
+            let credentials = BASE64.encode(format!("{user}:{password}"));
+            request = request.with_header("Authorization", format!("Basic {credentials}"));
+        }
+
+        let response = request.send().map_err(|e| RpcError::Request(e.to_string()))?;
+
+        // The node answers RPC errors with HTTP 500 and a JSON body, so only bail on other statuses
+        if response.status_code != 200 && response.status_code != 500 {
+            return Err(RpcError::Request(format!(
+                "HTTP {}: {}",
+                response.status_code, response.reason_phrase
+            )));
+        }
+
+        let body: RpcResponse<T> = response.json().map_err(|e| RpcError::Deserialize(e.to_string()))?;
+
+        if let Some(error) = body.error {
+            return Err(RpcError::Node {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        body.result
+            .ok_or_else(|| RpcError::Deserialize(format!("Missing result for {method}")))
+    }
+}
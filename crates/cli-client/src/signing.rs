@@ -1,10 +1,136 @@
-use simplicityhl::elements::{AddressParams, Transaction, TxOut};
+use contracts::option_offer::build_witness::OptionOfferBranch;
+use contracts::option_offer::finalize_option_offer_transaction;
+use contracts::options::build_witness::OptionBranch;
+use contracts::options::finalize_options_transaction;
+use simplicityhl::CompiledProgram;
+use simplicityhl::elements::{AddressParams, BlockHash, Sequence, Transaction, TxOut};
+use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
+use simplicityhl::simplicity::jet::Elements;
+use simplicityhl::simplicity::{BitIter, BitMachine, RedeemNode};
 use simplicityhl::tracker::TrackerLogLevel;
-use simplicityhl_core::{LIQUID_TESTNET_GENESIS, finalize_p2pk_transaction};
+use simplicityhl_core::{LIQUID_TESTNET_GENESIS, finalize_p2pk_transaction, get_and_verify_env};
 
+use crate::cli::TrackerLog;
 use crate::error::Error;
 use crate::wallet::Wallet;
 
+impl TrackerLog {
+    /// Tracker level handed to the contract finalizers.
+    #[must_use]
+    pub const fn level(self) -> TrackerLogLevel {
+        match self {
+            Self::Off => TrackerLogLevel::None,
+            Self::Error => TrackerLogLevel::Warning,
+            Self::Trace => TrackerLogLevel::Trace,
+        }
+    }
+}
+
+/// A spending path of an SDK contract, attached to a transaction with that contract's finalizer.
+///
+/// Lets the CLI finalize any contract input through [`finalize_contract_input`] instead of
+/// calling the per-contract SDK function with the matching branch type.
+pub trait ContractBranch {
+    /// Attach the Simplicity program and this branch's witness to input `index` of `tx`.
+    #[allow(clippy::too_many_arguments)]
+    fn finalize(
+        &self,
+        tx: Transaction,
+        taproot_pubkey: &XOnlyPublicKey,
+        program: &CompiledProgram,
+        utxos: &[TxOut],
+        index: usize,
+        params: &'static AddressParams,
+        genesis_hash: BlockHash,
+        log_level: TrackerLogLevel,
+    ) -> Result<Transaction, Error>;
+}
+
+impl ContractBranch for OptionBranch {
+    fn finalize(
+        &self,
+        tx: Transaction,
+        taproot_pubkey: &XOnlyPublicKey,
+        program: &CompiledProgram,
+        utxos: &[TxOut],
+        index: usize,
+        params: &'static AddressParams,
+        genesis_hash: BlockHash,
+        log_level: TrackerLogLevel,
+    ) -> Result<Transaction, Error> {
+        Ok(finalize_options_transaction(
+            tx,
+            taproot_pubkey,
+            program,
+            utxos,
+            index,
+            self,
+            params,
+            genesis_hash,
+            log_level,
+        )?)
+    }
+}
+
+impl ContractBranch for OptionOfferBranch {
+    fn finalize(
+        &self,
+        tx: Transaction,
+        taproot_pubkey: &XOnlyPublicKey,
+        program: &CompiledProgram,
+        utxos: &[TxOut],
+        index: usize,
+        params: &'static AddressParams,
+        genesis_hash: BlockHash,
+        log_level: TrackerLogLevel,
+    ) -> Result<Transaction, Error> {
+        Ok(finalize_option_offer_transaction(
+            tx,
+            taproot_pubkey,
+            program,
+            utxos,
+            index,
+            self,
+            params,
+            genesis_hash,
+            log_level,
+        )?)
+    }
+}
+
+/// Finalize contract input `index` of `tx` on `branch`, whichever contract it belongs to.
+///
+/// # Errors
+///
+/// Returns a [`signer::SignerError`] if `index` or `utxos` do not describe an input of `tx`
+/// (see [`signer::check_spend_inputs`]), or an error if the contract's finalizer rejects the
+/// branch or the input.
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_contract_input(
+    tx: Transaction,
+    taproot_pubkey: &XOnlyPublicKey,
+    program: &CompiledProgram,
+    utxos: &[TxOut],
+    index: usize,
+    branch: &dyn ContractBranch,
+    params: &'static AddressParams,
+    genesis_hash: BlockHash,
+    log_level: TrackerLogLevel,
+) -> Result<Transaction, Error> {
+    signer::check_spend_inputs(&tx, utxos, index)?;
+
+    branch.finalize(
+        tx,
+        taproot_pubkey,
+        program,
+        utxos,
+        index,
+        params,
+        genesis_hash,
+        log_level,
+    )
+}
+
 /// Sign multiple P2PK inputs in a transaction.
 ///
 /// This helper function handles the common pattern of iterating over UTXO inputs,
@@ -32,15 +158,17 @@ pub fn sign_p2pk_inputs(
     params: &'static AddressParams,
     start_index: usize,
 ) -> Result<Transaction, Error> {
-    for i in start_index..utxos.len() {
-        let signature = wallet
-            .signer()
-            .sign_p2pk(&tx, utxos, i, params, *LIQUID_TESTNET_GENESIS)?;
+    // Witnesses are not part of the sighash, so every input can be signed before any is finalized
+    let input_indices: Vec<usize> = (start_index..utxos.len()).collect();
+    let signatures = wallet
+        .signer()?
+        .sign_p2pk_batch(&tx, utxos, &input_indices, params, *LIQUID_TESTNET_GENESIS)?;
 
+    for (i, signature) in signatures {
         tx = finalize_p2pk_transaction(
             tx,
             utxos,
-            &wallet.signer().public_key(),
+            &wallet.public_key(),
             &signature,
             i,
             params,
@@ -51,3 +179,140 @@ pub fn sign_p2pk_inputs(
 
     Ok(tx)
 }
+
+/// Sign a transaction whose inputs are all wallet P2PK inputs.
+///
+/// With `rbf`, every input signals BIP125 replaceability before it is signed, so the
+/// transaction can later be replaced with `helper bump-fee`.
+///
+/// # Errors
+///
+/// Returns an error if signing or finalization fails for any input.
+pub fn sign_wallet_transaction(
+    mut tx: Transaction,
+    utxos: &[TxOut],
+    wallet: &Wallet,
+    params: &'static AddressParams,
+    rbf: bool,
+) -> Result<Transaction, Error> {
+    if rbf {
+        for input in &mut tx.input {
+            input.sequence = Sequence::ENABLE_RBF_NO_LOCKTIME;
+        }
+    }
+
+    sign_p2pk_inputs(tx, utxos, wallet, params, 0)
+}
+
+/// Execute the Simplicity program of a finalized contract input without broadcasting.
+///
+/// The program and its witness are decoded from the input's witness stack and run against
+/// the transaction environment, so this checks exactly what a node would check.
+///
+/// # Arguments
+///
+/// * `tx` - The finalized transaction
+/// * `utxos` - The outputs spent by every input of `tx`, in input order
+/// * `input_index` - The contract input to verify
+/// * `program` - The compiled contract program the input is expected to spend
+/// * `x_only_public_key` - Internal key of the contract's taproot output
+/// * `params` - Address parameters for the network (must be static)
+///
+/// # Errors
+///
+/// Returns an error if the input does not spend `program`, its witness cannot be decoded,
+/// or the program fails to execute.
+pub fn verify_finalized_transaction(
+    tx: &Transaction,
+    utxos: &[TxOut],
+    input_index: usize,
+    program: &CompiledProgram,
+    x_only_public_key: &XOnlyPublicKey,
+    params: &'static AddressParams,
+) -> Result<(), Error> {
+    execute_finalized_input(
+        tx,
+        program,
+        x_only_public_key,
+        utxos,
+        input_index,
+        params,
+        *LIQUID_TESTNET_GENESIS,
+    )?
+    .map_err(|message| Error::Verification {
+        input: input_index,
+        message,
+    })
+}
+
+/// Execute a contract input right after it was finalized for `branch`, before the transaction
+/// reaches the network.
+///
+/// Same check as [`verify_finalized_transaction`], but the error names the branch the witness
+/// was built for, so a wrong exercise or expiry witness is reported as such.
+///
+/// # Errors
+///
+/// Returns [`Error::BranchVerification`] if the witness cannot be decoded or the program fails
+/// to execute, and an environment error if the input does not spend `program`.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_finalized_input(
+    tx: &Transaction,
+    program: &CompiledProgram,
+    x_only_public_key: &XOnlyPublicKey,
+    utxos: &[TxOut],
+    input_index: usize,
+    branch: &str,
+    params: &'static AddressParams,
+    genesis_hash: BlockHash,
+) -> Result<(), Error> {
+    execute_finalized_input(tx, program, x_only_public_key, utxos, input_index, params, genesis_hash)?.map_err(
+        |message| Error::BranchVerification {
+            input: input_index,
+            branch: branch.to_string(),
+            message,
+        },
+    )
+}
+
+/// Run the Simplicity program and witness found in the input's witness stack.
+///
+/// The outer error means the inputs themselves are wrong: a bad input index, a UTXO list that
+/// does not match the inputs, or a spent output not locked to `program` (see
+/// [`signer::check_spend_inputs`]). The inner one describes why the program did not execute.
+fn execute_finalized_input(
+    tx: &Transaction,
+    program: &CompiledProgram,
+    x_only_public_key: &XOnlyPublicKey,
+    utxos: &[TxOut],
+    input_index: usize,
+    params: &'static AddressParams,
+    genesis_hash: BlockHash,
+) -> Result<Result<(), String>, Error> {
+    signer::check_spend_inputs(tx, utxos, input_index)?;
+    let env = get_and_verify_env(tx, program, x_only_public_key, utxos, params, genesis_hash, input_index)
+        .map_err(|source| signer::SignerError::ProgramMismatch { input_index, source })?;
+
+    let stack = &tx.input[input_index].witness.script_witness;
+    let [witness_bytes, program_bytes, ..] = stack.as_slice() else {
+        return Ok(Err("missing Simplicity witness".to_string()));
+    };
+
+    let redeem = match RedeemNode::<Elements>::decode(
+        BitIter::from(program_bytes.as_slice()),
+        BitIter::from(witness_bytes.as_slice()),
+    ) {
+        Ok(redeem) => redeem,
+        Err(e) => return Ok(Err(format!("failed to decode program: {e}"))),
+    };
+
+    let mut machine = match BitMachine::for_program(&redeem) {
+        Ok(machine) => machine,
+        Err(e) => return Ok(Err(format!("program exceeds limits: {e}"))),
+    };
+
+    Ok(machine
+        .exec(&redeem, &env)
+        .map(|_| ())
+        .map_err(|e| format!("execution failed: {e}")))
+}
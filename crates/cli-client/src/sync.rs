@@ -1,15 +1,38 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
 
-use coin_store::{Store, UtxoStore};
+use coin_store::{Store, UtxoFilter, UtxoQueryResult, UtxoStore};
+use contracts::option_offer::OptionOfferArguments;
+use futures::Stream;
 use options_relay::{ActionType, OptionCreatedEvent, OptionOfferCreatedEvent};
+use simplicityhl::elements::secp256k1_zkp::Keypair;
+use simplicityhl::elements::{AssetId, OutPoint, Script, Transaction, Txid};
 use simplicityhl_core::derive_public_blinder_key;
 
 use crate::cli::{GRANTOR_TOKEN_TAG, OPTION_OFFER_COLLATERAL_TAG, OPTION_TOKEN_TAG};
 use crate::error::Error;
-use crate::explorer::fetch_transaction;
+use crate::explorer::{fetch_outspends, fetch_scripthash_utxos, fetch_transaction, run_blocking};
 use crate::metadata::ContractMetadata;
 use crate::metadata::HistoryEntry;
 
+/// Decode stored contract arguments, logging format version mismatches separately from corruption.
+pub fn decode_contract_arguments(args_bytes: &[u8], taproot_pubkey_gen: &str) -> Option<simplicityhl::Arguments> {
+    match coin_store::decode_arguments(args_bytes) {
+        Ok(arguments) => Some(arguments),
+        Err(coin_store::StoreError::ArgumentsVersionMismatch { expected, found }) => {
+            tracing::warn!(
+                "Contract {taproot_pubkey_gen} has arguments in format v{found} (expected v{expected}) and needs migration"
+            );
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Corrupted arguments for contract {taproot_pubkey_gen}: {e}");
+            None
+        }
+    }
+}
+
 pub async fn sync_option_event(
     store: &Store,
     event: &OptionCreatedEvent,
@@ -37,6 +60,7 @@ pub async fn sync_option_event(
             arguments,
             event.taproot_pubkey_gen.clone(),
             Some(&metadata_bytes),
+            None,
         )
         .await?;
 
@@ -126,6 +150,7 @@ pub async fn sync_option_offer_event(
             arguments,
             event.taproot_pubkey_gen.clone(),
             Some(&metadata_bytes),
+            None,
         )
         .await?;
 
@@ -165,15 +190,118 @@ pub async fn update_contract_metadata(
     Ok(())
 }
 
-pub async fn add_history_entry(
+/// Point a stored contract at the NOSTR event it was republished under.
+/// Returns the event ID it was linked to before.
+///
+/// # Errors
+///
+/// Returns an error if the contract is not stored or its metadata cannot be written.
+pub async fn relink_contract_event(
+    store: &Store,
+    taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    event_id: &nostr::EventId,
+    author: &nostr::PublicKey,
+) -> Result<Option<String>, Error> {
+    let mut metadata = get_contract_metadata(store, taproot_pubkey_gen)
+        .await?
+        .ok_or_else(|| Error::Config(format!("Contract {taproot_pubkey_gen} is not stored locally")))?;
+
+    let previous = metadata.relink(event_id.to_hex(), author.to_hex());
+    update_contract_metadata(store, taproot_pubkey_gen, &metadata).await?;
+
+    Ok(previous)
+}
+
+/// Store a transaction made against a contract and append its history entry in one
+/// database transaction, so a failure cannot leave the coins recorded without the entry.
+pub async fn record_interaction(
     store: &Store,
+    tx: &Transaction,
+    out_blinder_keys: HashMap<usize, Keypair>,
     taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
     entry: HistoryEntry,
 ) -> Result<(), Error> {
-    if let Some(mut metadata) = get_contract_metadata(store, taproot_pubkey_gen).await? {
+    record_interaction_with(store, tx, out_blinder_keys, taproot_pubkey_gen, |metadata| {
         metadata.add_history(entry);
+    })
+    .await
+}
+
+/// [`record_interaction`] for a transaction spending an option offer's collateral, e.g. a take,
+/// also recording the collateral the offer has left.
+pub async fn record_option_offer_interaction(
+    store: &Store,
+    tx: &Transaction,
+    out_blinder_keys: HashMap<usize, Keypair>,
+    taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    option_offer_args: &OptionOfferArguments,
+    entry: HistoryEntry,
+) -> Result<(), Error> {
+    let remaining = option_offer_collateral_after(tx, taproot_pubkey_gen, option_offer_args);
+
+    record_interaction_with(store, tx, out_blinder_keys, taproot_pubkey_gen, |metadata| {
+        metadata.add_history(entry);
+        metadata.set_remaining_collateral(remaining);
+    })
+    .await
+}
+
+async fn record_interaction_with(
+    store: &Store,
+    tx: &Transaction,
+    out_blinder_keys: HashMap<usize, Keypair>,
+    taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    update: impl FnOnce(&mut ContractMetadata),
+) -> Result<(), Error> {
+    store
+        .record_interaction(tx, out_blinder_keys, taproot_pubkey_gen, |bytes| {
+            // Same fallback as `get_contract_metadata`: a corrupted record is replaced
+            let mut metadata = ContractMetadata::from_bytes(&bytes?).unwrap_or_default();
+            update(&mut metadata);
+            metadata
+                .to_bytes()
+                .inspect_err(|e| tracing::warn!("Could not encode contract metadata: {e}"))
+                .ok()
+        })
+        .await?;
+    Ok(())
+}
+
+/// Collateral `tx` pays back to the option offer's address, i.e. what the offer holds after it.
+///
+/// Only explicit outputs count; takes keep the collateral change explicit.
+#[must_use]
+pub fn option_offer_collateral_after(
+    tx: &Transaction,
+    taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    option_offer_args: &OptionOfferArguments,
+) -> u64 {
+    let script_pubkey = taproot_pubkey_gen.address.script_pubkey();
+    let collateral_asset = option_offer_args.get_collateral_asset_id();
+
+    tx.output
+        .iter()
+        .filter(|output| output.script_pubkey == script_pubkey && output.asset.explicit() == Some(collateral_asset))
+        .filter_map(|output| output.value.explicit())
+        .sum()
+}
+
+/// Update an option offer's remaining collateral from a take reported by a relay, fetching the
+/// taking transaction. Keeps the listing accurate before the UTXOs themselves are synced.
+pub async fn refresh_remaining_collateral(
+    store: &Store,
+    taproot_pubkey_gen: &contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    option_offer_args: &OptionOfferArguments,
+    take_txid: Txid,
+) -> Result<(), Error> {
+    let tx = run_blocking(move || fetch_transaction(take_txid)).await?;
+    let remaining = option_offer_collateral_after(&tx, taproot_pubkey_gen, option_offer_args);
+
+    if let Some(mut metadata) = get_contract_metadata(store, taproot_pubkey_gen).await? {
+        metadata.set_remaining_collateral(remaining);
         update_contract_metadata(store, taproot_pubkey_gen, &metadata).await?;
     }
+
     Ok(())
 }
 
@@ -194,3 +322,386 @@ pub async fn add_history_entry_if_new(
         Ok(false)
     }
 }
+
+/// Store a transaction, unblinding its confidential outputs with the public blinder key.
+///
+/// Returns how many of its outputs were new; outputs that cannot be unblinded are skipped.
+pub async fn import_transaction(store: &Store, tx: &Transaction) -> Result<usize, Error> {
+    let blinder_keypair = derive_public_blinder_key();
+    let blinder_keys: HashMap<usize, _> = tx
+        .output
+        .iter()
+        .enumerate()
+        .filter(|(_, out)| !out.is_fee())
+        .filter(|(_, out)| out.asset.is_confidential())
+        .map(|(i, _)| (i, blinder_keypair))
+        .collect();
+
+    match store.insert_transaction(tx, blinder_keys).await {
+        Ok(summary) => Ok(summary.inserted.len()),
+        Err(
+            coin_store::StoreError::UtxoAlreadyExists(_)
+            | coin_store::StoreError::MissingBlinderKey(_)
+            | coin_store::StoreError::Unblind(_),
+        ) => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// On-chain wallet activity reported by [`watch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletEvent {
+    /// An output at a tracked script confirmed and is now in the store.
+    Received {
+        outpoint: OutPoint,
+        asset: AssetId,
+        value: u64,
+        height: u32,
+    },
+    /// A stored output was spent on chain and is now marked spent.
+    Spent {
+        outpoint: OutPoint,
+        amount: Option<(AssetId, u64)>,
+    },
+}
+
+/// An output locked to a watched script, as reported by a [`ChainBackend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainUtxo {
+    pub outpoint: OutPoint,
+    /// Confirmation height, `None` while in the mempool.
+    pub height: Option<u32>,
+}
+
+/// Chain data source polled by [`watch`].
+///
+/// Methods may block; [`watch`] calls them on tokio's blocking thread pool.
+pub trait ChainBackend {
+    /// Unspent outputs locked to `script_pubkey`, mempool included.
+    fn script_utxos(&self, script_pubkey: &Script) -> Result<Vec<ChainUtxo>, Error>;
+
+    /// The subset of `outpoints` that is spent, mempool included.
+    fn spent(&self, outpoints: &[OutPoint]) -> Result<HashSet<OutPoint>, Error>;
+
+    fn transaction(&self, txid: Txid) -> Result<Transaction, Error>;
+}
+
+/// [`ChainBackend`] backed by the Esplora API. Esplora has no push notifications, so it is polled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Esplora;
+
+impl ChainBackend for Esplora {
+    fn script_utxos(&self, script_pubkey: &Script) -> Result<Vec<ChainUtxo>, Error> {
+        fetch_scripthash_utxos(script_pubkey)?
+            .iter()
+            .map(|utxo| {
+                Ok(ChainUtxo {
+                    outpoint: crate::explorer::esplora_utxo_to_outpoint(utxo)?,
+                    height: utxo
+                        .status
+                        .block_height
+                        .filter(|_| utxo.status.confirmed)
+                        .and_then(|height| u32::try_from(height).ok()),
+                })
+            })
+            .collect()
+    }
+
+    /// One `outspends` request per transaction rather than per output.
+    fn spent(&self, outpoints: &[OutPoint]) -> Result<HashSet<OutPoint>, Error> {
+        let txids: BTreeSet<Txid> = outpoints.iter().map(|outpoint| outpoint.txid).collect();
+
+        let mut spent = HashSet::new();
+        for txid in txids {
+            let outspends = fetch_outspends(txid)?;
+            spent.extend(outpoints.iter().filter(|outpoint| {
+                outpoint.txid == txid && outspends.get(outpoint.vout as usize).is_some_and(|status| status.spent)
+            }));
+        }
+
+        Ok(spent)
+    }
+
+    fn transaction(&self, txid: Txid) -> Result<Transaction, Error> {
+        Ok(fetch_transaction(txid)?)
+    }
+}
+
+/// Stream confirmed outputs arriving at, and stored outputs leaving, the wallet script and every
+/// tracked contract script.
+///
+/// Polls `backend` every `interval`, the first time immediately. Each event is recorded in the
+/// store (via `insert_transaction`, `set_height` and `mark_as_spent`) before it is yielded, so the
+/// store stays the single source of truth for anything consuming the stream. Outputs are reported
+/// once they confirm; spends as soon as the backend sees them. Failed polls are logged and retried
+/// on the next tick. The stream never ends.
+pub fn watch<B: ChainBackend + Send + Sync + 'static>(
+    store: &Store,
+    backend: B,
+    wallet_script: Script,
+    interval: Duration,
+) -> impl Stream<Item = WalletEvent> + '_ {
+    let watcher = Watcher {
+        store,
+        backend: Arc::new(backend),
+        wallet_script,
+        polled: false,
+        pending: VecDeque::new(),
+        ignored: HashSet::new(),
+    };
+
+    futures::stream::unfold(watcher, move |mut watcher| async move {
+        loop {
+            if let Some(event) = watcher.pending.pop_front() {
+                return Some((event, watcher));
+            }
+
+            if watcher.polled {
+                tokio::time::sleep(interval).await;
+            }
+            watcher.polled = true;
+
+            if let Err(e) = watcher.poll().await {
+                tracing::warn!("Wallet watch poll failed: {e}");
+            }
+        }
+    })
+}
+
+struct Watcher<'a, B> {
+    store: &'a Store,
+    backend: Arc<B>,
+    wallet_script: Script,
+    polled: bool,
+    pending: VecDeque<WalletEvent>,
+    /// Outputs at watched scripts that cannot be stored, e.g. other parties' confidential outputs.
+    ignored: HashSet<OutPoint>,
+}
+
+impl<B: ChainBackend + Send + Sync + 'static> Watcher<'_, B> {
+    /// Run `f` against the backend on the blocking thread pool.
+    async fn query_backend<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&B) -> Result<T, Error> + Send + 'static,
+    ) -> Result<T, Error> {
+        let backend = Arc::clone(&self.backend);
+        run_blocking(move || f(&backend)).await
+    }
+
+    async fn poll(&mut self) -> Result<(), Error> {
+        let mut scripts = vec![self.wallet_script.clone()];
+        scripts.extend(self.store.list_tracked_script_pubkeys().await?);
+
+        let queried = scripts.clone();
+        let on_chain: BTreeMap<OutPoint, Option<u32>> = self
+            .query_backend(move |backend| {
+                let mut on_chain = BTreeMap::new();
+                for script in &queried {
+                    for utxo in backend.script_utxos(script)? {
+                        on_chain.insert(utxo.outpoint, utxo.height);
+                    }
+                }
+                Ok(on_chain)
+            })
+            .await?;
+
+        let stored: BTreeMap<OutPoint, _> = match self
+            .store
+            .query_utxos(&[UtxoFilter::new().script_pubkeys(scripts)])
+            .await?
+            .pop()
+        {
+            Some(UtxoQueryResult::Found(entries, _)) => {
+                entries.into_iter().map(|entry| (*entry.outpoint(), entry)).collect()
+            }
+            _ => BTreeMap::new(),
+        };
+
+        let missing: Vec<OutPoint> = stored
+            .keys()
+            .filter(|outpoint| !on_chain.contains_key(*outpoint))
+            .copied()
+            .collect();
+        let spent = if missing.is_empty() {
+            HashSet::new()
+        } else {
+            self.query_backend(move |backend| backend.spent(&missing)).await?
+        };
+
+        for (outpoint, entry) in &stored {
+            if !spent.contains(outpoint) {
+                continue;
+            }
+
+            if self.store.mark_as_spent(*outpoint).await? {
+                self.pending.push_back(WalletEvent::Spent {
+                    outpoint: *outpoint,
+                    amount: entry.asset().zip(entry.value()),
+                });
+            }
+        }
+
+        for (outpoint, height) in on_chain {
+            let Some(height) = height else {
+                continue;
+            };
+            if self.ignored.contains(&outpoint) {
+                continue;
+            }
+
+            let amount = match stored.get(&outpoint) {
+                Some(entry) if entry.height().is_some() => continue,
+                Some(entry) => entry.asset().zip(entry.value()),
+                // Known, but already marked spent locally
+                None if self.store.get_output_amount(outpoint).await?.is_some() => continue,
+                None => {
+                    let tx = self
+                        .query_backend(move |backend| backend.transaction(outpoint.txid))
+                        .await?;
+                    import_transaction(self.store, &tx).await?;
+                    self.store.get_output_amount(outpoint).await?
+                }
+            };
+
+            let Some((asset, value)) = amount else {
+                self.ignored.insert(outpoint);
+                continue;
+            };
+
+            self.store.set_height(outpoint, height).await?;
+            self.pending.push_back(WalletEvent::Received {
+                outpoint,
+                asset,
+                value,
+                height,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+    use simplicityhl::elements::hashes::Hash;
+    use simplicityhl::elements::{LockTime, TxOut, TxOutWitness};
+
+    use futures::StreamExt;
+
+    struct FakeChain {
+        utxos: Vec<ChainUtxo>,
+        spent: HashSet<OutPoint>,
+        transactions: Vec<Transaction>,
+    }
+
+    impl ChainBackend for FakeChain {
+        fn script_utxos(&self, _script_pubkey: &Script) -> Result<Vec<ChainUtxo>, Error> {
+            Ok(self.utxos.clone())
+        }
+
+        fn spent(&self, outpoints: &[OutPoint]) -> Result<HashSet<OutPoint>, Error> {
+            Ok(outpoints
+                .iter()
+                .filter(|outpoint| self.spent.contains(*outpoint))
+                .copied()
+                .collect())
+        }
+
+        fn transaction(&self, txid: Txid) -> Result<Transaction, Error> {
+            self.transactions
+                .iter()
+                .find(|tx| tx.txid() == txid)
+                .cloned()
+                .ok_or_else(|| Error::Config(format!("unknown transaction {txid}")))
+        }
+    }
+
+    fn explicit_txout(asset: AssetId, value: u64, script_pubkey: &Script) -> TxOut {
+        TxOut {
+            asset: Asset::Explicit(asset),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_reports_spends_and_confirmed_receipts() {
+        let path = "/tmp/test_cli_wallet_watch.db";
+        let _ = std::fs::remove_file(path);
+        let store = Store::create(path).await.unwrap();
+
+        let asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let script_pubkey = Script::from(vec![0x51]);
+
+        let old = OutPoint::new(Txid::from_byte_array([1; 32]), 0);
+        store
+            .insert(old, explicit_txout(asset, 1_000, &script_pubkey), None)
+            .await
+            .unwrap();
+
+        let incoming = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![],
+            output: vec![explicit_txout(asset, 500, &script_pubkey)],
+        };
+        let received = OutPoint::new(incoming.txid(), 0);
+        let unconfirmed = OutPoint::new(Txid::from_byte_array([2; 32]), 0);
+
+        let chain = FakeChain {
+            utxos: vec![
+                ChainUtxo {
+                    outpoint: received,
+                    height: Some(100),
+                },
+                ChainUtxo {
+                    outpoint: unconfirmed,
+                    height: None,
+                },
+            ],
+            spent: HashSet::from([old]),
+            transactions: vec![incoming],
+        };
+
+        let events: Vec<WalletEvent> = watch(&store, chain, script_pubkey.clone(), Duration::from_secs(60))
+            .take(2)
+            .collect()
+            .await;
+
+        assert_eq!(
+            events,
+            vec![
+                WalletEvent::Spent {
+                    outpoint: old,
+                    amount: Some((asset, 1_000)),
+                },
+                WalletEvent::Received {
+                    outpoint: received,
+                    asset,
+                    value: 500,
+                    height: 100,
+                },
+            ]
+        );
+
+        let results = store
+            .query_utxos(&[UtxoFilter::new().script_pubkey(script_pubkey)])
+            .await
+            .unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(*entries[0].outpoint(), received);
+                assert_eq!(entries[0].height(), Some(100));
+            }
+            _ => panic!("Expected only the received output"),
+        }
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,43 +1,112 @@
 use std::path::Path;
 
-use coin_store::Store;
-use signer::Signer;
-use simplicityhl::elements::AddressParams;
+use coin_store::{LockedBalances, Store};
+use signer::{Signer, SigningBackend};
+use simplicityhl::elements::{Address, AddressParams};
+use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
+use simplicityhl_core::get_p2pk_address;
 
 use crate::error::Error;
 
 pub struct Wallet {
-    signer: Signer,
+    /// `None` for a watch-only wallet, which only knows the public key.
+    signer: Option<Box<dyn SigningBackend + Send + Sync>>,
+    public_key: XOnlyPublicKey,
     store: Store,
     params: &'static AddressParams,
 }
 
 impl Wallet {
+    /// Create the wallet database, encrypting it when `db_passphrase` is given.
     pub async fn create(
         seed: &[u8; Signer::SEED_LEN],
         db_path: impl AsRef<Path>,
+        db_passphrase: Option<&str>,
         params: &'static AddressParams,
     ) -> Result<Self, Error> {
         let signer = Signer::from_seed(seed)?;
-        let store = Store::create(db_path).await?;
+        let store = match db_passphrase {
+            None => Store::create(db_path).await?,
+            #[cfg(feature = "sqlcipher")]
+            Some(passphrase) => Store::create_encrypted(db_path, passphrase).await?,
+            #[cfg(not(feature = "sqlcipher"))]
+            Some(_) => return Err(encryption_unsupported()),
+        };
 
-        Ok(Self { signer, store, params })
+        Ok(Self {
+            public_key: signer.public_key(),
+            signer: Some(Box::new(signer)),
+            store,
+            params,
+        })
     }
 
+    /// Open the wallet database, which must have been created with the same `db_passphrase` (or none).
     pub async fn open(
         seed: &[u8; Signer::SEED_LEN],
         db_path: impl AsRef<Path>,
+        db_passphrase: Option<&str>,
         params: &'static AddressParams,
     ) -> Result<Self, Error> {
-        let signer = Signer::from_seed(seed)?;
-        let store = Store::connect(db_path).await?;
+        Self::open_with_backend(Box::new(Signer::from_seed(seed)?), db_path, db_passphrase, params).await
+    }
+
+    /// Open the wallet database with an external signer, e.g. a hardware wallet, in place of the seed.
+    pub async fn open_with_backend(
+        backend: Box<dyn SigningBackend + Send + Sync>,
+        db_path: impl AsRef<Path>,
+        db_passphrase: Option<&str>,
+        params: &'static AddressParams,
+    ) -> Result<Self, Error> {
+        let store = connect_store(db_path, db_passphrase).await?;
+
+        Ok(Self {
+            public_key: backend.public_key(),
+            signer: Some(backend),
+            store,
+            params,
+        })
+    }
+
+    /// Open the wallet database knowing only the wallet's public key. The wallet can check balances,
+    /// list coins and sync, but every operation that needs a signature fails with [`Error::WatchOnly`].
+    pub async fn open_watch_only(
+        public_key: XOnlyPublicKey,
+        db_path: impl AsRef<Path>,
+        db_passphrase: Option<&str>,
+        params: &'static AddressParams,
+    ) -> Result<Self, Error> {
+        let store = connect_store(db_path, db_passphrase).await?;
+
+        Ok(Self {
+            signer: None,
+            public_key,
+            store,
+            params,
+        })
+    }
 
-        Ok(Self { signer, store, params })
+    /// The signer, or [`Error::WatchOnly`] for a watch-only wallet.
+    pub fn signer(&self) -> Result<&dyn SigningBackend, Error> {
+        match &self.signer {
+            Some(signer) => Ok(signer.as_ref()),
+            None => Err(Error::WatchOnly),
+        }
     }
 
     #[must_use]
-    pub const fn signer(&self) -> &Signer {
-        &self.signer
+    pub const fn is_watch_only(&self) -> bool {
+        self.signer.is_none()
+    }
+
+    #[must_use]
+    pub const fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key
+    }
+
+    /// The P2PK address wallet coins are locked to.
+    pub fn p2pk_address(&self) -> Result<Address, Error> {
+        Ok(get_p2pk_address(&self.public_key, self.params)?)
     }
 
     #[must_use]
@@ -49,4 +118,26 @@ impl Wallet {
     pub const fn params(&self) -> &'static AddressParams {
         self.params
     }
+
+    /// Value locked in contracts this wallet owns, per asset and role, as of the Unix timestamp `now`.
+    pub async fn locked_collateral(&self, now: i64) -> Result<LockedBalances, Error> {
+        let wallet_pubkey = self.public_key.serialize();
+
+        Ok(self.store.locked_collateral(wallet_pubkey, now).await?)
+    }
+}
+
+async fn connect_store(db_path: impl AsRef<Path>, db_passphrase: Option<&str>) -> Result<Store, Error> {
+    Ok(match db_passphrase {
+        None => Store::connect(db_path).await?,
+        #[cfg(feature = "sqlcipher")]
+        Some(passphrase) => Store::connect_encrypted(db_path, passphrase).await?,
+        #[cfg(not(feature = "sqlcipher"))]
+        Some(_) => return Err(encryption_unsupported()),
+    })
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+pub(crate) fn encryption_unsupported() -> Error {
+    Error::Config("storage.encrypted requires building with the `sqlcipher` feature".to_string())
 }
@@ -112,7 +112,10 @@ pub async fn setup_db() -> (
         let tpg_for_filter = tpg.clone();
         let tpg_for_token = tpg;
 
-        store.add_contract(source_code, args, tpg_for_db, None).await.unwrap();
+        store
+            .add_contract(source_code, args, tpg_for_db, None, None)
+            .await
+            .unwrap();
 
         let option_asset_id = opts_args.option_token();
 
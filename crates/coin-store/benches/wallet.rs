@@ -0,0 +1,87 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use std::collections::HashMap;
+use std::fs;
+use tokio::runtime::Runtime;
+
+use coin_store::executor::UtxoStore;
+use coin_store::filter::UtxoFilter;
+use coin_store::store::Store;
+
+use simplicityhl::elements::confidential::{Asset, Nonce, Value as ConfidentialValue};
+use simplicityhl::elements::{AssetId, LockTime, Script, Transaction, TxOut, TxOutWitness};
+
+const TRANSACTIONS: usize = 50;
+const OUTPUTS_PER_TRANSACTION: usize = 1000;
+const SCRIPTS: usize = 20;
+const ASSETS: usize = 10;
+
+fn script(index: usize) -> Script {
+    Script::from(vec![0x51, u8::try_from(index).unwrap()])
+}
+
+fn asset(index: usize) -> AssetId {
+    AssetId::from_slice(&[u8::try_from(index + 1).unwrap(); 32]).unwrap()
+}
+
+/// Seed 50k explicit UTXOs spread over several scripts and assets.
+async fn setup_wallet_db() -> (Store, String) {
+    let path = "/tmp/benchmark_wallet.db";
+    let _ = fs::remove_file(path);
+
+    let store = Store::create(path).await.unwrap();
+
+    for tx_index in 0..TRANSACTIONS {
+        let output = (0..OUTPUTS_PER_TRANSACTION)
+            .map(|i| TxOut {
+                asset: Asset::Explicit(asset((tx_index + i) % ASSETS)),
+                value: ConfidentialValue::Explicit(1000 + i as u64),
+                nonce: Nonce::Null,
+                script_pubkey: script(i % SCRIPTS),
+                witness: TxOutWitness::default(),
+            })
+            .collect();
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::from_consensus(u32::try_from(tx_index).unwrap()),
+            input: vec![],
+            output,
+        };
+
+        store.insert_transaction(&tx, HashMap::new()).await.unwrap();
+    }
+
+    (store, path.to_string())
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let (store, db_path) = rt.block_on(async { setup_wallet_db().await });
+
+    let filters: Vec<UtxoFilter> = (0..ASSETS)
+        .map(|i| {
+            UtxoFilter::new()
+                .script_pubkey(script(i % SCRIPTS))
+                .asset_id(asset(i))
+                .required_value(5000)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("UTXO Queries (50k wallet)");
+    group.sample_size(10);
+    group.measurement_time(std::time::Duration::from_secs(10));
+
+    group.bench_function("script_and_asset", |b| {
+        b.to_async(&rt).iter(|| async {
+            store.query_utxos(black_box(&filters)).await.unwrap();
+        })
+    });
+
+    group.finish();
+
+    let _ = fs::remove_file(db_path);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,4 @@
+-- Prefix legacy contract arguments with format version 1 (see `ARGUMENTS_FORMAT_VERSION`).
+UPDATE simplicity_contracts
+SET arguments = CAST(X'01' || arguments AS BLOB)
+WHERE arguments IS NOT NULL;
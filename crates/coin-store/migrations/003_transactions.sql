@@ -0,0 +1,7 @@
+CREATE TABLE transactions
+(
+    txid       BLOB NOT NULL,
+    serialized BLOB NOT NULL,
+
+    PRIMARY KEY (txid)
+);
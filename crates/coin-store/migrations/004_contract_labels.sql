@@ -0,0 +1,3 @@
+ALTER TABLE simplicity_contracts ADD COLUMN label TEXT;
+
+CREATE INDEX idx_contracts_label ON simplicity_contracts (label);
@@ -0,0 +1,4 @@
+-- Contract (taproot pubkey gen) whose UTXO was spent by the transaction that created this wallet output.
+ALTER TABLE utxos ADD COLUMN from_contract TEXT;
+
+CREATE INDEX idx_utxos_from_contract ON utxos (from_contract);
@@ -0,0 +1,2 @@
+-- Wallet queries filter on script, asset and spent state together (balances, coin selection).
+CREATE INDEX idx_utxos_script_asset_spent ON utxos (script_pubkey, asset_id, is_spent);
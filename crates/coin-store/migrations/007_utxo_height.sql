@@ -0,0 +1,2 @@
+-- Block height the output was confirmed at, NULL while unconfirmed or not yet seen by sync.
+ALTER TABLE utxos ADD COLUMN height INTEGER;
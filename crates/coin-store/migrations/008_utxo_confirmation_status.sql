@@ -0,0 +1,3 @@
+-- Whether the transaction that created the output is in a block ('pending' or 'confirmed').
+-- Outputs stored before this column existed were treated as available, so they start confirmed.
+ALTER TABLE utxos ADD COLUMN confirmation_status TEXT NOT NULL DEFAULT 'confirmed';
@@ -0,0 +1,4 @@
+-- Expiry time decoded from the contract arguments, NULL for contracts whose arguments carry none.
+ALTER TABLE simplicity_contracts ADD COLUMN expiry INTEGER;
+
+CREATE INDEX idx_contracts_source_expiry ON simplicity_contracts (source_hash, expiry);
@@ -0,0 +1,7 @@
+-- Order outputs were first stored in, for oldest/newest coin selection. Rowids cannot serve:
+-- VACUUM may renumber them, as the table has no INTEGER PRIMARY KEY.
+ALTER TABLE utxos ADD COLUMN inserted_seq INTEGER;
+
+UPDATE utxos SET inserted_seq = rowid;
+
+CREATE INDEX idx_utxos_inserted_seq ON utxos (inserted_seq);
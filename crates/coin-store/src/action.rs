@@ -0,0 +1,119 @@
+use crate::UtxoEntry;
+
+use contracts::option_offer::OptionOfferArguments;
+use contracts::options::OptionsArguments;
+use simplicityhl::elements::AssetId;
+
+/// Something the wallet can do with one of its contracts, as found by `Store::actionable_contracts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionNeeded {
+    /// Settlement paid into the wallet's option offer by a taker, waiting to be withdrawn.
+    WithdrawOfferSettlement { asset_id: AssetId, amount: u64 },
+    /// Settlement from exercised options, claimable with the wallet's grantor tokens.
+    ClaimOptionSettlement { asset_id: AssetId, amount: u64 },
+    /// Collateral left in the wallet's expired option offer.
+    ReclaimOfferCollateral { asset_id: AssetId, amount: u64 },
+    /// Collateral left in an expired option the wallet holds grantor tokens for.
+    ReclaimOptionCollateral { asset_id: AssetId, amount: u64 },
+}
+
+impl ActionNeeded {
+    /// Lower is more urgent: settlement already earned comes before reclaiming unsold collateral.
+    #[must_use]
+    pub const fn priority(&self) -> u8 {
+        match self {
+            Self::WithdrawOfferSettlement { .. } => 0,
+            Self::ClaimOptionSettlement { .. } => 1,
+            Self::ReclaimOfferCollateral { .. } => 2,
+            Self::ReclaimOptionCollateral { .. } => 3,
+        }
+    }
+
+    #[must_use]
+    pub const fn asset_id(&self) -> AssetId {
+        match self {
+            Self::WithdrawOfferSettlement { asset_id, .. }
+            | Self::ClaimOptionSettlement { asset_id, .. }
+            | Self::ReclaimOfferCollateral { asset_id, .. }
+            | Self::ReclaimOptionCollateral { asset_id, .. } => *asset_id,
+        }
+    }
+
+    #[must_use]
+    pub const fn amount(&self) -> u64 {
+        match self {
+            Self::WithdrawOfferSettlement { amount, .. }
+            | Self::ClaimOptionSettlement { amount, .. }
+            | Self::ReclaimOfferCollateral { amount, .. }
+            | Self::ReclaimOptionCollateral { amount, .. } => *amount,
+        }
+    }
+}
+
+/// Actions on an option offer; only offers made with `wallet_pubkey` are the wallet's to act on.
+pub(crate) fn option_offer_actions(
+    args: &OptionOfferArguments,
+    entries: &[UtxoEntry],
+    wallet_pubkey: [u8; 32],
+    now: i64,
+) -> Vec<ActionNeeded> {
+    if args.user_pubkey() != wallet_pubkey {
+        return Vec::new();
+    }
+
+    let mut actions = Vec::new();
+
+    let settlement = total_of(entries, args.get_settlement_asset_id());
+    if settlement > 0 {
+        actions.push(ActionNeeded::WithdrawOfferSettlement {
+            asset_id: args.get_settlement_asset_id(),
+            amount: settlement,
+        });
+    }
+
+    let collateral = total_of(entries, args.get_collateral_asset_id());
+    if collateral > 0 && now >= i64::from(args.expiry_time()) {
+        actions.push(ActionNeeded::ReclaimOfferCollateral {
+            asset_id: args.get_collateral_asset_id(),
+            amount: collateral,
+        });
+    }
+
+    actions
+}
+
+/// Actions on an option; the wallet acts as grantor only while it holds grantor tokens.
+pub(crate) fn option_actions(args: &OptionsArguments, entries: &[UtxoEntry], now: i64) -> Vec<ActionNeeded> {
+    let (grantor_token_id, _) = args.get_grantor_token_ids();
+    if total_of(entries, grantor_token_id) == 0 {
+        return Vec::new();
+    }
+
+    let mut actions = Vec::new();
+
+    let settlement = total_of(entries, args.get_settlement_asset_id());
+    if settlement > 0 {
+        actions.push(ActionNeeded::ClaimOptionSettlement {
+            asset_id: args.get_settlement_asset_id(),
+            amount: settlement,
+        });
+    }
+
+    let collateral = total_of(entries, args.get_collateral_asset_id());
+    if collateral > 0 && now >= i64::from(args.expiry_time()) {
+        actions.push(ActionNeeded::ReclaimOptionCollateral {
+            asset_id: args.get_collateral_asset_id(),
+            amount: collateral,
+        });
+    }
+
+    actions
+}
+
+pub(crate) fn total_of(entries: &[UtxoEntry], asset_id: AssetId) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.asset() == Some(asset_id))
+        .filter_map(UtxoEntry::value)
+        .fold(0, u64::saturating_add)
+}
@@ -0,0 +1,77 @@
+use simplicityhl::Arguments;
+
+use crate::StoreError;
+
+/// Version byte prefixed to every serialized `Arguments` blob stored in the database.
+/// Bump this whenever the bincode config or the `Arguments` layout changes.
+pub const ARGUMENTS_FORMAT_VERSION: u8 = 1;
+
+/// Serialize contract arguments with a leading format version byte.
+pub fn encode_arguments(arguments: &Arguments) -> Result<Vec<u8>, StoreError> {
+    let mut bytes = vec![ARGUMENTS_FORMAT_VERSION];
+    bytes.extend(bincode::serde::encode_to_vec(arguments, bincode::config::standard())?);
+
+    Ok(bytes)
+}
+
+/// Deserialize contract arguments produced by [`encode_arguments`].
+///
+/// A blob written with a different format version yields [`StoreError::ArgumentsVersionMismatch`],
+/// while a blob with the current version that fails to decode yields [`StoreError::BincodeDecoding`].
+pub fn decode_arguments(bytes: &[u8]) -> Result<Arguments, StoreError> {
+    let Some((&version, payload)) = bytes.split_first() else {
+        return Err(bincode::error::DecodeError::UnexpectedEnd { additional: 1 }.into());
+    };
+
+    if version != ARGUMENTS_FORMAT_VERSION {
+        return Err(StoreError::ArgumentsVersionMismatch {
+            expected: ARGUMENTS_FORMAT_VERSION,
+            found: version,
+        });
+    }
+
+    let (arguments, _): (Arguments, usize) = bincode::serde::decode_from_slice(payload, bincode::config::standard())?;
+
+    Ok(arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arguments_roundtrip() {
+        let arguments = Arguments::default();
+
+        let bytes = encode_arguments(&arguments).unwrap();
+        assert_eq!(bytes[0], ARGUMENTS_FORMAT_VERSION);
+
+        let decoded = decode_arguments(&bytes).unwrap();
+        assert_eq!(
+            bincode::serde::encode_to_vec(&decoded, bincode::config::standard()).unwrap(),
+            bytes[1..]
+        );
+    }
+
+    #[test]
+    fn test_arguments_version_mismatch() {
+        let mut bytes = encode_arguments(&Arguments::default()).unwrap();
+        bytes[0] = ARGUMENTS_FORMAT_VERSION + 1;
+
+        let result = decode_arguments(&bytes);
+        assert!(matches!(
+            result,
+            Err(StoreError::ArgumentsVersionMismatch { expected, found })
+                if expected == ARGUMENTS_FORMAT_VERSION && found == ARGUMENTS_FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_arguments_corruption() {
+        assert!(matches!(decode_arguments(&[]), Err(StoreError::BincodeDecoding(_))));
+        assert!(matches!(
+            decode_arguments(&[ARGUMENTS_FORMAT_VERSION, 0xff, 0xff]),
+            Err(StoreError::BincodeDecoding(_))
+        ));
+    }
+}
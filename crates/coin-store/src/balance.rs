@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use crate::UtxoEntry;
+use crate::action::total_of;
+
+use contracts::option_offer::OptionOfferArguments;
+use contracts::options::OptionsArguments;
+use simplicityhl::elements::AssetId;
+
+/// Per-asset balance split by where the funds sit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetailedBalance {
+    /// Unspent outputs paying directly to the wallet script.
+    pub spendable: u64,
+    /// Unspent outputs locked at the wallet's contract addresses.
+    pub in_contracts: u64,
+}
+
+impl DetailedBalance {
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.spendable.saturating_add(self.in_contracts)
+    }
+}
+
+/// What a locked amount is held for in the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockedRole {
+    /// Collateral backing an offer or an option.
+    Collateral,
+    /// Premium deposited alongside the collateral of an option offer.
+    Premium,
+}
+
+impl LockedRole {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Collateral => "collateral",
+            Self::Premium => "premium",
+        }
+    }
+}
+
+/// Value the wallet has committed to its own contracts, as found by `Store::locked_collateral`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockedCollateral {
+    /// Still on offer: the contract has not expired and a taker or option holder can claim it.
+    pub offered: u64,
+    /// The contract has expired; the wallet can reclaim it.
+    pub reclaimable: u64,
+}
+
+impl LockedCollateral {
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.offered.saturating_add(self.reclaimable)
+    }
+
+    fn add(&mut self, amount: u64, expired: bool) {
+        if expired {
+            self.reclaimable = self.reclaimable.saturating_add(amount);
+        } else {
+            self.offered = self.offered.saturating_add(amount);
+        }
+    }
+}
+
+/// Locked amounts keyed by asset and by what they are held for.
+pub type LockedBalances = BTreeMap<(AssetId, LockedRole), LockedCollateral>;
+
+/// Collateral and premium locked in an option offer made with `wallet_pubkey`.
+///
+/// Premium is released in proportion to the collateral taken, so the locked premium is derived from
+/// the collateral still held. Settlement paid in by a taker is not counted, even in the premium
+/// asset: it is the wallet's to withdraw right away.
+pub(crate) fn option_offer_locked(
+    locked: &mut LockedBalances,
+    args: &OptionOfferArguments,
+    entries: &[UtxoEntry],
+    wallet_pubkey: [u8; 32],
+    now: i64,
+) {
+    if args.user_pubkey() != wallet_pubkey {
+        return;
+    }
+
+    let collateral_asset = args.get_collateral_asset_id();
+    let premium_asset = args.get_premium_asset_id();
+    let premium_per_collateral = args.premium_per_collateral();
+
+    let collateral_held = total_of(entries, collateral_asset);
+    let (collateral, premium) = if premium_asset == collateral_asset {
+        // Both sit in the same outputs: `collateral_held` is the collateral plus its premium
+        let collateral = collateral_held / premium_per_collateral.saturating_add(1);
+        (collateral, collateral.saturating_mul(premium_per_collateral))
+    } else {
+        let premium_cap = collateral_held.saturating_mul(premium_per_collateral);
+        (collateral_held, total_of(entries, premium_asset).min(premium_cap))
+    };
+
+    let expired = now >= i64::from(args.expiry_time());
+    for (key, amount) in [
+        ((collateral_asset, LockedRole::Collateral), collateral),
+        ((premium_asset, LockedRole::Premium), premium),
+    ] {
+        if amount > 0 {
+            locked.entry(key).or_default().add(amount, expired);
+        }
+    }
+}
+
+/// Collateral locked in an option the wallet holds grantor tokens for.
+pub(crate) fn option_locked(locked: &mut LockedBalances, args: &OptionsArguments, entries: &[UtxoEntry], now: i64) {
+    let (grantor_token_id, _) = args.get_grantor_token_ids();
+    if total_of(entries, grantor_token_id) == 0 {
+        return;
+    }
+
+    let amount = total_of(entries, args.get_collateral_asset_id());
+    if amount > 0 {
+        let expired = now >= i64::from(args.expiry_time());
+        locked
+            .entry((args.get_collateral_asset_id(), LockedRole::Collateral))
+            .or_default()
+            .add(amount, expired);
+    }
+}
@@ -0,0 +1,25 @@
+/// A stored contract as returned by aggregate contract queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractRow {
+    /// Versioned, encoded contract arguments (see `decode_arguments`).
+    pub arguments: Vec<u8>,
+    /// Taproot pubkey gen string identifying the contract.
+    pub taproot_pubkey_gen: String,
+    /// Application metadata stored alongside the contract.
+    pub app_metadata: Option<Vec<u8>>,
+    /// Optional user label the contract was stored with.
+    pub label: Option<String>,
+}
+
+/// A contract to store with `UtxoStore::add_contracts`, together with its token associations.
+#[derive(Clone)]
+pub struct NewContract {
+    /// Simplicity source the contract was compiled from.
+    pub source: String,
+    pub arguments: simplicityhl::Arguments,
+    pub taproot_pubkey_gen: contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen,
+    pub app_metadata: Option<Vec<u8>>,
+    pub label: Option<String>,
+    /// Token assets mapped to the contract, with their tags (see `insert_contract_token`).
+    pub tokens: Vec<(simplicityhl::elements::AssetId, String)>,
+}
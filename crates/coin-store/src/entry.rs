@@ -3,14 +3,26 @@ use std::collections::hash_map::Entry;
 use std::sync::Arc;
 
 use sha2::{Digest, Sha256};
-use simplicityhl::elements::hashes::sha256;
+use simplicityhl::elements::hashes::{Hash, sha256};
 use simplicityhl::elements::issuance::AssetId as IssuanceAssetId;
 use simplicityhl::elements::{AssetId, OutPoint, TxOut, TxOutSecrets};
 use simplicityhl::{Arguments, CompiledProgram};
 
 use crate::StoreError;
+use crate::arguments::decode_arguments;
 use crate::executor::UtxoRow;
 
+/// Compile a contract source, keeping the compiler's full diagnostic (including source location) on failure.
+pub fn compile_program(source: &str, arguments: Arguments) -> Result<CompiledProgram, StoreError> {
+    CompiledProgram::new(source, arguments, false).map_err(|message| {
+        let source_hash = sha256::Hash::hash(source.as_bytes());
+        StoreError::SimplicityCompilation {
+            source_hash: source_hash.to_string(),
+            message,
+        }
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct ContractContext {
     programs: HashMap<[u8; 32], Arc<CompiledProgram>>,
@@ -38,8 +50,12 @@ impl ContractContext {
         let source_str =
             String::from_utf8(source_bytes.clone()).map_err(|_| sqlx::Error::Decode("Invalid source UTF-8".into()))?;
 
-        let (arguments, _): (Arguments, usize) =
-            bincode::serde::decode_from_slice(args_bytes, bincode::config::standard())?;
+        let arguments = match decode_arguments(args_bytes) {
+            Ok(arguments) => arguments,
+            Err(e @ StoreError::ArgumentsVersionMismatch { .. }) => return Err(e),
+            // Rows with corrupt arguments are skipped by `UtxoRow::into_entry_or_skip`
+            Err(_) => return Ok(self),
+        };
 
         self.add_program(source_str, arguments)
     }
@@ -47,7 +63,7 @@ impl ContractContext {
     pub fn add_program(mut self, source: String, arguments: Arguments) -> Result<Self, StoreError> {
         let key = Self::build_key(&source, &arguments)?;
 
-        let program = CompiledProgram::new(source, arguments, false).map_err(StoreError::SimplicityCompilation)?;
+        let program = compile_program(&source, arguments)?;
 
         if let Entry::Vacant(v) = self.programs.entry(key) {
             v.insert(Arc::new(program));
@@ -64,8 +80,7 @@ impl ContractContext {
         let source_str =
             String::from_utf8(source_bytes.clone()).map_err(|_| sqlx::Error::Decode("Invalid source UTF-8".into()))?;
 
-        let (arguments, _): (Arguments, usize) =
-            bincode::serde::decode_from_slice(args_bytes, bincode::config::standard())?;
+        let arguments = decode_arguments(args_bytes)?;
 
         self.get_program(&source_str, &arguments)
     }
@@ -90,6 +105,31 @@ impl ContractContext {
     }
 }
 
+/// Whether the transaction that created an output has been seen in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationStatus {
+    /// Stored from a broadcast or mempool transaction; may still be evicted.
+    Pending,
+    Confirmed,
+}
+
+impl ConfirmationStatus {
+    pub(crate) const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Confirmed => "confirmed",
+        }
+    }
+
+    pub(crate) fn from_sql(value: &str) -> Self {
+        if value == "pending" {
+            Self::Pending
+        } else {
+            Self::Confirmed
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UtxoEntry {
     outpoint: OutPoint,
@@ -100,6 +140,8 @@ pub struct UtxoEntry {
     is_confidential: Option<bool>,
     taproot_pubkey_gen: Option<String>,
     arguments: Option<Arguments>,
+    height: Option<u32>,
+    confirmation_status: ConfirmationStatus,
 }
 
 impl UtxoEntry {
@@ -114,6 +156,8 @@ impl UtxoEntry {
             is_confidential: None,
             taproot_pubkey_gen: None,
             arguments: None,
+            height: None,
+            confirmation_status: ConfirmationStatus::Confirmed,
         }
     }
 
@@ -128,6 +172,8 @@ impl UtxoEntry {
             is_confidential: None,
             taproot_pubkey_gen: None,
             arguments: None,
+            height: None,
+            confirmation_status: ConfirmationStatus::Confirmed,
         }
     }
 
@@ -156,6 +202,18 @@ impl UtxoEntry {
         self
     }
 
+    #[must_use]
+    pub const fn with_height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_confirmation_status(mut self, status: ConfirmationStatus) -> Self {
+        self.confirmation_status = status;
+        self
+    }
+
     #[must_use]
     pub const fn outpoint(&self) -> &OutPoint {
         &self.outpoint
@@ -166,6 +224,18 @@ impl UtxoEntry {
         &self.txout
     }
 
+    /// Cloned `(outpoint, txout)` tuple, as taken by the SDK transaction builders.
+    #[must_use]
+    pub fn as_input(&self) -> (OutPoint, TxOut) {
+        (self.outpoint, self.txout.clone())
+    }
+
+    /// Like [`Self::as_input`], also carrying the unblinding secrets of a confidential output.
+    #[must_use]
+    pub fn as_input_with_secrets(&self) -> (OutPoint, TxOut, Option<TxOutSecrets>) {
+        (self.outpoint, self.txout.clone(), self.secrets)
+    }
+
     #[must_use]
     pub fn asset(&self) -> Option<AssetId> {
         self.secrets
@@ -227,6 +297,31 @@ impl UtxoEntry {
     pub const fn arguments(&self) -> Option<&Arguments> {
         self.arguments.as_ref()
     }
+
+    /// Block height the output was confirmed at, if sync has recorded it.
+    #[must_use]
+    pub const fn height(&self) -> Option<u32> {
+        self.height
+    }
+
+    #[must_use]
+    pub const fn confirmation_status(&self) -> ConfirmationStatus {
+        self.confirmation_status
+    }
+
+    #[must_use]
+    pub const fn is_confirmed(&self) -> bool {
+        matches!(self.confirmation_status, ConfirmationStatus::Confirmed)
+    }
+
+    /// Number of confirmations at the given chain tip; 0 while unconfirmed.
+    #[must_use]
+    pub const fn confirmations(&self, tip_height: u32) -> u32 {
+        match self.height {
+            Some(height) if height <= tip_height => tip_height - height + 1,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -1,6 +1,6 @@
 use simplicityhl::elements::hashes::FromSliceError;
 use simplicityhl::elements::secp256k1_zkp::UpstreamError;
-use simplicityhl::elements::{OutPoint, UnblindError};
+use simplicityhl::elements::{AssetId, OutPoint, UnblindError};
 use std::path::PathBuf;
 
 #[derive(thiserror::Error, Debug)]
@@ -20,6 +20,16 @@ pub enum StoreError {
     #[error("UTXO not found: {0}")]
     UtxoNotFound(OutPoint),
 
+    #[error("No UTXOs found for asset {asset}")]
+    NoCoins { asset: AssetId },
+
+    #[error("Insufficient {asset}: need {required}, have {available}")]
+    InsufficientCoins {
+        asset: AssetId,
+        required: u64,
+        available: u64,
+    },
+
     #[error("Missing blinder key for confidential output: {0}")]
     MissingBlinderKey(OutPoint),
 
@@ -35,6 +45,9 @@ pub enum StoreError {
     #[error("Bincode decoding error, err: {0}")]
     BincodeDecoding(#[from] bincode::error::DecodeError),
 
+    #[error("Arguments format version mismatch: expected {expected}, found {found}")]
+    ArgumentsVersionMismatch { expected: u8, found: u8 },
+
     #[error("Invalid secret key, err: {0}")]
     InvalidSecretKey(#[from] UpstreamError),
 
@@ -50,12 +63,18 @@ pub enum StoreError {
     #[error("Value overflow during calculation")]
     ValueOverflow,
 
-    #[error("Simplicity compilation error: {0}")]
-    SimplicityCompilation(String),
+    #[error("Simplicity compilation error in source {source_hash}:\n{message}")]
+    SimplicityCompilation { source_hash: String, message: String },
 
     #[error("Hash slice error: {0}")]
     HashSlice(#[from] FromSliceError),
 
     #[error("Invalid asset ID")]
     InvalidAssetId,
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid backup archive: {0}")]
+    InvalidBackup(String),
 }
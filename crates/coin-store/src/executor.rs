@@ -1,20 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
-use crate::entry::{ContractContext, UtxoEntry};
-use crate::{Store, StoreError, UtxoFilter, UtxoQueryResult};
+use crate::action::{option_actions, option_offer_actions};
+use crate::arguments::{decode_arguments, encode_arguments};
+use crate::balance::{LockedBalances, option_locked, option_offer_locked};
+use crate::entry::{ConfirmationStatus, ContractContext, UtxoEntry, compile_program};
+use crate::selection;
+use crate::{
+    ActionNeeded, ContractRow, DetailedBalance, InsertSummary, NewContract, RollbackSummary, Store, StoreError,
+    UtxoFilter, UtxoQueryResult,
+};
 
 use futures::future::try_join_all;
 
+use contracts::option_offer::{OPTION_OFFER_SOURCE, OptionOfferArguments};
+use contracts::options::{OPTION_SOURCE, OptionsArguments};
 use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
 
+use simplicityhl::Arguments;
 use simplicityhl::elements::encode;
 use simplicityhl::elements::hashes::{Hash, sha256};
 use simplicityhl::elements::hex::ToHex;
 use simplicityhl::elements::issuance::{AssetId as IssuanceAssetId, ContractHash};
 use simplicityhl::elements::secp256k1_zkp::{self as secp256k1, Keypair, SecretKey, ZERO_TWEAK};
-use simplicityhl::elements::{AssetId, OutPoint, Transaction, TxOut, TxOutWitness, Txid};
-use simplicityhl::{Arguments, CompiledProgram};
+use simplicityhl::elements::{AssetId, OutPoint, Script, Transaction, TxOut, TxOutSecrets, TxOutWitness, Txid};
 
 use sqlx::{QueryBuilder, Sqlite};
 
@@ -31,16 +40,56 @@ pub trait UtxoStore {
 
     async fn mark_as_spent(&self, prev_outpoint: OutPoint) -> Result<bool, Self::Error>;
 
+    /// Inverse of `mark_as_spent`, for when the spending transaction was evicted or reorged out.
+    /// Returns whether a spent output was found.
+    async fn mark_as_unspent(&self, outpoint: OutPoint) -> Result<bool, Self::Error>;
+
+    /// Delete an output and its blinder key, returning whether it was stored.
+    async fn remove_utxo(&self, outpoint: OutPoint) -> Result<bool, Self::Error>;
+
+    /// Undo `insert_transaction` for a transaction that was evicted from the mempool or reorged out.
+    ///
+    /// Marks the stored outputs it spent as unspent, deletes the outputs it created with their
+    /// blinder keys, and forgets the recorded transaction, all in one database transaction.
+    /// Inputs can only be restored if the transaction was recorded by `insert_transaction`.
+    /// Transactions spending its outputs are not rolled back; roll those back first.
+    async fn rollback_transaction(&self, txid: Txid) -> Result<RollbackSummary, Self::Error>;
+
+    /// Run each filter, returning one result per filter in the same order.
+    ///
+    /// Entries within a result follow the filter's `UtxoOrder`; value orderings break ties by
+    /// txid then vout, so equal-valued outputs always come back in the same order.
     async fn query_utxos(&self, filters: &[UtxoFilter]) -> Result<Vec<UtxoQueryResult>, Self::Error>;
 
+    /// One page of the outputs matching `filter`, and whether more follow it.
+    ///
+    /// Pages follow the filter's `UtxoOrder`, which should be deterministic (not `Random`) to page
+    /// through a set. The filter's own `limit`, `required_value` and `selection_strategy` are ignored.
+    async fn query_utxos_paged(
+        &self,
+        filter: &UtxoFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<UtxoEntry>, bool), Self::Error>;
+
+    /// Record a contract and its source in one database transaction, so a cancelled call
+    /// never leaves the source stored without the contract.
+    ///
+    /// Idempotent per `taproot_pubkey_gen`: adding a known contract again keeps the stored row,
+    /// including its metadata and label.
     async fn add_contract(
         &self,
         source: &str,
         arguments: Arguments,
         taproot_pubkey_gen: TaprootPubkeyGen,
         app_metadata: Option<&[u8]>,
+        label: Option<&str>,
     ) -> Result<(), Self::Error>;
 
+    /// Record several contracts, their sources and token associations in one database
+    /// transaction: either all of them are stored or none is.
+    async fn add_contracts(&self, contracts: &[NewContract]) -> Result<(), Self::Error>;
+
     async fn get_contract_metadata(
         &self,
         taproot_pubkey_gen: &TaprootPubkeyGen,
@@ -59,6 +108,10 @@ pub trait UtxoStore {
         script_pubkey: &simplicityhl::elements::Script,
     ) -> Result<Option<(Vec<u8>, Vec<u8>, String)>, Self::Error>;
 
+    /// Find the contract whose address holds `outpoint`, e.g. the funding output announced with it.
+    /// Returns the `taproot_pubkey_gen` string; `None` if the output is not stored or not at a contract.
+    async fn get_contract_by_outpoint(&self, outpoint: OutPoint) -> Result<Option<String>, Self::Error>;
+
     /// List all contracts matching a source.
     /// Returns a list of (`arguments_bytes`, `taproot_pubkey_gen_string`) tuples.
     async fn list_contracts_by_source(&self, source: &str) -> Result<Vec<(Vec<u8>, String)>, Self::Error>;
@@ -70,6 +123,25 @@ pub trait UtxoStore {
         source: &str,
     ) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Self::Error>;
 
+    /// List contracts matching a source, keeping only expired (`Some(true)`) or unexpired
+    /// (`Some(false)`) ones, judged by the expiry stored with the contract and the current time.
+    ///
+    /// Contracts without a stored expiry count as unexpired. `None` lists every contract.
+    /// Returns a list of (`arguments_bytes`, `taproot_pubkey_gen_string`, `app_metadata`) tuples.
+    async fn list_contracts_by_source_filtered(
+        &self,
+        source: &str,
+        expired: Option<bool>,
+    ) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Self::Error>;
+
+    /// List contracts matching a source that were stored with the given label.
+    /// Returns a list of (`arguments_bytes`, `taproot_pubkey_gen_string`, `app_metadata`) tuples.
+    async fn list_contracts_by_label(
+        &self,
+        source: &str,
+        label: &str,
+    ) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Self::Error>;
+
     /// Process a transaction by inserting its outputs and marking inputs as spent.
     ///
     /// # Arguments
@@ -77,12 +149,36 @@ pub trait UtxoStore {
     /// * `out_blinder_keys` - Map from output index to keypair for unblinding.
     ///   Outputs not in the map are attempted as explicit; unblind failures are skipped.
     ///
-    /// Also inserts asset entropy entries for any inputs with new issuances.
+    /// Also inserts asset entropy entries for any inputs with new issuances, and tags
+    /// non-contract outputs with the contract whose UTXO the transaction spent.
+    ///
+    /// Inserting the same transaction again is harmless; the returned summary tells what changed.
+    ///
+    /// All writes happen in one database transaction. The call is cancellation safe: if the
+    /// future is dropped before the commit completes, the transaction is rolled back and no
+    /// partial rows persist.
     async fn insert_transaction(
         &self,
         tx: &Transaction,
         out_blinder_keys: HashMap<usize, Keypair>,
-    ) -> Result<(), Self::Error>;
+    ) -> Result<InsertSummary, Self::Error>;
+
+    /// `insert_transaction` plus a rewrite of the metadata of the contract it interacted with,
+    /// committed together.
+    ///
+    /// `update_metadata` receives the contract's stored metadata and returns the bytes to store,
+    /// or `None` to leave it unchanged. It runs inside the same database transaction as the
+    /// insert, so concurrent interactions with one contract cannot overwrite each other's
+    /// updates. If the contract is not stored, only the transaction is inserted.
+    async fn record_interaction<F>(
+        &self,
+        tx: &Transaction,
+        out_blinder_keys: HashMap<usize, Keypair>,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        update_metadata: F,
+    ) -> Result<InsertSummary, Self::Error>
+    where
+        F: FnOnce(Option<Vec<u8>>) -> Option<Vec<u8>> + Send;
 
     /// List all unspent outpoints in the store.
     /// Returns a list of (txid, vout) tuples for UTXOs where `is_spent` = 0.
@@ -108,6 +204,12 @@ pub trait UtxoStore {
     /// List all asset IDs with a specific tag (e.g., "`option_token`").
     /// Returns a list of (`asset_id`, `taproot_pubkey_gen`) tuples.
     async fn list_tokens_by_tag(&self, tag: &str) -> Result<Vec<(AssetId, String)>, Self::Error>;
+
+    /// Get a full transaction previously recorded via `insert_transaction`.
+    async fn get_transaction(&self, txid: Txid) -> Result<Option<Transaction>, Self::Error>;
+
+    /// Get the (unblinded) asset and value of a stored output, spent or not.
+    async fn get_output_amount(&self, outpoint: OutPoint) -> Result<Option<(AssetId, u64)>, Self::Error>;
 }
 
 #[async_trait::async_trait]
@@ -131,7 +233,8 @@ impl UtxoStore for Store {
 
         let tx: sqlx::Transaction<'_, Sqlite> = self.pool.begin().await?;
 
-        self.internal_utxo_insert(tx, outpoint, txout, blinder_key).await
+        self.internal_utxo_insert(tx, outpoint, txout, blinder_key, ConfirmationStatus::Pending)
+            .await
     }
 
     async fn mark_as_spent(&self, prev_outpoint: OutPoint) -> Result<bool, Self::Error> {
@@ -147,48 +250,151 @@ impl UtxoStore for Store {
         Ok(result.rows_affected() > 0)
     }
 
+    async fn mark_as_unspent(&self, outpoint: OutPoint) -> Result<bool, Self::Error> {
+        let txid: &[u8] = outpoint.txid.as_ref();
+
+        let result = sqlx::query("UPDATE utxos SET is_spent = 0 WHERE txid = ? AND vout = ? AND is_spent = 1")
+            .bind(txid)
+            .bind(i64::from(outpoint.vout))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove_utxo(&self, outpoint: OutPoint) -> Result<bool, Self::Error> {
+        let mut db_tx = self.pool.begin().await?;
+
+        let removed = Self::internal_utxo_remove_with_tx(&mut db_tx, outpoint).await?;
+
+        db_tx.commit().await?;
+
+        Ok(removed)
+    }
+
+    async fn rollback_transaction(&self, txid: Txid) -> Result<RollbackSummary, Self::Error> {
+        let txid_bytes: &[u8] = txid.as_ref();
+        let mut db_tx = self.pool.begin().await?;
+        let mut summary = RollbackSummary::default();
+
+        let row: Option<(Vec<u8>,)> = sqlx::query_as("SELECT serialized FROM transactions WHERE txid = ?")
+            .bind(txid_bytes)
+            .fetch_optional(&mut *db_tx)
+            .await?;
+
+        if let Some((bytes,)) = row {
+            let tx: Transaction = encode::deserialize(&bytes)?;
+
+            for input in &tx.input {
+                let prev_txid: &[u8] = input.previous_output.txid.as_ref();
+
+                let unspent = sqlx::query("UPDATE utxos SET is_spent = 0 WHERE txid = ? AND vout = ? AND is_spent = 1")
+                    .bind(prev_txid)
+                    .bind(i64::from(input.previous_output.vout))
+                    .execute(&mut *db_tx)
+                    .await?;
+                if unspent.rows_affected() > 0 {
+                    summary.unspent.push(input.previous_output);
+                }
+            }
+        }
+
+        let vouts: Vec<(i64,)> = sqlx::query_as("SELECT vout FROM utxos WHERE txid = ? ORDER BY vout")
+            .bind(txid_bytes)
+            .fetch_all(&mut *db_tx)
+            .await?;
+
+        for (vout,) in vouts {
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            let outpoint = OutPoint::new(txid, vout as u32);
+            Self::internal_utxo_remove_with_tx(&mut db_tx, outpoint).await?;
+            summary.removed.push(outpoint);
+        }
+
+        sqlx::query("DELETE FROM transactions WHERE txid = ?")
+            .bind(txid_bytes)
+            .execute(&mut *db_tx)
+            .await?;
+
+        db_tx.commit().await?;
+
+        Ok(summary)
+    }
+
     async fn query_utxos(&self, filters: &[UtxoFilter]) -> Result<Vec<UtxoQueryResult>, Self::Error> {
         let futures: Vec<_> = filters.iter().map(|f| self.query_all_filter_utxos(f)).collect();
 
         try_join_all(futures).await
     }
 
+    async fn query_utxos_paged(
+        &self,
+        filter: &UtxoFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<UtxoEntry>, bool), Self::Error> {
+        // One row past the page tells whether another page exists
+        let (mut rows, context) = self
+            .fetch_utxo_rows(filter, Some(i64::from(limit) + 1), Some(i64::from(offset)))
+            .await?;
+
+        let has_more = rows.len() > limit as usize;
+        rows.truncate(limit as usize);
+
+        let entries = rows
+            .into_iter()
+            .filter_map(|row| row.into_entry_or_skip(&context).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((entries, has_more))
+    }
+
     async fn add_contract(
         &self,
         source: &str,
         arguments: Arguments,
         taproot_pubkey_gen: TaprootPubkeyGen,
         app_metadata: Option<&[u8]>,
+        label: Option<&str>,
     ) -> Result<(), Self::Error> {
-        let compiled_program =
-            CompiledProgram::new(source, arguments.clone(), false).map_err(StoreError::SimplicityCompilation)?;
-        let cmr = compiled_program.commit().cmr();
+        let mut db_tx = self.pool.begin().await?;
 
-        let script_pubkey = taproot_pubkey_gen.address.script_pubkey();
-        let taproot_gen_str = taproot_pubkey_gen.to_string();
-        let arguments_bytes = bincode::serde::encode_to_vec(&arguments, bincode::config::standard())?;
+        Self::internal_add_contract_with_tx(&mut db_tx, source, arguments, &taproot_pubkey_gen, app_metadata, label)
+            .await?;
 
-        let source_hash = sha256::Hash::hash(source.as_bytes());
-        let source_hash_bytes: &[u8] = source_hash.as_ref();
+        db_tx.commit().await?;
 
-        sqlx::query("INSERT OR IGNORE INTO simplicity_sources (source_hash, source) VALUES (?, ?)")
-            .bind(source_hash_bytes)
-            .bind(source.as_bytes())
-            .execute(&self.pool)
+        Ok(())
+    }
+
+    async fn add_contracts(&self, contracts: &[NewContract]) -> Result<(), Self::Error> {
+        let mut db_tx = self.pool.begin().await?;
+
+        for contract in contracts {
+            Self::internal_add_contract_with_tx(
+                &mut db_tx,
+                &contract.source,
+                contract.arguments.clone(),
+                &contract.taproot_pubkey_gen,
+                contract.app_metadata.as_deref(),
+                contract.label.as_deref(),
+            )
             .await?;
 
-        sqlx::query(
-            "INSERT INTO simplicity_contracts (script_pubkey, taproot_pubkey_gen, cmr, source_hash, arguments, app_metadata)
-             VALUES (?, ?, ?, ?, ?, ?)",
-        )
-        .bind(script_pubkey.as_bytes())
-        .bind(taproot_gen_str)
-        .bind(cmr.as_ref())
-        .bind(source_hash_bytes)
-        .bind(arguments_bytes)
-        .bind(app_metadata)
-        .execute(&self.pool)
-        .await?;
+            let taproot_gen_str = contract.taproot_pubkey_gen.to_string();
+            for (asset_id, tag) in &contract.tokens {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO contract_tokens (taproot_pubkey_gen, asset_id, tag) VALUES (?, ?, ?)",
+                )
+                .bind(&taproot_gen_str)
+                .bind(asset_id.to_hex())
+                .bind(tag)
+                .execute(&mut *db_tx)
+                .await?;
+            }
+        }
+
+        db_tx.commit().await?;
 
         Ok(())
     }
@@ -242,6 +448,22 @@ impl UtxoStore for Store {
         }
     }
 
+    async fn get_contract_by_outpoint(&self, outpoint: OutPoint) -> Result<Option<String>, Self::Error> {
+        let txid: &[u8] = outpoint.txid.as_ref();
+
+        let result: Option<(String,)> = sqlx::query_as(
+            "SELECT c.taproot_pubkey_gen FROM utxos u
+             JOIN simplicity_contracts c ON c.script_pubkey = u.script_pubkey
+             WHERE u.txid = ? AND u.vout = ?",
+        )
+        .bind(txid)
+        .bind(i64::from(outpoint.vout))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|(tpg,)| tpg))
+    }
+
     async fn list_contracts_by_source(&self, source: &str) -> Result<Vec<(Vec<u8>, String)>, Self::Error> {
         let source_hash = sha256::Hash::hash(source.as_bytes());
         let source_hash_bytes: &[u8] = source_hash.as_ref();
@@ -272,68 +494,125 @@ impl UtxoStore for Store {
         Ok(results)
     }
 
+    async fn list_contracts_by_source_filtered(
+        &self,
+        source: &str,
+        expired: Option<bool>,
+    ) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Self::Error> {
+        let source_hash = sha256::Hash::hash(source.as_bytes());
+        let source_hash_bytes: &[u8] = source_hash.as_ref();
+
+        // Matches `is_offer_expired`: a contract is expired once the current time is past its expiry.
+        // Rows with a NULL expiry are always fetched and decoded below, in case the backfill missed them.
+        let expiry_condition = match expired {
+            None => "",
+            Some(true) => " AND (expiry IS NULL OR expiry < ?)",
+            Some(false) => " AND (expiry IS NULL OR expiry >= ?)",
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX));
+
+        let sql = format!(
+            "SELECT arguments, taproot_pubkey_gen, app_metadata, expiry FROM simplicity_contracts WHERE source_hash = ?{expiry_condition}"
+        );
+        let mut query =
+            sqlx::query_as::<_, (Vec<u8>, String, Option<Vec<u8>>, Option<i64>)>(&sql).bind(source_hash_bytes);
+        if expired.is_some() {
+            query = query.bind(now);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+
+        let results = rows
+            .into_iter()
+            .filter(|(arguments, _, _, expiry)| {
+                let Some(expired) = expired else {
+                    return true;
+                };
+                // A contract without an expiry counts as unexpired
+                let is_expired =
+                    Self::stored_contract_expiry(source, arguments, *expiry).is_some_and(|expiry| expiry < now);
+
+                is_expired == expired
+            })
+            .map(|(arguments, taproot_pubkey_gen, app_metadata, _)| (arguments, taproot_pubkey_gen, app_metadata))
+            .collect();
+
+        Ok(results)
+    }
+
+    async fn list_contracts_by_label(
+        &self,
+        source: &str,
+        label: &str,
+    ) -> Result<Vec<(Vec<u8>, String, Option<Vec<u8>>)>, Self::Error> {
+        let source_hash = sha256::Hash::hash(source.as_bytes());
+        let source_hash_bytes: &[u8] = source_hash.as_ref();
+
+        let results: Vec<(Vec<u8>, String, Option<Vec<u8>>)> = sqlx::query_as(
+            "SELECT arguments, taproot_pubkey_gen, app_metadata FROM simplicity_contracts
+             WHERE source_hash = ? AND label = ?",
+        )
+        .bind(source_hash_bytes)
+        .bind(label)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results)
+    }
+
     async fn insert_transaction(
         &self,
         tx: &Transaction,
         out_blinder_keys: HashMap<usize, Keypair>,
-    ) -> Result<(), Self::Error> {
-        let txid = tx.txid();
+    ) -> Result<InsertSummary, Self::Error> {
         let mut db_tx = self.pool.begin().await?;
+        let summary = self
+            .internal_insert_transaction_with_tx(&mut db_tx, tx, &out_blinder_keys)
+            .await?;
+        db_tx.commit().await?;
 
-        for input in &tx.input {
-            let prev_txid: &[u8] = input.previous_output.txid.as_ref();
-            let prev_vout = i64::from(input.previous_output.vout);
+        Ok(summary)
+    }
 
-            sqlx::query("UPDATE utxos SET is_spent = 1 WHERE txid = ? AND vout = ?")
-                .bind(prev_txid)
-                .bind(prev_vout)
-                .execute(&mut *db_tx)
-                .await?;
+    async fn record_interaction<F>(
+        &self,
+        tx: &Transaction,
+        out_blinder_keys: HashMap<usize, Keypair>,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        update_metadata: F,
+    ) -> Result<InsertSummary, Self::Error>
+    where
+        F: FnOnce(Option<Vec<u8>>) -> Option<Vec<u8>> + Send,
+    {
+        let taproot_gen_str = taproot_pubkey_gen.to_string();
 
-            if input.has_issuance() && input.asset_issuance.asset_blinding_nonce == ZERO_TWEAK {
-                let contract_hash = ContractHash::from_byte_array(input.asset_issuance.asset_entropy);
-                let entropy = IssuanceAssetId::generate_asset_entropy(input.previous_output, contract_hash);
-                let asset_id = IssuanceAssetId::from_entropy(entropy);
-                let is_confidential = input.asset_issuance.amount.is_confidential();
+        let mut db_tx = self.pool.begin().await?;
+        let summary = self
+            .internal_insert_transaction_with_tx(&mut db_tx, tx, &out_blinder_keys)
+            .await?;
 
-                sqlx::query(
-                    "INSERT OR IGNORE INTO asset_entropy (asset_id, issuance_is_confidential, entropy) VALUES (?, ?, ?)",
-                )
-                .bind(asset_id.to_hex())
-                .bind(is_confidential)
-                .bind(entropy.as_ref())
-                .execute(&mut *db_tx)
+        // The insert above already holds the write lock, so nothing can change the metadata in between
+        let stored: Option<(Option<Vec<u8>>,)> =
+            sqlx::query_as("SELECT app_metadata FROM simplicity_contracts WHERE taproot_pubkey_gen = ?")
+                .bind(&taproot_gen_str)
+                .fetch_optional(&mut *db_tx)
                 .await?;
-            }
-        }
-
-        for (vout, txout) in tx.output.iter().enumerate() {
-            if txout.is_fee() {
-                continue;
-            }
-
-            #[allow(clippy::cast_possible_truncation)]
-            let outpoint = OutPoint::new(txid, vout as u32);
-            let blinder_key = out_blinder_keys.get(&vout);
-
-            let blinder_key_bytes = blinder_key.map(|kp| kp.secret_key().secret_bytes());
 
-            if let Err(e) = self
-                .internal_utxo_insert_with_tx(&mut db_tx, outpoint, txout.clone(), blinder_key_bytes)
-                .await
-            {
-                match e {
-                    // Skip outputs we can't unblind - the blinder key may not work for this output
-                    // (e.g., outputs belonging to other parties in the same transaction)
-                    StoreError::MissingBlinderKey(_) | StoreError::Unblind(_) => {}
-                    _ => return Err(e),
-                }
-            }
+        if let Some((metadata,)) = stored
+            && let Some(updated) = update_metadata(metadata)
+        {
+            sqlx::query("UPDATE simplicity_contracts SET app_metadata = ? WHERE taproot_pubkey_gen = ?")
+                .bind(updated)
+                .bind(&taproot_gen_str)
+                .execute(&mut *db_tx)
+                .await?;
         }
 
         db_tx.commit().await?;
 
-        Ok(())
+        Ok(summary)
     }
 
     async fn list_unspent_outpoints(&self) -> Result<Vec<OutPoint>, Self::Error> {
@@ -413,6 +692,39 @@ impl UtxoStore for Store {
 
         Ok(results)
     }
+
+    async fn get_transaction(&self, txid: Txid) -> Result<Option<Transaction>, Self::Error> {
+        let row: Option<(Vec<u8>,)> = sqlx::query_as("SELECT serialized FROM transactions WHERE txid = ?")
+            .bind(AsRef::<[u8]>::as_ref(&txid))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|(bytes,)| encode::deserialize(&bytes))
+            .transpose()
+            .map_err(StoreError::from)
+    }
+
+    async fn get_output_amount(&self, outpoint: OutPoint) -> Result<Option<(AssetId, u64)>, Self::Error> {
+        let txid: &[u8] = outpoint.txid.as_ref();
+
+        let row: Option<(String, i64)> =
+            sqlx::query_as("SELECT asset_id, value FROM utxos WHERE txid = ? AND vout = ?")
+                .bind(txid)
+                .bind(i64::from(outpoint.vout))
+                .fetch_optional(&self.pool)
+                .await?;
+
+        let Some((asset_id_hex, value)) = row else {
+            return Ok(None);
+        };
+
+        let asset_id = asset_id_hex
+            .parse::<AssetId>()
+            .map_err(|_| StoreError::InvalidAssetId)?;
+        let value = u64::try_from(value).map_err(|_| StoreError::ValueOverflow)?;
+
+        Ok(Some((asset_id, value)))
+    }
 }
 
 impl Store {
@@ -446,8 +758,9 @@ impl Store {
         outpoint: OutPoint,
         txout: TxOut,
         blinder_key: Option<[u8; crate::store::BLINDING_KEY_LEN]>,
+        status: ConfirmationStatus,
     ) -> Result<(), StoreError> {
-        self.internal_utxo_insert_with_tx(&mut tx, outpoint, txout, blinder_key)
+        self.internal_utxo_insert_with_tx(&mut tx, outpoint, txout, blinder_key, status)
             .await?;
 
         tx.commit().await?;
@@ -455,31 +768,246 @@ impl Store {
         Ok(())
     }
 
-    async fn internal_utxo_insert_with_tx(
-        &self,
-        tx: &mut sqlx::Transaction<'_, Sqlite>,
-        outpoint: OutPoint,
-        txout: TxOut,
-        blinder_key: Option<[u8; crate::store::BLINDING_KEY_LEN]>,
-    ) -> Result<(), StoreError> {
-        let (asset_id, value, is_confidential) = Self::unblind_or_explicit(&outpoint, &txout, blinder_key)?;
+    /// Expiry time carried by the arguments of the bundled contracts, `None` for other sources.
+    fn contract_expiry(source: &str, arguments: &Arguments) -> Option<u32> {
+        if source == OPTION_OFFER_SOURCE {
+            OptionOfferArguments::from_arguments(arguments)
+                .ok()
+                .map(|args| args.expiry_time())
+        } else if source == OPTION_SOURCE {
+            OptionsArguments::from_arguments(arguments)
+                .ok()
+                .map(|args| args.expiry_time())
+        } else {
+            None
+        }
+    }
 
-        let txid: &[u8] = outpoint.txid.as_ref();
-        let vout = i64::from(outpoint.vout);
+    /// Expiry of a stored contract row, decoded from its arguments when the column is NULL.
+    ///
+    /// The column is empty for rows written before migration 009 whose arguments could not be
+    /// decoded by [`Store::backfill_contract_expiry`].
+    fn stored_contract_expiry(source: &str, arguments: &[u8], expiry: Option<i64>) -> Option<i64> {
+        expiry.or_else(|| {
+            let arguments = decode_arguments(arguments).ok()?;
+            Self::contract_expiry(source, &arguments).map(i64::from)
+        })
+    }
 
-        sqlx::query(
-            "INSERT OR IGNORE INTO utxos (txid, vout, script_pubkey, asset_id, value, serialized, serialized_witness, is_confidential)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    /// Fill the `expiry` column of contracts stored before it existed.
+    ///
+    /// The expiry lives inside the encoded arguments, so the migration alone cannot set it.
+    /// Rows of other sources, or whose arguments fail to decode, keep a NULL expiry.
+    pub(crate) async fn backfill_contract_expiry(&self) -> Result<(), StoreError> {
+        let rows: Vec<(String, Vec<u8>, Vec<u8>)> = sqlx::query_as(
+            "SELECT c.taproot_pubkey_gen, s.source, c.arguments
+             FROM simplicity_contracts c
+             JOIN simplicity_sources s ON s.source_hash = c.source_hash
+             WHERE c.expiry IS NULL",
         )
-        .bind(txid)
-        .bind(vout)
-        .bind(txout.script_pubkey.as_bytes())
-        .bind(asset_id.to_hex())
-        .bind(value)
-        .bind(encode::serialize(&txout))
-        .bind(encode::serialize(&txout.witness))
-        .bind(i64::from(is_confidential))
-        .execute(&mut **tx)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut db_tx = self.pool.begin().await?;
+
+        for (taproot_pubkey_gen, source, arguments) in rows {
+            let Ok(source) = std::str::from_utf8(&source) else {
+                continue;
+            };
+            let Some(expiry) = Self::stored_contract_expiry(source, &arguments, None) else {
+                continue;
+            };
+
+            sqlx::query("UPDATE simplicity_contracts SET expiry = ? WHERE taproot_pubkey_gen = ?")
+                .bind(expiry)
+                .bind(taproot_pubkey_gen)
+                .execute(&mut *db_tx)
+                .await?;
+        }
+
+        db_tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn internal_add_contract_with_tx(
+        db_tx: &mut sqlx::Transaction<'_, Sqlite>,
+        source: &str,
+        arguments: Arguments,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+        app_metadata: Option<&[u8]>,
+        label: Option<&str>,
+    ) -> Result<(), StoreError> {
+        let compiled_program = compile_program(source, arguments.clone())?;
+        let cmr = compiled_program.commit().cmr();
+
+        let script_pubkey = taproot_pubkey_gen.address.script_pubkey();
+        let taproot_gen_str = taproot_pubkey_gen.to_string();
+        let arguments_bytes = encode_arguments(&arguments)?;
+        let expiry = Self::contract_expiry(source, &arguments);
+
+        let source_hash = sha256::Hash::hash(source.as_bytes());
+        let source_hash_bytes: &[u8] = source_hash.as_ref();
+
+        sqlx::query("INSERT OR IGNORE INTO simplicity_sources (source_hash, source) VALUES (?, ?)")
+            .bind(source_hash_bytes)
+            .bind(source.as_bytes())
+            .execute(&mut **db_tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO simplicity_contracts (script_pubkey, taproot_pubkey_gen, cmr, source_hash, arguments, app_metadata, label, expiry)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(taproot_pubkey_gen) DO NOTHING",
+        )
+        .bind(script_pubkey.as_bytes())
+        .bind(taproot_gen_str)
+        .bind(cmr.as_ref())
+        .bind(source_hash_bytes)
+        .bind(arguments_bytes)
+        .bind(app_metadata)
+        .bind(label)
+        .bind(expiry)
+        .execute(&mut **db_tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn internal_insert_transaction_with_tx(
+        &self,
+        db_tx: &mut sqlx::Transaction<'_, Sqlite>,
+        tx: &Transaction,
+        out_blinder_keys: &HashMap<usize, Keypair>,
+    ) -> Result<InsertSummary, StoreError> {
+        let txid = tx.txid();
+        let mut summary = InsertSummary::default();
+
+        sqlx::query("INSERT OR IGNORE INTO transactions (txid, serialized) VALUES (?, ?)")
+            .bind(AsRef::<[u8]>::as_ref(&txid))
+            .bind(encode::serialize(tx))
+            .execute(&mut **db_tx)
+            .await?;
+
+        let mut from_contract: Option<String> = None;
+
+        for input in &tx.input {
+            let prev_txid: &[u8] = input.previous_output.txid.as_ref();
+            let prev_vout = i64::from(input.previous_output.vout);
+
+            if from_contract.is_none() {
+                let row: Option<(String,)> = sqlx::query_as(
+                    "SELECT c.taproot_pubkey_gen FROM utxos u
+                     INNER JOIN simplicity_contracts c ON u.script_pubkey = c.script_pubkey
+                     WHERE u.txid = ? AND u.vout = ?",
+                )
+                .bind(prev_txid)
+                .bind(prev_vout)
+                .fetch_optional(&mut **db_tx)
+                .await?;
+                from_contract = row.map(|(tpg,)| tpg);
+            }
+
+            let spent = sqlx::query("UPDATE utxos SET is_spent = 1 WHERE txid = ? AND vout = ? AND is_spent = 0")
+                .bind(prev_txid)
+                .bind(prev_vout)
+                .execute(&mut **db_tx)
+                .await?;
+            if spent.rows_affected() > 0 {
+                summary.spent.push(input.previous_output);
+            }
+
+            if input.has_issuance() && input.asset_issuance.asset_blinding_nonce == ZERO_TWEAK {
+                let contract_hash = ContractHash::from_byte_array(input.asset_issuance.asset_entropy);
+                let entropy = IssuanceAssetId::generate_asset_entropy(input.previous_output, contract_hash);
+                let asset_id = IssuanceAssetId::from_entropy(entropy);
+                let is_confidential = input.asset_issuance.amount.is_confidential();
+
+                sqlx::query(
+                    "INSERT OR IGNORE INTO asset_entropy (asset_id, issuance_is_confidential, entropy) VALUES (?, ?, ?)",
+                )
+                .bind(asset_id.to_hex())
+                .bind(is_confidential)
+                .bind(entropy.as_ref())
+                .execute(&mut **db_tx)
+                .await?;
+            }
+        }
+
+        for (vout, txout) in tx.output.iter().enumerate() {
+            if txout.is_fee() {
+                continue;
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let outpoint = OutPoint::new(txid, vout as u32);
+            let blinder_key = out_blinder_keys.get(&vout);
+
+            let blinder_key_bytes = blinder_key.map(|kp| kp.secret_key().secret_bytes());
+
+            match self
+                .internal_utxo_insert_with_tx(
+                    db_tx,
+                    outpoint,
+                    txout.clone(),
+                    blinder_key_bytes,
+                    ConfirmationStatus::Pending,
+                )
+                .await
+            {
+                Ok(true) => summary.inserted.push(outpoint),
+                Ok(false) => summary.skipped_duplicate.push(outpoint),
+                // Skip outputs we can't unblind - the blinder key may not work for this output
+                // (e.g., outputs belonging to other parties in the same transaction)
+                Err(StoreError::MissingBlinderKey(_) | StoreError::Unblind(_)) => {
+                    summary.skipped_unblindable.push(outpoint);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Tag non-contract outputs (our change) with the contract the transaction interacted with
+        if let Some(tpg) = from_contract {
+            sqlx::query(
+                "UPDATE utxos SET from_contract = ?
+                 WHERE txid = ? AND script_pubkey NOT IN (SELECT script_pubkey FROM simplicity_contracts)",
+            )
+            .bind(tpg)
+            .bind(AsRef::<[u8]>::as_ref(&txid))
+            .execute(&mut **db_tx)
+            .await?;
+        }
+
+        Ok(summary)
+    }
+
+    async fn internal_utxo_insert_with_tx(
+        &self,
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        outpoint: OutPoint,
+        txout: TxOut,
+        blinder_key: Option<[u8; crate::store::BLINDING_KEY_LEN]>,
+        status: ConfirmationStatus,
+    ) -> Result<bool, StoreError> {
+        let (asset_id, value, is_confidential) = Self::unblind_or_explicit(&outpoint, &txout, blinder_key)?;
+
+        let txid: &[u8] = outpoint.txid.as_ref();
+        let vout = i64::from(outpoint.vout);
+
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO utxos (txid, vout, script_pubkey, asset_id, value, serialized, serialized_witness, is_confidential, confirmation_status, inserted_seq)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(inserted_seq), 0) + 1 FROM utxos))",
+        )
+        .bind(txid)
+        .bind(vout)
+        .bind(txout.script_pubkey.as_bytes())
+        .bind(asset_id.to_hex())
+        .bind(value)
+        .bind(encode::serialize(&txout))
+        .bind(encode::serialize(&txout.witness))
+        .bind(i64::from(is_confidential))
+        .bind(status.as_sql())
+        .execute(&mut **tx)
         .await?;
 
         if let Some(key) = blinder_key {
@@ -491,7 +1019,30 @@ impl Store {
                 .await?;
         }
 
-        Ok(())
+        Ok(inserted.rows_affected() > 0)
+    }
+
+    async fn internal_utxo_remove_with_tx(
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        outpoint: OutPoint,
+    ) -> Result<bool, StoreError> {
+        let txid: &[u8] = outpoint.txid.as_ref();
+        let vout = i64::from(outpoint.vout);
+
+        // The blinder key references the output, so it goes first
+        sqlx::query("DELETE FROM blinder_keys WHERE txid = ? AND vout = ?")
+            .bind(txid)
+            .bind(vout)
+            .execute(&mut **tx)
+            .await?;
+
+        let removed = sqlx::query("DELETE FROM utxos WHERE txid = ? AND vout = ?")
+            .bind(txid)
+            .bind(vout)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(removed.rows_affected() > 0)
     }
 
     async fn does_outpoint_exist(&self, tx_id: &[u8], vout: i64) -> Result<bool, StoreError> {
@@ -519,7 +1070,8 @@ impl Store {
         let needs_contract_join = filter.is_contract_join();
 
         let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
-            "SELECT u.txid, u.vout, u.serialized, u.serialized_witness, u.is_confidential, u.value, b.blinding_key",
+            "SELECT u.txid, u.vout, u.serialized, u.serialized_witness, u.is_confidential, u.is_spent, u.value, u.height,
+                    u.confirmation_status, b.blinding_key",
         );
 
         if needs_contract_join {
@@ -558,6 +1110,11 @@ impl Store {
             builder.push(" AND u.is_spent = 0");
         }
 
+        if filter.confirmed_only {
+            builder.push(" AND u.confirmation_status = ");
+            builder.push_bind(ConfirmationStatus::Confirmed.as_sql());
+        }
+
         if let Some(ref asset_id) = filter.asset_id {
             builder.push(" AND u.asset_id = ");
             builder.push_bind(asset_id.to_hex());
@@ -568,6 +1125,19 @@ impl Store {
             builder.push_bind(script.as_bytes().to_vec());
         }
 
+        if let Some(ref scripts) = filter.script_pubkeys {
+            if scripts.is_empty() {
+                builder.push(" AND 0");
+            } else {
+                builder.push(" AND u.script_pubkey IN (");
+                let mut separated = builder.separated(", ");
+                for script in scripts {
+                    separated.push_bind(script.as_bytes().to_vec());
+                }
+                separated.push_unseparated(")");
+            }
+        }
+
         if let Some(ref token_tag) = filter.token_tag {
             builder.push(" AND ct.tag = ");
             builder.push_bind(token_tag.clone());
@@ -588,7 +1158,22 @@ impl Store {
             builder.push_bind(source_hash.to_vec());
         }
 
-        builder.push(" ORDER BY u.value DESC");
+        if let Some(ref label) = filter.label {
+            builder.push(" AND c.label = ");
+            builder.push_bind(label.clone());
+        }
+
+        builder.push(" ORDER BY ");
+        if let Some(ref tpg) = filter.prefer_from_contract {
+            builder.push("COALESCE(u.from_contract = ");
+            builder.push_bind(tpg.to_string());
+            builder.push(", 0) DESC, ");
+        }
+        builder.push(filter.order.as_sql());
+        if let Some(tiebreak) = filter.order.tiebreak_sql() {
+            builder.push(", ");
+            builder.push(tiebreak);
+        }
 
         if let Some(limit) = limit {
             builder.push(" LIMIT ");
@@ -618,12 +1203,30 @@ impl Store {
             return Ok(UtxoQueryResult::Empty);
         }
 
+        let rows = match (filter.selection_strategy, filter.required_value) {
+            (Some(strategy), Some(required)) => {
+                let values: Vec<u64> = rows.iter().map(|row| row.value).collect();
+                match selection::select(&values, required, strategy) {
+                    Some(selected) => {
+                        let mut rows: Vec<Option<UtxoRow>> = rows.into_iter().map(Some).collect();
+                        selected.into_iter().filter_map(|index| rows[index].take()).collect()
+                    }
+                    // Not enough value either way; report every candidate as before
+                    None => rows,
+                }
+            }
+            _ => rows,
+        };
+
         let mut entries = Vec::with_capacity(rows.len());
         let mut total_value: u64 = 0;
 
         for row in rows {
-            total_value = total_value.saturating_add(row.value);
-            entries.push(row.into_entry(&context)?);
+            let value = row.value;
+            if let Some(entry) = row.into_entry_or_skip(&context)? {
+                total_value = total_value.saturating_add(value);
+                entries.push(entry);
+            }
         }
 
         if filter.required_value.is_some_and(|required| total_value < required) {
@@ -634,92 +1237,441 @@ impl Store {
     }
 }
 
-#[derive(sqlx::FromRow)]
-pub struct UtxoRow {
-    txid: Vec<u8>,
-    vout: u32,
-    serialized: Vec<u8>,
-    serialized_witness: Option<Vec<u8>>,
-    is_confidential: i64,
-    value: u64,
-    blinding_key: Option<Vec<u8>>,
-    pub source: Option<Vec<u8>>,
-    pub arguments: Option<Vec<u8>>,
-    pub taproot_pubkey_gen: Option<String>,
-    pub entropy: Option<Vec<u8>>,
-    pub issuance_is_confidential: Option<i64>,
-}
+impl Store {
+    /// Unblind a stored output with the given key, returning the unblind error instead of skipping it.
+    pub async fn force_unblind(
+        &self,
+        outpoint: OutPoint,
+        blinder_key: [u8; crate::store::BLINDING_KEY_LEN],
+    ) -> Result<TxOutSecrets, StoreError> {
+        let txid: &[u8] = outpoint.txid.as_ref();
 
-impl UtxoRow {
-    fn into_entry(self, context: &ContractContext) -> Result<UtxoEntry, StoreError> {
-        let contract = context.get_program_from_row(&self)?;
+        let row: Option<(Vec<u8>, Option<Vec<u8>>)> =
+            sqlx::query_as("SELECT serialized, serialized_witness FROM utxos WHERE txid = ? AND vout = ?")
+                .bind(txid)
+                .bind(i64::from(outpoint.vout))
+                .fetch_optional(&self.pool)
+                .await?;
 
-        let entropy: Option<sha256::Midstate> = self
-            .entropy
-            .as_ref()
-            .map(|e| sha256::Midstate::from_slice(e))
-            .transpose()?;
+        let Some((serialized, serialized_witness)) = row else {
+            return Err(StoreError::UtxoNotFound(outpoint));
+        };
 
-        let issuance_is_confidential: Option<bool> = self.issuance_is_confidential.map(|v| v != 0);
+        let mut txout: TxOut = encode::deserialize(&serialized)?;
 
-        let txid_array: [u8; Txid::LEN] = self
-            .txid
-            .try_into()
-            .map_err(|_| sqlx::Error::Decode("Invalid txid length".into()))?;
+        if let Some(witness) = serialized_witness {
+            txout.witness = encode::deserialize(&witness)?;
+        }
 
-        let txid = Txid::from_byte_array(txid_array);
-        let outpoint = OutPoint::new(txid, self.vout);
-        let mut txout: TxOut = encode::deserialize(&self.serialized)?;
+        let secret_key = SecretKey::from_slice(&blinder_key)?;
 
-        // Parse arguments from row if present
-        let arguments: Option<Arguments> = self.arguments.as_ref().and_then(|args_bytes| {
-            bincode::serde::decode_from_slice(args_bytes, bincode::config::standard())
-                .ok()
-                .map(|(args, _)| args)
-        });
+        Ok(txout.unblind(secp256k1::SECP256K1, secret_key)?)
+    }
 
-        if self.is_confidential != 1 {
-            let mut entry = UtxoEntry::new_explicit(outpoint, txout);
+    /// Get the blinding key stored for an output, if one was recorded on insert.
+    pub async fn get_blinder_key(
+        &self,
+        outpoint: OutPoint,
+    ) -> Result<Option<[u8; crate::store::BLINDING_KEY_LEN]>, StoreError> {
+        let txid: &[u8] = outpoint.txid.as_ref();
 
-            if let Some(c) = contract {
-                entry = entry.with_contract(Arc::clone(c));
-            }
-            if let Some((e, c)) = entropy.zip(issuance_is_confidential) {
-                entry = entry.with_issuance(e, c);
-            }
-            if let Some(tpg) = self.taproot_pubkey_gen {
-                entry = entry.with_taproot_pubkey_gen(tpg);
-            }
-            if let Some(args) = arguments {
-                entry = entry.with_arguments(args);
-            }
+        let row: Option<(Vec<u8>,)> =
+            sqlx::query_as("SELECT blinding_key FROM blinder_keys WHERE txid = ? AND vout = ?")
+                .bind(txid)
+                .bind(i64::from(outpoint.vout))
+                .fetch_optional(&self.pool)
+                .await?;
 
-            return Ok(entry);
-        }
+        row.map(|(key,)| {
+            key.try_into()
+                .map_err(|_| sqlx::Error::Decode("Invalid blinding key length".into()).into())
+        })
+        .transpose()
+    }
 
-        let key_bytes: [u8; crate::store::BLINDING_KEY_LEN] = self
-            .blinding_key
-            .ok_or_else(|| sqlx::Error::Decode("Missing blinding key for confidential output".into()))?
-            .try_into()
-            .map_err(|_| sqlx::Error::Decode("Invalid blinding key length".into()))?;
+    /// Record the block height an output was confirmed at, promoting it from pending to confirmed.
+    /// Returns `false` if the output is not in the store.
+    pub async fn set_height(&self, outpoint: OutPoint, height: u32) -> Result<bool, StoreError> {
+        let txid: &[u8] = outpoint.txid.as_ref();
 
-        let serialized_witness = self
-            .serialized_witness
-            .as_ref()
-            .ok_or(StoreError::MissingSerializedTxOutWitness(outpoint))?;
+        let result = sqlx::query("UPDATE utxos SET height = ?, confirmation_status = ? WHERE txid = ? AND vout = ?")
+            .bind(i64::from(height))
+            .bind(ConfirmationStatus::Confirmed.as_sql())
+            .bind(txid)
+            .bind(i64::from(outpoint.vout))
+            .execute(&self.pool)
+            .await?;
 
-        let deserialized_witness: TxOutWitness = encode::deserialize(serialized_witness)?;
-        txout.witness = deserialized_witness;
+        Ok(result.rows_affected() > 0)
+    }
 
-        let secret_key = SecretKey::from_slice(&key_bytes)?;
-        let secrets = txout.unblind(secp256k1::SECP256K1, secret_key)?;
+    /// List every output a contract has produced, spent or not.
+    /// Returns (entry, `is_spent`) pairs.
+    pub async fn contract_activity(
+        &self,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+    ) -> Result<Vec<(UtxoEntry, bool)>, StoreError> {
+        let filter = UtxoFilter::new()
+            .taproot_pubkey_gen(taproot_pubkey_gen.clone())
+            .include_spent();
+
+        let (rows, context) = self.fetch_utxo_rows(&filter, None, None).await?;
+
+        rows.into_iter()
+            .filter_map(|row| {
+                let is_spent = row.is_spent != 0;
+                row.into_entry_or_skip(&context)
+                    .map(|entry| entry.map(|entry| (entry, is_spent)))
+                    .transpose()
+            })
+            .collect()
+    }
 
-        let mut entry = UtxoEntry::new_confidential(outpoint, txout, secrets);
+    /// List every contract of a source together with its unspent outputs, in a single query.
+    ///
+    /// A contract's outputs are those locked at its script plus those holding one of its tokens.
+    /// Contracts without any unspent output are returned with an empty list.
+    pub async fn contracts_with_utxos(&self, source: &str) -> Result<Vec<(ContractRow, Vec<UtxoEntry>)>, StoreError> {
+        let source_hash = sha256::Hash::hash(source.as_bytes());
+        let source_hash_bytes: &[u8] = source_hash.as_ref();
 
-        if let Some(c) = contract {
-            entry = entry.with_contract(Arc::clone(c));
-        }
-        if let Some((e, c)) = entropy.zip(issuance_is_confidential) {
+        let rows: Vec<ContractUtxoRow> = sqlx::query_as(
+            "SELECT c.taproot_pubkey_gen, c.arguments, c.app_metadata, c.label, s.source,
+                    u.txid, u.vout, u.serialized, u.serialized_witness, u.is_confidential, u.is_spent, u.value,
+                    u.height, u.confirmation_status, b.blinding_key
+             FROM simplicity_contracts c
+             INNER JOIN simplicity_sources s ON c.source_hash = s.source_hash
+             LEFT JOIN utxos u ON u.is_spent = 0
+                 AND (u.script_pubkey = c.script_pubkey
+                      OR u.asset_id IN (SELECT ct.asset_id FROM contract_tokens ct
+                                        WHERE ct.taproot_pubkey_gen = c.taproot_pubkey_gen))
+             LEFT JOIN blinder_keys b ON u.txid = b.txid AND u.vout = b.vout
+             WHERE c.source_hash = ?
+             ORDER BY c.taproot_pubkey_gen, u.value DESC, u.txid, u.vout",
+        )
+        .bind(source_hash_bytes)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut contracts: Vec<(ContractRow, Vec<UtxoEntry>)> = Vec::new();
+        let mut context = ContractContext::new();
+
+        for row in rows {
+            let is_new_contract = contracts
+                .last()
+                .is_none_or(|(contract, _)| contract.taproot_pubkey_gen != row.taproot_pubkey_gen);
+
+            if is_new_contract {
+                contracts.push((
+                    ContractRow {
+                        arguments: row.arguments.clone().unwrap_or_default(),
+                        taproot_pubkey_gen: row.taproot_pubkey_gen.clone(),
+                        app_metadata: row.app_metadata.clone(),
+                        label: row.label.clone(),
+                    },
+                    Vec::new(),
+                ));
+            }
+
+            if let Some(utxo_row) = row.into_utxo_row() {
+                context = context.add_program_from_row(&utxo_row)?;
+                let entry = utxo_row.into_entry(&context)?;
+
+                if let Some((_, entries)) = contracts.last_mut() {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(contracts)
+    }
+
+    /// List the option and option offer contracts the wallet can act on, most urgent first.
+    ///
+    /// Offers count when made with `wallet_pubkey`, options when the store holds their grantor tokens.
+    /// Contracts whose arguments no longer decode are skipped. `now` is a Unix timestamp compared to expiries.
+    pub async fn actionable_contracts(
+        &self,
+        wallet_pubkey: [u8; 32],
+        now: i64,
+    ) -> Result<Vec<(String, ActionNeeded)>, StoreError> {
+        let mut actionable = Vec::new();
+
+        for (contract, entries) in self.contracts_with_utxos(OPTION_OFFER_SOURCE).await? {
+            let Some(args) = decode_arguments(&contract.arguments)
+                .ok()
+                .and_then(|arguments| OptionOfferArguments::from_arguments(&arguments).ok())
+            else {
+                continue;
+            };
+
+            for action in option_offer_actions(&args, &entries, wallet_pubkey, now) {
+                actionable.push((contract.taproot_pubkey_gen.clone(), action));
+            }
+        }
+
+        for (contract, entries) in self.contracts_with_utxos(OPTION_SOURCE).await? {
+            let Some(args) = decode_arguments(&contract.arguments)
+                .ok()
+                .and_then(|arguments| OptionsArguments::from_arguments(&arguments).ok())
+            else {
+                continue;
+            };
+
+            for action in option_actions(&args, &entries, now) {
+                actionable.push((contract.taproot_pubkey_gen.clone(), action));
+            }
+        }
+
+        actionable.sort_by_key(|(_, action)| (action.priority(), std::cmp::Reverse(action.amount())));
+
+        Ok(actionable)
+    }
+
+    /// Sum, per asset and role, what the wallet has locked in its own option offers and in options it
+    /// holds grantor tokens for, split between what is still on offer and what is reclaimable after expiry.
+    ///
+    /// `now` is a Unix timestamp compared to expiries.
+    pub async fn locked_collateral(&self, wallet_pubkey: [u8; 32], now: i64) -> Result<LockedBalances, StoreError> {
+        let mut locked = LockedBalances::new();
+
+        for (contract, entries) in self.contracts_with_utxos(OPTION_OFFER_SOURCE).await? {
+            if let Some(args) = decode_arguments(&contract.arguments)
+                .ok()
+                .and_then(|arguments| OptionOfferArguments::from_arguments(&arguments).ok())
+            {
+                option_offer_locked(&mut locked, &args, &entries, wallet_pubkey, now);
+            }
+        }
+
+        for (contract, entries) in self.contracts_with_utxos(OPTION_SOURCE).await? {
+            if let Some(args) = decode_arguments(&contract.arguments)
+                .ok()
+                .and_then(|arguments| OptionsArguments::from_arguments(&arguments).ok())
+            {
+                option_locked(&mut locked, &args, &entries, now);
+            }
+        }
+
+        Ok(locked)
+    }
+
+    /// Sum unspent outputs per asset, split between the wallet script and the given contract scripts.
+    pub async fn balance_detailed(
+        &self,
+        script_pubkey: &Script,
+        contract_scripts: &[Script],
+    ) -> Result<BTreeMap<AssetId, DetailedBalance>, StoreError> {
+        let mut balances: BTreeMap<AssetId, DetailedBalance> = BTreeMap::new();
+
+        for (asset_id, value) in self.unspent_totals_by_asset(script_pubkey).await? {
+            balances.entry(asset_id).or_default().spendable += value;
+        }
+
+        for script in contract_scripts {
+            for (asset_id, value) in self.unspent_totals_by_asset(script).await? {
+                balances.entry(asset_id).or_default().in_contracts += value;
+            }
+        }
+
+        Ok(balances)
+    }
+
+    async fn unspent_totals_by_asset(&self, script_pubkey: &Script) -> Result<Vec<(AssetId, u64)>, StoreError> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT asset_id, SUM(value) FROM utxos WHERE is_spent = 0 AND script_pubkey = ? GROUP BY asset_id",
+        )
+        .bind(script_pubkey.as_bytes())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(asset_id_hex, value)| {
+                let asset_id = asset_id_hex
+                    .parse::<AssetId>()
+                    .map_err(|_| StoreError::InvalidAssetId)?;
+                let value = u64::try_from(value).map_err(|_| StoreError::ValueOverflow)?;
+                Ok((asset_id, value))
+            })
+            .collect()
+    }
+
+    /// Get the Simplicity source a contract was registered with.
+    pub async fn get_contract_source(
+        &self,
+        taproot_pubkey_gen: &TaprootPubkeyGen,
+    ) -> Result<Option<String>, StoreError> {
+        let row: Option<(Vec<u8>,)> = sqlx::query_as(
+            "SELECT s.source FROM simplicity_contracts c
+             JOIN simplicity_sources s ON s.source_hash = c.source_hash
+             WHERE c.taproot_pubkey_gen = ?",
+        )
+        .bind(taproot_pubkey_gen.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(source,)| {
+            String::from_utf8(source).map_err(|_| sqlx::Error::Decode("Invalid UTF-8 in contract source".into()).into())
+        })
+        .transpose()
+    }
+}
+
+#[derive(sqlx::FromRow)]
+pub struct UtxoRow {
+    txid: Vec<u8>,
+    vout: u32,
+    serialized: Vec<u8>,
+    serialized_witness: Option<Vec<u8>>,
+    is_confidential: i64,
+    is_spent: i64,
+    value: u64,
+    height: Option<u32>,
+    confirmation_status: String,
+    blinding_key: Option<Vec<u8>>,
+    pub source: Option<Vec<u8>>,
+    pub arguments: Option<Vec<u8>>,
+    pub taproot_pubkey_gen: Option<String>,
+    pub entropy: Option<Vec<u8>>,
+    pub issuance_is_confidential: Option<i64>,
+}
+
+/// A contract joined with one of its unspent outputs; the output columns are NULL when it has none.
+#[derive(sqlx::FromRow)]
+struct ContractUtxoRow {
+    taproot_pubkey_gen: String,
+    arguments: Option<Vec<u8>>,
+    app_metadata: Option<Vec<u8>>,
+    label: Option<String>,
+    source: Vec<u8>,
+    txid: Option<Vec<u8>>,
+    vout: Option<u32>,
+    serialized: Option<Vec<u8>>,
+    serialized_witness: Option<Vec<u8>>,
+    is_confidential: Option<i64>,
+    is_spent: Option<i64>,
+    value: Option<u64>,
+    height: Option<u32>,
+    confirmation_status: Option<String>,
+    blinding_key: Option<Vec<u8>>,
+}
+
+impl ContractUtxoRow {
+    fn into_utxo_row(self) -> Option<UtxoRow> {
+        Some(UtxoRow {
+            txid: self.txid?,
+            vout: self.vout?,
+            serialized: self.serialized?,
+            serialized_witness: self.serialized_witness,
+            is_confidential: self.is_confidential?,
+            is_spent: self.is_spent?,
+            value: self.value?,
+            height: self.height,
+            confirmation_status: self.confirmation_status?,
+            blinding_key: self.blinding_key,
+            source: Some(self.source),
+            arguments: self.arguments,
+            taproot_pubkey_gen: Some(self.taproot_pubkey_gen),
+            entropy: None,
+            issuance_is_confidential: None,
+        })
+    }
+}
+
+impl UtxoRow {
+    /// Like [`UtxoRow::into_entry`], but skips a row whose stored contract arguments fail to decode.
+    ///
+    /// One corrupt contract row would otherwise fail every query that touches it. Arguments
+    /// written with another format version are not corrupt, so they still fail with
+    /// [`StoreError::ArgumentsVersionMismatch`] instead of every contract UTXO vanishing after a
+    /// format bump.
+    fn into_entry_or_skip(self, context: &ContractContext) -> Result<Option<UtxoEntry>, StoreError> {
+        match self.arguments.as_deref().map(decode_arguments) {
+            Some(Err(e @ StoreError::ArgumentsVersionMismatch { .. })) => return Err(e),
+            Some(Err(e)) => {
+                let txid = self.txid.to_hex();
+                tracing::warn!(
+                    "Skipping UTXO {txid}:{} with corrupt contract arguments: {e}",
+                    self.vout
+                );
+
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        self.into_entry(context).map(Some)
+    }
+
+    fn into_entry(self, context: &ContractContext) -> Result<UtxoEntry, StoreError> {
+        let contract = context.get_program_from_row(&self)?;
+
+        let entropy: Option<sha256::Midstate> = self
+            .entropy
+            .as_ref()
+            .map(|e| sha256::Midstate::from_slice(e))
+            .transpose()?;
+
+        let issuance_is_confidential: Option<bool> = self.issuance_is_confidential.map(|v| v != 0);
+
+        let txid_array: [u8; Txid::LEN] = self
+            .txid
+            .try_into()
+            .map_err(|_| sqlx::Error::Decode("Invalid txid length".into()))?;
+
+        let txid = Txid::from_byte_array(txid_array);
+        let outpoint = OutPoint::new(txid, self.vout);
+        let mut txout: TxOut = encode::deserialize(&self.serialized)?;
+
+        // Parse arguments from row if present
+        let arguments: Option<Arguments> = self.arguments.as_deref().map(decode_arguments).transpose()?;
+
+        if self.is_confidential != 1 {
+            let mut entry = UtxoEntry::new_explicit(outpoint, txout);
+
+            if let Some(c) = contract {
+                entry = entry.with_contract(Arc::clone(c));
+            }
+            if let Some((e, c)) = entropy.zip(issuance_is_confidential) {
+                entry = entry.with_issuance(e, c);
+            }
+            if let Some(tpg) = self.taproot_pubkey_gen {
+                entry = entry.with_taproot_pubkey_gen(tpg);
+            }
+            if let Some(args) = arguments {
+                entry = entry.with_arguments(args);
+            }
+            if let Some(height) = self.height {
+                entry = entry.with_height(height);
+            }
+            entry = entry.with_confirmation_status(ConfirmationStatus::from_sql(&self.confirmation_status));
+
+            return Ok(entry);
+        }
+
+        let key_bytes: [u8; crate::store::BLINDING_KEY_LEN] = self
+            .blinding_key
+            .ok_or_else(|| sqlx::Error::Decode("Missing blinding key for confidential output".into()))?
+            .try_into()
+            .map_err(|_| sqlx::Error::Decode("Invalid blinding key length".into()))?;
+
+        let serialized_witness = self
+            .serialized_witness
+            .as_ref()
+            .ok_or(StoreError::MissingSerializedTxOutWitness(outpoint))?;
+
+        let deserialized_witness: TxOutWitness = encode::deserialize(serialized_witness)?;
+        txout.witness = deserialized_witness;
+
+        let secret_key = SecretKey::from_slice(&key_bytes)?;
+        let secrets = txout.unblind(secp256k1::SECP256K1, secret_key)?;
+
+        let mut entry = UtxoEntry::new_confidential(outpoint, txout, secrets);
+
+        if let Some(c) = contract {
+            entry = entry.with_contract(Arc::clone(c));
+        }
+        if let Some((e, c)) = entropy.zip(issuance_is_confidential) {
             entry = entry.with_issuance(e, c);
         }
         if let Some(tpg) = self.taproot_pubkey_gen {
@@ -728,6 +1680,10 @@ impl UtxoRow {
         if let Some(args) = arguments {
             entry = entry.with_arguments(args);
         }
+        if let Some(height) = self.height {
+            entry = entry.with_height(height);
+        }
+        entry = entry.with_confirmation_status(ConfirmationStatus::from_sql(&self.confirmation_status));
 
         Ok(entry)
     }
@@ -737,6 +1693,9 @@ impl UtxoRow {
 mod tests {
     use super::*;
 
+    use crate::arguments::ARGUMENTS_FORMAT_VERSION;
+    use crate::{LockedCollateral, LockedRole, UtxoOrder};
+
     use std::fs;
 
     use contracts::bytes32_tr_storage::{
@@ -748,6 +1707,9 @@ mod tests {
     use simplicityhl::simplicity::bitcoin::PublicKey;
     use simplicityhl::simplicity::bitcoin::key::Parity;
 
+    use proptest::prelude::{any, prop_assert, prop_assert_eq, proptest};
+    use proptest::{collection, option};
+
     fn make_explicit_txout(asset_id: AssetId, value: u64) -> TxOut {
         TxOut {
             asset: Asset::Explicit(asset_id),
@@ -905,405 +1867,2312 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_mark_as_spent() {
-        let path = "/tmp/test_coin_store_spent.db";
+    async fn test_query_order_by() {
+        let path = "/tmp/test_coin_store_order_by.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
         let asset = test_asset_id();
-        let outpoint1 = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
 
-        store
-            .insert(outpoint1, make_explicit_txout(asset, 1000), None)
-            .await
-            .unwrap();
+        // Inserted in this order: 200, 300, 100
+        for (i, value) in [200, 300, 100].into_iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let outpoint = OutPoint::new(Txid::from_byte_array([i as u8 + 1; Txid::LEN]), 0);
+            store
+                .insert(outpoint, make_explicit_txout(asset, value), None)
+                .await
+                .unwrap();
+        }
 
-        let filter = UtxoFilter::new().asset_id(asset);
-        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
-        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
+        let values_for = async |order| {
+            let filter = UtxoFilter::new().asset_id(asset).order_by(order);
+            let results = store.query_utxos(&[filter]).await.unwrap();
+            match &results[0] {
+                UtxoQueryResult::Found(entries, _) => entries.iter().filter_map(UtxoEntry::value).collect::<Vec<_>>(),
+                _ => panic!("Expected Found result"),
+            }
+        };
 
-        store.mark_as_spent(outpoint1).await.unwrap();
+        assert_eq!(values_for(UtxoOrder::ValueDesc).await, vec![300, 200, 100]);
+        assert_eq!(values_for(UtxoOrder::ValueAsc).await, vec![100, 200, 300]);
+        assert_eq!(values_for(UtxoOrder::Oldest).await, vec![200, 300, 100]);
+        assert_eq!(values_for(UtxoOrder::Newest).await, vec![100, 300, 200]);
 
-        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        // Insertion order is stored explicitly, so compacting the file cannot reorder it
+        store.vacuum().await.unwrap();
+        assert_eq!(values_for(UtxoOrder::Oldest).await, vec![200, 300, 100]);
+
+        let mut random = values_for(UtxoOrder::Random).await;
+        random.sort_unstable();
+        assert_eq!(random, vec![100, 200, 300]);
+
+        // Default ordering stays largest-first
+        let results = store.query_utxos(&[UtxoFilter::new().asset_id(asset)]).await.unwrap();
         match &results[0] {
-            UtxoQueryResult::Empty => {}
-            _ => panic!("Expected non-Empty result"),
+            UtxoQueryResult::Found(entries, _) => assert_eq!(entries[0].value(), Some(300)),
+            _ => panic!("Expected Found result"),
         }
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_query_empty() {
-        let path = "/tmp/test_coin_store_empty.db";
+    async fn test_query_equal_values_ordered_by_outpoint() {
+        let path = "/tmp/test_coin_store_order_tiebreak.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let filter = UtxoFilter::new().asset_id(test_asset_id());
-        let results = store.query_utxos(&[filter]).await.unwrap();
+        let asset = test_asset_id();
 
-        assert!(matches!(&results[0], UtxoQueryResult::Empty));
+        // Inserted with descending txids so insertion order and outpoint order differ
+        for byte in [3, 1, 2] {
+            for vout in [1, 0] {
+                let outpoint = OutPoint::new(Txid::from_byte_array([byte; Txid::LEN]), vout);
+                store
+                    .insert(outpoint, make_explicit_txout(asset, 500), None)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        for order in [UtxoOrder::ValueDesc, UtxoOrder::ValueAsc] {
+            let results = store
+                .query_utxos(&[UtxoFilter::new().asset_id(asset).order_by(order)])
+                .await
+                .unwrap();
+            let outpoints: Vec<OutPoint> = match &results[0] {
+                UtxoQueryResult::Found(entries, _) => entries.iter().map(|entry| *entry.outpoint()).collect(),
+                _ => panic!("Expected Found result"),
+            };
+
+            let mut sorted = outpoints.clone();
+            sorted.sort_by_key(|outpoint| (outpoint.txid.to_byte_array(), outpoint.vout));
+            assert_eq!(outpoints, sorted);
+        }
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_multi_filter_query() {
-        let path = "/tmp/test_coin_store_multi_filter.db";
+    async fn test_query_utxos_paged() {
+        let path = "/tmp/test_coin_store_paged.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let asset1 = AssetId::from_slice(&[1; 32]).unwrap();
-        let asset2 = AssetId::from_slice(&[2; 32]).unwrap();
-
-        store
-            .insert(
-                OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0),
-                make_explicit_txout(asset1, 1000),
-                None,
-            )
-            .await
-            .unwrap();
-
-        store
-            .insert(
-                OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0),
-                make_explicit_txout(asset2, 2000),
-                None,
-            )
-            .await
-            .unwrap();
+        let asset = test_asset_id();
+        for value in 1..=50u64 {
+            #[allow(clippy::cast_possible_truncation)]
+            let outpoint = OutPoint::new(Txid::from_byte_array([value as u8; Txid::LEN]), 0);
+            store
+                .insert(outpoint, make_explicit_txout(asset, value * 100), None)
+                .await
+                .unwrap();
+        }
 
-        let filter1 = UtxoFilter::new().asset_id(asset1);
-        let filter2 = UtxoFilter::new().asset_id(asset2);
+        let filter = UtxoFilter::new().asset_id(asset).order_by(UtxoOrder::ValueDesc);
+        let mut values = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (entries, has_more) = store.query_utxos_paged(&filter, 10, offset).await.unwrap();
+            assert_eq!(entries.len(), 10);
+            values.extend(entries.iter().filter_map(UtxoEntry::value));
+            offset += 10;
+
+            if !has_more {
+                break;
+            }
+        }
 
-        let results = store.query_utxos(&[filter1, filter2]).await.unwrap();
+        assert_eq!(offset, 50);
+        assert_eq!(values, (1..=50u64).rev().map(|value| value * 100).collect::<Vec<_>>());
 
-        assert_eq!(results.len(), 2);
-        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
-        assert!(matches!(&results[1], UtxoQueryResult::Found(e, _) if e.len() == 1));
+        let (entries, has_more) = store.query_utxos_paged(&filter, 10, 50).await.unwrap();
+        assert!(entries.is_empty());
+        assert!(!has_more);
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_add_contract() {
-        let path = "/tmp/test_coin_store_add_contract.db";
+    async fn test_query_selection_strategies() {
+        use crate::SelectionStrategy;
+
+        let path = "/tmp/test_coin_store_selection.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let tpg1 = make_test_taproot_pubkey_gen([0u8; 32]);
-        let tpg2 = make_test_taproot_pubkey_gen([1u8; 32]);
-        let arguments = simplicityhl::Arguments::default();
+        let asset = test_asset_id();
 
-        let result = store
-            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments.clone(), tpg1, None)
-            .await;
-        assert!(result.is_ok());
+        let values = [5_000, 1_200, 300, 2_500, 800, 10_000];
+        let outpoints: Vec<OutPoint> = (1u8..=6)
+            .map(|byte| OutPoint::new(Txid::from_byte_array([byte; Txid::LEN]), 0))
+            .collect();
+        for (outpoint, value) in outpoints.iter().zip(values) {
+            store
+                .insert(*outpoint, make_explicit_txout(asset, value), None)
+                .await
+                .unwrap();
+        }
 
-        let result = store
-            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg2, None)
-            .await;
-        assert!(result.is_ok());
+        let selected = async |strategy, required| {
+            let filter = UtxoFilter::new()
+                .asset_id(asset)
+                .required_value(required)
+                .selection_strategy(strategy);
+            let results = store.query_utxos(&[filter]).await.unwrap();
+            match &results[0] {
+                UtxoQueryResult::Found(entries, _) => entries.iter().map(|e| *e.outpoint()).collect::<Vec<_>>(),
+                _ => panic!("Expected Found result"),
+            }
+        };
+
+        assert_eq!(
+            selected(SelectionStrategy::LargestFirst, 12_000).await,
+            vec![outpoints[5], outpoints[0]]
+        );
+        assert_eq!(
+            selected(SelectionStrategy::SmallestFirst, 2_000).await,
+            vec![outpoints[2], outpoints[4], outpoints[1]]
+        );
+        assert_eq!(
+            selected(SelectionStrategy::MinimizeChange, 2_000).await,
+            vec![outpoints[3]]
+        );
+        assert_eq!(
+            selected(SelectionStrategy::BranchAndBound { tolerance: 0 }, 3_300).await,
+            vec![outpoints[3], outpoints[4]]
+        );
+        assert_eq!(
+            selected(SelectionStrategy::BranchAndBound { tolerance: 50 }, 10_100).await,
+            vec![outpoints[5], outpoints[0]]
+        );
+
+        // Without enough value every candidate is reported, as without a strategy
+        let filter = UtxoFilter::new()
+            .asset_id(asset)
+            .required_value(50_000)
+            .selection_strategy(SelectionStrategy::MinimizeChange);
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::InsufficientValue(entries, _) if entries.len() == values.len()));
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_query_by_cmr() {
-        let path = "/tmp/test_coin_store_query_cmr.db";
+    async fn test_coin_selector() {
+        use crate::{CoinSelector, SelectionStrategy};
+
+        let path = "/tmp/test_coin_store_coin_selector.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
-        let arguments = simplicityhl::Arguments::default();
-        let script_pubkey = tpg.address.script_pubkey();
+        let asset = test_asset_id();
+        let script = Script::new();
 
-        store
-            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments.clone(), tpg, None)
+        let err = CoinSelector::new()
+            .select(&store, asset, &script, 100)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StoreError::NoCoins { asset: a } if a == asset));
+
+        for (byte, value) in [(1u8, 400), (2, 300), (3, 700)] {
+            let outpoint = OutPoint::new(Txid::from_byte_array([byte; Txid::LEN]), 0);
+            store
+                .insert(outpoint, make_explicit_txout(asset, value), None)
+                .await
+                .unwrap();
+        }
+
+        let coins = CoinSelector::new().select(&store, asset, &script, 1_000).await.unwrap();
+        assert_eq!(coins.total(), 1_100);
+        assert!(coins.single().is_none());
+        let inputs = coins.inputs();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].0, OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 0));
+
+        let coins = CoinSelector::new()
+            .strategy(SelectionStrategy::MinimizeChange)
+            .select(&store, asset, &script, 350)
             .await
             .unwrap();
+        assert_eq!(coins.single().and_then(UtxoEntry::value), Some(400));
 
-        let outpoint = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
-        let mut txout = make_explicit_txout(test_asset_id(), 1000);
-        txout.script_pubkey = script_pubkey;
+        let err = CoinSelector::new()
+            .select(&store, asset, &script, 2_000)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            StoreError::InsufficientCoins {
+                required: 2_000,
+                available: 1_400,
+                ..
+            }
+        ));
 
-        store.insert(outpoint, txout, None).await.unwrap();
+        let _ = fs::remove_file(path);
+    }
 
-        let program = simplicityhl::CompiledProgram::new(BYTES32_TR_STORAGE_SOURCE, arguments, false).unwrap();
-        let cmr = program.commit().cmr();
+    #[tokio::test]
+    async fn test_query_multiple_script_pubkeys() {
+        let path = "/tmp/test_coin_store_script_pubkeys.db";
+        let _ = fs::remove_file(path);
 
-        let filter = UtxoFilter::new().cmr(cmr);
-        let results = store.query_utxos(&[filter]).await.unwrap();
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+        let scripts: Vec<Script> = (1u8..=4).map(|byte| Script::from(vec![0x51, byte])).collect();
+
+        for (byte, script) in (1u8..).zip(&scripts) {
+            let outpoint = OutPoint::new(Txid::from_byte_array([byte; Txid::LEN]), 0);
+            let txout = TxOut {
+                script_pubkey: script.clone(),
+                ..make_explicit_txout(asset, u64::from(byte) * 100)
+            };
+            store.insert(outpoint, txout, None).await.unwrap();
+        }
 
+        // Three of the four scripts, with a required value only their combined UTXOs cover
+        let filter = UtxoFilter::new()
+            .asset_id(asset)
+            .script_pubkeys(scripts[..3].to_vec())
+            .required_value(600);
+        let results = store.query_utxos(&[filter]).await.unwrap();
         match &results[0] {
             UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 1);
-                assert_eq!(entries[0].value(), Some(1000));
+                assert_eq!(
+                    entries.iter().filter_map(UtxoEntry::value).collect::<Vec<_>>(),
+                    vec![300, 200, 100]
+                );
             }
             _ => panic!("Expected Found result"),
         }
 
+        // Combined with the single-script setter, both must match
+        let filter = UtxoFilter::new()
+            .script_pubkeys(scripts[..3].to_vec())
+            .script_pubkey(scripts[3].clone());
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        assert!(matches!(results[0], UtxoQueryResult::Empty));
+
+        let results = store
+            .query_utxos(&[UtxoFilter::new().script_pubkeys(Vec::new())])
+            .await
+            .unwrap();
+        assert!(matches!(results[0], UtxoQueryResult::Empty));
+
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_query_by_taproot_pubkey_gen() {
-        let path = "/tmp/test_coin_store_query_tpg.db";
+    async fn test_balance_detailed() {
+        let path = "/tmp/test_coin_store_balance_detailed.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
-        let arguments = simplicityhl::Arguments::default();
-        let script_pubkey = tpg.address.script_pubkey();
-
-        store
-            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg.clone(), None)
-            .await
-            .unwrap();
+        let asset = test_asset_id();
+        let wallet_script = Script::from(vec![0x51]);
+        let contract_script = Script::from(vec![0x52]);
+        let other_script = Script::from(vec![0x53]);
+
+        let insert = async |seed: u8, script: &Script, value: u64| {
+            let mut txout = make_explicit_txout(asset, value);
+            txout.script_pubkey = script.clone();
+            let outpoint = OutPoint::new(Txid::from_byte_array([seed; Txid::LEN]), 0);
+            store.insert(outpoint, txout, None).await.unwrap();
+            outpoint
+        };
 
-        let outpoint = OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0);
-        let mut txout = make_explicit_txout(test_asset_id(), 2000);
-        txout.script_pubkey = script_pubkey;
+        insert(1, &wallet_script, 1000).await;
+        let spent = insert(2, &wallet_script, 500).await;
+        insert(3, &contract_script, 700).await;
+        insert(4, &other_script, 9000).await;
 
-        store.insert(outpoint, txout, None).await.unwrap();
+        store.mark_as_spent(spent).await.unwrap();
 
-        let filter = UtxoFilter::new().taproot_pubkey_gen(tpg);
-        let results = store.query_utxos(&[filter]).await.unwrap();
+        let balances = store
+            .balance_detailed(&wallet_script, std::slice::from_ref(&contract_script))
+            .await
+            .unwrap();
 
-        match &results[0] {
-            UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 1);
-                assert_eq!(entries[0].value(), Some(2000));
-            }
-            _ => panic!("Expected Found result"),
-        }
+        assert_eq!(
+            balances.get(&asset),
+            Some(&DetailedBalance {
+                spendable: 1000,
+                in_contracts: 700,
+            })
+        );
+        assert_eq!(balances.len(), 1);
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_query_by_source_hash() {
-        let path = "/tmp/test_coin_store_query_source_hash.db";
+    async fn test_mark_as_spent() {
+        let path = "/tmp/test_coin_store_spent.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
-        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
-        let arguments = simplicityhl::Arguments::default();
-        let script_pubkey = tpg.address.script_pubkey();
+        let asset = test_asset_id();
+        let outpoint1 = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
 
         store
-            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg, None)
+            .insert(outpoint1, make_explicit_txout(asset, 1000), None)
             .await
             .unwrap();
 
-        let outpoint = OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 0);
-        let mut txout = make_explicit_txout(test_asset_id(), 3000);
-        txout.script_pubkey = script_pubkey;
-
-        store.insert(outpoint, txout, None).await.unwrap();
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
 
-        let filter = UtxoFilter::new().source(BYTES32_TR_STORAGE_SOURCE);
-        let results = store.query_utxos(&[filter]).await.unwrap();
+        store.mark_as_spent(outpoint1).await.unwrap();
 
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
         match &results[0] {
-            UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 1);
-                assert_eq!(entries[0].value(), Some(3000));
-            }
-            _ => panic!("Expected Found result"),
+            UtxoQueryResult::Empty => {}
+            _ => panic!("Expected non-Empty result"),
         }
 
         let _ = fs::remove_file(path);
     }
 
-    fn make_explicit_txout_with_script(asset_id: AssetId, value: u64) -> TxOut {
-        TxOut {
-            asset: Asset::Explicit(asset_id),
-            value: Value::Explicit(value),
-            nonce: Nonce::Null,
-            script_pubkey: Script::new_op_return(b"burn"),
-            witness: TxOutWitness::default(),
-        }
-    }
-
     #[tokio::test]
-    async fn test_insert_transaction_ignores_duplicates() {
-        let path = "/tmp/test_coin_store_tx_dup.db";
+    async fn test_mark_as_unspent_and_remove_utxo() {
+        let path = "/tmp/test_coin_store_unspent.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
         let asset = test_asset_id();
+        let outpoint = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
+        let filter = UtxoFilter::new().asset_id(asset);
 
-        let txout0 = make_explicit_txout_with_script(asset, 1000);
-        let txout1 = make_explicit_txout_with_script(asset, 2000);
+        store
+            .insert(outpoint, make_explicit_txout(asset, 1000), None)
+            .await
+            .unwrap();
 
-        let tx = Transaction {
-            version: 2,
-            lock_time: simplicityhl::elements::LockTime::ZERO,
-            input: vec![],
-            output: vec![txout0, txout1],
-        };
+        // Only a spent output can be unspent
+        assert!(!store.mark_as_unspent(outpoint).await.unwrap());
 
-        let result = store.insert_transaction(&tx, HashMap::new()).await;
-        assert!(result.is_ok(), "First insert_transaction should succeed");
+        assert!(store.mark_as_spent(outpoint).await.unwrap());
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Empty));
 
-        let filter = UtxoFilter::new().asset_id(asset);
-        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
-        match &results[0] {
-            UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 2, "Both UTXOs should be present after first insert");
-            }
-            _ => panic!("Expected Found result with 2 entries"),
-        }
+        assert!(store.mark_as_unspent(outpoint).await.unwrap());
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
 
-        let result = store.insert_transaction(&tx, HashMap::new()).await;
-        assert!(
-            result.is_ok(),
-            "Second insert_transaction should succeed (INSERT OR IGNORE)"
-        );
+        assert!(store.remove_utxo(outpoint).await.unwrap());
+        assert!(!store.remove_utxo(outpoint).await.unwrap());
+        assert_eq!(store.get_output_amount(outpoint).await.unwrap(), None);
 
-        let results = store.query_utxos(&[filter]).await.unwrap();
-        match &results[0] {
-            UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 2, "Should still have exactly 2 UTXOs");
-            }
-            _ => panic!("Expected Found result with 2 entries"),
-        }
+        // A removed output can be stored again
+        store
+            .insert(outpoint, make_explicit_txout(asset, 1000), None)
+            .await
+            .unwrap();
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_insert_transaction_skips_unblindable_outputs() {
-        let path = "/tmp/test_coin_store_tx_unblind.db";
+    async fn test_set_height() {
+        let path = "/tmp/test_coin_store_height.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
         let asset = test_asset_id();
+        let outpoint = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
+        let missing = OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0);
 
-        let txout_explicit_0 = make_explicit_txout_with_script(asset, 1000);
-
-        let tag = secp256k1::Tag::from([1u8; 32]);
-        let generator = secp256k1::Generator::new_unblinded(secp256k1::SECP256K1, tag);
-        let txout_confidential = TxOut {
-            asset: Asset::Confidential(generator),
-            value: Value::Confidential(
-                secp256k1::PedersenCommitment::from_slice(&[
-                    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                    0x01,
-                ])
-                .unwrap(),
-            ),
-            nonce: Nonce::Null,
-            script_pubkey: Script::new_op_return(b"burn"),
-            witness: TxOutWitness::default(),
-        };
-        let txout_explicit_2 = make_explicit_txout_with_script(asset, 3000);
+        store
+            .insert(outpoint, make_explicit_txout(asset, 1000), None)
+            .await
+            .unwrap();
 
-        let tx = Transaction {
-            version: 2,
-            lock_time: simplicityhl::elements::LockTime::ZERO,
-            input: vec![],
-            output: vec![txout_explicit_0, txout_confidential, txout_explicit_2],
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        let UtxoQueryResult::Found(entries, _) = &results[0] else {
+            panic!("Expected Found result");
         };
+        assert_eq!(entries[0].height(), None);
+        assert_eq!(entries[0].confirmations(100), 0);
 
-        let result = store.insert_transaction(&tx, HashMap::new()).await;
-        assert!(
-            result.is_ok(),
-            "insert_transaction should succeed, skipping unblindable outputs"
-        );
+        assert!(store.set_height(outpoint, 95).await.unwrap());
+        assert!(!store.set_height(missing, 95).await.unwrap());
 
-        let filter = UtxoFilter::new().asset_id(asset);
-        let results = store.query_utxos(&[filter]).await.unwrap();
-        match &results[0] {
-            UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 2, "Only explicit outputs should be inserted");
-                let values: Vec<_> = entries.iter().map(super::super::entry::UtxoEntry::value).collect();
-                assert!(values.contains(&Some(1000)));
-                assert!(values.contains(&Some(3000)));
-            }
-            _ => panic!("Expected Found result"),
-        }
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        let UtxoQueryResult::Found(entries, _) = &results[0] else {
+            panic!("Expected Found result");
+        };
+        assert_eq!(entries[0].height(), Some(95));
+        assert_eq!(entries[0].confirmations(100), 6);
+        assert_eq!(entries[0].confirmations(95), 1);
 
         let _ = fs::remove_file(path);
     }
 
     #[tokio::test]
-    async fn test_insert_transaction_marks_inputs_as_spent() {
-        let path = "/tmp/test_coin_store_tx_spent.db";
+    async fn test_confirmation_status() {
+        let path = "/tmp/test_coin_store_confirmation_status.db";
         let _ = fs::remove_file(path);
 
         let store = Store::create(path).await.unwrap();
 
         let asset = test_asset_id();
+        let outpoint = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
 
-        let prev_txout = make_explicit_txout_with_script(asset, 500);
-        let prev_tx = Transaction {
-            version: 2,
-            lock_time: simplicityhl::elements::LockTime::ZERO,
-            input: vec![],
-            output: vec![prev_txout],
+        store
+            .insert(outpoint, make_explicit_txout(asset, 1000), None)
+            .await
+            .unwrap();
+
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(std::slice::from_ref(&filter)).await.unwrap();
+        let UtxoQueryResult::Found(entries, _) = &results[0] else {
+            panic!("Expected Found result");
         };
-        store.insert_transaction(&prev_tx, HashMap::new()).await.unwrap();
+        assert_eq!(entries[0].confirmation_status(), ConfirmationStatus::Pending);
 
-        let prev_txid = prev_tx.txid();
-        let prev_outpoint = OutPoint::new(prev_txid, 0);
+        let confirmed = UtxoFilter::new().asset_id(asset).confirmed_only();
+        let results = store.query_utxos(std::slice::from_ref(&confirmed)).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Empty));
 
-        let filter = UtxoFilter::new().asset_id(asset);
-        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
-        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
+        // Sync recording the block height promotes the output
+        store.set_height(outpoint, 95).await.unwrap();
 
-        let new_txout = make_explicit_txout_with_script(asset, 400);
-        let tx_input = simplicityhl::elements::TxIn {
-            previous_output: prev_outpoint,
-            is_pegin: false,
-            script_sig: Script::new(),
-            sequence: simplicityhl::elements::Sequence::MAX,
-            asset_issuance: simplicityhl::elements::AssetIssuance::default(),
-            witness: simplicityhl::elements::TxInWitness::default(),
+        let results = store.query_utxos(std::slice::from_ref(&confirmed)).await.unwrap();
+        let UtxoQueryResult::Found(entries, _) = &results[0] else {
+            panic!("Expected Found result");
         };
+        assert!(entries[0].is_confirmed());
 
-        let spending_tx = Transaction {
-            version: 2,
-            lock_time: simplicityhl::elements::LockTime::ZERO,
-            input: vec![tx_input],
-            output: vec![new_txout],
-        };
+        let _ = fs::remove_file(path);
+    }
 
-        store.insert_transaction(&spending_tx, HashMap::new()).await.unwrap();
+    #[tokio::test]
+    async fn test_query_empty() {
+        let path = "/tmp/test_coin_store_empty.db";
+        let _ = fs::remove_file(path);
 
-        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
-        match &results[0] {
+        let store = Store::create(path).await.unwrap();
+
+        let filter = UtxoFilter::new().asset_id(test_asset_id());
+        let results = store.query_utxos(&[filter]).await.unwrap();
+
+        assert!(matches!(&results[0], UtxoQueryResult::Empty));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_multi_filter_query() {
+        let path = "/tmp/test_coin_store_multi_filter.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset1 = AssetId::from_slice(&[1; 32]).unwrap();
+        let asset2 = AssetId::from_slice(&[2; 32]).unwrap();
+
+        store
+            .insert(
+                OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0),
+                make_explicit_txout(asset1, 1000),
+                None,
+            )
+            .await
+            .unwrap();
+
+        store
+            .insert(
+                OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0),
+                make_explicit_txout(asset2, 2000),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let filter1 = UtxoFilter::new().asset_id(asset1);
+        let filter2 = UtxoFilter::new().asset_id(asset2);
+
+        let results = store.query_utxos(&[filter1, filter2]).await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
+        assert!(matches!(&results[1], UtxoQueryResult::Found(e, _) if e.len() == 1));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_add_contract() {
+        let path = "/tmp/test_coin_store_add_contract.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg1 = make_test_taproot_pubkey_gen([0u8; 32]);
+        let tpg2 = make_test_taproot_pubkey_gen([1u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+
+        let result = store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments.clone(), tpg1, None, None)
+            .await;
+        assert!(result.is_ok());
+
+        let result = store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg2, None, None)
+            .await;
+        assert!(result.is_ok());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_get_contract_source() {
+        let path = "/tmp/test_coin_store_contract_source.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let unknown_tpg = make_test_taproot_pubkey_gen([1u8; 32]);
+
+        store
+            .add_contract(
+                BYTES32_TR_STORAGE_SOURCE,
+                simplicityhl::Arguments::default(),
+                tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let source = store.get_contract_source(&tpg).await.unwrap();
+        assert_eq!(source.as_deref(), Some(BYTES32_TR_STORAGE_SOURCE));
+
+        assert!(store.get_contract_source(&unknown_tpg).await.unwrap().is_none());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_query_by_cmr() {
+        let path = "/tmp/test_coin_store_query_cmr.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+        let script_pubkey = tpg.address.script_pubkey();
+
+        store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments.clone(), tpg, None, None)
+            .await
+            .unwrap();
+
+        let outpoint = OutPoint::new(Txid::from_byte_array([1; Txid::LEN]), 0);
+        let mut txout = make_explicit_txout(test_asset_id(), 1000);
+        txout.script_pubkey = script_pubkey;
+
+        store.insert(outpoint, txout, None).await.unwrap();
+
+        let program = simplicityhl::CompiledProgram::new(BYTES32_TR_STORAGE_SOURCE, arguments, false).unwrap();
+        let cmr = program.commit().cmr();
+
+        let filter = UtxoFilter::new().cmr(cmr);
+        let results = store.query_utxos(&[filter]).await.unwrap();
+
+        match &results[0] {
             UtxoQueryResult::Found(entries, _) => {
                 assert_eq!(entries.len(), 1);
-                assert_eq!(entries[0].value(), Some(400));
+                assert_eq!(entries[0].value(), Some(1000));
             }
-            _ => panic!("Expected Found result with the new output"),
+            _ => panic!("Expected Found result"),
         }
 
-        let filter_with_spent = UtxoFilter::new().asset_id(asset).include_spent();
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_query_by_taproot_pubkey_gen() {
+        let path = "/tmp/test_coin_store_query_tpg.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+        let script_pubkey = tpg.address.script_pubkey();
+
+        store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        let outpoint = OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0);
+        let mut txout = make_explicit_txout(test_asset_id(), 2000);
+        txout.script_pubkey = script_pubkey;
+
+        store.insert(outpoint, txout, None).await.unwrap();
+
+        let filter = UtxoFilter::new().taproot_pubkey_gen(tpg);
+        let results = store.query_utxos(&[filter]).await.unwrap();
+
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].value(), Some(2000));
+            }
+            _ => panic!("Expected Found result"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_query_fails_on_arguments_version_mismatch() {
+        let path = "/tmp/test_coin_store_arguments_version_mismatch.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+        let script_pubkey = tpg.address.script_pubkey();
+
+        store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments.clone(), tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        let outpoint = OutPoint::new(Txid::from_byte_array([2; Txid::LEN]), 0);
+        let mut txout = make_explicit_txout(test_asset_id(), 2000);
+        txout.script_pubkey = script_pubkey;
+        store.insert(outpoint, txout, None).await.unwrap();
+
+        // Simulate arguments written by a newer format version
+        let mut stored = encode_arguments(&arguments).unwrap();
+        stored[0] = ARGUMENTS_FORMAT_VERSION + 1;
+        sqlx::query("UPDATE simplicity_contracts SET arguments = ? WHERE taproot_pubkey_gen = ?")
+            .bind(stored)
+            .bind(tpg.to_string())
+            .execute(&store.pool)
+            .await
+            .unwrap();
+
+        // The contract UTXO is reported, not silently dropped
+        let filter = UtxoFilter::new().taproot_pubkey_gen(tpg);
+        let result = store.query_utxos(&[filter]).await;
+        assert!(matches!(
+            result,
+            Err(StoreError::ArgumentsVersionMismatch { expected, found })
+                if expected == ARGUMENTS_FORMAT_VERSION && found == ARGUMENTS_FORMAT_VERSION + 1
+        ));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_query_contracts_only() {
+        let path = "/tmp/test_coin_store_query_contracts_only.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg1 = make_test_taproot_pubkey_gen([0u8; 32]);
+        let tpg2 = make_test_taproot_pubkey_gen([1u8; 32]);
+
+        for (seed, tpg, value) in [(1u8, &tpg1, 2000), (2, &tpg2, 3000)] {
+            store
+                .add_contract(
+                    BYTES32_TR_STORAGE_SOURCE,
+                    simplicityhl::Arguments::default(),
+                    tpg.clone(),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+
+            let mut txout = make_explicit_txout(test_asset_id(), value);
+            txout.script_pubkey = tpg.address.script_pubkey();
+            store
+                .insert(OutPoint::new(Txid::from_byte_array([seed; Txid::LEN]), 0), txout, None)
+                .await
+                .unwrap();
+        }
+
+        // Wallet UTXO at a non-contract script
+        store
+            .insert(
+                OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 0),
+                make_explicit_txout_with_script(test_asset_id(), 5000),
+                None,
+            )
+            .await
+            .unwrap();
+
         let results = store
-            .query_utxos(std::slice::from_ref(&filter_with_spent))
+            .query_utxos(&[UtxoFilter::new().contracts_only(), UtxoFilter::new()])
             .await
             .unwrap();
+
         match &results[0] {
             UtxoQueryResult::Found(entries, _) => {
-                assert_eq!(entries.len(), 2);
+                let mut values: Vec<u64> = entries.iter().filter_map(UtxoEntry::value).collect();
+                values.sort_unstable();
+                assert_eq!(values, vec![2000, 3000]);
+                assert!(entries.iter().all(|e| e.taproot_pubkey_gen().is_some()));
             }
-            _ => panic!("Expected Found result with both UTXOs"),
+            _ => panic!("Expected Found result"),
+        }
+
+        match &results[1] {
+            UtxoQueryResult::Found(entries, _) => assert_eq!(entries.len(), 3),
+            _ => panic!("Expected Found result"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_contract_labels() {
+        let path = "/tmp/test_coin_store_contract_labels.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg1 = make_test_taproot_pubkey_gen([0u8; 32]);
+        let tpg2 = make_test_taproot_pubkey_gen([1u8; 32]);
+
+        for (seed, tpg, label, value) in [(1u8, &tpg1, Some("mm-batch-1"), 2000), (2, &tpg2, None, 3000)] {
+            store
+                .add_contract(
+                    BYTES32_TR_STORAGE_SOURCE,
+                    simplicityhl::Arguments::default(),
+                    tpg.clone(),
+                    None,
+                    label,
+                )
+                .await
+                .unwrap();
+
+            let mut txout = make_explicit_txout(test_asset_id(), value);
+            txout.script_pubkey = tpg.address.script_pubkey();
+            store
+                .insert(OutPoint::new(Txid::from_byte_array([seed; Txid::LEN]), 0), txout, None)
+                .await
+                .unwrap();
+        }
+
+        let labeled = store
+            .list_contracts_by_label(BYTES32_TR_STORAGE_SOURCE, "mm-batch-1")
+            .await
+            .unwrap();
+        assert_eq!(labeled.len(), 1);
+        assert_eq!(labeled[0].1, tpg1.to_string());
+
+        assert!(
+            store
+                .list_contracts_by_label(BYTES32_TR_STORAGE_SOURCE, "test")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let results = store
+            .query_utxos(&[UtxoFilter::new().label("mm-batch-1")])
+            .await
+            .unwrap();
+
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].value(), Some(2000));
+            }
+            _ => panic!("Expected Found result"),
         }
 
         let _ = fs::remove_file(path);
     }
+
+    #[tokio::test]
+    async fn test_add_contract_twice_keeps_one_row() {
+        let path = "/tmp/test_coin_store_add_contract_twice.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+
+        for metadata in [b"first".as_slice(), b"second".as_slice()] {
+            store
+                .add_contract(
+                    BYTES32_TR_STORAGE_SOURCE,
+                    simplicityhl::Arguments::default(),
+                    tpg.clone(),
+                    Some(metadata),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let contracts = store.list_contracts_by_source(BYTES32_TR_STORAGE_SOURCE).await.unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(
+            store.get_contract_metadata(&tpg).await.unwrap(),
+            Some(b"first".to_vec())
+        );
+
+        // The funding output is found once it is stored at the contract address
+        let funding = OutPoint::new(Txid::from_byte_array([7; 32]), 1);
+        assert_eq!(store.get_contract_by_outpoint(funding).await.unwrap(), None);
+
+        let mut txout = make_explicit_txout(test_asset_id(), 1000);
+        txout.script_pubkey = tpg.address.script_pubkey();
+        store.insert(funding, txout, None).await.unwrap();
+        assert_eq!(
+            store.get_contract_by_outpoint(funding).await.unwrap(),
+            Some(tpg.to_string())
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_add_contracts_is_all_or_nothing() {
+        let path = "/tmp/test_coin_store_add_contracts.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg1 = make_test_taproot_pubkey_gen([0u8; 32]);
+        let tpg2 = make_test_taproot_pubkey_gen([1u8; 32]);
+        let token = AssetId::from_slice(&[9u8; 32]).unwrap();
+
+        let new_contract = |tpg: &TaprootPubkeyGen, tokens: Vec<(AssetId, String)>| NewContract {
+            source: BYTES32_TR_STORAGE_SOURCE.to_string(),
+            arguments: simplicityhl::Arguments::default(),
+            taproot_pubkey_gen: tpg.clone(),
+            app_metadata: Some(b"created".to_vec()),
+            label: Some("grid".to_string()),
+            tokens,
+        };
+
+        let uncompilable = [
+            new_contract(&tpg1, Vec::new()),
+            NewContract {
+                source: "fn main() {".to_string(),
+                ..new_contract(&tpg2, Vec::new())
+            },
+        ];
+        assert!(store.add_contracts(&uncompilable).await.is_err());
+        assert!(
+            store
+                .list_contracts_by_label(BYTES32_TR_STORAGE_SOURCE, "grid")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let batch = [
+            new_contract(&tpg1, vec![(token, "collateral".to_string())]),
+            new_contract(&tpg2, Vec::new()),
+        ];
+        store.add_contracts(&batch).await.unwrap();
+
+        let stored = store
+            .list_contracts_by_label(BYTES32_TR_STORAGE_SOURCE, "grid")
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(
+            store.get_contract_metadata(&tpg2).await.unwrap().as_deref(),
+            Some(b"created".as_slice())
+        );
+        assert_eq!(
+            store.get_contract_by_token(token).await.unwrap(),
+            Some((tpg1.to_string(), "collateral".to_string()))
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_list_contracts_by_source_filtered_by_expiry() {
+        use contracts::option_offer::get_option_offer_address;
+
+        let path = "/tmp/test_coin_store_contracts_by_expiry.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let expired = OptionOfferArguments::new(asset, asset, asset, 100, 1, 1_000, [2; 32]);
+        let active = OptionOfferArguments::new(asset, asset, asset, 100, 1, u32::MAX, [2; 32]);
+
+        let mut tpgs = Vec::new();
+        for args in [&expired, &active] {
+            let tpg = TaprootPubkeyGen::from(args, &AddressParams::LIQUID_TESTNET, &get_option_offer_address).unwrap();
+            store
+                .add_contract(OPTION_OFFER_SOURCE, args.build_arguments(), tpg.clone(), None, None)
+                .await
+                .unwrap();
+            tpgs.push(tpg.to_string());
+        }
+
+        // Sources without a known argument layout have no expiry and never count as expired
+        let storage_tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        store
+            .add_contract(
+                BYTES32_TR_STORAGE_SOURCE,
+                simplicityhl::Arguments::default(),
+                storage_tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let tpgs_of = |rows: Vec<(Vec<u8>, String, Option<Vec<u8>>)>| -> Vec<String> {
+            rows.into_iter().map(|(_, tpg, _)| tpg).collect()
+        };
+
+        let expired_rows = store
+            .list_contracts_by_source_filtered(OPTION_OFFER_SOURCE, Some(true))
+            .await
+            .unwrap();
+        assert_eq!(tpgs_of(expired_rows), vec![tpgs[0].clone()]);
+
+        let active_rows = store
+            .list_contracts_by_source_filtered(OPTION_OFFER_SOURCE, Some(false))
+            .await
+            .unwrap();
+        assert_eq!(tpgs_of(active_rows), vec![tpgs[1].clone()]);
+
+        let all_rows = store
+            .list_contracts_by_source_filtered(OPTION_OFFER_SOURCE, None)
+            .await
+            .unwrap();
+        assert_eq!(all_rows.len(), 2);
+
+        assert!(
+            store
+                .list_contracts_by_source_filtered(BYTES32_TR_STORAGE_SOURCE, Some(true))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            tpgs_of(
+                store
+                    .list_contracts_by_source_filtered(BYTES32_TR_STORAGE_SOURCE, Some(false))
+                    .await
+                    .unwrap()
+            ),
+            vec![storage_tpg.to_string()]
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_contract_expiry_backfilled_for_rows_without_it() {
+        use contracts::option_offer::get_option_offer_address;
+
+        let path = "/tmp/test_coin_store_contract_expiry_backfill.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let expired = OptionOfferArguments::new(asset, asset, asset, 100, 1, 1_000, [3; 32]);
+        let tpg = TaprootPubkeyGen::from(&expired, &AddressParams::LIQUID_TESTNET, &get_option_offer_address).unwrap();
+        store
+            .add_contract(OPTION_OFFER_SOURCE, expired.build_arguments(), tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        // Simulate a row written before migration 009
+        sqlx::query("UPDATE simplicity_contracts SET expiry = NULL")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+
+        // Rows the backfill has not reached yet are decoded on the fly
+        let expired_rows = store
+            .list_contracts_by_source_filtered(OPTION_OFFER_SOURCE, Some(true))
+            .await
+            .unwrap();
+        assert_eq!(expired_rows.len(), 1);
+        assert!(
+            store
+                .list_contracts_by_source_filtered(OPTION_OFFER_SOURCE, Some(false))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        drop(store);
+        let store = Store::connect(path).await.unwrap();
+
+        let (expiry,): (Option<i64>,) =
+            sqlx::query_as("SELECT expiry FROM simplicity_contracts WHERE taproot_pubkey_gen = ?")
+                .bind(tpg.to_string())
+                .fetch_one(&store.pool)
+                .await
+                .unwrap();
+        assert_eq!(expiry, Some(1_000));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_query_by_source_hash() {
+        let path = "/tmp/test_coin_store_query_source_hash.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+        let script_pubkey = tpg.address.script_pubkey();
+
+        store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg, None, None)
+            .await
+            .unwrap();
+
+        let outpoint = OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 0);
+        let mut txout = make_explicit_txout(test_asset_id(), 3000);
+        txout.script_pubkey = script_pubkey;
+
+        store.insert(outpoint, txout, None).await.unwrap();
+
+        let filter = UtxoFilter::new().source(BYTES32_TR_STORAGE_SOURCE);
+        let results = store.query_utxos(&[filter]).await.unwrap();
+
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].value(), Some(3000));
+            }
+            _ => panic!("Expected Found result"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    fn make_explicit_txout_with_script(asset_id: AssetId, value: u64) -> TxOut {
+        TxOut {
+            asset: Asset::Explicit(asset_id),
+            value: Value::Explicit(value),
+            nonce: Nonce::Null,
+            script_pubkey: Script::new_op_return(b"burn"),
+            witness: TxOutWitness::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_ignores_duplicates() {
+        let path = "/tmp/test_coin_store_tx_dup.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+
+        let txout0 = make_explicit_txout_with_script(asset, 1000);
+        let txout1 = make_explicit_txout_with_script(asset, 2000);
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![txout0, txout1],
+        };
+
+        let result = store.insert_transaction(&tx, HashMap::new()).await;
+        assert!(result.is_ok(), "First insert_transaction should succeed");
+        let summary = result.unwrap();
+        assert_eq!(
+            summary.inserted,
+            vec![OutPoint::new(tx.txid(), 0), OutPoint::new(tx.txid(), 1)]
+        );
+        assert!(summary.skipped_duplicate.is_empty());
+
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 2, "Both UTXOs should be present after first insert");
+            }
+            _ => panic!("Expected Found result with 2 entries"),
+        }
+
+        let result = store.insert_transaction(&tx, HashMap::new()).await;
+        assert!(
+            result.is_ok(),
+            "Second insert_transaction should succeed (INSERT OR IGNORE)"
+        );
+        let summary = result.unwrap();
+        assert!(summary.inserted.is_empty());
+        assert_eq!(summary.skipped_duplicate.len(), 2);
+
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 2, "Should still have exactly 2 UTXOs");
+            }
+            _ => panic!("Expected Found result with 2 entries"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_cancelled_leaves_no_partial_rows() {
+        use std::task::Poll;
+
+        let path = "/tmp/test_coin_store_tx_cancel.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+        let tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: (1..=8)
+                .map(|value| make_explicit_txout_with_script(asset, value * 1000))
+                .collect(),
+        };
+        let txid = tx.txid();
+
+        // Drop the insert after an increasing number of polls, so it gets cancelled at
+        // every await point it reaches before committing.
+        for polls in 1..=200 {
+            let mut insert = Box::pin(store.insert_transaction(&tx, HashMap::new()));
+            let mut completed = false;
+            for _ in 0..polls {
+                if let Poll::Ready(result) = futures::poll!(insert.as_mut()) {
+                    result.unwrap();
+                    completed = true;
+                    break;
+                }
+                tokio::task::yield_now().await;
+            }
+            drop(insert);
+
+            let (tx_rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions WHERE txid = ?")
+                .bind(AsRef::<[u8]>::as_ref(&txid))
+                .fetch_one(&store.pool)
+                .await
+                .unwrap();
+            let (utxo_rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM utxos WHERE txid = ?")
+                .bind(AsRef::<[u8]>::as_ref(&txid))
+                .fetch_one(&store.pool)
+                .await
+                .unwrap();
+
+            // A commit that landed just before the drop is fine; anything in between is not
+            assert!(
+                (tx_rows, utxo_rows) == (0, 0) || (tx_rows, utxo_rows) == (1, 8),
+                "partial insert persisted after {polls} polls: {tx_rows} transaction rows, {utxo_rows} utxos"
+            );
+
+            if completed || tx_rows == 1 {
+                break;
+            }
+        }
+
+        let summary = store.insert_transaction(&tx, HashMap::new()).await.unwrap();
+        assert_eq!(summary.inserted.len() + summary.skipped_duplicate.len(), 8);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_skips_unblindable_outputs() {
+        let path = "/tmp/test_coin_store_tx_unblind.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+
+        let txout_explicit_0 = make_explicit_txout_with_script(asset, 1000);
+
+        let tag = secp256k1::Tag::from([1u8; 32]);
+        let generator = secp256k1::Generator::new_unblinded(secp256k1::SECP256K1, tag);
+        let txout_confidential = TxOut {
+            asset: Asset::Confidential(generator),
+            value: Value::Confidential(
+                secp256k1::PedersenCommitment::from_slice(&[
+                    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x01,
+                ])
+                .unwrap(),
+            ),
+            nonce: Nonce::Null,
+            script_pubkey: Script::new_op_return(b"burn"),
+            witness: TxOutWitness::default(),
+        };
+        let txout_explicit_2 = make_explicit_txout_with_script(asset, 3000);
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![txout_explicit_0, txout_confidential, txout_explicit_2],
+        };
+
+        let result = store.insert_transaction(&tx, HashMap::new()).await;
+        assert!(
+            result.is_ok(),
+            "insert_transaction should succeed, skipping unblindable outputs"
+        );
+        let summary = result.unwrap();
+        assert_eq!(
+            summary.inserted,
+            vec![OutPoint::new(tx.txid(), 0), OutPoint::new(tx.txid(), 2)]
+        );
+        assert_eq!(summary.skipped_unblindable, vec![OutPoint::new(tx.txid(), 1)]);
+
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 2, "Only explicit outputs should be inserted");
+                let values: Vec<_> = entries.iter().map(super::super::entry::UtxoEntry::value).collect();
+                assert!(values.contains(&Some(1000)));
+                assert!(values.contains(&Some(3000)));
+            }
+            _ => panic!("Expected Found result"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_prefer_from_contract_change() {
+        let path = "/tmp/test_coin_store_from_contract.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        store
+            .add_contract(
+                BYTES32_TR_STORAGE_SOURCE,
+                simplicityhl::Arguments::default(),
+                tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let contract_outpoint = OutPoint::new(Txid::from_byte_array([4; Txid::LEN]), 0);
+        let mut contract_txout = make_explicit_txout(asset, 1000);
+        contract_txout.script_pubkey = tpg.address.script_pubkey();
+        store.insert(contract_outpoint, contract_txout, None).await.unwrap();
+
+        // Unrelated, larger wallet coin
+        store
+            .insert(
+                OutPoint::new(Txid::from_byte_array([5; Txid::LEN]), 0),
+                make_explicit_txout_with_script(asset, 5000),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let spending_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![simplicityhl::elements::TxIn {
+                previous_output: contract_outpoint,
+                is_pegin: false,
+                script_sig: Script::new(),
+                sequence: simplicityhl::elements::Sequence::MAX,
+                asset_issuance: simplicityhl::elements::AssetIssuance::default(),
+                witness: simplicityhl::elements::TxInWitness::default(),
+            }],
+            output: vec![make_explicit_txout_with_script(asset, 900)],
+        };
+        store.insert_transaction(&spending_tx, HashMap::new()).await.unwrap();
+
+        let plain = UtxoFilter::new().asset_id(asset);
+        let preferring = UtxoFilter::new().asset_id(asset).prefer_from_contract(tpg);
+        let results = store.query_utxos(&[plain, preferring]).await.unwrap();
+
+        let values = |result: &UtxoQueryResult| match result {
+            UtxoQueryResult::Found(entries, _) => entries.iter().filter_map(UtxoEntry::value).collect::<Vec<_>>(),
+            _ => panic!("Expected Found result"),
+        };
+        assert_eq!(values(&results[0]), vec![5000, 900]);
+        assert_eq!(values(&results[1]), vec![900, 5000]);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_marks_inputs_as_spent() {
+        let path = "/tmp/test_coin_store_tx_spent.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+
+        let prev_txout = make_explicit_txout_with_script(asset, 500);
+        let prev_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![prev_txout],
+        };
+        store.insert_transaction(&prev_tx, HashMap::new()).await.unwrap();
+
+        let prev_txid = prev_tx.txid();
+        let prev_outpoint = OutPoint::new(prev_txid, 0);
+
+        let filter = UtxoFilter::new().asset_id(asset);
+        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
+
+        let new_txout = make_explicit_txout_with_script(asset, 400);
+        let tx_input = simplicityhl::elements::TxIn {
+            previous_output: prev_outpoint,
+            is_pegin: false,
+            script_sig: Script::new(),
+            sequence: simplicityhl::elements::Sequence::MAX,
+            asset_issuance: simplicityhl::elements::AssetIssuance::default(),
+            witness: simplicityhl::elements::TxInWitness::default(),
+        };
+
+        let spending_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![tx_input],
+            output: vec![new_txout],
+        };
+
+        let summary = store.insert_transaction(&spending_tx, HashMap::new()).await.unwrap();
+        assert_eq!(summary.spent, vec![prev_outpoint]);
+        assert_eq!(summary.inserted, vec![OutPoint::new(spending_tx.txid(), 0)]);
+
+        // Spending an output already marked spent is not reported again
+        let summary = store.insert_transaction(&spending_tx, HashMap::new()).await.unwrap();
+        assert!(summary.spent.is_empty());
+
+        let results = store.query_utxos(std::slice::from_ref(&filter.clone())).await.unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].value(), Some(400));
+            }
+            _ => panic!("Expected Found result with the new output"),
+        }
+
+        let filter_with_spent = UtxoFilter::new().asset_id(asset).include_spent();
+        let results = store
+            .query_utxos(std::slice::from_ref(&filter_with_spent))
+            .await
+            .unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 2);
+            }
+            _ => panic!("Expected Found result with both UTXOs"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_transaction_restores_inputs_and_removes_outputs() {
+        use simplicityhl::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+
+        let path = "/tmp/test_coin_store_tx_rollback.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+
+        let prev_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![make_explicit_txout_with_script(asset, 500)],
+        };
+        store.insert_transaction(&prev_tx, HashMap::new()).await.unwrap();
+        let prev_outpoint = OutPoint::new(prev_tx.txid(), 0);
+
+        // The spending transaction pays to a confidential output, so a blinder key row is stored
+        let secret_key = SecretKey::from_slice(&[5u8; crate::store::BLINDING_KEY_LEN]).unwrap();
+        let blinding_keypair = Keypair::from_secret_key(secp256k1::SECP256K1, &secret_key);
+        let address = simplicityhl::elements::Address::p2sh(
+            &Script::new(),
+            Some(blinding_keypair.public_key()),
+            &AddressParams::LIQUID_TESTNET,
+        );
+        let spent_secrets = TxOutSecrets::new(asset, AssetBlindingFactor::zero(), 400, ValueBlindingFactor::zero());
+        let (confidential_txout, _, _, _) = TxOut::new_not_last_confidential(
+            &mut rand::thread_rng(),
+            secp256k1::SECP256K1,
+            400,
+            address,
+            asset,
+            &[spent_secrets],
+        )
+        .unwrap();
+
+        let spending_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![simplicityhl::elements::TxIn {
+                previous_output: prev_outpoint,
+                is_pegin: false,
+                script_sig: Script::new(),
+                sequence: simplicityhl::elements::Sequence::MAX,
+                asset_issuance: simplicityhl::elements::AssetIssuance::default(),
+                witness: simplicityhl::elements::TxInWitness::default(),
+            }],
+            output: vec![confidential_txout, make_explicit_txout_with_script(asset, 50)],
+        };
+        let spending_txid = spending_tx.txid();
+        let created = [OutPoint::new(spending_txid, 0), OutPoint::new(spending_txid, 1)];
+
+        let blinder_keys_rows = async || -> i64 {
+            let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM blinder_keys")
+                .fetch_one(&store.pool)
+                .await
+                .unwrap();
+            count
+        };
+
+        // Spend, roll back, and spend again: the store ends up where it started each time
+        for _ in 0..2 {
+            let mut keys = HashMap::new();
+            keys.insert(0, blinding_keypair);
+            let summary = store.insert_transaction(&spending_tx, keys).await.unwrap();
+            assert_eq!(summary.spent, vec![prev_outpoint]);
+            assert_eq!(summary.inserted, created);
+            assert_eq!(blinder_keys_rows().await, 1);
+
+            let summary = store.rollback_transaction(spending_txid).await.unwrap();
+            assert_eq!(summary.unspent, vec![prev_outpoint]);
+            assert_eq!(summary.removed, created);
+
+            assert_eq!(blinder_keys_rows().await, 0);
+            assert_eq!(store.get_blinder_key(created[0]).await.unwrap(), None);
+            assert!(store.get_transaction(spending_txid).await.unwrap().is_none());
+
+            let results = store.query_utxos(&[UtxoFilter::new().asset_id(asset)]).await.unwrap();
+            match &results[0] {
+                UtxoQueryResult::Found(entries, _) => {
+                    assert_eq!(entries.len(), 1);
+                    assert_eq!(*entries[0].outpoint(), prev_outpoint);
+                }
+                _ => panic!("Expected only the restored input"),
+            }
+        }
+
+        // Rolling back a transaction the store does not know changes nothing
+        let summary = store.rollback_transaction(spending_txid).await.unwrap();
+        assert_eq!(summary, RollbackSummary::default());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_stores_full_transaction() {
+        let path = "/tmp/test_coin_store_tx_full.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let asset = test_asset_id();
+        let funding_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![make_explicit_txout_with_script(asset, 5000)],
+        };
+        store.insert_transaction(&funding_tx, HashMap::new()).await.unwrap();
+
+        let funding_outpoint = OutPoint::new(funding_tx.txid(), 0);
+        assert_eq!(
+            store.get_output_amount(funding_outpoint).await.unwrap(),
+            Some((asset, 5000))
+        );
+
+        let spend_tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![simplicityhl::elements::TxIn {
+                previous_output: funding_outpoint,
+                is_pegin: false,
+                script_sig: Script::new(),
+                sequence: simplicityhl::elements::Sequence::MAX,
+                asset_issuance: simplicityhl::elements::AssetIssuance::default(),
+                witness: simplicityhl::elements::TxInWitness::default(),
+            }],
+            output: vec![make_explicit_txout_with_script(asset, 4900)],
+        };
+        store.insert_transaction(&spend_tx, HashMap::new()).await.unwrap();
+
+        let stored = store.get_transaction(spend_tx.txid()).await.unwrap().unwrap();
+        assert_eq!(stored, spend_tx);
+
+        // Spent outputs keep their recorded amount
+        assert_eq!(
+            store.get_output_amount(funding_outpoint).await.unwrap(),
+            Some((asset, 5000))
+        );
+
+        let unknown = Txid::from_byte_array([7; 32]);
+        assert!(store.get_transaction(unknown).await.unwrap().is_none());
+        assert!(
+            store
+                .get_output_amount(OutPoint::new(unknown, 0))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_force_unblind_surfaces_errors() {
+        let path = "/tmp/test_coin_store_force_unblind.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let outpoint = OutPoint::new(Txid::from_byte_array([7; Txid::LEN]), 0);
+        let key = [1u8; crate::store::BLINDING_KEY_LEN];
+
+        let result = store.force_unblind(outpoint, key).await;
+        assert!(matches!(result, Err(StoreError::UtxoNotFound(o)) if o == outpoint));
+
+        store
+            .insert(outpoint, make_explicit_txout(test_asset_id(), 1000), None)
+            .await
+            .unwrap();
+
+        let result = store.force_unblind(outpoint, key).await;
+        assert!(matches!(result, Err(StoreError::Unblind(_))));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_get_blinder_key() {
+        use simplicityhl::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+
+        let path = "/tmp/test_coin_store_get_blinder_key.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let key = [5u8; crate::store::BLINDING_KEY_LEN];
+        let secret_key = SecretKey::from_slice(&key).unwrap();
+        let blinding_pubkey = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &secret_key);
+        let address = simplicityhl::elements::Address::p2sh(
+            &Script::new(),
+            Some(blinding_pubkey),
+            &AddressParams::LIQUID_TESTNET,
+        );
+
+        let spent_secrets = TxOutSecrets::new(
+            test_asset_id(),
+            AssetBlindingFactor::zero(),
+            1000,
+            ValueBlindingFactor::zero(),
+        );
+        let (txout, _, _, _) = TxOut::new_not_last_confidential(
+            &mut rand::thread_rng(),
+            secp256k1::SECP256K1,
+            1000,
+            address,
+            test_asset_id(),
+            &[spent_secrets],
+        )
+        .unwrap();
+
+        let confidential = OutPoint::new(Txid::from_byte_array([8; Txid::LEN]), 0);
+        let explicit = OutPoint::new(Txid::from_byte_array([8; Txid::LEN]), 1);
+
+        store.insert(confidential, txout, Some(key)).await.unwrap();
+        store
+            .insert(explicit, make_explicit_txout(test_asset_id(), 1000), None)
+            .await
+            .unwrap();
+
+        assert_eq!(store.get_blinder_key(confidential).await.unwrap(), Some(key));
+        assert_eq!(store.get_blinder_key(explicit).await.unwrap(), None);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_option_token_unblinds_after_reopen() {
+        use simplicityhl::elements::confidential::{AssetBlindingFactor, ValueBlindingFactor};
+
+        let path = "/tmp/test_coin_store_unblind_after_reopen.db";
+        let _ = fs::remove_file(path);
+
+        // Same keypair `option create` blinds the token outputs to
+        let blinding_keypair = simplicityhl_core::derive_public_blinder_key();
+        let address = simplicityhl::elements::Address::p2sh(
+            &Script::new(),
+            Some(blinding_keypair.public_key()),
+            &AddressParams::LIQUID_TESTNET,
+        );
+
+        let spent_secrets = TxOutSecrets::new(
+            test_asset_id(),
+            AssetBlindingFactor::zero(),
+            1,
+            ValueBlindingFactor::zero(),
+        );
+        let (txout, _, _, _) = TxOut::new_not_last_confidential(
+            &mut rand::thread_rng(),
+            secp256k1::SECP256K1,
+            1,
+            address,
+            test_asset_id(),
+            &[spent_secrets],
+        )
+        .unwrap();
+
+        let store = Store::create(path).await.unwrap();
+        let mut blinder_keys = HashMap::new();
+        blinder_keys.insert(0, blinding_keypair);
+        let tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![txout],
+        };
+        let outpoint = OutPoint::new(tx.txid(), 0);
+        store.insert_transaction(&tx, blinder_keys).await.unwrap();
+        store.pool.close().await;
+
+        // Reopening must not depend on re-deriving the key: it is read back from `blinder_keys`
+        let store = Store::connect(path).await.unwrap();
+        let stored_key = store.get_blinder_key(outpoint).await.unwrap();
+        assert_eq!(stored_key, Some(blinding_keypair.secret_key().secret_bytes()));
+
+        let secrets = store.force_unblind(outpoint, stored_key.unwrap()).await.unwrap();
+        assert_eq!(secrets.asset, test_asset_id());
+        assert_eq!(secrets.value, 1);
+
+        let results = store
+            .query_utxos(&[UtxoFilter::new().asset_id(test_asset_id())])
+            .await
+            .unwrap();
+        match &results[0] {
+            UtxoQueryResult::Found(entries, _) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].value(), Some(1));
+            }
+            _ => panic!("Expected Found result"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_contracts_with_utxos_groups_by_contract() {
+        let path = "/tmp/test_coin_store_contracts_with_utxos.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let funded = make_test_taproot_pubkey_gen([0u8; 32]);
+        let empty = make_test_taproot_pubkey_gen([1u8; 32]);
+        let token_asset = AssetId::from_slice(&[9; 32]).unwrap();
+
+        for tpg in [&funded, &empty] {
+            store
+                .add_contract(
+                    BYTES32_TR_STORAGE_SOURCE,
+                    simplicityhl::Arguments::default(),
+                    tpg.clone(),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        store
+            .insert_contract_token(&funded, token_asset, "option_token")
+            .await
+            .unwrap();
+
+        let mut collateral = make_explicit_txout(test_asset_id(), 5000);
+        collateral.script_pubkey = funded.address.script_pubkey();
+        let collateral_outpoint = OutPoint::new(Txid::from_byte_array([4; Txid::LEN]), 0);
+        store
+            .insert(collateral_outpoint, collateral.clone(), None)
+            .await
+            .unwrap();
+
+        // Spent outputs are not part of the portfolio
+        let spent_outpoint = OutPoint::new(Txid::from_byte_array([4; Txid::LEN]), 1);
+        store.insert(spent_outpoint, collateral, None).await.unwrap();
+        assert!(store.mark_as_spent(spent_outpoint).await.unwrap());
+
+        // A token held by the wallet belongs to the contract that issued it
+        let token_outpoint = OutPoint::new(Txid::from_byte_array([5; Txid::LEN]), 0);
+        store
+            .insert(token_outpoint, make_explicit_txout(token_asset, 10), None)
+            .await
+            .unwrap();
+
+        let contracts = store.contracts_with_utxos(BYTES32_TR_STORAGE_SOURCE).await.unwrap();
+        assert_eq!(contracts.len(), 2);
+
+        let (_, funded_entries) = contracts
+            .iter()
+            .find(|(contract, _)| contract.taproot_pubkey_gen == funded.to_string())
+            .unwrap();
+        let outpoints: Vec<OutPoint> = funded_entries.iter().map(|entry| *entry.outpoint()).collect();
+        assert_eq!(outpoints, vec![collateral_outpoint, token_outpoint]);
+        assert!(funded_entries.iter().all(|entry| entry.contract().is_some()));
+
+        let (_, empty_entries) = contracts
+            .iter()
+            .find(|(contract, _)| contract.taproot_pubkey_gen == empty.to_string())
+            .unwrap();
+        assert!(empty_entries.is_empty());
+
+        assert!(store.contracts_with_utxos("unknown source").await.unwrap().is_empty());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_actionable_contracts_for_own_option_offers() {
+        use contracts::option_offer::get_option_offer_address;
+
+        let path = "/tmp/test_coin_store_actionable_contracts.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let collateral_asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium_asset = AssetId::from_slice(&[2; 32]).unwrap();
+        let settlement_asset = AssetId::from_slice(&[3; 32]).unwrap();
+        let wallet_pubkey = Keypair::from_seckey_slice(secp256k1::SECP256K1, &[7; 32])
+            .unwrap()
+            .x_only_public_key()
+            .0
+            .serialize();
+        let other_pubkey = Keypair::from_seckey_slice(secp256k1::SECP256K1, &[8; 32])
+            .unwrap()
+            .x_only_public_key()
+            .0
+            .serialize();
+
+        let expiry = 1_704_067_200;
+        let own = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            settlement_asset,
+            100,
+            1,
+            expiry,
+            wallet_pubkey,
+        );
+        let foreign = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            settlement_asset,
+            100,
+            1,
+            expiry,
+            other_pubkey,
+        );
+
+        let mut vout = 0;
+        for (args, collateral, settlement) in [(&own, 400, 6_000), (&foreign, 0, 9_000)] {
+            let tpg = TaprootPubkeyGen::from(args, &AddressParams::LIQUID_TESTNET, &get_option_offer_address).unwrap();
+            store
+                .add_contract(OPTION_OFFER_SOURCE, args.build_arguments(), tpg.clone(), None, None)
+                .await
+                .unwrap();
+
+            for (asset, value) in [(collateral_asset, collateral), (settlement_asset, settlement)] {
+                if value == 0 {
+                    continue;
+                }
+                let mut txout = make_explicit_txout(asset, value);
+                txout.script_pubkey = tpg.address.script_pubkey();
+                store
+                    .insert(OutPoint::new(Txid::from_byte_array([6; Txid::LEN]), vout), txout, None)
+                    .await
+                    .unwrap();
+                vout += 1;
+            }
+        }
+
+        // Before expiry only the settlement taken so far needs action
+        let before = store
+            .actionable_contracts(wallet_pubkey, i64::from(expiry) - 1)
+            .await
+            .unwrap();
+        let actions: Vec<ActionNeeded> = before.iter().map(|(_, action)| *action).collect();
+        assert_eq!(
+            actions,
+            vec![ActionNeeded::WithdrawOfferSettlement {
+                asset_id: settlement_asset,
+                amount: 6_000
+            }]
+        );
+
+        let after = store
+            .actionable_contracts(wallet_pubkey, i64::from(expiry))
+            .await
+            .unwrap();
+        let actions: Vec<ActionNeeded> = after.iter().map(|(_, action)| *action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                ActionNeeded::WithdrawOfferSettlement {
+                    asset_id: settlement_asset,
+                    amount: 6_000
+                },
+                ActionNeeded::ReclaimOfferCollateral {
+                    asset_id: collateral_asset,
+                    amount: 400
+                },
+            ]
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_locked_collateral_in_own_option_offers() {
+        use contracts::option_offer::get_option_offer_address;
+
+        let path = "/tmp/test_coin_store_locked_collateral.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let collateral_asset = AssetId::from_slice(&[1; 32]).unwrap();
+        let premium_asset = AssetId::from_slice(&[2; 32]).unwrap();
+        let settlement_asset = AssetId::from_slice(&[3; 32]).unwrap();
+        let wallet_pubkey = Keypair::from_seckey_slice(secp256k1::SECP256K1, &[7; 32])
+            .unwrap()
+            .x_only_public_key()
+            .0
+            .serialize();
+
+        let expiry = 1_704_067_200;
+        let args = OptionOfferArguments::new(
+            collateral_asset,
+            premium_asset,
+            settlement_asset,
+            100,
+            1,
+            expiry,
+            wallet_pubkey,
+        );
+        let tpg = TaprootPubkeyGen::from(&args, &AddressParams::LIQUID_TESTNET, &get_option_offer_address).unwrap();
+        store
+            .add_contract(OPTION_OFFER_SOURCE, args.build_arguments(), tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        for (vout, (asset, value)) in [(collateral_asset, 400), (premium_asset, 40), (settlement_asset, 6_000)]
+            .into_iter()
+            .enumerate()
+        {
+            let mut txout = make_explicit_txout(asset, value);
+            txout.script_pubkey = tpg.address.script_pubkey();
+            store
+                .insert(
+                    OutPoint::new(Txid::from_byte_array([9; Txid::LEN]), u32::try_from(vout).unwrap()),
+                    txout,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let before = store
+            .locked_collateral(wallet_pubkey, i64::from(expiry) - 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            before.get(&(collateral_asset, LockedRole::Collateral)),
+            Some(&LockedCollateral {
+                offered: 400,
+                reclaimable: 0
+            })
+        );
+        assert_eq!(
+            before
+                .get(&(premium_asset, LockedRole::Premium))
+                .map(LockedCollateral::total),
+            Some(40)
+        );
+        assert!(before.keys().all(|(asset, _)| *asset != settlement_asset));
+
+        let after = store.locked_collateral(wallet_pubkey, i64::from(expiry)).await.unwrap();
+        assert_eq!(
+            after.get(&(collateral_asset, LockedRole::Collateral)),
+            Some(&LockedCollateral {
+                offered: 0,
+                reclaimable: 400
+            })
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_locked_collateral_with_shared_assets() {
+        use contracts::option_offer::get_option_offer_address;
+
+        let path = "/tmp/test_coin_store_locked_shared_assets.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let lbtc = AssetId::from_slice(&[1; 32]).unwrap();
+        let usdt = AssetId::from_slice(&[2; 32]).unwrap();
+        let wallet_pubkey = Keypair::from_seckey_slice(secp256k1::SECP256K1, &[7; 32])
+            .unwrap()
+            .x_only_public_key()
+            .0
+            .serialize();
+
+        let expiry = 1_704_067_200;
+        // Premium paid in the collateral asset, settlement in a third asset
+        let same_premium = OptionOfferArguments::new(lbtc, lbtc, usdt, 100, 2, expiry, wallet_pubkey);
+        // Premium paid in the settlement asset
+        let same_settlement = OptionOfferArguments::new(lbtc, usdt, usdt, 100, 2, expiry, wallet_pubkey);
+
+        let outputs = [
+            (&same_premium, vec![(lbtc, 300), (lbtc, 600)]),
+            (&same_settlement, vec![(lbtc, 200), (usdt, 400), (usdt, 5_000)]),
+        ];
+        for (seed, (args, values)) in (10u8..).zip(outputs) {
+            let tpg = TaprootPubkeyGen::from(args, &AddressParams::LIQUID_TESTNET, &get_option_offer_address).unwrap();
+            store
+                .add_contract(OPTION_OFFER_SOURCE, args.build_arguments(), tpg.clone(), None, None)
+                .await
+                .unwrap();
+
+            for (vout, (asset, value)) in (0u32..).zip(values) {
+                let mut txout = make_explicit_txout(asset, value);
+                txout.script_pubkey = tpg.address.script_pubkey();
+                store
+                    .insert(
+                        OutPoint::new(Txid::from_byte_array([seed; Txid::LEN]), vout),
+                        txout,
+                        None,
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let locked = store
+            .locked_collateral(wallet_pubkey, i64::from(expiry) - 1)
+            .await
+            .unwrap();
+        let total = |asset, role| locked.get(&(asset, role)).map_or(0, LockedCollateral::total);
+
+        // 300 collateral with 600 premium in the same asset, plus 200 collateral of the second offer
+        assert_eq!(total(lbtc, LockedRole::Collateral), 500);
+        assert_eq!(total(lbtc, LockedRole::Premium), 600);
+        // The taker's 5000 settlement is not premium
+        assert_eq!(total(usdt, LockedRole::Premium), 400);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_contract_activity_includes_spent() {
+        let path = "/tmp/test_coin_store_contract_activity.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        let arguments = simplicityhl::Arguments::default();
+        let script_pubkey = tpg.address.script_pubkey();
+
+        store
+            .add_contract(BYTES32_TR_STORAGE_SOURCE, arguments, tpg.clone(), None, None)
+            .await
+            .unwrap();
+
+        let spent_outpoint = OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 0);
+        let unspent_outpoint = OutPoint::new(Txid::from_byte_array([3; Txid::LEN]), 1);
+
+        for (outpoint, value) in [(spent_outpoint, 1000), (unspent_outpoint, 2000)] {
+            let mut txout = make_explicit_txout(test_asset_id(), value);
+            txout.script_pubkey = script_pubkey.clone();
+            store.insert(outpoint, txout, None).await.unwrap();
+        }
+
+        assert!(store.mark_as_spent(spent_outpoint).await.unwrap());
+
+        let filter = UtxoFilter::new().taproot_pubkey_gen(tpg.clone());
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 1));
+
+        let filter = UtxoFilter::new().taproot_pubkey_gen(tpg.clone()).include_spent();
+        let results = store.query_utxos(&[filter]).await.unwrap();
+        assert!(matches!(&results[0], UtxoQueryResult::Found(e, _) if e.len() == 2));
+
+        let activity = store.contract_activity(&tpg).await.unwrap();
+        assert_eq!(activity.len(), 2);
+
+        for (entry, is_spent) in &activity {
+            assert_eq!(*is_spent, *entry.outpoint() == spent_outpoint);
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_prune_spent_keeps_unspent_and_contract_outputs() {
+        let path = "/tmp/test_coin_store_prune.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        store
+            .add_contract(
+                BYTES32_TR_STORAGE_SOURCE,
+                simplicityhl::Arguments::default(),
+                tpg.clone(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let asset = test_asset_id();
+        let outpoints: Vec<OutPoint> = (1u8..=6)
+            .map(|byte| OutPoint::new(Txid::from_byte_array([byte; Txid::LEN]), 0))
+            .collect();
+        for (index, outpoint) in outpoints.iter().enumerate() {
+            let mut txout = make_explicit_txout(asset, 1_000 * (index as u64 + 1));
+            if index == 5 {
+                txout.script_pubkey = tpg.address.script_pubkey();
+            }
+            store.insert(*outpoint, txout, None).await.unwrap();
+        }
+
+        // Three wallet outputs and the contract output are spent
+        for outpoint in [outpoints[0], outpoints[2], outpoints[4], outpoints[5]] {
+            store.mark_as_spent(outpoint).await.unwrap();
+        }
+
+        let unspent = |results: Vec<UtxoQueryResult>| -> Vec<OutPoint> {
+            match &results[0] {
+                UtxoQueryResult::Found(entries, _) => entries.iter().map(|entry| *entry.outpoint()).collect(),
+                _ => panic!("Expected Found result"),
+            }
+        };
+        let filter = UtxoFilter::new().asset_id(asset);
+        let before = unspent(store.query_utxos(std::slice::from_ref(&filter)).await.unwrap());
+
+        assert_eq!(store.prune_spent(None).await.unwrap(), 3);
+        store.vacuum().await.unwrap();
+
+        let after = unspent(store.query_utxos(&[filter]).await.unwrap());
+        assert_eq!(before, after);
+
+        // The contract's spent output stays part of its history
+        let activity = store.contract_activity(&tpg).await.unwrap();
+        assert_eq!(activity.len(), 1);
+        assert!(activity[0].1);
+
+        assert_eq!(store.prune_spent(None).await.unwrap(), 0);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_record_interaction_inserts_and_updates_metadata() {
+        let path = "/tmp/test_coin_store_record_interaction.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create(path).await.unwrap();
+
+        let tpg = make_test_taproot_pubkey_gen([0u8; 32]);
+        store
+            .add_contract(
+                BYTES32_TR_STORAGE_SOURCE,
+                simplicityhl::Arguments::default(),
+                tpg.clone(),
+                Some(b"created".as_slice()),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: simplicityhl::elements::LockTime::ZERO,
+            input: vec![],
+            output: vec![make_explicit_txout_with_script(test_asset_id(), 500)],
+        };
+
+        let summary = store
+            .record_interaction(&tx, HashMap::new(), &tpg, |metadata| {
+                let mut metadata = metadata.unwrap_or_default();
+                metadata.extend_from_slice(b",taken");
+                Some(metadata)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(summary.inserted, vec![OutPoint::new(tx.txid(), 0)]);
+        assert_eq!(
+            store.get_contract_metadata(&tpg).await.unwrap(),
+            Some(b"created,taken".to_vec())
+        );
+
+        // Declining the update leaves the metadata untouched
+        store
+            .record_interaction(&tx, HashMap::new(), &tpg, |_| None)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get_contract_metadata(&tpg).await.unwrap(),
+            Some(b"created,taken".to_vec())
+        );
+
+        let _ = fs::remove_file(path);
+    }
+
+    /// A row as `fetch_utxo_rows` would read it, with the columns most likely to be corrupted left to the caller.
+    fn make_row(
+        txid: Vec<u8>,
+        serialized: Vec<u8>,
+        is_confidential: bool,
+        serialized_witness: Option<Vec<u8>>,
+        blinding_key: Option<Vec<u8>>,
+    ) -> UtxoRow {
+        UtxoRow {
+            txid,
+            vout: 0,
+            serialized,
+            serialized_witness,
+            is_confidential: i64::from(is_confidential),
+            is_spent: 0,
+            value: 0,
+            height: None,
+            confirmation_status: ConfirmationStatus::Pending.as_sql().to_string(),
+            blinding_key,
+            source: None,
+            arguments: None,
+            taproot_pubkey_gen: None,
+            entropy: None,
+            issuance_is_confidential: None,
+        }
+    }
+
+    #[test]
+    fn test_row_with_corrupt_arguments_is_skipped() {
+        let serialized = encode::serialize(&make_explicit_txout(test_asset_id(), 1000));
+        let mut row = make_row(vec![7; Txid::LEN], serialized.clone(), false, None, None);
+        row.source = Some(b"fn main() {}".to_vec());
+        row.arguments = Some(vec![ARGUMENTS_FORMAT_VERSION, 0xff, 0xff]);
+        let context = ContractContext::new().add_program_from_row(&row).unwrap();
+        assert!(row.into_entry_or_skip(&context).unwrap().is_none());
+        let row = make_row(vec![7; Txid::LEN], serialized, false, None, None);
+        assert!(row.into_entry_or_skip(&ContractContext::new()).unwrap().is_some());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_into_entry_does_not_panic_on_arbitrary_row(
+            txid in collection::vec(any::<u8>(), 0..40),
+            serialized in collection::vec(any::<u8>(), 0..256),
+            is_confidential in any::<bool>(),
+            serialized_witness in option::of(collection::vec(any::<u8>(), 0..256)),
+            blinding_key in option::of(collection::vec(any::<u8>(), 0..40)),
+            source in option::of(collection::vec(any::<u8>(), 0..64)),
+            arguments in option::of(collection::vec(any::<u8>(), 0..64)),
+            entropy in option::of(collection::vec(any::<u8>(), 0..40)),
+        ) {
+            let mut row = make_row(txid, serialized, is_confidential, serialized_witness, blinding_key);
+            row.source = source;
+            row.arguments = arguments;
+            row.entropy = entropy;
+            row.issuance_is_confidential = Some(1);
+
+            let _ = row.into_entry(&ContractContext::new());
+        }
+
+        #[test]
+        fn prop_into_entry_rejects_malformed_explicit_row(
+            txid_len in 0usize..40,
+            arguments in option::of(collection::vec(any::<u8>(), 0..64)),
+            entropy_len in 0usize..40,
+        ) {
+            let serialized = encode::serialize(&make_explicit_txout(test_asset_id(), 1000));
+            let mut row = make_row(vec![7; txid_len], serialized, false, None, None);
+            row.source = arguments.as_ref().map(|_| b"fn main() {}".to_vec());
+            row.arguments.clone_from(&arguments);
+            row.entropy = Some(vec![3; entropy_len]);
+
+            let well_formed = txid_len == Txid::LEN
+                && entropy_len == 32
+                && arguments.as_deref().is_none_or(|bytes| decode_arguments(bytes).is_ok());
+
+            prop_assert_eq!(row.into_entry(&ContractContext::new()).is_ok(), well_formed);
+        }
+
+        #[test]
+        fn prop_into_entry_rejects_malformed_confidential_row(
+            serialized in collection::vec(any::<u8>(), 0..256),
+            serialized_witness in option::of(collection::vec(any::<u8>(), 0..256)),
+            blinding_key in option::of(collection::vec(any::<u8>(), 0..40)),
+        ) {
+            // An explicit output can never be unblinded, whatever the other columns contain
+            let serialized = if serialized.is_empty() {
+                encode::serialize(&make_explicit_txout(test_asset_id(), 1000))
+            } else {
+                serialized
+            };
+            let row = make_row(vec![7; Txid::LEN], serialized, true, serialized_witness, blinding_key);
+
+            prop_assert!(row.into_entry(&ContractContext::new()).is_err());
+        }
+    }
 }
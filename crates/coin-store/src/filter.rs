@@ -1,3 +1,5 @@
+use crate::selection::SelectionStrategy;
+
 use contracts::sdk::taproot_pubkey_gen::TaprootPubkeyGen;
 use simplicityhl::elements::hashes::{Hash, sha256};
 use simplicityhl::{
@@ -5,18 +7,63 @@ use simplicityhl::{
     simplicity::Cmr,
 };
 
+/// Row ordering applied to UTXO queries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UtxoOrder {
+    /// Largest value first (largest-first coin selection).
+    #[default]
+    ValueDesc,
+    /// Smallest value first.
+    ValueAsc,
+    /// Earliest stored first (FIFO).
+    Oldest,
+    /// Most recently stored first.
+    Newest,
+    /// Random order, for privacy-preserving selection.
+    Random,
+}
+
+impl UtxoOrder {
+    /// SQL `ORDER BY` clause body. Insertion order is tracked by the `utxos.inserted_seq` column.
+    pub(crate) const fn as_sql(self) -> &'static str {
+        match self {
+            Self::ValueDesc => "u.value DESC",
+            Self::ValueAsc => "u.value ASC",
+            Self::Oldest => "u.inserted_seq ASC",
+            Self::Newest => "u.inserted_seq DESC",
+            Self::Random => "RANDOM()",
+        }
+    }
+
+    /// Secondary `ORDER BY` keys breaking ties by outpoint, so equal values come back in a stable order.
+    pub(crate) const fn tiebreak_sql(self) -> Option<&'static str> {
+        match self {
+            Self::ValueDesc | Self::ValueAsc => Some("u.txid ASC, u.vout ASC"),
+            // Insert sequence numbers are unique, and a random order is meant to differ between queries
+            Self::Oldest | Self::Newest | Self::Random => None,
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct UtxoFilter {
     pub asset_id: Option<AssetId>,
     pub script_pubkey: Option<Script>,
+    pub script_pubkeys: Option<Vec<Script>>,
     pub required_value: Option<u64>,
     pub limit: Option<i64>,
     pub include_spent: bool,
+    pub confirmed_only: bool,
     pub include_entropy: bool,
     pub cmr: Option<Cmr>,
     pub taproot_pubkey_gen: Option<TaprootPubkeyGen>,
     pub source_hash: Option<[u8; 32]>,
     pub token_tag: Option<String>,
+    pub label: Option<String>,
+    pub contracts_only: bool,
+    pub prefer_from_contract: Option<TaprootPubkeyGen>,
+    pub order: UtxoOrder,
+    pub selection_strategy: Option<SelectionStrategy>,
 }
 
 impl UtxoFilter {
@@ -42,6 +89,15 @@ impl UtxoFilter {
         self
     }
 
+    /// Match UTXOs locked to any of `scripts`, e.g. every address of a wallet.
+    ///
+    /// Combines with `script_pubkey` by AND; an empty list matches nothing.
+    #[must_use]
+    pub fn script_pubkeys(mut self, scripts: Vec<Script>) -> Self {
+        self.script_pubkeys = Some(scripts);
+        self
+    }
+
     #[must_use]
     pub const fn required_value(mut self, value: u64) -> Self {
         self.required_value = Some(value);
@@ -54,12 +110,41 @@ impl UtxoFilter {
         self
     }
 
+    #[must_use]
+    pub const fn order_by(mut self, order: UtxoOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// With a `required_value`, return only the UTXOs picked by `strategy` instead of every
+    /// matching row. The picked UTXOs come back in the order the strategy chose them.
+    #[must_use]
+    pub const fn selection_strategy(mut self, strategy: SelectionStrategy) -> Self {
+        self.selection_strategy = Some(strategy);
+        self
+    }
+
+    /// Rank change previously received from transactions with this contract ahead of other
+    /// coins, then apply the regular order.
+    #[must_use]
+    pub fn prefer_from_contract(mut self, tpg: TaprootPubkeyGen) -> Self {
+        self.prefer_from_contract = Some(tpg);
+        self
+    }
+
     #[must_use]
     pub const fn include_spent(mut self) -> Self {
         self.include_spent = true;
         self
     }
 
+    /// Skip outputs whose creating transaction has not been seen in a block yet.
+    #[must_use]
+    pub const fn confirmed_only(mut self) -> Self {
+        self.confirmed_only = true;
+        self
+    }
+
     #[must_use]
     pub const fn include_entropy(mut self) -> Self {
         self.include_entropy = true;
@@ -95,12 +180,28 @@ impl UtxoFilter {
         self
     }
 
+    /// Restrict results to UTXOs of contracts stored with the given label.
+    #[must_use]
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Restrict results to UTXOs sitting at any tracked contract address.
+    #[must_use]
+    pub const fn contracts_only(mut self) -> Self {
+        self.contracts_only = true;
+        self
+    }
+
     #[must_use]
     pub(crate) const fn is_contract_join(&self) -> bool {
-        self.cmr.is_some()
+        self.contracts_only
+            || self.cmr.is_some()
             || self.taproot_pubkey_gen.is_some()
             || self.source_hash.is_some()
             || self.token_tag.is_some()
+            || self.label.is_some()
     }
 
     #[must_use]
@@ -1,16 +1,28 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
+pub mod action;
+pub mod arguments;
+pub mod balance;
+pub mod contract;
 pub mod entry;
 pub mod error;
 pub mod executor;
 pub mod filter;
+pub mod selection;
 pub mod store;
+pub mod summary;
 
+pub use action::ActionNeeded;
+pub use arguments::{ARGUMENTS_FORMAT_VERSION, decode_arguments, encode_arguments};
+pub use balance::{DetailedBalance, LockedBalances, LockedCollateral, LockedRole};
+pub use contract::{ContractRow, NewContract};
 pub use error::StoreError;
 pub use simplicityhl::elements::AssetId;
 pub use store::Store;
+pub use summary::{InsertSummary, RollbackSummary};
 
-pub use entry::{UtxoEntry, UtxoQueryResult};
+pub use entry::{ConfirmationStatus, UtxoEntry, UtxoQueryResult, compile_program};
 pub use executor::UtxoStore;
-pub use filter::UtxoFilter;
+pub use filter::{UtxoFilter, UtxoOrder};
+pub use selection::{CoinSelector, SelectedCoins, SelectionStrategy};
@@ -0,0 +1,227 @@
+use crate::{Store, StoreError, UtxoEntry, UtxoFilter, UtxoQueryResult, UtxoStore};
+
+use std::cmp::Reverse;
+
+use simplicityhl::elements::{AssetId, OutPoint, Script, TxOut};
+
+/// How a query with a `required_value` picks UTXOs from the matching rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionStrategy {
+    /// Accumulate the largest UTXOs until the target is covered.
+    LargestFirst,
+    /// Accumulate the smallest UTXOs until the target is covered, consolidating dust.
+    SmallestFirst,
+    /// Search for a set whose total lands within `tolerance` above the target, so no change
+    /// output is needed. Falls back to `LargestFirst` when no such set is found.
+    BranchAndBound { tolerance: u64 },
+    /// Take the smallest single UTXO covering the target, falling back to `LargestFirst`.
+    MinimizeChange,
+}
+
+/// Picks wallet coins of one asset locked to one script, replacing the filter, query and
+/// result matching otherwise repeated at every call site.
+#[derive(Clone, Copy, Debug)]
+pub struct CoinSelector {
+    strategy: SelectionStrategy,
+}
+
+impl Default for CoinSelector {
+    fn default() -> Self {
+        Self {
+            strategy: SelectionStrategy::LargestFirst,
+        }
+    }
+}
+
+impl CoinSelector {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn strategy(mut self, strategy: SelectionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Select unspent coins of `asset` at `script_pubkey` worth at least `amount`.
+    ///
+    /// Fails with [`StoreError::NoCoins`] when there is nothing to select from, and with
+    /// [`StoreError::InsufficientCoins`] when all coins together fall short.
+    pub async fn select(
+        &self,
+        store: &Store,
+        asset: AssetId,
+        script_pubkey: &Script,
+        amount: u64,
+    ) -> Result<SelectedCoins, StoreError> {
+        let filter = UtxoFilter::new()
+            .asset_id(asset)
+            .script_pubkey(script_pubkey.clone())
+            .required_value(amount)
+            .selection_strategy(self.strategy);
+
+        match store.query_utxos(&[filter]).await?.pop() {
+            Some(UtxoQueryResult::Found(entries, _)) => Ok(SelectedCoins::new(entries)),
+            Some(UtxoQueryResult::InsufficientValue(entries, _)) => Err(StoreError::InsufficientCoins {
+                asset,
+                required: amount,
+                available: SelectedCoins::new(entries).total(),
+            }),
+            Some(UtxoQueryResult::Empty) | None => Err(StoreError::NoCoins { asset }),
+        }
+    }
+}
+
+/// Coins chosen by a [`CoinSelector`], in the order the strategy picked them.
+#[derive(Debug)]
+pub struct SelectedCoins {
+    entries: Vec<UtxoEntry>,
+    total: u64,
+}
+
+impl SelectedCoins {
+    fn new(entries: Vec<UtxoEntry>) -> Self {
+        let total = entries.iter().filter_map(UtxoEntry::value).sum();
+        Self { entries, total }
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[UtxoEntry] {
+        &self.entries
+    }
+
+    /// Combined value of the selected coins.
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The selected coin, if the amount was covered by exactly one.
+    #[must_use]
+    pub fn single(&self) -> Option<&UtxoEntry> {
+        match self.entries.as_slice() {
+            [entry] => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// The selected coins as `(outpoint, txout)` transaction inputs.
+    #[must_use]
+    pub fn inputs(&self) -> Vec<(OutPoint, TxOut)> {
+        self.entries.iter().map(UtxoEntry::as_input).collect()
+    }
+}
+
+/// Upper bound on branches visited by the branch-and-bound search.
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// Pick UTXOs worth at least `target` from `values`, returning their indices.
+///
+/// Equal values keep the order they were given in. Returns `None` if all values together
+/// fall short of the target.
+pub(crate) fn select(values: &[u64], target: u64, strategy: SelectionStrategy) -> Option<Vec<usize>> {
+    let total: u128 = values.iter().map(|&value| u128::from(value)).sum();
+    if total < u128::from(target) {
+        return None;
+    }
+
+    let mut descending: Vec<usize> = (0..values.len()).collect();
+    descending.sort_by_key(|&index| Reverse(values[index]));
+
+    match strategy {
+        SelectionStrategy::LargestFirst => Some(accumulate(values, &descending, target)),
+        SelectionStrategy::SmallestFirst => {
+            let mut ascending: Vec<usize> = (0..values.len()).collect();
+            ascending.sort_by_key(|&index| values[index]);
+            Some(accumulate(values, &ascending, target))
+        }
+        SelectionStrategy::BranchAndBound { tolerance } => Some(
+            branch_and_bound(values, &descending, target, tolerance)
+                .unwrap_or_else(|| accumulate(values, &descending, target)),
+        ),
+        SelectionStrategy::MinimizeChange => {
+            let single = (0..values.len())
+                .filter(|&index| values[index] >= target)
+                .min_by_key(|&index| values[index]);
+            Some(single.map_or_else(|| accumulate(values, &descending, target), |index| vec![index]))
+        }
+    }
+}
+
+fn accumulate(values: &[u64], order: &[usize], target: u64) -> Vec<usize> {
+    let mut selected = Vec::new();
+    let mut sum: u128 = 0;
+
+    for &index in order {
+        if sum >= u128::from(target) {
+            break;
+        }
+        sum += u128::from(values[index]);
+        selected.push(index);
+    }
+
+    selected
+}
+
+/// Depth-first search over `order` (largest first) for the set with the least excess over
+/// `target`, accepting at most `tolerance` of excess.
+fn branch_and_bound(values: &[u64], order: &[usize], target: u64, tolerance: u64) -> Option<Vec<usize>> {
+    struct Search<'a> {
+        values: &'a [u64],
+        order: &'a [usize],
+        remaining: Vec<u128>,
+        target: u128,
+        ceiling: u128,
+        tries: usize,
+        current: Vec<usize>,
+        best: Option<(u128, Vec<usize>)>,
+    }
+
+    impl Search<'_> {
+        fn visit(&mut self, position: usize, sum: u128) {
+            if sum > self.ceiling || self.tries >= BNB_MAX_TRIES {
+                return;
+            }
+            self.tries += 1;
+
+            if sum >= self.target {
+                if self.best.as_ref().is_none_or(|(best_sum, _)| sum < *best_sum) {
+                    self.best = Some((sum, self.current.clone()));
+                }
+                return;
+            }
+
+            if position == self.order.len() || sum + self.remaining[position] < self.target {
+                return;
+            }
+
+            let index = self.order[position];
+            self.current.push(index);
+            self.visit(position + 1, sum + u128::from(self.values[index]));
+            self.current.pop();
+
+            self.visit(position + 1, sum);
+        }
+    }
+
+    let mut remaining = vec![0u128; order.len() + 1];
+    for position in (0..order.len()).rev() {
+        remaining[position] = remaining[position + 1] + u128::from(values[order[position]]);
+    }
+
+    let mut search = Search {
+        values,
+        order,
+        remaining,
+        target: u128::from(target),
+        ceiling: u128::from(target) + u128::from(tolerance),
+        tries: 0,
+        current: Vec::new(),
+        best: None,
+    };
+    search.visit(0, 0);
+
+    search.best.map(|(_, selected)| selected)
+}
@@ -1,12 +1,24 @@
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::str::FromStr;
 
 use sqlx::SqlitePool;
 use sqlx::migrate::Migrator;
+use sqlx::sqlite::SqliteConnectOptions;
 
 use crate::error::StoreError;
 
 static MIGRATOR: Migrator = sqlx::migrate!();
+/// Table added to backup archives, holding the manifest as key/value pairs.
+const BACKUP_MANIFEST_TABLE: &str = "backup_manifest";
+/// Manifest key recording the schema version of the backed up database.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
 pub(crate) const BLINDING_KEY_LEN: usize = 32;
+/// Spent outputs `prune_spent` may delete; binds the height limit twice.
+const PRUNABLE_SPENT_CONDITION: &str = "u.is_spent = 1
+    AND (? IS NULL OR (u.height IS NOT NULL AND u.height < ?))
+    AND NOT EXISTS (SELECT 1 FROM simplicity_contracts c WHERE c.script_pubkey = u.script_pubkey)
+    AND NOT EXISTS (SELECT 1 FROM contract_tokens ct WHERE ct.asset_id = u.asset_id)";
 
 pub struct Store {
     pub(crate) pool: SqlitePool,
@@ -39,29 +51,267 @@ impl Store {
         let path = path.as_ref();
         let pool = SqlitePool::connect(&Self::connection_url(path, true)).await?;
 
-        if !Self::is_empty(&pool).await? {
-            return Err(StoreError::DbAlreadyExists(path.to_path_buf()));
+        Self::initialize(pool, path).await
+    }
+
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Err(StoreError::NotFound(path.to_path_buf()));
         }
 
-        MIGRATOR.run(&pool).await?;
+        let pool = SqlitePool::connect(&Self::connection_url(path, false)).await?;
 
-        Ok(Self { pool })
+        Self::open_initialized(pool, path).await
     }
 
-    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+    /// Delete spent outputs and their blinder keys, returning how many outputs were removed.
+    ///
+    /// With `older_than_height`, only outputs confirmed below that height are removed. Spent
+    /// outputs at a contract address or holding a contract token are kept, as contract history is
+    /// read from them. Transactions spending pruned outputs can no longer be rolled back.
+    pub async fn prune_spent(&self, older_than_height: Option<u32>) -> Result<u64, StoreError> {
+        let prunable = format!("SELECT u.txid, u.vout FROM utxos u WHERE {PRUNABLE_SPENT_CONDITION}");
+
+        let mut db_tx = self.pool.begin().await?;
+
+        sqlx::query(&format!("DELETE FROM blinder_keys WHERE (txid, vout) IN ({prunable})"))
+            .bind(older_than_height)
+            .bind(older_than_height)
+            .execute(&mut *db_tx)
+            .await?;
+
+        let removed = sqlx::query(&format!("DELETE FROM utxos WHERE (txid, vout) IN ({prunable})"))
+            .bind(older_than_height)
+            .bind(older_than_height)
+            .execute(&mut *db_tx)
+            .await?
+            .rows_affected();
+
+        db_tx.commit().await?;
+
+        Ok(removed)
+    }
+
+    /// Rebuild the database file, returning the space freed by deletes such as [`Store::prune_spent`].
+    pub async fn vacuum(&self) -> Result<(), StoreError> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    /// Create a database encrypted with SQLCipher under `passphrase`.
+    ///
+    /// Encrypted and plain databases are not interchangeable: an encrypted file can only be
+    /// opened with [`Store::connect_encrypted`] and the same passphrase, and an existing plain
+    /// database is not encrypted in place.
+    #[cfg(feature = "sqlcipher")]
+    pub async fn create_encrypted(path: impl AsRef<Path>, passphrase: &str) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        let pool = SqlitePool::connect_with(Self::encrypted_options(path, true, passphrase)?).await?;
+
+        Self::initialize(pool, path).await
+    }
+
+    /// Open a database created with [`Store::create_encrypted`].
+    #[cfg(feature = "sqlcipher")]
+    pub async fn connect_encrypted(path: impl AsRef<Path>, passphrase: &str) -> Result<Self, StoreError> {
         let path = path.as_ref();
 
         if !path.exists() {
             return Err(StoreError::NotFound(path.to_path_buf()));
         }
 
-        let pool = SqlitePool::connect(&Self::connection_url(path, false)).await?;
+        let pool = SqlitePool::connect_with(Self::encrypted_options(path, false, passphrase)?).await?;
+
+        Self::open_initialized(pool, path).await
+    }
+
+    /// Connection options that set the SQLCipher key, which sqlx issues before any other statement.
+    #[cfg(feature = "sqlcipher")]
+    fn encrypted_options(path: &Path, create: bool, passphrase: &str) -> Result<SqliteConnectOptions, StoreError> {
+        let key = format!("'{}'", passphrase.replace('\'', "''"));
+
+        Ok(SqliteConnectOptions::from_str(&Self::connection_url(path, create))?.pragma("key", key))
+    }
+
+    /// Latest migration version this build of the store creates and expects.
+    #[must_use]
+    pub fn schema_version() -> i64 {
+        MIGRATOR.iter().map(|migration| migration.version).max().unwrap_or(0)
+    }
+
+    /// Write a consistent snapshot of the database to `archive`, tagged with `manifest` entries.
+    ///
+    /// The snapshot is taken with `VACUUM INTO`, so writes in progress are never captured half-way.
+    /// The archive is itself an SQLite database, encrypted under the same passphrase as an
+    /// encrypted store; the manifest, including the schema version, is stored in an extra table
+    /// that [`Store::restore`] removes again.
+    pub async fn backup(&self, archive: impl AsRef<Path>, manifest: &[(&str, String)]) -> Result<(), StoreError> {
+        let archive = archive.as_ref();
+        if archive.exists() {
+            return Err(StoreError::DbAlreadyExists(archive.to_path_buf()));
+        }
+
+        sqlx::query("VACUUM INTO ?")
+            .bind(archive.to_string_lossy().into_owned())
+            .execute(&self.pool)
+            .await?;
+
+        // `VACUUM INTO` encrypts the archive with the store's own key, so it is opened with the same options
+        let archive_options = (*self.pool.connect_options())
+            .clone()
+            .filename(archive)
+            .create_if_missing(false);
+        let archive_pool = SqlitePool::connect_with(archive_options).await?;
+        sqlx::query(&format!(
+            "CREATE TABLE {BACKUP_MANIFEST_TABLE} (key TEXT PRIMARY KEY, value TEXT NOT NULL)"
+        ))
+        .execute(&archive_pool)
+        .await?;
+
+        let schema_version = Self::schema_version().to_string();
+        let entries = manifest
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .chain([(SCHEMA_VERSION_KEY, schema_version.as_str())]);
+        for (key, value) in entries {
+            sqlx::query(&format!(
+                "INSERT INTO {BACKUP_MANIFEST_TABLE} (key, value) VALUES (?, ?)"
+            ))
+            .bind(key)
+            .bind(value)
+            .execute(&archive_pool)
+            .await?;
+        }
+
+        archive_pool.close().await;
+
+        Ok(())
+    }
+
+    /// Read the manifest of an archive written by [`Store::backup`].
+    pub async fn read_backup_manifest(archive: impl AsRef<Path>) -> Result<BTreeMap<String, String>, StoreError> {
+        let archive = archive.as_ref();
+
+        Self::read_backup_manifest_with(archive, Self::plain_options(archive)?).await
+    }
+
+    /// Read the manifest of an archive written by [`Store::backup`] from an encrypted store.
+    #[cfg(feature = "sqlcipher")]
+    pub async fn read_backup_manifest_encrypted(
+        archive: impl AsRef<Path>,
+        passphrase: &str,
+    ) -> Result<BTreeMap<String, String>, StoreError> {
+        let archive = archive.as_ref();
+
+        Self::read_backup_manifest_with(archive, Self::encrypted_options(archive, false, passphrase)?).await
+    }
+
+    async fn read_backup_manifest_with(
+        archive: &Path,
+        options: SqliteConnectOptions,
+    ) -> Result<BTreeMap<String, String>, StoreError> {
+        if !archive.exists() {
+            return Err(StoreError::NotFound(archive.to_path_buf()));
+        }
+
+        let pool = SqlitePool::connect_with(options.read_only(true)).await?;
+        let rows: Result<Vec<(String, String)>, sqlx::Error> =
+            sqlx::query_as(&format!("SELECT key, value FROM {BACKUP_MANIFEST_TABLE}"))
+                .fetch_all(&pool)
+                .await;
+        pool.close().await;
+
+        let rows = rows.map_err(|_| StoreError::InvalidBackup("no backup manifest".to_string()))?;
+
+        Ok(rows.into_iter().collect())
+    }
 
+    /// Restore an archive written by [`Store::backup`] to `path`, which must not exist yet.
+    ///
+    /// Archives from a different schema version are refused rather than migrated.
+    pub async fn restore(archive: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let (archive, path) = (archive.as_ref(), path.as_ref());
+
+        Self::restore_with(archive, path, Self::plain_options(archive)?, Self::plain_options(path)?).await
+    }
+
+    /// Restore an archive written by [`Store::backup`] from an encrypted store, keeping the
+    /// restored database encrypted under the same passphrase.
+    #[cfg(feature = "sqlcipher")]
+    pub async fn restore_encrypted(
+        archive: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+        passphrase: &str,
+    ) -> Result<Self, StoreError> {
+        let (archive, path) = (archive.as_ref(), path.as_ref());
+
+        Self::restore_with(
+            archive,
+            path,
+            Self::encrypted_options(archive, false, passphrase)?,
+            Self::encrypted_options(path, false, passphrase)?,
+        )
+        .await
+    }
+
+    async fn restore_with(
+        archive: &Path,
+        path: &Path,
+        archive_options: SqliteConnectOptions,
+        path_options: SqliteConnectOptions,
+    ) -> Result<Self, StoreError> {
+        if path.exists() {
+            return Err(StoreError::DbAlreadyExists(path.to_path_buf()));
+        }
+
+        let manifest = Self::read_backup_manifest_with(archive, archive_options).await?;
+        let expected = Self::schema_version().to_string();
+        match manifest.get(SCHEMA_VERSION_KEY) {
+            Some(found) if *found == expected => {}
+            found => {
+                return Err(StoreError::InvalidBackup(format!(
+                    "schema version {}, this build expects {expected}",
+                    found.map_or("unknown", String::as_str)
+                )));
+            }
+        }
+
+        std::fs::copy(archive, path)?;
+
+        let pool = SqlitePool::connect_with(path_options).await?;
+        sqlx::query(&format!("DROP TABLE {BACKUP_MANIFEST_TABLE}"))
+            .execute(&pool)
+            .await?;
+
+        Self::open_initialized(pool, path).await
+    }
+
+    fn plain_options(path: &Path) -> Result<SqliteConnectOptions, StoreError> {
+        Ok(SqliteConnectOptions::from_str(&Self::connection_url(path, false))?)
+    }
+
+    async fn initialize(pool: SqlitePool, path: &Path) -> Result<Self, StoreError> {
+        if !Self::is_empty(&pool).await? {
+            return Err(StoreError::DbAlreadyExists(path.to_path_buf()));
+        }
+
+        MIGRATOR.run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    async fn open_initialized(pool: SqlitePool, path: &Path) -> Result<Self, StoreError> {
         if Self::is_empty(&pool).await? {
             return Err(StoreError::NotInitialized(path.to_path_buf()));
         }
 
-        Ok(Self { pool })
+        let store = Self { pool };
+        store.backfill_contract_expiry().await?;
+
+        Ok(store)
     }
 }
 
@@ -104,4 +354,96 @@ mod tests {
 
         let _ = fs::remove_file(path);
     }
+
+    #[cfg(feature = "sqlcipher")]
+    #[tokio::test]
+    async fn test_encrypted_requires_passphrase() {
+        let path = "/tmp/test_coin_store_encrypted.db";
+        let _ = fs::remove_file(path);
+
+        let store = Store::create_encrypted(path, "correct horse").await.unwrap();
+        drop(store);
+
+        let _store = Store::connect_encrypted(path, "correct horse").await.unwrap();
+        assert!(Store::connect_encrypted(path, "wrong").await.is_err());
+        assert!(Store::connect(path).await.is_err());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let path = "/tmp/test_coin_store_backup_source.db";
+        let archive = "/tmp/test_coin_store_backup.archive";
+        let restored = "/tmp/test_coin_store_backup_restored.db";
+        for file in [path, archive, restored] {
+            let _ = fs::remove_file(file);
+        }
+
+        let store = Store::create(path).await.unwrap();
+        store
+            .backup(archive, &[("network", "testnet".to_string())])
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.backup(archive, &[]).await,
+            Err(StoreError::DbAlreadyExists(_))
+        ));
+
+        let manifest = Store::read_backup_manifest(archive).await.unwrap();
+        assert_eq!(manifest.get("network").map(String::as_str), Some("testnet"));
+        assert_eq!(
+            manifest.get(SCHEMA_VERSION_KEY),
+            Some(&Store::schema_version().to_string())
+        );
+
+        let _restored = Store::restore(archive, restored).await.unwrap();
+        assert!(matches!(
+            Store::read_backup_manifest(restored).await,
+            Err(StoreError::InvalidBackup(_))
+        ));
+        assert!(matches!(
+            Store::restore(archive, restored).await,
+            Err(StoreError::DbAlreadyExists(_))
+        ));
+
+        for file in [path, archive, restored] {
+            let _ = fs::remove_file(file);
+        }
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[tokio::test]
+    async fn test_encrypted_backup_and_restore() {
+        let path = "/tmp/test_coin_store_encrypted_backup_source.db";
+        let archive = "/tmp/test_coin_store_encrypted_backup.archive";
+        let restored = "/tmp/test_coin_store_encrypted_backup_restored.db";
+        for file in [path, archive, restored] {
+            let _ = fs::remove_file(file);
+        }
+
+        let store = Store::create_encrypted(path, "correct horse").await.unwrap();
+        store
+            .backup(archive, &[("network", "testnet".to_string())])
+            .await
+            .unwrap();
+
+        assert!(Store::read_backup_manifest(archive).await.is_err());
+        assert!(Store::read_backup_manifest_encrypted(archive, "wrong").await.is_err());
+        let manifest = Store::read_backup_manifest_encrypted(archive, "correct horse")
+            .await
+            .unwrap();
+        assert_eq!(manifest.get("network").map(String::as_str), Some("testnet"));
+
+        let restored_store = Store::restore_encrypted(archive, restored, "correct horse")
+            .await
+            .unwrap();
+        drop(restored_store);
+        assert!(Store::connect(restored).await.is_err());
+        let _restored = Store::connect_encrypted(restored, "correct horse").await.unwrap();
+
+        for file in [path, archive, restored] {
+            let _ = fs::remove_file(file);
+        }
+    }
 }
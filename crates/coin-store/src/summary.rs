@@ -0,0 +1,23 @@
+use simplicityhl::elements::OutPoint;
+
+/// What `insert_transaction` did with each output and input of a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InsertSummary {
+    /// Outputs stored for the first time.
+    pub inserted: Vec<OutPoint>,
+    /// Outputs that were already in the store.
+    pub skipped_duplicate: Vec<OutPoint>,
+    /// Outputs that could not be unblinded with the given keys (usually other parties' outputs).
+    pub skipped_unblindable: Vec<OutPoint>,
+    /// Previously unspent stored outputs the transaction spends.
+    pub spent: Vec<OutPoint>,
+}
+
+/// What `rollback_transaction` undid for a transaction that left the chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollbackSummary {
+    /// Stored outputs the transaction spent, marked unspent again.
+    pub unspent: Vec<OutPoint>,
+    /// Outputs the transaction created, deleted along with their blinder keys.
+    pub removed: Vec<OutPoint>,
+}
@@ -1,5 +1,7 @@
 mod publishing;
 mod read_only;
+mod subscription;
 
-pub use publishing::PublishingClient;
+pub use publishing::{PublishingClient, RelayProbe};
 pub use read_only::ReadOnlyClient;
+pub use subscription::{LiveEvent, SubscriptionHandle};
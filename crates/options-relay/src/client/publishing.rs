@@ -1,8 +1,12 @@
 use crate::config::NostrRelayConfig;
 use crate::error::{ParseError, RelayError};
-use crate::events::{ActionCompletedEvent, OptionCreatedEvent, OptionOfferCreatedEvent};
+use crate::events::{
+    ACTION_COMPLETED, ActionCompletedEvent, OPTION_CREATED, OPTION_OFFER_CREATED, OptionCreatedEvent,
+    OptionOfferCreatedEvent,
+};
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use nostr::prelude::*;
 use nostr_sdk::prelude::Events;
@@ -11,6 +15,23 @@ use tracing::instrument;
 
 use super::ReadOnlyClient;
 
+/// Content of the throwaway events published by [`PublishingClient::probe_relay`].
+const PROBE_CONTENT: &str = "simplicity-dex relay probe";
+
+/// Probe events carry a NIP-40 expiration so relays drop them even if the deletion is ignored.
+const PROBE_TTL: Duration = Duration::from_secs(60);
+
+/// Result of checking a single relay with [`PublishingClient::probe_relay`].
+#[derive(Debug, Clone)]
+pub struct RelayProbe {
+    /// Round trip of a single-event query.
+    pub latency: Duration,
+    /// Event kinds the relay rejected, with the relay's message.
+    pub rejected_kinds: Vec<(Kind, String)>,
+    /// Whether the relay accepted the deletion request for the probe events.
+    pub cleaned_up: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct PublishingClient {
     reader: ReadOnlyClient,
@@ -68,15 +89,49 @@ impl PublishingClient {
         Ok(output.val)
     }
 
+    /// Build and publish an event to `relays` only, instead of every configured relay.
+    ///
+    /// Relays that are not configured are added to this client and connected first, so ad-hoc
+    /// relays work too.
+    #[instrument(skip(self, builder), level = "debug")]
+    pub async fn publish_to(&self, builder: EventBuilder, relays: &[String]) -> Result<EventId, RelayError> {
+        if relays.is_empty() {
+            return Err(RelayError::NoRelaysConfigured);
+        }
+
+        let client = self.reader.inner_client();
+        let mut urls = Vec::with_capacity(relays.len());
+        for relay in relays {
+            let relay_url = Url::parse(relay)?;
+            if client.add_relay(relay_url.clone()).await? {
+                client
+                    .try_connect_relay(relay_url.clone(), self.config().timeout())
+                    .await?;
+            }
+            urls.push(relay_url);
+        }
+
+        let output = client.send_event_builder_to(urls, builder).await?;
+
+        tracing::debug!(
+            event_id = %output.val,
+            success_count = output.success.len(),
+            failed_count = output.failed.len(),
+            "Event published to selected relays"
+        );
+
+        Ok(output.val)
+    }
+
     pub async fn publish_option_created(&self, event: &OptionCreatedEvent) -> Result<EventId, RelayError> {
         let pubkey = self.public_key().await?;
-        let builder = event.to_event_builder(pubkey)?;
+        let builder = event.to_event_builder_with(pubkey, self.config().content_encoding())?;
         self.publish(builder).await
     }
 
     pub async fn publish_option_offer_created(&self, event: &OptionOfferCreatedEvent) -> Result<EventId, RelayError> {
         let pubkey = self.public_key().await?;
-        let builder = event.to_event_builder(pubkey)?;
+        let builder = event.to_event_builder_with(pubkey, self.config().content_encoding())?;
         self.publish(builder).await
     }
 
@@ -86,11 +141,96 @@ impl PublishingClient {
         self.publish(builder).await
     }
 
+    /// Like [`Self::publish_option_created`], but only to `relays`.
+    pub async fn publish_option_created_to(
+        &self,
+        event: &OptionCreatedEvent,
+        relays: &[String],
+    ) -> Result<EventId, RelayError> {
+        let pubkey = self.public_key().await?;
+        let builder = event.to_event_builder_with(pubkey, self.config().content_encoding())?;
+        self.publish_to(builder, relays).await
+    }
+
+    /// Like [`Self::publish_option_offer_created`], but only to `relays`.
+    pub async fn publish_option_offer_created_to(
+        &self,
+        event: &OptionOfferCreatedEvent,
+        relays: &[String],
+    ) -> Result<EventId, RelayError> {
+        let pubkey = self.public_key().await?;
+        let builder = event.to_event_builder_with(pubkey, self.config().content_encoding())?;
+        self.publish_to(builder, relays).await
+    }
+
+    /// Like [`Self::publish_action_completed`], but only to `relays`.
+    pub async fn publish_action_completed_to(
+        &self,
+        event: &ActionCompletedEvent,
+        relays: &[String],
+    ) -> Result<EventId, RelayError> {
+        let pubkey = self.public_key().await?;
+        let builder = event.to_event_builder(pubkey);
+        self.publish_to(builder, relays).await
+    }
+
     #[instrument(skip(self), level = "debug")]
     pub async fn fetch_events(&self, filter: Filter) -> Result<Events, RelayError> {
         self.reader.fetch_events(filter).await
     }
 
+    /// Measure latency to one relay and check that it accepts every event kind this crate publishes.
+    ///
+    /// Accepted probe events are deleted again (NIP-09) before returning.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn probe_relay(&self, url: &str) -> Result<RelayProbe, RelayError> {
+        let relay_url = Url::parse(url)?;
+        let client = self.reader.inner_client();
+
+        let started = Instant::now();
+        client
+            .fetch_events_from(
+                [relay_url.clone()],
+                Filter::new().kind(ACTION_COMPLETED).limit(1),
+                self.config().timeout(),
+            )
+            .await?;
+        let latency = started.elapsed();
+
+        let mut accepted = Vec::new();
+        let mut rejected_kinds = Vec::new();
+        for kind in [OPTION_CREATED, OPTION_OFFER_CREATED, ACTION_COMPLETED] {
+            let builder = EventBuilder::new(kind, PROBE_CONTENT).tag(Tag::expiration(Timestamp::now() + PROBE_TTL));
+            let output = client.send_event_builder_to([relay_url.clone()], builder).await?;
+
+            if output.success.is_empty() {
+                let message = output.failed.into_values().next().unwrap_or_default();
+                rejected_kinds.push((kind, message));
+            } else {
+                accepted.push(output.val);
+            }
+        }
+
+        let cleaned_up = if accepted.is_empty() {
+            true
+        } else {
+            let deletion = EventBuilder::delete(EventDeletionRequest::new().ids(accepted).reason(PROBE_CONTENT));
+            !client
+                .send_event_builder_to([relay_url], deletion)
+                .await?
+                .success
+                .is_empty()
+        };
+
+        tracing::debug!(?latency, rejected = rejected_kinds.len(), cleaned_up, "Relay probed");
+
+        Ok(RelayProbe {
+            latency,
+            rejected_kinds,
+            cleaned_up,
+        })
+    }
+
     pub async fn fetch_options(
         &self,
         params: &'static AddressParams,
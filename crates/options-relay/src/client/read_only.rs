@@ -1,7 +1,7 @@
+use super::SubscriptionHandle;
 use crate::config::NostrRelayConfig;
 use crate::error::{ParseError, RelayError};
-use crate::events::kinds::TAG_EXPIRY;
-use crate::events::{ActionCompletedEvent, OptionCreatedEvent, OptionOfferCreatedEvent, filters};
+use crate::events::{ActionCompletedEvent, OptionCreatedEvent, OptionOfferCreatedEvent, advertised_expiry, filters};
 
 use nostr::prelude::*;
 use nostr_sdk::Client;
@@ -13,12 +13,7 @@ use tracing::instrument;
 /// Returns `false` if the expiry tag is missing or if the contract has expired.
 fn is_active(event: &Event) -> bool {
     let now = Timestamp::now().as_secs();
-    event
-        .tags
-        .iter()
-        .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_EXPIRY))
-        .and_then(|t| t.content()?.parse::<u64>().ok())
-        .is_some_and(|expiry| expiry > now)
+    advertised_expiry(event).is_some_and(|expiry| expiry > now)
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +51,12 @@ impl ReadOnlyClient {
         Ok(self.client.fetch_combined_events(filter, self.config.timeout()).await?)
     }
 
+    /// Fetch a single event by id, if any connected relay has it.
+    pub async fn fetch_event(&self, event_id: EventId) -> Result<Option<Event>, RelayError> {
+        let events = self.fetch_events(filters::by_id(event_id)).await?;
+        Ok(events.into_iter().next())
+    }
+
     pub async fn fetch_options(
         &self,
         params: &'static AddressParams,
@@ -80,6 +81,31 @@ impl ReadOnlyClient {
             .collect())
     }
 
+    /// Fetch every option offer on the relays, expired ones included, for historical analysis.
+    pub async fn fetch_option_offer_history(
+        &self,
+        params: &'static AddressParams,
+    ) -> Result<Vec<Result<OptionOfferCreatedEvent, ParseError>>, RelayError> {
+        let events = self.fetch_events(filters::option_offer_created()).await?;
+        Ok(events
+            .iter()
+            .map(|e| OptionOfferCreatedEvent::from_event(e, params))
+            .collect())
+    }
+
+    /// Fetch every completed action on the relays, optionally only those published since a timestamp.
+    pub async fn fetch_actions(
+        &self,
+        since: Option<Timestamp>,
+    ) -> Result<Vec<Result<ActionCompletedEvent, ParseError>>, RelayError> {
+        let filter = match since {
+            Some(since) => filters::action_completed().since(since),
+            None => filters::action_completed(),
+        };
+        let events = self.fetch_events(filter).await?;
+        Ok(events.iter().map(ActionCompletedEvent::from_event).collect())
+    }
+
     pub async fn fetch_actions_for_event(
         &self,
         original_event_id: EventId,
@@ -97,6 +123,22 @@ impl ReadOnlyClient {
         Ok(self.client.subscribe(filter, None).await?.val)
     }
 
+    /// Stream events of `kinds` published from now on, parsed, over a long-lived subscription.
+    ///
+    /// The subscription survives dropped relay connections (see [`SubscriptionHandle`]); events
+    /// published before the call are fetched with the `fetch_*` methods instead. Fails if the
+    /// subscription cannot be opened.
+    #[instrument(skip(self), level = "debug", err)]
+    pub async fn subscribe_live(
+        &self,
+        kinds: Vec<Kind>,
+        params: &'static AddressParams,
+    ) -> Result<SubscriptionHandle, RelayError> {
+        tracing::debug!(?kinds, "Starting live subscription");
+
+        SubscriptionHandle::open(self.client.clone(), kinds, params).await
+    }
+
     #[instrument(skip(self), level = "debug")]
     pub async fn unsubscribe(&self, subscription_id: &SubscriptionId) {
         tracing::debug!(%subscription_id, "Unsubscribing");
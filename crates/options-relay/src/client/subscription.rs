@@ -0,0 +1,265 @@
+use crate::error::{ParseError, RelayError};
+use crate::events::{
+    ACTION_COMPLETED, ActionCompletedEvent, OPTION_CREATED, OPTION_OFFER_CREATED, OptionCreatedEvent,
+    OptionOfferCreatedEvent,
+};
+
+use nostr::prelude::*;
+use nostr_sdk::{Client, RelayPoolNotification};
+use simplicityhl::elements::AddressParams;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Events buffered for a slow consumer before the subscription waits for it.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// An event delivered by [`ReadOnlyClient::subscribe_live`](super::ReadOnlyClient::subscribe_live).
+#[derive(Debug)]
+pub enum LiveEvent {
+    OptionCreated(OptionCreatedEvent),
+    OptionOfferCreated(OptionOfferCreatedEvent),
+    ActionCompleted(ActionCompletedEvent),
+    /// An event of a subscribed kind that failed to parse or verify.
+    Invalid {
+        event_id: EventId,
+        error: ParseError,
+    },
+}
+
+impl LiveEvent {
+    fn parse(event: &Event, params: &'static AddressParams) -> Self {
+        let parsed = if event.kind == OPTION_CREATED {
+            OptionCreatedEvent::from_event(event, params).map(Self::OptionCreated)
+        } else if event.kind == OPTION_OFFER_CREATED {
+            OptionOfferCreatedEvent::from_event(event, params).map(Self::OptionOfferCreated)
+        } else if event.kind == ACTION_COMPLETED {
+            ActionCompletedEvent::from_event(event).map(Self::ActionCompleted)
+        } else {
+            Err(ParseError::InvalidKind)
+        };
+
+        parsed.unwrap_or_else(|error| Self::Invalid {
+            event_id: event.id,
+            error,
+        })
+    }
+}
+
+/// A live subscription kept open by a background task until [`Self::unsubscribe`] or drop.
+///
+/// Reconnection is left to nostr-sdk: the relay pool reconnects dropped relays on its own and
+/// re-sends open subscriptions when they come back, and notifies each event only the first time
+/// any relay delivers it. The task ends, and [`Self::recv`] returns `None`, when the client shuts
+/// down.
+#[derive(Debug)]
+pub struct SubscriptionHandle {
+    events: mpsc::Receiver<LiveEvent>,
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl SubscriptionHandle {
+    /// Open the subscription on the relays and start forwarding its events.
+    ///
+    /// Failing to open the subscription is returned here rather than ending the task silently.
+    pub(crate) async fn open(
+        client: Client,
+        kinds: Vec<Kind>,
+        params: &'static AddressParams,
+    ) -> Result<Self, RelayError> {
+        // Listen before subscribing so no event slips in between
+        let notifications = client.notifications();
+        let filter = Filter::new().kinds(kinds).since(Timestamp::now());
+
+        let subscription_id = client.subscribe(filter, None).await?.val;
+
+        let (events_tx, events) = mpsc::channel(CHANNEL_CAPACITY);
+        let (stop, stop_rx) = oneshot::channel();
+
+        let task = tokio::spawn(run_subscription(
+            client,
+            subscription_id,
+            notifications,
+            params,
+            events_tx,
+            stop_rx,
+        ));
+
+        Ok(Self {
+            events,
+            stop: Some(stop),
+            task,
+        })
+    }
+
+    /// Next event, or `None` once the subscription has stopped.
+    pub async fn recv(&mut self) -> Option<LiveEvent> {
+        self.events.recv().await
+    }
+
+    /// Close the subscription on the relays and wait for the background task to finish.
+    pub async fn unsubscribe(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+
+        if let Err(e) = (&mut self.task).await {
+            tracing::debug!("Subscription task ended abnormally: {e}");
+        }
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+/// Forward the subscription's events until stopped or the client shuts down, then close it.
+async fn run_subscription(
+    client: Client,
+    subscription_id: SubscriptionId,
+    notifications: broadcast::Receiver<RelayPoolNotification>,
+    params: &'static AddressParams,
+    events: mpsc::Sender<LiveEvent>,
+    stop: oneshot::Receiver<()>,
+) {
+    forward_events(notifications, &subscription_id, params, &events, stop).await;
+
+    client.unsubscribe(&subscription_id).await;
+}
+
+/// Forward events of `subscription_id` until stopped, the receiver is dropped or the relay pool shuts down.
+async fn forward_events(
+    mut notifications: broadcast::Receiver<RelayPoolNotification>,
+    subscription_id: &SubscriptionId,
+    params: &'static AddressParams,
+    events: &mpsc::Sender<LiveEvent>,
+    mut stop: oneshot::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            _ = &mut stop => return,
+            notification = notifications.recv() => match notification {
+                Ok(RelayPoolNotification::Event {
+                    subscription_id: id,
+                    event,
+                    ..
+                }) if id == *subscription_id => {
+                    if events.send(LiveEvent::parse(&event, params)).await.is_err() {
+                        // The receiver was dropped along with the handle
+                        return;
+                    }
+                }
+                Ok(RelayPoolNotification::Shutdown) | Err(RecvError::Closed) => {
+                    tracing::debug!("Relay pool shut down, ending live subscription");
+                    return;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Subscription lagged behind, {skipped} relay notification(s) skipped");
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::events::ActionType;
+    use simplicityhl::elements::OutPoint;
+    use simplicityhl::elements::hashes::Hash;
+
+    fn action_event(keys: &Keys, vout: u32) -> anyhow::Result<Event> {
+        let outpoint = OutPoint::new(simplicityhl::elements::Txid::all_zeros(), vout);
+
+        Ok(
+            ActionCompletedEvent::new(EventId::all_zeros(), ActionType::OptionExercised, outpoint)
+                .to_event_builder(keys.public_key())
+                .sign_with_keys(keys)?,
+        )
+    }
+
+    /// A relay dropping and reconnecting only shows up as other pool notifications, so the
+    /// subscription keeps forwarding across it; a pool shutdown ends it instead of retrying.
+    #[tokio::test]
+    async fn subscription_survives_relay_reconnects_and_ends_on_shutdown() -> anyhow::Result<()> {
+        let keys = Keys::generate();
+        let params = &AddressParams::LIQUID_TESTNET;
+        let relay_url = RelayUrl::parse("wss://relay.example.com")?;
+        let subscription_id = SubscriptionId::generate();
+
+        let (notify, notifications) = broadcast::channel(16);
+        let (events_tx, mut events) = mpsc::channel(16);
+        let (_stop, stop_rx) = oneshot::channel();
+
+        let task = tokio::spawn({
+            let subscription_id = subscription_id.clone();
+            async move { forward_events(notifications, &subscription_id, params, &events_tx, stop_rx).await }
+        });
+
+        let event = |event: Event| RelayPoolNotification::Event {
+            relay_url: relay_url.clone(),
+            subscription_id: subscription_id.clone(),
+            event: Box::new(event),
+        };
+
+        notify.send(event(action_event(&keys, 0)?))?;
+        notify.send(RelayPoolNotification::Message {
+            relay_url: relay_url.clone(),
+            message: RelayMessage::notice("reconnected"),
+        })?;
+        notify.send(event(action_event(&keys, 1)?))?;
+        notify.send(RelayPoolNotification::Shutdown)?;
+
+        for vout in [0, 1] {
+            assert!(matches!(
+                events.recv().await,
+                Some(LiveEvent::ActionCompleted(parsed)) if parsed.outpoint.vout == vout
+            ));
+        }
+        assert!(events.recv().await.is_none());
+        task.await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn live_events_are_parsed_by_kind() -> anyhow::Result<()> {
+        let keys = Keys::generate();
+        let params = &AddressParams::LIQUID_TESTNET;
+        let outpoint = OutPoint::new(simplicityhl::elements::Txid::all_zeros(), 0);
+
+        let action = ActionCompletedEvent::new(EventId::all_zeros(), ActionType::OptionExercised, outpoint)
+            .to_event_builder(keys.public_key())
+            .sign_with_keys(&keys)?;
+        assert!(matches!(
+            LiveEvent::parse(&action, params),
+            LiveEvent::ActionCompleted(parsed) if parsed.outpoint == outpoint
+        ));
+
+        // An announcement kind without its tags is reported, not dropped
+        let malformed = EventBuilder::new(OPTION_OFFER_CREATED, "").sign_with_keys(&keys)?;
+        assert!(matches!(
+            LiveEvent::parse(&malformed, params),
+            LiveEvent::Invalid { event_id, .. } if event_id == malformed.id
+        ));
+
+        let unrelated = EventBuilder::new(Kind::TextNote, "gm").sign_with_keys(&keys)?;
+        assert!(matches!(
+            LiveEvent::parse(&unrelated, params),
+            LiveEvent::Invalid {
+                error: ParseError::InvalidKind,
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+}
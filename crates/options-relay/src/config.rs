@@ -1,11 +1,14 @@
 use std::time::Duration;
 
+use crate::events::ContentEncoding;
+
 #[derive(Debug, Clone)]
 pub struct NostrRelayConfig {
     primary_relay: String,
     backup_relays: Vec<String>,
     timeout: Duration,
     retry_count: u32,
+    content_encoding: ContentEncoding,
 }
 
 impl NostrRelayConfig {
@@ -19,6 +22,7 @@ impl NostrRelayConfig {
             backup_relays: Vec::new(),
             timeout: Self::DEFAULT_TIMEOUT,
             retry_count: Self::DEFAULT_RETRY_COUNT,
+            content_encoding: ContentEncoding::default(),
         }
     }
 
@@ -46,6 +50,13 @@ impl NostrRelayConfig {
         self
     }
 
+    /// Encoding used for the option and offer events this client publishes.
+    #[must_use]
+    pub const fn with_content_encoding(mut self, encoding: ContentEncoding) -> Self {
+        self.content_encoding = encoding;
+        self
+    }
+
     #[must_use]
     pub fn primary_relay(&self) -> &str {
         &self.primary_relay
@@ -67,6 +78,11 @@ impl NostrRelayConfig {
     pub const fn retry_count(&self) -> u32 {
         self.retry_count
     }
+
+    #[must_use]
+    pub const fn content_encoding(&self) -> ContentEncoding {
+        self.content_encoding
+    }
 }
 
 #[cfg(test)]
@@ -81,6 +97,7 @@ mod tests {
         assert_eq!(config.all_relays().len(), 1);
         assert_eq!(config.timeout(), NostrRelayConfig::DEFAULT_TIMEOUT);
         assert_eq!(config.retry_count(), NostrRelayConfig::DEFAULT_RETRY_COUNT);
+        assert_eq!(config.content_encoding(), ContentEncoding::Tags);
     }
 
     #[test]
@@ -100,9 +117,11 @@ mod tests {
     fn test_config_with_custom_settings() {
         let config = NostrRelayConfig::new("wss://relay.example.com")
             .with_timeout(Duration::from_secs(60))
-            .with_retry_count(5);
+            .with_retry_count(5)
+            .with_content_encoding(ContentEncoding::CompactV1);
 
         assert_eq!(config.timeout(), Duration::from_secs(60));
         assert_eq!(config.retry_count(), 5);
+        assert_eq!(config.content_encoding(), ContentEncoding::CompactV1);
     }
 }
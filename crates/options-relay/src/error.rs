@@ -51,4 +51,10 @@ pub enum ParseError {
 
     #[error("Taproot verification failed")]
     TaprootVerification(#[from] TaprootPubkeyGenError),
+
+    #[error("Unsupported content encoding: {0}")]
+    UnsupportedEncoding(String),
+
+    #[error("Invalid event content: {0}")]
+    InvalidContent(String),
 }
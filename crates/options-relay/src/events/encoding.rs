@@ -0,0 +1,111 @@
+//! Event content encodings.
+//!
+//! Legacy events carry contract arguments and the funding outpoint as hex tags. The compact
+//! encoding moves both into the event content as base64 bincode, marked by the `enc` tag.
+//! Filterable tags (`p`, `t`, `expiry`) are emitted in both encodings.
+
+use crate::error::ParseError;
+use crate::events::kinds::TAG_ENCODING;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use nostr::{Event, Tag, TagKind};
+use simplicityhl::elements::{OutPoint, encode};
+
+/// `enc` tag value for the compact binary content encoding.
+pub const ENCODING_COMPACT_V1: &str = "bin1";
+
+/// How contract data is laid out in an event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentEncoding {
+    /// Arguments and outpoint as hex tags (readable by every client version).
+    #[default]
+    Tags,
+    /// Arguments and outpoint as base64 bincode in the event content.
+    CompactV1,
+}
+
+impl ContentEncoding {
+    /// Detect the encoding of a received event; events without an `enc` tag use [`ContentEncoding::Tags`].
+    pub fn detect(event: &Event) -> Result<Self, ParseError> {
+        let encoding = event
+            .tags
+            .iter()
+            .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_ENCODING))
+            .and_then(|t| t.content());
+
+        match encoding {
+            None => Ok(Self::Tags),
+            Some(ENCODING_COMPACT_V1) => Ok(Self::CompactV1),
+            Some(other) => Err(ParseError::UnsupportedEncoding(other.to_string())),
+        }
+    }
+
+    #[must_use]
+    pub fn tag() -> Tag {
+        Tag::custom(TagKind::custom(TAG_ENCODING), [ENCODING_COMPACT_V1])
+    }
+}
+
+/// Pack encoded contract arguments and the funding outpoint into compact event content.
+#[must_use]
+pub fn encode_compact_content(args_bytes: &[u8], utxo: OutPoint) -> String {
+    #[allow(clippy::cast_possible_truncation)]
+    let mut payload = (args_bytes.len() as u32).to_le_bytes().to_vec();
+    payload.extend_from_slice(args_bytes);
+    payload.extend(encode::serialize(&utxo));
+
+    STANDARD.encode(payload)
+}
+
+/// Unpack compact event content into encoded contract arguments and the funding outpoint.
+pub fn decode_compact_content(content: &str) -> Result<(Vec<u8>, OutPoint), ParseError> {
+    let payload = STANDARD
+        .decode(content)
+        .map_err(|e| ParseError::InvalidContent(e.to_string()))?;
+
+    let (len_bytes, rest) = payload
+        .split_first_chunk::<4>()
+        .ok_or_else(|| ParseError::InvalidContent("truncated length prefix".to_string()))?;
+    let args_len = u32::from_le_bytes(*len_bytes) as usize;
+
+    if rest.len() < args_len {
+        return Err(ParseError::InvalidContent("truncated arguments".to_string()));
+    }
+    let (args_bytes, utxo_bytes) = rest.split_at(args_len);
+
+    let utxo: OutPoint = encode::deserialize(utxo_bytes).map_err(|e| ParseError::InvalidContent(e.to_string()))?;
+
+    Ok((args_bytes.to_vec(), utxo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nostr::hashes::Hash;
+    use simplicityhl::elements::Txid;
+
+    #[test]
+    fn compact_content_roundtrip() -> anyhow::Result<()> {
+        let utxo = OutPoint::new(Txid::from_slice(&[7; 32])?, 3);
+        let args = vec![1, 2, 3, 4, 5];
+
+        let content = encode_compact_content(&args, utxo);
+        let (decoded_args, decoded_utxo) = decode_compact_content(&content)?;
+
+        assert_eq!(decoded_args, args);
+        assert_eq!(decoded_utxo, utxo);
+
+        Ok(())
+    }
+
+    #[test]
+    fn compact_content_rejects_truncated_payload() {
+        let content = STANDARD.encode([10, 0, 0, 0, 1, 2]);
+        assert!(matches!(
+            decode_compact_content(&content),
+            Err(ParseError::InvalidContent(_))
+        ));
+    }
+}
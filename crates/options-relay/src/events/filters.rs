@@ -36,3 +36,8 @@ pub fn action_completed_for_event(original_event_id: nostr::EventId) -> Filter {
 pub fn all_option_events() -> Filter {
     Filter::new().kinds([OPTION_CREATED, OPTION_OFFER_CREATED, ACTION_COMPLETED])
 }
+
+#[must_use]
+pub fn by_id(event_id: nostr::EventId) -> Filter {
+    Filter::new().id(event_id)
+}
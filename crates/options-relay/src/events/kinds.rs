@@ -12,6 +12,7 @@ pub const TAG_TAPROOT_GEN: &str = "t";
 pub const TAG_ACTION: &str = "action";
 pub const TAG_OUTPOINT: &str = "outpoint";
 pub const TAG_EXPIRY: &str = "expiry";
+pub const TAG_ENCODING: &str = "enc";
 
 pub const ACTION_OPTION_CREATED: &str = "option_created";
 pub const ACTION_OPTION_FUNDED: &str = "option_funded";
@@ -1,10 +1,27 @@
 mod action_completed;
+pub mod encoding;
 pub mod filters;
 pub mod kinds;
 mod option_created;
 mod option_offer_created;
 
+use nostr::{Event, TagKind};
+
 pub use action_completed::{ActionCompletedEvent, ActionType};
+pub use encoding::ContentEncoding;
 pub use kinds::*;
 pub use option_created::OptionCreatedEvent;
 pub use option_offer_created::OptionOfferCreatedEvent;
+
+/// The expiry an announcement advertises in its `expiry` tag, which relays and listings filter on.
+///
+/// The tag is set by the publisher and is not part of the contract; the expiry the program
+/// enforces is the one in the announced arguments.
+#[must_use]
+pub fn advertised_expiry(event: &Event) -> Option<u64> {
+    event
+        .tags
+        .iter()
+        .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_EXPIRY))
+        .and_then(|t| t.content()?.parse::<u64>().ok())
+}
@@ -1,4 +1,5 @@
 use crate::error::{ParseError, RelayError};
+use crate::events::encoding::{ContentEncoding, decode_compact_content, encode_compact_content};
 use crate::events::kinds::{OPTION_CREATED, TAG_EXPIRY, TAG_OPTIONS_ARGS, TAG_OPTIONS_UTXO, TAG_TAPROOT_GEN};
 
 use contracts::options::{OptionsArguments, get_options_address};
@@ -31,12 +32,30 @@ impl OptionCreatedEvent {
     }
 
     pub fn to_event_builder(&self, creator_pubkey: PublicKey) -> Result<EventBuilder, RelayError> {
-        let args_hex = self.options_args.to_hex()?;
+        self.to_event_builder_with(creator_pubkey, ContentEncoding::Tags)
+    }
 
-        Ok(EventBuilder::new(OPTION_CREATED, "")
+    pub fn to_event_builder_with(
+        &self,
+        creator_pubkey: PublicKey,
+        encoding: ContentEncoding,
+    ) -> Result<EventBuilder, RelayError> {
+        let builder = match encoding {
+            ContentEncoding::Tags => EventBuilder::new(OPTION_CREATED, "")
+                .tag(Tag::custom(
+                    TagKind::custom(TAG_OPTIONS_ARGS),
+                    [self.options_args.to_hex()?],
+                ))
+                .tag(Tag::custom(TagKind::custom(TAG_OPTIONS_UTXO), [self.utxo.to_string()])),
+            ContentEncoding::CompactV1 => EventBuilder::new(
+                OPTION_CREATED,
+                encode_compact_content(&self.options_args.encode()?, self.utxo),
+            )
+            .tag(ContentEncoding::tag()),
+        };
+
+        Ok(builder
             .tag(Tag::public_key(creator_pubkey))
-            .tag(Tag::custom(TagKind::custom(TAG_OPTIONS_ARGS), [args_hex]))
-            .tag(Tag::custom(TagKind::custom(TAG_OPTIONS_UTXO), [self.utxo.to_string()]))
             .tag(Tag::custom(
                 TagKind::custom(TAG_TAPROOT_GEN),
                 [self.taproot_pubkey_gen.to_string()],
@@ -54,23 +73,29 @@ impl OptionCreatedEvent {
             return Err(ParseError::InvalidKind);
         }
 
-        let args_hex = event
-            .tags
-            .iter()
-            .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTIONS_ARGS))
-            .and_then(|t| t.content())
-            .ok_or(ParseError::MissingTag(TAG_OPTIONS_ARGS))?;
-
-        let options_args = OptionsArguments::from_hex(args_hex)?;
-
-        let utxo_str = event
-            .tags
-            .iter()
-            .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTIONS_UTXO))
-            .and_then(|t| t.content())
-            .ok_or(ParseError::MissingTag(TAG_OPTIONS_UTXO))?;
-
-        let utxo: OutPoint = utxo_str.parse()?;
+        let (options_args, utxo) = match ContentEncoding::detect(event)? {
+            ContentEncoding::Tags => {
+                let args_hex = event
+                    .tags
+                    .iter()
+                    .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTIONS_ARGS))
+                    .and_then(|t| t.content())
+                    .ok_or(ParseError::MissingTag(TAG_OPTIONS_ARGS))?;
+
+                let utxo_str = event
+                    .tags
+                    .iter()
+                    .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTIONS_UTXO))
+                    .and_then(|t| t.content())
+                    .ok_or(ParseError::MissingTag(TAG_OPTIONS_UTXO))?;
+
+                (OptionsArguments::from_hex(args_hex)?, utxo_str.parse::<OutPoint>()?)
+            }
+            ContentEncoding::CompactV1 => {
+                let (args_bytes, utxo) = decode_compact_content(&event.content)?;
+                (OptionsArguments::decode(&args_bytes)?, utxo)
+            }
+        };
 
         let taproot_str = event
             .tags
@@ -97,7 +122,7 @@ impl OptionCreatedEvent {
 mod tests {
     use super::*;
 
-    use nostr::{Keys, hashes::Hash};
+    use nostr::{JsonUtil, Keys, hashes::Hash};
 
     use contracts::sdk::taproot_pubkey_gen::get_random_seed;
 
@@ -146,4 +171,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn option_created_event_compact_roundtrip() -> anyhow::Result<()> {
+        let keys = Keys::generate();
+        let (args, taproot_pubkey_gen) = get_mocked_data()?;
+        let utxo = OutPoint::new(Txid::all_zeros(), 1);
+
+        let event = OptionCreatedEvent::new(args.clone(), utxo, taproot_pubkey_gen.clone());
+
+        let compact = event
+            .to_event_builder_with(keys.public_key(), ContentEncoding::CompactV1)?
+            .sign_with_keys(&keys)?;
+        let legacy = event.to_event_builder(keys.public_key())?.sign_with_keys(&keys)?;
+
+        assert!(compact.as_json().len() < legacy.as_json().len());
+
+        let parsed = OptionCreatedEvent::from_event(&compact, &AddressParams::LIQUID_TESTNET)?;
+
+        assert_eq!(parsed.options_args, args);
+        assert_eq!(parsed.utxo, utxo);
+        assert_eq!(parsed.taproot_pubkey_gen.to_string(), taproot_pubkey_gen.to_string());
+
+        Ok(())
+    }
 }
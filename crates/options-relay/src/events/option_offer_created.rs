@@ -1,4 +1,5 @@
 use crate::error::{ParseError, RelayError};
+use crate::events::encoding::{ContentEncoding, decode_compact_content, encode_compact_content};
 use crate::events::kinds::{
     OPTION_OFFER_CREATED, TAG_EXPIRY, TAG_OPTION_OFFER_ARGS, TAG_OPTION_OFFER_UTXO, TAG_TAPROOT_GEN,
 };
@@ -33,15 +34,33 @@ impl OptionOfferCreatedEvent {
     }
 
     pub fn to_event_builder(&self, creator_pubkey: PublicKey) -> Result<EventBuilder, RelayError> {
-        let args_hex = self.option_offer_args.to_hex()?;
+        self.to_event_builder_with(creator_pubkey, ContentEncoding::Tags)
+    }
 
-        Ok(EventBuilder::new(OPTION_OFFER_CREATED, "")
+    pub fn to_event_builder_with(
+        &self,
+        creator_pubkey: PublicKey,
+        encoding: ContentEncoding,
+    ) -> Result<EventBuilder, RelayError> {
+        let builder = match encoding {
+            ContentEncoding::Tags => EventBuilder::new(OPTION_OFFER_CREATED, "")
+                .tag(Tag::custom(
+                    TagKind::custom(TAG_OPTION_OFFER_ARGS),
+                    [self.option_offer_args.to_hex()?],
+                ))
+                .tag(Tag::custom(
+                    TagKind::custom(TAG_OPTION_OFFER_UTXO),
+                    [self.utxo.to_string()],
+                )),
+            ContentEncoding::CompactV1 => EventBuilder::new(
+                OPTION_OFFER_CREATED,
+                encode_compact_content(&self.option_offer_args.encode()?, self.utxo),
+            )
+            .tag(ContentEncoding::tag()),
+        };
+
+        Ok(builder
             .tag(Tag::public_key(creator_pubkey))
-            .tag(Tag::custom(TagKind::custom(TAG_OPTION_OFFER_ARGS), [args_hex]))
-            .tag(Tag::custom(
-                TagKind::custom(TAG_OPTION_OFFER_UTXO),
-                [self.utxo.to_string()],
-            ))
             .tag(Tag::custom(
                 TagKind::custom(TAG_TAPROOT_GEN),
                 [self.taproot_pubkey_gen.to_string()],
@@ -59,23 +78,29 @@ impl OptionOfferCreatedEvent {
             return Err(ParseError::InvalidKind);
         }
 
-        let args_hex = event
-            .tags
-            .iter()
-            .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTION_OFFER_ARGS))
-            .and_then(|t| t.content())
-            .ok_or(ParseError::MissingTag(TAG_OPTION_OFFER_ARGS))?;
-
-        let option_offer_args = OptionOfferArguments::from_hex(args_hex)?;
-
-        let utxo_str = event
-            .tags
-            .iter()
-            .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTION_OFFER_UTXO))
-            .and_then(|t| t.content())
-            .ok_or(ParseError::MissingTag(TAG_OPTION_OFFER_UTXO))?;
-
-        let utxo: OutPoint = utxo_str.parse()?;
+        let (option_offer_args, utxo) = match ContentEncoding::detect(event)? {
+            ContentEncoding::Tags => {
+                let args_hex = event
+                    .tags
+                    .iter()
+                    .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTION_OFFER_ARGS))
+                    .and_then(|t| t.content())
+                    .ok_or(ParseError::MissingTag(TAG_OPTION_OFFER_ARGS))?;
+
+                let utxo_str = event
+                    .tags
+                    .iter()
+                    .find(|t| matches!(t.kind(), TagKind::Custom(s) if s.as_ref() == TAG_OPTION_OFFER_UTXO))
+                    .and_then(|t| t.content())
+                    .ok_or(ParseError::MissingTag(TAG_OPTION_OFFER_UTXO))?;
+
+                (OptionOfferArguments::from_hex(args_hex)?, utxo_str.parse::<OutPoint>()?)
+            }
+            ContentEncoding::CompactV1 => {
+                let (args_bytes, utxo) = decode_compact_content(&event.content)?;
+                (OptionOfferArguments::decode(&args_bytes)?, utxo)
+            }
+        };
 
         let taproot_str = event
             .tags
@@ -102,7 +127,7 @@ impl OptionOfferCreatedEvent {
 mod tests {
     use super::*;
 
-    use nostr::{Keys, hashes::Hash};
+    use nostr::{JsonUtil, Keys, hashes::Hash};
 
     use simplicityhl::elements::{AssetId, Txid};
     use simplicityhl_core::{LIQUID_TESTNET_BITCOIN_ASSET, LIQUID_TESTNET_TEST_ASSET_ID_STR};
@@ -140,6 +165,34 @@ mod tests {
 
         let parsed = OptionOfferCreatedEvent::from_event(&built_event, &AddressParams::LIQUID_TESTNET)?;
 
+        assert_eq!(parsed.option_offer_args, args);
+        assert_eq!(parsed.utxo, utxo);
+        assert_eq!(parsed.taproot_pubkey_gen.to_string(), taproot_pubkey_gen.to_string());
+        assert_eq!(
+            crate::events::advertised_expiry(&built_event),
+            Some(u64::from(args.expiry_time()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn option_offer_created_event_compact_roundtrip() -> anyhow::Result<()> {
+        let keys = Keys::generate();
+        let (args, taproot_pubkey_gen) = get_mocked_data()?;
+        let utxo = OutPoint::new(Txid::all_zeros(), 1);
+
+        let event = OptionOfferCreatedEvent::new(args.clone(), utxo, taproot_pubkey_gen.clone());
+
+        let compact = event
+            .to_event_builder_with(keys.public_key(), ContentEncoding::CompactV1)?
+            .sign_with_keys(&keys)?;
+        let legacy = event.to_event_builder(keys.public_key())?.sign_with_keys(&keys)?;
+
+        assert!(compact.as_json().len() < legacy.as_json().len());
+
+        let parsed = OptionOfferCreatedEvent::from_event(&compact, &AddressParams::LIQUID_TESTNET)?;
+
         assert_eq!(parsed.option_offer_args, args);
         assert_eq!(parsed.utxo, utxo);
         assert_eq!(parsed.taproot_pubkey_gen.to_string(), taproot_pubkey_gen.to_string());
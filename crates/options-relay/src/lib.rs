@@ -6,12 +6,12 @@ pub mod config;
 pub mod error;
 pub mod events;
 
-pub use client::{PublishingClient, ReadOnlyClient};
+pub use client::{LiveEvent, PublishingClient, ReadOnlyClient, RelayProbe, SubscriptionHandle};
 pub use config::NostrRelayConfig;
 pub use error::{ParseError, RelayError};
 pub use events::{
     ACTION_COMPLETED, ACTION_OPTION_CANCELLED, ACTION_OPTION_CREATED, ACTION_OPTION_EXERCISED, ACTION_OPTION_EXPIRED,
     ACTION_OPTION_FUNDED, ACTION_OPTION_OFFER_CANCELLED, ACTION_OPTION_OFFER_CREATED, ACTION_OPTION_OFFER_EXERCISED,
-    ACTION_SETTLEMENT_CLAIMED, ActionCompletedEvent, ActionType, OPTION_CREATED, OPTION_OFFER_CREATED,
-    OptionCreatedEvent, OptionOfferCreatedEvent,
+    ACTION_SETTLEMENT_CLAIMED, ActionCompletedEvent, ActionType, ContentEncoding, OPTION_CREATED, OPTION_OFFER_CREATED,
+    OptionCreatedEvent, OptionOfferCreatedEvent, advertised_expiry,
 };
@@ -0,0 +1,72 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+
+use signer::{Signer, SigningBackend};
+use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+use simplicityhl::elements::hashes::Hash;
+use simplicityhl::elements::{
+    AddressParams, AssetId, BlockHash, LockTime, OutPoint, Transaction, TxIn, TxOut, TxOutWitness, Txid,
+};
+
+const INPUTS: u8 = 20;
+
+/// A transaction spending `INPUTS` coins locked to the signer's P2PK address.
+fn consolidation(signer: &Signer, params: &'static AddressParams) -> (Transaction, Vec<TxOut>) {
+    let script_pubkey = signer.p2pk_address(params).unwrap().script_pubkey();
+
+    let utxos = (0..INPUTS)
+        .map(|_| TxOut {
+            asset: Asset::Explicit(AssetId::from_slice(&[1; 32]).unwrap()),
+            value: Value::Explicit(1_000),
+            nonce: Nonce::Null,
+            script_pubkey: script_pubkey.clone(),
+            witness: TxOutWitness::default(),
+        })
+        .collect();
+    let tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: (0..INPUTS)
+            .map(|seed| TxIn {
+                previous_output: OutPoint::new(Txid::from_byte_array([seed; 32]), 0),
+                ..TxIn::default()
+            })
+            .collect(),
+        output: Vec::new(),
+    };
+
+    (tx, utxos)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let params = &AddressParams::LIQUID_TESTNET;
+    let genesis_hash = BlockHash::all_zeros();
+    let signer = Signer::from_seed(&[5; Signer::SEED_LEN]).unwrap();
+    let (tx, utxos) = consolidation(&signer, params);
+    let indices: Vec<usize> = (0..usize::from(INPUTS)).collect();
+
+    let mut group = c.benchmark_group("P2PK signing (20 inputs)");
+    group.sample_size(10);
+
+    group.bench_function("per_input", |b| {
+        b.iter(|| {
+            for &index in &indices {
+                signer
+                    .sign_p2pk(black_box(&tx), &utxos, index, params, genesis_hash)
+                    .unwrap();
+            }
+        });
+    });
+
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            signer
+                .sign_p2pk_batch(black_box(&tx), &utxos, &indices, params, genesis_hash)
+                .unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,11 +1,19 @@
 #![warn(clippy::all, clippy::pedantic)]
 #![allow(clippy::missing_errors_doc)]
 
+use simplicityhl::elements::pset::{self, PartiallySignedTransaction};
 use simplicityhl::elements::secp256k1_zkp::{self as secp256k1, Keypair, Message, schnorr::Signature};
 use simplicityhl::elements::{Address, AddressParams, BlockHash, Transaction, TxOut};
-use simplicityhl::simplicity::bitcoin::XOnlyPublicKey;
-use simplicityhl::simplicity::hashes::Hash as _;
-use simplicityhl_core::{ProgramError, get_and_verify_env, get_p2pk_address, get_p2pk_program, hash_script};
+use simplicityhl::simplicity::bitcoin::bip32::{self, ChildNumber, DerivationPath, Xpriv, Xpub};
+use simplicityhl::simplicity::bitcoin::{NetworkKind, XOnlyPublicKey};
+use simplicityhl::simplicity::hashes::{Hash as _, HashEngine as _, sha256};
+use simplicityhl::tracker::TrackerLogLevel;
+use simplicityhl_core::{
+    ProgramError, finalize_p2pk_transaction, get_and_verify_env, get_p2pk_address, get_p2pk_program, hash_script,
+};
+
+/// BIP-340 tag for signed messages, keeping message signatures apart from transaction sighashes.
+const MESSAGE_TAG: &[u8] = b"simplicity-dex/message";
 
 #[derive(thiserror::Error, Debug)]
 pub enum SignerError {
@@ -17,23 +25,82 @@ pub enum SignerError {
 
     #[error("Program error")]
     Address(#[from] ProgramError),
+
+    #[error("BIP32 derivation error: {0}")]
+    Bip32(#[from] bip32::Error),
+
+    #[error("Input index {index} is out of range for a transaction with {inputs} input(s)")]
+    InputIndexOutOfRange { index: usize, inputs: usize },
+
+    #[error("Got {utxos} spent output(s) for a transaction with {inputs} input(s)")]
+    UtxoCountMismatch { utxos: usize, inputs: usize },
+
+    #[error("Output spent by input {input_index} is not locked to the program: {source}")]
+    ProgramMismatch {
+        input_index: usize,
+        #[source]
+        source: ProgramError,
+    },
+
+    #[error("PSET error: {0}")]
+    Pset(#[from] pset::Error),
+
+    #[error("Signing backend error: {0}")]
+    Backend(String),
 }
 
 pub struct Signer {
     keypair: Keypair,
+    /// BIP32 node children are derived from.
+    xpriv: Xpriv,
 }
 
 impl Signer {
     pub const SEED_LEN: usize = secp256k1::constants::SECRET_KEY_SIZE;
 
+    /// Use the seed directly as the secret key. Existing wallets are keyed this way.
+    ///
+    /// This key is not part of the BIP32 tree: [`Self::derive_child`] and [`Self::xpub`] work from
+    /// the seed's BIP32 master key instead.
     pub fn from_seed(seed: &[u8; Self::SEED_LEN]) -> Result<Self, SignerError> {
         let secp = secp256k1::Secp256k1::new();
 
         let secret_key = secp256k1::SecretKey::from_slice(seed)?;
 
         let keypair = Keypair::from_secret_key(&secp, &secret_key);
+        let xpriv = Xpriv::new_master(NetworkKind::Test, seed)?;
 
-        Ok(Self { keypair })
+        Ok(Self { keypair, xpriv })
+    }
+
+    /// Sign with the BIP32 key at `path` below the seed's master key, e.g. `m/0'` for an account.
+    pub fn from_seed_with_path(seed: &[u8; Self::SEED_LEN], path: &DerivationPath) -> Result<Self, SignerError> {
+        let secp = secp256k1::Secp256k1::new();
+
+        let xpriv = Xpriv::new_master(NetworkKind::Test, seed)?.derive_priv(&secp, path)?;
+
+        Ok(Self {
+            keypair: xpriv.to_keypair(&secp),
+            xpriv,
+        })
+    }
+
+    /// Signer for the non-hardened child `index`, so the same child is derivable from [`Self::xpub`].
+    pub fn derive_child(&self, index: u32) -> Result<Self, SignerError> {
+        let secp = secp256k1::Secp256k1::new();
+
+        let xpriv = self.xpriv.derive_priv(&secp, &[ChildNumber::from_normal_idx(index)?])?;
+
+        Ok(Self {
+            keypair: xpriv.to_keypair(&secp),
+            xpriv,
+        })
+    }
+
+    /// Extended public key of this signer's BIP32 node, for deriving child addresses without the seed.
+    #[must_use]
+    pub fn xpub(&self) -> Xpub {
+        Xpub::from_priv(&secp256k1::Secp256k1::signing_only(), &self.xpriv)
     }
 
     #[must_use]
@@ -41,19 +108,37 @@ impl Signer {
         self.keypair.sign_schnorr(message)
     }
 
+    /// Sign an arbitrary message, hashed with [`message_digest`].
+    #[must_use]
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        self.sign(message_digest(message))
+    }
+
     #[must_use]
     pub fn public_key(&self) -> XOnlyPublicKey {
         self.keypair.x_only_public_key().0
     }
+}
+
+/// Source of Schnorr signatures for a single key.
+///
+/// [`Signer`] is the software implementation holding the key in memory. External signers, such as a
+/// hardware wallet or a remote HSM, implement the two required methods and get transaction signing
+/// from the provided ones.
+pub trait SigningBackend {
+    /// Sign a 32-byte message digest with BIP-340 Schnorr.
+    fn sign_schnorr(&self, message: Message) -> Result<Signature, SignerError>;
+
+    /// The key signatures are made with.
+    fn public_key(&self) -> XOnlyPublicKey;
 
-    pub fn p2pk_address(&self, params: &'static AddressParams) -> Result<Address, SignerError> {
-        let public_key = self.keypair.x_only_public_key().0;
-        let address = get_p2pk_address(&public_key, params)?;
+    fn p2pk_address(&self, params: &'static AddressParams) -> Result<Address, SignerError> {
+        let address = get_p2pk_address(&self.public_key(), params)?;
 
         Ok(address)
     }
 
-    pub fn p2pk_script_hash(&self, params: &'static AddressParams) -> Result<[u8; 32], SignerError> {
+    fn p2pk_script_hash(&self, params: &'static AddressParams) -> Result<[u8; 32], SignerError> {
         let address = self.p2pk_address(params)?;
 
         let mut script_hash: [u8; 32] = hash_script(&address.script_pubkey());
@@ -62,7 +147,7 @@ impl Signer {
         Ok(script_hash)
     }
 
-    pub fn print_details(&self) -> Result<(), SignerError> {
+    fn print_details(&self) -> Result<(), SignerError> {
         let public_key = self.public_key();
         let address = self.p2pk_address(&AddressParams::LIQUID_TESTNET)?;
         let script_hash = self.p2pk_script_hash(&AddressParams::LIQUID_TESTNET)?;
@@ -74,7 +159,7 @@ impl Signer {
         Ok(())
     }
 
-    pub fn sign_p2pk(
+    fn sign_p2pk(
         &self,
         tx: &Transaction,
         utxos: &[TxOut],
@@ -82,28 +167,91 @@ impl Signer {
         params: &'static AddressParams,
         genesis_hash: BlockHash,
     ) -> Result<Signature, SignerError> {
-        let x_only_public_key = self.keypair.x_only_public_key().0;
-        let p2pk_program = get_p2pk_program(&x_only_public_key)?;
+        let p2pk_program = get_p2pk_program(&self.public_key())?;
 
-        let env = get_and_verify_env(
-            tx,
-            &p2pk_program,
-            &x_only_public_key,
-            utxos,
-            params,
-            genesis_hash,
-            input_index,
-        )?;
+        sign_p2pk_with(self, &p2pk_program, tx, utxos, input_index, params, genesis_hash)
+    }
+
+    /// Sign several P2PK inputs of `tx`, returning `(input_index, signature)` in request order.
+    ///
+    /// The P2PK program is compiled once for all inputs. Each input still gets its own
+    /// environment, since the sighash commits to the index of the input being signed.
+    fn sign_p2pk_batch(
+        &self,
+        tx: &Transaction,
+        utxos: &[TxOut],
+        input_indices: &[usize],
+        params: &'static AddressParams,
+        genesis_hash: BlockHash,
+    ) -> Result<Vec<(usize, Signature)>, SignerError> {
+        let p2pk_program = get_p2pk_program(&self.public_key())?;
+
+        input_indices
+            .iter()
+            .map(|&input_index| {
+                let signature = sign_p2pk_with(self, &p2pk_program, tx, utxos, input_index, params, genesis_hash)?;
+                Ok((input_index, signature))
+            })
+            .collect()
+    }
+
+    /// Sign and finalize, in place, every input of `pset` that spends this signer's P2PK script.
+    ///
+    /// `utxos` are the outputs spent by the PSET's inputs, in input order. Inputs locked to
+    /// anything else, e.g. contract inputs, are left untouched for their own finalizer. Returns
+    /// how many inputs were signed, so callers can check that every input they expected is covered.
+    fn sign_pset(
+        &self,
+        pset: &mut PartiallySignedTransaction,
+        utxos: &[TxOut],
+        params: &'static AddressParams,
+        genesis_hash: BlockHash,
+    ) -> Result<usize, SignerError> {
+        let tx = pset.extract_tx()?;
+        if utxos.len() != tx.input.len() {
+            return Err(SignerError::UtxoCountMismatch {
+                utxos: utxos.len(),
+                inputs: tx.input.len(),
+            });
+        }
+
+        let script_pubkey = self.p2pk_address(params)?.script_pubkey();
+        let input_indices: Vec<usize> = utxos
+            .iter()
+            .enumerate()
+            .filter(|(_, utxo)| utxo.script_pubkey == script_pubkey)
+            .map(|(index, _)| index)
+            .collect();
 
-        let sighash_all = Message::from_digest(env.c_tx_env().sighash_all().to_byte_array());
+        // Witnesses are not part of the sighash, so every input is signed against the same transaction
+        let signatures = self.sign_p2pk_batch(&tx, utxos, &input_indices, params, genesis_hash)?;
+        for (input_index, signature) in signatures {
+            let finalized = finalize_p2pk_transaction(
+                tx.clone(),
+                utxos,
+                &self.public_key(),
+                &signature,
+                input_index,
+                params,
+                genesis_hash,
+                TrackerLogLevel::None,
+            )?;
 
-        Ok(self.keypair.sign_schnorr(sighash_all))
+            pset.inputs_mut()[input_index].final_script_witness =
+                Some(finalized.input[input_index].witness.script_witness.clone());
+        }
+
+        Ok(input_indices.len())
     }
 
     /// Sign a contract transaction input.
     /// This is used for Simplicity contracts that require a user signature (e.g., swap withdraw).
+    ///
+    /// Fails as described in [`check_spend_inputs`], or with [`SignerError::ProgramMismatch`] if
+    /// the spent output is not locked to `program` under `x_only_pubkey`. Whether the program
+    /// is satisfied by the spend is only known once the input is finalized.
     #[allow(clippy::too_many_arguments)]
-    pub fn sign_contract(
+    fn sign_contract(
         &self,
         tx: &Transaction,
         program: &simplicityhl::CompiledProgram,
@@ -113,10 +261,343 @@ impl Signer {
         params: &'static AddressParams,
         genesis_hash: BlockHash,
     ) -> Result<Signature, SignerError> {
-        let env = get_and_verify_env(tx, program, x_only_pubkey, utxos, params, genesis_hash, input_index)?;
+        let sighash_all = sighash_all(tx, program, x_only_pubkey, utxos, params, genesis_hash, input_index)?;
+
+        self.sign_schnorr(sighash_all)
+    }
+}
+
+fn sign_p2pk_with<B: SigningBackend + ?Sized>(
+    backend: &B,
+    p2pk_program: &simplicityhl::CompiledProgram,
+    tx: &Transaction,
+    utxos: &[TxOut],
+    input_index: usize,
+    params: &'static AddressParams,
+    genesis_hash: BlockHash,
+) -> Result<Signature, SignerError> {
+    let sighash_all = sighash_all(
+        tx,
+        p2pk_program,
+        &backend.public_key(),
+        utxos,
+        params,
+        genesis_hash,
+        input_index,
+    )?;
+
+    backend.sign_schnorr(sighash_all)
+}
+
+impl SigningBackend for Signer {
+    fn sign_schnorr(&self, message: Message) -> Result<Signature, SignerError> {
+        Ok(self.sign(message))
+    }
+
+    fn public_key(&self) -> XOnlyPublicKey {
+        self.public_key()
+    }
+}
+
+/// Check that `input_index` and `utxos` describe an input of `tx` before its environment is built.
+///
+/// Fails with [`SignerError::InputIndexOutOfRange`] if `tx` has no input at `input_index`, and
+/// with [`SignerError::UtxoCountMismatch`] unless `utxos` holds exactly the output spent by each
+/// input, in input order.
+pub fn check_spend_inputs(tx: &Transaction, utxos: &[TxOut], input_index: usize) -> Result<(), SignerError> {
+    let inputs = tx.input.len();
+
+    if input_index >= inputs {
+        return Err(SignerError::InputIndexOutOfRange {
+            index: input_index,
+            inputs,
+        });
+    }
+    if utxos.len() != inputs {
+        return Err(SignerError::UtxoCountMismatch {
+            utxos: utxos.len(),
+            inputs,
+        });
+    }
+
+    Ok(())
+}
+
+/// `SIGHASH_ALL` message of `input_index`, after [`check_spend_inputs`] and the program check.
+#[allow(clippy::too_many_arguments)]
+fn sighash_all(
+    tx: &Transaction,
+    program: &simplicityhl::CompiledProgram,
+    x_only_pubkey: &XOnlyPublicKey,
+    utxos: &[TxOut],
+    params: &'static AddressParams,
+    genesis_hash: BlockHash,
+    input_index: usize,
+) -> Result<Message, SignerError> {
+    check_spend_inputs(tx, utxos, input_index)?;
+
+    let env = get_and_verify_env(tx, program, x_only_pubkey, utxos, params, genesis_hash, input_index)
+        .map_err(|source| SignerError::ProgramMismatch { input_index, source })?;
+
+    Ok(Message::from_digest(env.c_tx_env().sighash_all().to_byte_array()))
+}
+
+/// Tagged hash (BIP-340 style) of a message signed with [`Signer::sign_message`].
+#[must_use]
+pub fn message_digest(message: &[u8]) -> Message {
+    let tag = sha256::Hash::hash(MESSAGE_TAG);
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+
+    Message::from_digest(sha256::Hash::from_engine(engine).to_byte_array())
+}
+
+/// Verify a signature made with [`Signer::sign_message`].
+#[must_use]
+pub fn verify_message(public_key: &XOnlyPublicKey, message: &[u8], signature: &Signature) -> bool {
+    verify_batch(&[(*public_key, message_digest(message), *signature)])
+}
+
+/// Verify a set of Schnorr signatures, returning `true` only if every one is valid.
+///
+/// The secp256k1 bindings do not expose batch verification, so signatures are checked one
+/// by one with a single shared verification context, stopping at the first invalid one.
+#[must_use]
+pub fn verify_batch(items: &[(XOnlyPublicKey, Message, Signature)]) -> bool {
+    let secp = secp256k1::Secp256k1::verification_only();
+
+    items
+        .iter()
+        .all(|(public_key, message, signature)| secp.verify_schnorr(signature, message, public_key).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(seed: u8, digest: u8) -> (XOnlyPublicKey, Message, Signature) {
+        let signer = Signer::from_seed(&[seed; Signer::SEED_LEN]).unwrap();
+        let message = Message::from_digest([digest; 32]);
+        (signer.public_key(), message, signer.sign(message))
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let mut items = vec![signed(1, 10), signed(2, 20), signed(3, 30)];
+        assert!(verify_batch(&items));
+        assert!(verify_batch(&[]));
+
+        // Signature over a different message
+        items[1].1 = Message::from_digest([21; 32]);
+        assert!(!verify_batch(&items));
+    }
+
+    #[test]
+    fn test_derived_children_match_xpub() {
+        let seed = [7; Signer::SEED_LEN];
+        let legacy = Signer::from_seed(&seed).unwrap();
+        let account = Signer::from_seed_with_path(&seed, &"m/0'".parse().unwrap()).unwrap();
+
+        let first = account.derive_child(0).unwrap();
+        let second = account.derive_child(1).unwrap();
+        assert_ne!(first.public_key(), second.public_key());
+        assert_ne!(first.public_key(), legacy.public_key());
+        assert_ne!(
+            first.p2pk_address(&AddressParams::LIQUID_TESTNET).unwrap(),
+            second.p2pk_address(&AddressParams::LIQUID_TESTNET).unwrap()
+        );
+
+        // Watch-only derivation from the xpub reaches the same keys
+        let secp = secp256k1::Secp256k1::verification_only();
+        let child = account
+            .xpub()
+            .derive_pub(&secp, &[ChildNumber::from_normal_idx(1).unwrap()])
+            .unwrap();
+        assert_eq!(child.to_x_only_pub(), second.public_key());
+
+        // The same path always yields the same key
+        let again = Signer::from_seed_with_path(&seed, &"m/0'/1".parse().unwrap()).unwrap();
+        assert_eq!(again.public_key(), second.public_key());
+    }
+
+    #[test]
+    fn test_sign_p2pk_batch_signs_each_requested_input() {
+        use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+        use simplicityhl::elements::{AssetId, LockTime, OutPoint, TxIn, TxOutWitness, Txid};
+
+        let params = &AddressParams::LIQUID_TESTNET;
+        let genesis_hash = BlockHash::all_zeros();
+        let signer = Signer::from_seed(&[5; Signer::SEED_LEN]).unwrap();
+        let script_pubkey = signer.p2pk_address(params).unwrap().script_pubkey();
+
+        let utxos: Vec<TxOut> = (0..3)
+            .map(|_| TxOut {
+                asset: Asset::Explicit(AssetId::from_slice(&[1; 32]).unwrap()),
+                value: Value::Explicit(1_000),
+                nonce: Nonce::Null,
+                script_pubkey: script_pubkey.clone(),
+                witness: TxOutWitness::default(),
+            })
+            .collect();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: (0..3u8)
+                .map(|seed| TxIn {
+                    previous_output: OutPoint::new(Txid::from_byte_array([seed; 32]), 0),
+                    ..TxIn::default()
+                })
+                .collect(),
+            output: Vec::new(),
+        };
+
+        let signatures = signer
+            .sign_p2pk_batch(&tx, &utxos, &[2, 0], params, genesis_hash)
+            .unwrap();
+        assert_eq!(
+            signatures.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![2, 0]
+        );
+
+        let p2pk_program = get_p2pk_program(&signer.public_key()).unwrap();
+        let secp = secp256k1::Secp256k1::verification_only();
+        for (index, signature) in &signatures {
+            let env = get_and_verify_env(
+                &tx,
+                &p2pk_program,
+                &signer.public_key(),
+                &utxos,
+                params,
+                genesis_hash,
+                *index,
+            )
+            .unwrap();
+            let sighash_all = Message::from_digest(env.c_tx_env().sighash_all().to_byte_array());
+            assert!(
+                secp.verify_schnorr(signature, &sighash_all, &signer.public_key())
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_sign_p2pk_reports_why_inputs_are_rejected() {
+        use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+        use simplicityhl::elements::{AssetId, LockTime, OutPoint, TxIn, TxOutWitness, Txid};
+
+        let params = &AddressParams::LIQUID_TESTNET;
+        let genesis_hash = BlockHash::all_zeros();
+        let signer = Signer::from_seed(&[5; Signer::SEED_LEN]).unwrap();
+        let other = Signer::from_seed(&[6; Signer::SEED_LEN]).unwrap();
+
+        let utxo = |signer: &Signer| TxOut {
+            asset: Asset::Explicit(AssetId::from_slice(&[1; 32]).unwrap()),
+            value: Value::Explicit(1_000),
+            nonce: Nonce::Null,
+            script_pubkey: signer.p2pk_address(params).unwrap().script_pubkey(),
+            witness: TxOutWitness::default(),
+        };
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: (0..2u8)
+                .map(|seed| TxIn {
+                    previous_output: OutPoint::new(Txid::from_byte_array([seed; 32]), 0),
+                    ..TxIn::default()
+                })
+                .collect(),
+            output: Vec::new(),
+        };
+        let utxos = vec![utxo(&signer), utxo(&other)];
+
+        assert!(signer.sign_p2pk(&tx, &utxos, 0, params, genesis_hash).is_ok());
+        assert!(matches!(
+            signer.sign_p2pk(&tx, &utxos, 2, params, genesis_hash),
+            Err(SignerError::InputIndexOutOfRange { index: 2, inputs: 2 })
+        ));
+        assert!(matches!(
+            signer.sign_p2pk(&tx, &utxos[..1], 0, params, genesis_hash),
+            Err(SignerError::UtxoCountMismatch { utxos: 1, inputs: 2 })
+        ));
+        assert!(matches!(
+            signer.sign_p2pk(&tx, &utxos, 1, params, genesis_hash),
+            Err(SignerError::ProgramMismatch { input_index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_sign_pset_finalizes_only_own_p2pk_inputs() {
+        use simplicityhl::elements::confidential::{Asset, Nonce, Value};
+        use simplicityhl::elements::pset::Input;
+        use simplicityhl::elements::{AssetId, OutPoint, TxOutWitness, Txid};
+
+        let params = &AddressParams::LIQUID_TESTNET;
+        let genesis_hash = BlockHash::all_zeros();
+        let signer = Signer::from_seed(&[5; Signer::SEED_LEN]).unwrap();
+        let other = Signer::from_seed(&[6; Signer::SEED_LEN]).unwrap();
+
+        let utxo = |signer: &Signer| TxOut {
+            asset: Asset::Explicit(AssetId::from_slice(&[1; 32]).unwrap()),
+            value: Value::Explicit(1_000),
+            nonce: Nonce::Null,
+            script_pubkey: signer.p2pk_address(params).unwrap().script_pubkey(),
+            witness: TxOutWitness::default(),
+        };
+        let utxos = vec![utxo(&signer), utxo(&other), utxo(&signer)];
+
+        let mut pset = PartiallySignedTransaction::new_v2();
+        for seed in 0..3u8 {
+            pset.add_input(Input::from_prevout(OutPoint::new(Txid::from_byte_array([seed; 32]), 0)));
+        }
+
+        assert_eq!(signer.sign_pset(&mut pset, &utxos, params, genesis_hash).unwrap(), 2);
+        assert!(
+            pset.inputs()[0]
+                .final_script_witness
+                .as_ref()
+                .is_some_and(|w| !w.is_empty())
+        );
+        assert!(pset.inputs()[1].final_script_witness.is_none());
+        assert!(
+            pset.inputs()[2]
+                .final_script_witness
+                .as_ref()
+                .is_some_and(|w| !w.is_empty())
+        );
+
+        assert!(matches!(
+            signer.sign_pset(&mut pset, &utxos[..2], params, genesis_hash),
+            Err(SignerError::UtxoCountMismatch { utxos: 2, inputs: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_message() {
+        let signer = Signer::from_seed(&[1; Signer::SEED_LEN]).unwrap();
+        let other = Signer::from_seed(&[2; Signer::SEED_LEN]).unwrap();
+        let signature = signer.sign_message(b"otc deal 42");
+
+        assert!(verify_message(&signer.public_key(), b"otc deal 42", &signature));
+        assert!(!verify_message(&signer.public_key(), b"otc deal 43", &signature));
+        assert!(!verify_message(&other.public_key(), b"otc deal 42", &signature));
+    }
+
+    #[test]
+    fn test_boxed_backend_signs_for_its_key() {
+        let signer = Signer::from_seed(&[1; Signer::SEED_LEN]).unwrap();
+        let expected_address = signer.p2pk_address(&AddressParams::LIQUID_TESTNET).unwrap();
+        let backend: Box<dyn SigningBackend> = Box::new(signer);
 
-        let sighash_all = Message::from_digest(env.c_tx_env().sighash_all().to_byte_array());
+        let message = message_digest(b"otc deal 42");
+        let signature = backend.sign_schnorr(message).unwrap();
 
-        Ok(self.keypair.sign_schnorr(sighash_all))
+        assert!(verify_batch(&[(backend.public_key(), message, signature)]));
+        assert_eq!(
+            backend.p2pk_address(&AddressParams::LIQUID_TESTNET).unwrap(),
+            expected_address
+        );
     }
 }